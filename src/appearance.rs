@@ -0,0 +1,155 @@
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+
+// Longest icon accepted, in characters; enough for emoji built from several code points
+const MAX_ICON_CHARS: usize = 8;
+
+// Set by `--no-color`
+static COLORS_OFF: AtomicBool = AtomicBool::new(false);
+
+// Colors a project can be marked with. Only the basic ANSI colors, so every terminal
+// shows them the same way in the CLI and the TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectColor {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+}
+
+impl ProjectColor {
+    pub const ALL: [ProjectColor; 8] = [
+        ProjectColor::Red,
+        ProjectColor::Green,
+        ProjectColor::Yellow,
+        ProjectColor::Blue,
+        ProjectColor::Magenta,
+        ProjectColor::Cyan,
+        ProjectColor::White,
+        ProjectColor::Gray,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProjectColor::Red => "red",
+            ProjectColor::Green => "green",
+            ProjectColor::Yellow => "yellow",
+            ProjectColor::Blue => "blue",
+            ProjectColor::Magenta => "magenta",
+            ProjectColor::Cyan => "cyan",
+            ProjectColor::White => "white",
+            ProjectColor::Gray => "gray",
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Self> {
+        let wanted = input.trim().to_lowercase();
+        let wanted = if wanted == "grey" { "gray" } else { &wanted };
+        Self::ALL
+            .into_iter()
+            .find(|color| color.name() == wanted)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|c| c.name()).collect();
+                TaskMasterError::InvalidOperation(format!(
+                    "Unknown color '{}'; expected one of: {}",
+                    input,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    fn ansi_code(self) -> u8 {
+        match self {
+            ProjectColor::Red => 31,
+            ProjectColor::Green => 32,
+            ProjectColor::Yellow => 33,
+            ProjectColor::Blue => 34,
+            ProjectColor::Magenta => 35,
+            ProjectColor::Cyan => 36,
+            ProjectColor::White => 37,
+            ProjectColor::Gray => 90,
+        }
+    }
+}
+
+impl fmt::Display for ProjectColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Check a project icon: a short emoji or symbol without spaces
+pub fn parse_icon(input: &str) -> Result<String> {
+    let icon = input.trim();
+    if icon.is_empty() || icon.chars().any(char::is_whitespace) {
+        return Err(TaskMasterError::InvalidOperation(
+            "An icon must be a single emoji or short symbol without spaces".to_string(),
+        ));
+    }
+    if icon.chars().count() > MAX_ICON_CHARS {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Icons can be at most {} characters",
+            MAX_ICON_CHARS
+        )));
+    }
+    Ok(icon.to_string())
+}
+
+// Color text for the terminal. Left plain when stdout is redirected or NO_COLOR is set,
+// so scripts and files get clean output.
+pub fn paint(text: &str, color: Option<ProjectColor>) -> String {
+    match color {
+        Some(color) if colors_enabled() => {
+            format!("\x1b[{}m{}\x1b[0m", color.ansi_code(), text)
+        }
+        _ => text.to_string(),
+    }
+}
+
+// Faint text, for things that matter less such as finished tasks
+pub fn dim(text: &str) -> String {
+    if colors_enabled() {
+        format!("\x1b[2m{}\x1b[0m", text)
+    } else {
+        text.to_string()
+    }
+}
+
+// Red for urgent, yellow for normal, green for low priority work, by priority weight
+pub fn priority_color(weight: u8) -> ProjectColor {
+    if weight >= 70 {
+        ProjectColor::Red
+    } else if weight >= 40 {
+        ProjectColor::Yellow
+    } else {
+        ProjectColor::Green
+    }
+}
+
+pub fn disable_colors() {
+    COLORS_OFF.store(true, Ordering::Relaxed);
+}
+
+fn colors_enabled() -> bool {
+    !COLORS_OFF.load(Ordering::Relaxed)
+        && std::env::var_os("NO_COLOR").is_none()
+        && io::stdout().is_terminal()
+}
+
+// Make text safe to place in HTML pages such as the printable planner and reports
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,27 +1,139 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use tokio::sync::mpsc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex as AsyncMutex};
 use tokio::time;
 
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosMonkey;
+use crate::correlation::RunId;
 use crate::error::{Result, TaskMasterError};
-use crate::task::{Task};
+use crate::task::{Task, TaskStatus};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskEvent {
-    Started { task_id: u32 },
-    Completed { task_id: u32 },
-    Failed { task_id: u32, error_message: String },
-    Timeout { task_id: u32 },
-    Terminated { task_id: u32 },
+    Started {
+        task_id: u32,
+        run_id: RunId,
+    },
+    Completed {
+        task_id: u32,
+        run_id: RunId,
+    },
+    Failed {
+        task_id: u32,
+        run_id: RunId,
+        error_message: String,
+        // Missing from events recorded before causes were
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cause: Option<FailureCause>,
+    },
+    Timeout {
+        task_id: u32,
+        run_id: RunId,
+    },
+    Terminated {
+        task_id: u32,
+        run_id: RunId,
+    },
+    // A dependency finished and nothing else holds the task back any more
+    Unblocked {
+        task_id: u32,
+        run_id: RunId,
+        dependency: u32,
+        // The status the task is in now
+        status: TaskStatus,
+    },
+}
+
+// What made a run fail
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCause {
+    // The handler returned an error of this kind (see `TaskMasterError::kind`)
+    Error(String),
+    Panicked,
+}
+
+impl TaskEvent {
+    pub fn task_id(&self) -> u32 {
+        match self {
+            TaskEvent::Started { task_id, .. }
+            | TaskEvent::Completed { task_id, .. }
+            | TaskEvent::Failed { task_id, .. }
+            | TaskEvent::Timeout { task_id, .. }
+            | TaskEvent::Terminated { task_id, .. }
+            | TaskEvent::Unblocked { task_id, .. } => *task_id,
+        }
+    }
+
+    pub fn run_id(&self) -> &RunId {
+        match self {
+            TaskEvent::Started { run_id, .. }
+            | TaskEvent::Completed { run_id, .. }
+            | TaskEvent::Failed { run_id, .. }
+            | TaskEvent::Timeout { run_id, .. }
+            | TaskEvent::Terminated { run_id, .. }
+            | TaskEvent::Unblocked { run_id, .. } => run_id,
+        }
+    }
+}
+
+impl fmt::Display for TaskEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskEvent::Started { task_id, run_id } => {
+                write!(f, "[{}] Task {} started", run_id, task_id)
+            }
+            TaskEvent::Completed { task_id, run_id } => {
+                write!(f, "[{}] Task {} completed", run_id, task_id)
+            }
+            TaskEvent::Failed {
+                task_id,
+                run_id,
+                error_message,
+                cause: Some(FailureCause::Panicked),
+            } => write!(
+                f,
+                "[{}] Task {} panicked: {}",
+                run_id, task_id, error_message
+            ),
+            TaskEvent::Failed {
+                task_id,
+                run_id,
+                error_message,
+                ..
+            } => write!(f, "[{}] Task {} failed: {}", run_id, task_id, error_message),
+            TaskEvent::Timeout { task_id, run_id } => {
+                write!(f, "[{}] Task {} timed out", run_id, task_id)
+            }
+            TaskEvent::Terminated { task_id, run_id } => {
+                write!(f, "[{}] Task {} terminated", run_id, task_id)
+            }
+            TaskEvent::Unblocked {
+                task_id,
+                run_id,
+                dependency,
+                status,
+            } => write!(
+                f,
+                "[{}] Task {} can start now that task {} is done ({})",
+                run_id, task_id, dependency, status
+            ),
+        }
+    }
 }
 
 pub struct AsyncTaskExecutor {
     running_tasks: Arc<Mutex<HashMap<u32, Instant>>>,
     timeout: Duration,
     event_tx: mpsc::Sender<TaskEvent>,
-    event_rx: Arc<Mutex<mpsc::Receiver<TaskEvent>>>,
+    event_rx: Arc<AsyncMutex<mpsc::Receiver<TaskEvent>>>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<ChaosMonkey>>,
 }
 
 impl AsyncTaskExecutor {
@@ -32,12 +144,23 @@ impl AsyncTaskExecutor {
             running_tasks: Arc::new(Mutex::new(HashMap::new())),
             timeout: Duration::from_secs(timeout_seconds),
             event_tx,
-            event_rx: Arc::new(Mutex::new(event_rx)),
+            event_rx: Arc::new(AsyncMutex::new(event_rx)),
+            #[cfg(feature = "chaos")]
+            chaos: None,
         }
     }
 
-    pub async fn execute_task(&self, task: Task) -> Result<()> {
+    // Inject delays and failures into every task this executor runs
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Arc<ChaosMonkey>) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    // Submit a task and return the run ID carried by all of its events
+    pub async fn execute_task(&self, task: Task) -> Result<RunId> {
         let task_id = task.id;
+        let run_id = RunId::new();
 
         {
             let mut running = self.running_tasks.lock().unwrap();
@@ -49,16 +172,44 @@ impl AsyncTaskExecutor {
 
         // Send started event
         event_tx
-            .send(TaskEvent::Started { task_id })
+            .send(TaskEvent::Started {
+                task_id,
+                run_id: run_id.clone(),
+            })
             .await
             .map_err(|_| {
                 TaskMasterError::ChannelError("Failed to send task started event".to_string())
             })?;
 
         // Spawn a new task
+        let job_run_id = run_id.clone();
+        #[cfg(feature = "chaos")]
+        let chaos = self.chaos.clone();
         tokio::spawn(async move {
+            #[cfg(feature = "chaos")]
+            if let Some(chaos) = chaos {
+                time::sleep(chaos.random_delay()).await;
+                if let Err(e) = chaos.maybe_fail_handler(task_id) {
+                    running_tasks.lock().unwrap().remove(&task_id);
+                    let _ = event_tx
+                        .send(TaskEvent::Failed {
+                            task_id,
+                            run_id: job_run_id,
+                            error_message: e.to_string(),
+                            cause: Some(FailureCause::Error(e.kind().to_string())),
+                        })
+                        .await;
+                    return;
+                }
+            }
+
             // Simulate task execution
-            println!("Async executing task: {}", task.title);
+            crate::log_event!(
+                Info,
+                "[{}] Async executing task: {}",
+                job_run_id,
+                task.title
+            );
             time::sleep(Duration::from_secs(2)).await;
 
             // Mark task as completed
@@ -68,9 +219,14 @@ impl AsyncTaskExecutor {
             }
 
             // Send completed event
-            let _ = event_tx.send(TaskEvent::Completed { task_id }).await;
+            let _ = event_tx
+                .send(TaskEvent::Completed {
+                    task_id,
+                    run_id: job_run_id,
+                })
+                .await;
         });
-        Ok(())
+        Ok(run_id)
     }
 
     pub async fn cancel_task(&self, task_id: u32) -> Result<()> {
@@ -100,7 +256,7 @@ impl AsyncTaskExecutor {
     }
 
     pub async fn next_event(&self) -> Option<TaskEvent> {
-        let mut rx = self.event_rx.lock().unwrap();
+        let mut rx = self.event_rx.lock().await;
         rx.recv().await
     }
 
@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::blob_store::BlobBackend;
+use crate::error::{Result, TaskMasterError};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AttachmentKind {
+    // A file copied into the attachment store, relative to the store root
+    File { stored_path: PathBuf },
+    // A file kept by another blob backend, under the key it returned
+    Blob { backend: BlobBackend, key: String },
+    // An external reference that is opened as-is
+    Link { url: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Attachment {
+    pub name: String,
+    pub kind: AttachmentKind,
+}
+
+impl Attachment {
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            AttachmentKind::File { stored_path } => {
+                format!("{} (file: {})", self.name, stored_path.display())
+            }
+            AttachmentKind::Blob { backend, key } => {
+                format!("{} ({}: {})", self.name, backend, key)
+            }
+            AttachmentKind::Link { url } => format!("{} (link: {})", self.name, url),
+        }
+    }
+
+    // The backend and key of the stored file, if there is one
+    pub fn blob(&self) -> Option<(BlobBackend, String)> {
+        match &self.kind {
+            AttachmentKind::File { stored_path } => Some((
+                BlobBackend::Local,
+                stored_path.to_string_lossy().to_string(),
+            )),
+            AttachmentKind::Blob { backend, key } => Some((backend.clone(), key.clone())),
+            AttachmentKind::Link { .. } => None,
+        }
+    }
+}
+
+// Manages attachment files, storing new ones with the configured backend. Files already
+// attached stay with the backend that stored them.
+pub struct AttachmentStore {
+    data_dir: PathBuf,
+    backend: BlobBackend,
+}
+
+impl AttachmentStore {
+    pub fn new<P: AsRef<Path>>(data_dir: P) -> Self {
+        AttachmentStore::with_backend(data_dir, BlobBackend::Local)
+    }
+
+    pub fn with_backend<P: AsRef<Path>>(data_dir: P, backend: BlobBackend) -> Self {
+        AttachmentStore {
+            data_dir: data_dir.as_ref().to_path_buf(),
+            backend,
+        }
+    }
+
+    // Turn a path or URL into an attachment, copying local files into the store
+    pub fn attach(
+        &self,
+        project_id: u32,
+        task_id: u32,
+        source: &str,
+        name: Option<&str>,
+    ) -> Result<Attachment> {
+        if is_url(source) {
+            return Ok(Attachment {
+                name: name.unwrap_or(source).to_string(),
+                kind: AttachmentKind::Link {
+                    url: source.to_string(),
+                },
+            });
+        }
+
+        let source_path = Path::new(source);
+        if !source_path.is_file() {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Attachment source is not a file: {}",
+                source
+            )));
+        }
+
+        let file_name = source_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                TaskMasterError::InvalidOperation(format!("Invalid attachment path: {}", source))
+            })?;
+
+        let hint = Path::new(&project_id.to_string())
+            .join(task_id.to_string())
+            .join(&file_name);
+        let key = self.backend.open(&self.data_dir).put(source_path, &hint)?;
+
+        let kind = match &self.backend {
+            BlobBackend::Local => AttachmentKind::File {
+                stored_path: PathBuf::from(key),
+            },
+            backend => AttachmentKind::Blob {
+                backend: backend.clone(),
+                key,
+            },
+        };
+        Ok(Attachment {
+            name: name.unwrap_or(&file_name).to_string(),
+            kind,
+        })
+    }
+
+    // Remove any stored file behind an attachment; links need no cleanup, and blobs other
+    // attachments may share are left for `prune`
+    pub fn remove(&self, attachment: &Attachment) -> Result<()> {
+        if let Some((backend, key)) = attachment.blob() {
+            let store = backend.open(&self.data_dir);
+            if !store.shared() {
+                store.delete(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Remove every stored file for a task
+    pub fn remove_task(&self, attachments: &[Attachment]) -> Result<()> {
+        for attachment in attachments {
+            self.remove(attachment)?;
+        }
+        Ok(())
+    }
+
+    pub fn location(&self, attachment: &Attachment) -> Result<String> {
+        match &attachment.kind {
+            AttachmentKind::File { stored_path } => BlobBackend::Local
+                .open(&self.data_dir)
+                .location(&stored_path.to_string_lossy()),
+            AttachmentKind::Blob { backend, key } => backend.open(&self.data_dir).location(key),
+            AttachmentKind::Link { url } => Ok(url.clone()),
+        }
+    }
+
+    // Hand the attachment to the platform's default opener
+    pub fn open(&self, attachment: &Attachment) -> Result<()> {
+        let location = self.location(attachment)?;
+
+        let status = if cfg!(target_os = "macos") {
+            Command::new("open").arg(&location).status()
+        } else if cfg!(target_os = "windows") {
+            Command::new("cmd")
+                .args(["/C", "start", "", &location])
+                .status()
+        } else {
+            Command::new("xdg-open").arg(&location).status()
+        }?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(TaskMasterError::InvalidOperation(format!(
+                "Failed to open attachment: {}",
+                location
+            )))
+        }
+    }
+}
+
+fn is_url(source: &str) -> bool {
+    ["http://", "https://", "file://", "mailto:"]
+        .iter()
+        .any(|scheme| source.starts_with(scheme))
+}
@@ -0,0 +1,174 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, Local, NaiveDate};
+
+use crate::error::{Result, TaskMasterError};
+use crate::planning::remaining_hours;
+use crate::project::Project;
+use crate::schedule::WorkCalendar;
+use crate::task::{Task, TaskStatus};
+use crate::task_dependencies::DependencyKind;
+
+// How far ahead to look for free capacity before giving up on a task
+const HORIZON_DAYS: i64 = 730;
+
+// Suggested dates for one unscheduled task
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    pub task_id: u32,
+    pub title: String,
+    pub start: NaiveDate,
+    pub due: NaiveDate,
+    // Remaining estimated work booked between the two
+    pub hours: f64,
+}
+
+// Start and due dates for every open task that has no due date yet, fitted around the work
+// already dated in the project
+#[derive(Debug, Clone)]
+pub struct AutoSchedule {
+    pub start: NaiveDate,
+    // In start order
+    pub proposals: Vec<Proposal>,
+    // Open tasks without an estimate, placed as taking no time
+    pub unestimated: Vec<u32>,
+    // Tasks that found no room within the horizon
+    pub unplaced: Vec<u32>,
+}
+
+impl AutoSchedule {
+    // Tasks are placed one at a time, highest priority first among those whose dependencies
+    // are already placed or dated, each as early as its dependencies allow and on the days
+    // with capacity left. Work on dated open tasks is booked on their due day, as on the
+    // planning board. Later proposals assume the earlier ones are accepted.
+    pub fn propose(project: &Project, start: NaiveDate, calendar: &WorkCalendar) -> Result<Self> {
+        if calendar.hours_per_day <= 0.0 {
+            return Err(TaskMasterError::InvalidOperation(
+                "Hours per day must be positive".to_string(),
+            ));
+        }
+        // Also rejects dependency cycles
+        project.get_task_execution_order()?;
+
+        let local_date = |at: chrono::DateTime<chrono::Utc>| at.with_timezone(&Local).date_naive();
+        let mut booked: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+        // First and last day of each open task with dates, scheduled or proposed
+        let mut windows: HashMap<u32, (NaiveDate, NaiveDate)> = HashMap::new();
+        let mut pending: Vec<&Task> = Vec::new();
+        for task in &project.tasks {
+            if task.status == TaskStatus::Done {
+                continue;
+            }
+            match task.due.map(local_date) {
+                Some(due) => {
+                    *booked.entry(due).or_default() += remaining_hours(task);
+                    let begin = task.planned_start.map(local_date).unwrap_or(due).min(due);
+                    windows.insert(task.id, (begin, due));
+                }
+                None => pending.push(task),
+            }
+        }
+
+        let scale = &project.settings.priority_scale;
+        let mut proposals = Vec::new();
+        let mut unestimated = Vec::new();
+        let mut unplaced = Vec::new();
+        while !pending.is_empty() {
+            // Dependencies on tasks still waiting to be placed hold a task back
+            let ready = pending
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| {
+                    task.dependency_ids()
+                        .all(|dep| !pending.iter().any(|other| other.id == dep))
+                })
+                .max_by_key(|(_, task)| (scale.weight(&task.priority), Reverse(task.rank.as_str())))
+                .map(|(index, _)| index);
+            // Unplaced dependencies count as ready so their dependents still get dates
+            let task = pending.remove(ready.unwrap_or(0));
+
+            let mut earliest = start;
+            let mut finish_no_earlier = start;
+            for dep in task.dependencies.iter().flatten() {
+                let Some(&(dep_start, dep_finish)) = windows.get(&dep.task) else {
+                    continue;
+                };
+                let lag_days = (dep.lag_minutes.unwrap_or(0).max(0) + 1439) / 1440;
+                let lag = Duration::days(lag_days);
+                match dep.kind {
+                    DependencyKind::FinishToStart => earliest = earliest.max(dep_finish + lag),
+                    DependencyKind::StartToStart => earliest = earliest.max(dep_start + lag),
+                    DependencyKind::FinishToFinish => {
+                        finish_no_earlier = finish_no_earlier.max(dep_finish + lag)
+                    }
+                    DependencyKind::StartToFinish => {
+                        finish_no_earlier = finish_no_earlier.max(dep_start + lag)
+                    }
+                }
+            }
+
+            let hours = remaining_hours(task);
+            if task.estimate_hours.is_none() {
+                unestimated.push(task.id);
+            }
+            match place(&mut booked, calendar, earliest, finish_no_earlier, hours) {
+                Some((first, last)) => {
+                    windows.insert(task.id, (first, last));
+                    proposals.push(Proposal {
+                        task_id: task.id,
+                        title: task.title.clone(),
+                        start: first,
+                        due: last,
+                        hours,
+                    });
+                }
+                None => unplaced.push(task.id),
+            }
+        }
+        proposals.sort_by_key(|p| (p.start, p.due, p.task_id));
+
+        Ok(AutoSchedule {
+            start,
+            proposals,
+            unestimated,
+            unplaced,
+        })
+    }
+}
+
+// Book `hours` of work on the working days from `earliest` with capacity left, returning the
+// first and last day used. The last day is moved out to `finish_no_earlier` if needed.
+fn place(
+    booked: &mut BTreeMap<NaiveDate, f64>,
+    calendar: &WorkCalendar,
+    earliest: NaiveDate,
+    finish_no_earlier: NaiveDate,
+    hours: f64,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let mut remaining = hours;
+    let mut first = None;
+    let mut day = earliest;
+    while day < earliest + Duration::days(HORIZON_DAYS) {
+        if calendar.is_working_day(day) {
+            let used = booked.entry(day).or_default();
+            let free = (calendar.hours_per_day - *used).max(0.0);
+            if remaining > 0.0 && free > 0.0 {
+                let take = free.min(remaining);
+                *used += take;
+                remaining -= take;
+                first.get_or_insert(day);
+            }
+            if remaining <= 1e-9 {
+                // Tasks with no work left start on the first working day they may
+                let first = first.unwrap_or(day);
+                return Some((
+                    first,
+                    calendar.first_working_day(day.max(finish_no_earlier)),
+                ));
+            }
+        }
+        day += Duration::days(1);
+    }
+    None
+}
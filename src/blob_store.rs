@@ -0,0 +1,406 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+
+// Local attachment files live in `<data-dir>/attachments`
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+// The deduplicating store keeps its blobs in `<data-dir>/blobs`
+pub const BLOBS_DIR: &str = "blobs";
+
+// Where attachment files are kept, set with `attachment_backend` in config.json
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BlobBackend {
+    // Copied under `<data-dir>/attachments/<project>/<task>/`
+    #[default]
+    Local,
+    // Stored once per distinct content under `<data-dir>/blobs/`, named by its SHA-256
+    Dedup,
+    // Uploaded to an S3 bucket through the `aws` command-line tool
+    S3 {
+        bucket: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+impl BlobBackend {
+    pub fn open<P: AsRef<Path>>(&self, data_dir: P) -> Box<dyn BlobStore> {
+        let data_dir = data_dir.as_ref();
+        match self {
+            BlobBackend::Local => Box::new(LocalStore {
+                root: data_dir.join(ATTACHMENTS_DIR),
+            }),
+            BlobBackend::Dedup => Box::new(DedupStore {
+                root: data_dir.join(BLOBS_DIR),
+            }),
+            BlobBackend::S3 { bucket, prefix } => Box::new(S3Store {
+                bucket: bucket.clone(),
+                prefix: prefix.clone(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for BlobBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlobBackend::Local => write!(f, "local"),
+            BlobBackend::Dedup => write!(f, "dedup"),
+            BlobBackend::S3 { bucket, prefix } => write!(f, "s3://{}/{}", bucket, prefix),
+        }
+    }
+}
+
+// A place attachment files can be put, found again by the key `put` returns
+pub trait BlobStore {
+    // Store a copy of `source`. `hint` is a `<project>/<task>/<file name>` path that
+    // stores may use to name the blob.
+    fn put(&self, source: &Path, hint: &Path) -> Result<String>;
+
+    fn delete(&self, key: &str) -> Result<()>;
+
+    // A path or URL the system opener can use
+    fn location(&self, key: &str) -> Result<String>;
+
+    // Every key currently in the store
+    fn keys(&self) -> Result<Vec<String>>;
+
+    // Whether one blob can back several attachments, so deleting an attachment must
+    // leave its blob for `prune`
+    fn shared(&self) -> bool {
+        false
+    }
+}
+
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl BlobStore for LocalStore {
+    fn put(&self, source: &Path, hint: &Path) -> Result<String> {
+        let dir = self.root.join(hint.parent().unwrap_or(Path::new("")));
+        let file_name = hint
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        fs::create_dir_all(&dir)?;
+        let target = unique_path(&dir, &file_name);
+        fs::copy(source, &target)?;
+
+        let stored_path = target
+            .strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .unwrap_or(target.clone());
+        Ok(stored_path.to_string_lossy().to_string())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let path = self.root.join(key);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        // Drop task and project directories left empty
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if d == self.root || fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+        Ok(())
+    }
+
+    fn location(&self, key: &str) -> Result<String> {
+        Ok(self.root.join(key).to_string_lossy().to_string())
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        if self.root.exists() {
+            walk(&self.root, &self.root, &mut keys)?;
+        }
+        Ok(keys)
+    }
+}
+
+pub struct DedupStore {
+    root: PathBuf,
+}
+
+impl DedupStore {
+    // Blobs are spread over subdirectories named by the first two hex digits
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key.get(..2).unwrap_or(key)).join(key)
+    }
+}
+
+impl BlobStore for DedupStore {
+    fn put(&self, source: &Path, hint: &Path) -> Result<String> {
+        let contents = fs::read(source)?;
+        // The extension is kept so the system opener still knows what the file is
+        let extension = hint
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let key = format!("{}{}", sha256_hex(&contents), extension);
+
+        let path = self.path(&key);
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, contents)?;
+        }
+        Ok(key)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path(key);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        if let Some(dir) = path.parent() {
+            let _ = fs::remove_dir(dir);
+        }
+        Ok(())
+    }
+
+    fn location(&self, key: &str) -> Result<String> {
+        Ok(self.path(key).to_string_lossy().to_string())
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+        if self.root.exists() {
+            walk(&self.root, &self.root, &mut paths)?;
+        }
+        Ok(paths
+            .iter()
+            .filter_map(|p| Path::new(p).file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect())
+    }
+
+    fn shared(&self) -> bool {
+        true
+    }
+}
+
+pub struct S3Store {
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Store {
+    fn url(&self, key: &str) -> String {
+        format!("s3://{}/{}{}", self.bucket, self.prefix, key)
+    }
+
+    fn aws(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("aws")
+            .arg("s3")
+            .args(args)
+            .output()
+            .map_err(|e| {
+                TaskMasterError::InvalidOperation(format!(
+                    "Could not run the aws command-line tool: {}",
+                    e
+                ))
+            })?;
+        if !output.status.success() {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "aws s3 {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl BlobStore for S3Store {
+    fn put(&self, source: &Path, hint: &Path) -> Result<String> {
+        // Prefix the file name with the upload time so attaching a file with the same name
+        // never replaces an earlier upload
+        let file_name = hint
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let key = hint
+            .with_file_name(format!("{}-{}", Utc::now().timestamp_millis(), file_name))
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        self.aws(&[
+            "cp",
+            "--only-show-errors",
+            &source.to_string_lossy(),
+            &self.url(&key),
+        ])?;
+        Ok(key)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.aws(&["rm", "--only-show-errors", &self.url(key)])?;
+        Ok(())
+    }
+
+    // A presigned HTTPS link, since openers don't understand s3:// URLs
+    fn location(&self, key: &str) -> Result<String> {
+        Ok(self.aws(&["presign", &self.url(key)])?.trim().to_string())
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        let listing = self.aws(&["ls", "--recursive", &self.url("")])?;
+        // Each line is "<date> <time> <size> <key>", with the key relative to the bucket
+        Ok(listing
+            .lines()
+            .filter_map(|line| {
+                let mut rest = line.trim_start();
+                for _ in 0..3 {
+                    let end = rest.find(char::is_whitespace)?;
+                    rest = rest[end..].trim_start();
+                }
+                rest.strip_prefix(self.prefix.as_str()).map(str::to_string)
+            })
+            .collect())
+    }
+}
+
+// Delete every blob in `store` whose key is not in `referenced`, returning the deleted keys.
+// With `dry_run` nothing is deleted.
+pub fn collect_garbage(
+    store: &dyn BlobStore,
+    referenced: &BTreeSet<String>,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let mut removed = Vec::new();
+    for key in store.keys()? {
+        if referenced.contains(&key) {
+            continue;
+        }
+        if !dry_run {
+            store.delete(&key)?;
+        }
+        removed.push(key);
+    }
+    Ok(removed)
+}
+
+// Avoid overwriting an existing attachment with the same file name
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut counter = 1;
+    loop {
+        let candidate = dir.join(format!("{}-{}{}", stem, counter, extension));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+// Collect the files under `dir` as paths relative to `root`
+fn walk(root: &Path, dir: &Path, out: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, out)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            out.push(relative.to_string_lossy().to_string());
+        }
+    }
+    Ok(())
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// SHA-256 of `data` as lowercase hex, the name of a blob in the deduplicating store
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut message = data.to_vec();
+    let bit_len = (data.len() as u64).wrapping_mul(8);
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&bit_len.to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (value, add) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    h.iter().map(|v| format!("{:08x}", v)).collect()
+}
@@ -0,0 +1,310 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Timelike, Utc};
+
+use crate::error::{Result, TaskMasterError};
+use crate::locale;
+use crate::project::Project;
+use crate::report::ReportTask;
+use crate::task::{TaskPriority, TaskStatus};
+
+// Spoken lists stop after this many tasks and give a count of the rest
+const MAX_SPOKEN: usize = 5;
+
+// A task in the briefing, with the project it belongs to
+#[derive(Debug, Clone)]
+pub struct BriefItem {
+    pub project: String,
+    pub task: ReportTask,
+    // High priority or above
+    pub urgent: bool,
+}
+
+// Today's agenda, what is overdue, and what got done yesterday, across projects
+#[derive(Debug, Clone)]
+pub struct Briefing {
+    pub today: NaiveDate,
+    pub generated: DateTime<Utc>,
+    // Open tasks due today, soonest first; missed habits come round again here too
+    pub agenda: Vec<BriefItem>,
+    // Open tasks due before today, most overdue first
+    pub overdue: Vec<BriefItem>,
+    // In the order they were finished
+    pub completed_yesterday: Vec<BriefItem>,
+}
+
+impl Briefing {
+    pub fn build(projects: &[Project], now: DateTime<Utc>) -> Self {
+        let today = now.with_timezone(&Local).date_naive();
+        let yesterday = today - Duration::days(1);
+        let local_date = |at: DateTime<Utc>| at.with_timezone(&Local).date_naive();
+
+        let mut agenda = Vec::new();
+        let mut overdue = Vec::new();
+        let mut completed_yesterday = Vec::new();
+        for project in projects {
+            let scale = &project.settings.priority_scale;
+            for task in &project.tasks {
+                let item = BriefItem {
+                    project: project.label(),
+                    task: ReportTask::of(project, task),
+                    urgent: scale.weight(&task.priority) >= scale.weight(&TaskPriority::High),
+                };
+                if task.status == TaskStatus::Done {
+                    if task.completed_at().map(local_date) == Some(yesterday) {
+                        completed_yesterday.push(item);
+                    }
+                    continue;
+                }
+                match task.due.map(local_date) {
+                    Some(date) if date == today => agenda.push(item),
+                    Some(date) if date < today && task.habit.is_some() => agenda.push(item),
+                    Some(date) if date < today => overdue.push(item),
+                    _ => {}
+                }
+            }
+        }
+        agenda.sort_by_key(|item| item.task.due);
+        overdue.sort_by_key(|item| item.task.due);
+        completed_yesterday.sort_by_key(|item| item.task.completed);
+
+        Briefing {
+            today,
+            generated: now,
+            agenda,
+            overdue,
+            completed_yesterday,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agenda.is_empty() && self.overdue.is_empty() && self.completed_yesterday.is_empty()
+    }
+
+    // For reading on screen
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Briefing for {}\n", locale::weekday_date(self.today));
+        if self.is_empty() {
+            text.push_str("\nNothing due, nothing overdue, and nothing finished yesterday\n");
+            return text;
+        }
+
+        let sections = [
+            ("Due today", &self.agenda),
+            ("Overdue", &self.overdue),
+            ("Done yesterday", &self.completed_yesterday),
+        ];
+        for (heading, items) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            text.push_str(&format!("\n{} ({}):\n", heading, items.len()));
+            for item in items {
+                let mut details = vec![item.task.priority.clone()];
+                if let Some(due) = item.task.due {
+                    let due = due.with_timezone(&Local);
+                    if due.date_naive() == self.today {
+                        details.push(locale::time(due.time()));
+                    } else {
+                        details.push(format!("due {}", locale::date(due.date_naive())));
+                    }
+                }
+                text.push_str(&format!(
+                    "  - [{}] {} {} ({})\n",
+                    item.project,
+                    item.task.id,
+                    item.task.title,
+                    details.join(", ")
+                ));
+            }
+        }
+        text
+    }
+
+    // Plain sentences for a text-to-speech engine: no IDs, symbols or tables, short lists,
+    // and times said the way people say them
+    pub fn to_speech(&self) -> String {
+        let hour = self.generated.with_timezone(&Local).hour();
+        let greeting = match hour {
+            0..=11 => "Good morning.",
+            12..=17 => "Good afternoon.",
+            _ => "Good evening.",
+        };
+        let mut sentences = vec![
+            greeting.to_string(),
+            format!(
+                "Here is your briefing for {}.",
+                self.today.format("%A, %B %-d")
+            ),
+        ];
+
+        if self.agenda.is_empty() {
+            sentences.push("Nothing is due today.".to_string());
+        } else {
+            let spoken: Vec<String> = self
+                .agenda
+                .iter()
+                .map(|item| {
+                    let mut phrase = speakable(&item.task.title);
+                    if let Some(due) = item.task.due {
+                        let due = due.with_timezone(&Local);
+                        if due.date_naive() == self.today && (due.hour(), due.minute()) != (0, 0) {
+                            phrase.push_str(&format!(
+                                " at {}",
+                                spoken_time(due.hour(), due.minute())
+                            ));
+                        }
+                    }
+                    if item.urgent {
+                        phrase.push_str(", which is high priority");
+                    }
+                    phrase
+                })
+                .collect();
+            sentences.push(format!(
+                "You have {} due today: {}.",
+                count(self.agenda.len(), "task"),
+                spoken_list(&spoken)
+            ));
+        }
+
+        if !self.overdue.is_empty() {
+            let spoken: Vec<String> = self
+                .overdue
+                .iter()
+                .map(|item| {
+                    let mut phrase = speakable(&item.task.title);
+                    if let Some(due) = item.task.due {
+                        let days = (self.today - due.with_timezone(&Local).date_naive()).num_days();
+                        phrase.push_str(&match days {
+                            1 => ", due yesterday".to_string(),
+                            days => format!(", due {} days ago", days),
+                        });
+                    }
+                    phrase
+                })
+                .collect();
+            let verb = if self.overdue.len() == 1 { "is" } else { "are" };
+            sentences.push(format!(
+                "{} {} overdue: {}.",
+                capitalize(&count(self.overdue.len(), "task")),
+                verb,
+                spoken_list(&spoken)
+            ));
+        }
+
+        if self.completed_yesterday.is_empty() {
+            sentences.push("Nothing was finished yesterday.".to_string());
+        } else {
+            let spoken: Vec<String> = self
+                .completed_yesterday
+                .iter()
+                .map(|item| speakable(&item.task.title))
+                .collect();
+            sentences.push(format!(
+                "Yesterday you finished {}: {}.",
+                count(self.completed_yesterday.len(), "task"),
+                spoken_list(&spoken)
+            ));
+        }
+
+        format!("{}\n", sentences.join(" "))
+    }
+}
+
+// Pipe `text` into a speech command such as "say", "espeak" or "piper --output-raw",
+// which reads it from standard input
+pub fn speak(command: &str, text: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| {
+        TaskMasterError::InvalidOperation("The speech command is empty".to_string())
+    })?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            TaskMasterError::InvalidOperation(format!(
+                "Could not run speech command '{}': {}",
+                program, e
+            ))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Speech command '{}' exited with {}",
+            command, status
+        )));
+    }
+    Ok(())
+}
+
+// "1 task", "3 tasks"
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// "A", "A and B", "A, B and C", "A, B, C, D, E and 2 more"
+fn spoken_list(items: &[String]) -> String {
+    let (shown, rest) = items.split_at(items.len().min(MAX_SPOKEN));
+    let mut parts: Vec<String> = shown.to_vec();
+    if !rest.is_empty() {
+        parts.push(format!("{} more", rest.len()));
+    }
+    match parts.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, others)) => format!("{} and {}", others.join(", "), last),
+        None => String::new(),
+    }
+}
+
+// "9 AM", "5:30 PM", "noon"
+fn spoken_time(hour: u32, minute: u32) -> String {
+    if (hour, minute) == (12, 0) {
+        return "noon".to_string();
+    }
+    let suffix = if hour < 12 { "AM" } else { "PM" };
+    let hour = match hour % 12 {
+        0 => 12,
+        h => h,
+    };
+    if minute == 0 {
+        format!("{} {}", hour, suffix)
+    } else {
+        format!("{}:{:02} {}", hour, minute, suffix)
+    }
+}
+
+// Drop characters speech engines read out literally, such as underscores and hashes
+fn speakable(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| {
+            if matches!(c, '_' | '*' | '#' | '`' | '~' | '|') {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+use crate::fields;
+use crate::project::Project;
+use crate::task::{Task, TaskPriority, TaskStatus};
+
+// One "key=value" condition of a bulk filter
+#[derive(Debug, Clone)]
+enum Condition {
+    Status(TaskStatus),
+    Priority(TaskPriority),
+    Tag(String),
+    // None matches tasks without a milestone
+    Milestone(Option<u32>),
+    Field(String, String),
+}
+
+// Which tasks a bulk change applies to; every condition must hold, so an empty filter
+// matches all tasks
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    conditions: Vec<Condition>,
+}
+
+impl TaskFilter {
+    // Parse "status=todo", "priority=high", "tag=docs", "milestone=<ID, name or none>" or
+    // "<custom field>=<value>", resolving statuses and priorities against the project's
+    // workflow and scale
+    pub fn parse(project: &Project, conditions: &[String]) -> Result<Self> {
+        let conditions = conditions
+            .iter()
+            .map(|condition| {
+                let (key, value) = fields::parse_assignment(condition)?;
+                Ok(match key.to_lowercase().as_str() {
+                    "status" => Condition::Status(project.settings.workflow.parse_status(&value)?),
+                    "priority" => {
+                        Condition::Priority(project.settings.priority_scale.parse(&value)?)
+                    }
+                    "tag" => Condition::Tag(value),
+                    "milestone" => Condition::Milestone(milestone_arg(project, &value)?),
+                    _ => Condition::Field(key, value),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(TaskFilter { conditions })
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            Condition::Status(status) => &task.status == status,
+            Condition::Priority(priority) => &task.priority == priority,
+            Condition::Tag(tag) => task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Condition::Milestone(milestone) => &task.milestone == milestone,
+            Condition::Field(name, value) => task.field_matches(name, value),
+        })
+    }
+}
+
+// The edits a bulk update makes to each matching task
+#[derive(Debug, Clone, Default)]
+pub struct TaskChange {
+    pub status: Option<TaskStatus>,
+    pub priority: Option<TaskPriority>,
+    // Some(None) clears the due date
+    pub due: Option<Option<DateTime<Utc>>>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    // Some(None) takes tasks out of their milestone
+    pub milestone: Option<Option<u32>>,
+    // A None value clears the field
+    pub fields: Vec<(String, Option<String>)>,
+}
+
+impl TaskChange {
+    // Parse "status=done", "priority=high", "due=friday" (or "due=none"), "tag=docs" to add
+    // a tag, "untag=docs" to remove one, "milestone=<ID, name or none>", or
+    // "<custom field>=<value>" ("<field>=" clears it)
+    pub fn parse(project: &Project, assignments: &[String]) -> Result<Self> {
+        let mut change = TaskChange::default();
+        for assignment in assignments {
+            let (key, value) = fields::parse_assignment(assignment)?;
+            match key.to_lowercase().as_str() {
+                "status" => change.status = Some(project.settings.workflow.parse_status(&value)?),
+                "priority" => {
+                    change.priority = Some(project.settings.priority_scale.parse(&value)?)
+                }
+                "due" => change.due = Some(dates::parse_due(&value)?),
+                "tag" => change.add_tags.push(value),
+                "untag" => change.remove_tags.push(value),
+                "milestone" => change.milestone = Some(milestone_arg(project, &value)?),
+                _ => change
+                    .fields
+                    .push((key, Some(value).filter(|v| !v.is_empty()))),
+            }
+        }
+        if change.is_empty() {
+            return Err(TaskMasterError::InvalidOperation(
+                "Nothing to change; give at least one key=value to set".to_string(),
+            ));
+        }
+        Ok(change)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.priority.is_none()
+            && self.due.is_none()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+            && self.milestone.is_none()
+            && self.fields.is_empty()
+    }
+}
+
+// A milestone given by ID or name, or "none"
+fn milestone_arg(project: &Project, value: &str) -> Result<Option<u32>> {
+    if value.eq_ignore_ascii_case("none") {
+        Ok(None)
+    } else {
+        project.find_milestone(value).map(|m| Some(m.id))
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Task counts at the end of one local day, rebuilt from creation times and status history
+#[derive(Debug, Clone, Serialize)]
+pub struct BurndownDay {
+    pub date: NaiveDate,
+    // Tasks that existed and were not Done at the end of the day
+    pub open: usize,
+    // Tasks that existed and were Done at the end of the day
+    pub done: usize,
+    // Moves into Done during the day
+    pub closed: usize,
+}
+
+// One row per day for the `days` days ending today
+pub fn burndown(project: &Project, days: u32, today: NaiveDate) -> Vec<BurndownDay> {
+    (0..days)
+        .rev()
+        .map(|back| {
+            let date = today - Duration::days(back as i64);
+            let start = start_of_day(date);
+            let end = start_of_day(date + Duration::days(1));
+
+            let mut day = BurndownDay {
+                date,
+                open: 0,
+                done: 0,
+                closed: 0,
+            };
+            for task in &project.tasks {
+                day.closed += task
+                    .history
+                    .iter()
+                    .filter(|c| c.to == TaskStatus::Done && c.changed_at >= start)
+                    .filter(|c| c.changed_at < end)
+                    .count();
+                if task.created_at >= end {
+                    continue;
+                }
+                if status_at(task, end) == TaskStatus::Done {
+                    day.done += 1;
+                } else {
+                    day.open += 1;
+                }
+            }
+            day
+        })
+        .collect()
+}
+
+// The status a task had just before `time`: the target of the last change before it, or
+// the status it started in
+fn status_at(task: &Task, time: DateTime<Utc>) -> TaskStatus {
+    match task.history.iter().rev().find(|c| c.changed_at < time) {
+        Some(change) => change.to.clone(),
+        None => task
+            .history
+            .first()
+            .map_or_else(|| task.status.clone(), |c| c.from.clone()),
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local.from_local_datetime(&midnight).earliest().map_or_else(
+        || Utc.from_utc_datetime(&midnight),
+        |t| t.with_timezone(&Utc),
+    )
+}
+
+// Open-task trend as a row of block characters, scaled to the busiest day
+pub fn sparkline(days: &[BurndownDay]) -> String {
+    let max = days.iter().map(|d| d.open).max().unwrap_or(0);
+    days.iter()
+        .map(|d| {
+            let bar = (d.open * (SPARK_BARS.len() - 1))
+                .checked_div(max)
+                .unwrap_or(0);
+            SPARK_BARS[bar]
+        })
+        .collect()
+}
+
+pub fn to_csv(days: &[BurndownDay]) -> String {
+    let mut csv = String::from("date,open,done,closed\n");
+    for day in days {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            day.date, day.open, day.done, day.closed
+        ));
+    }
+    csv
+}
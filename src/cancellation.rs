@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, TaskMasterError};
+
+// How often `sleep` looks at the flag
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Handed to every job so synchronous handlers can stop early: TaskExecutor raises it when
+// the task is cancelled or times out, and long-running handlers poll it between steps.
+// Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationFlag {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationFlag {
+    pub fn new() -> Self {
+        CancellationFlag::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    // An error to return with `?` once the flag is raised
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(TaskMasterError::InvalidOperation("Cancelled".to_string()));
+        }
+        Ok(())
+    }
+
+    // Sleep for `duration`, waking early with an error if the flag is raised meanwhile
+    pub fn sleep(&self, duration: Duration) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.check()?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            thread::sleep(remaining.min(POLL_INTERVAL));
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::project::Project;
+use crate::storage::Storage;
+use crate::task::Task;
+
+// Fault-injection knobs, read from the `chaos` section of the config file
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ChaosConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // Probability (0.0 - 1.0) that a task handler fails
+    #[serde(default)]
+    pub handler_failure_rate: f64,
+    // Upper bound for the random delay added before each handler runs
+    #[serde(default)]
+    pub max_delay_ms: u64,
+    // Probability (0.0 - 1.0) that a storage operation fails
+    #[serde(default)]
+    pub storage_failure_rate: f64,
+    // Seed for the random generator so chaos runs are reproducible
+    #[serde(default)]
+    pub seed: u64,
+}
+
+// Decides when to inject faults, shared between executors and storage
+pub struct ChaosMonkey {
+    config: ChaosConfig,
+    state: Mutex<u64>,
+}
+
+impl ChaosMonkey {
+    pub fn new(config: ChaosConfig) -> Self {
+        // xorshift must never be seeded with zero
+        let seed = if config.seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            config.seed
+        };
+
+        ChaosMonkey {
+            config,
+            state: Mutex::new(seed),
+        }
+    }
+
+    pub fn shared(config: ChaosConfig) -> Arc<Self> {
+        Arc::new(ChaosMonkey::new(config))
+    }
+
+    // Uniform value in [0, 1) from an xorshift64* generator
+    fn next_f64(&self) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        let mut x = *state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        *state = x;
+        let value = x.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn roll(&self, rate: f64) -> bool {
+        self.config.enabled && rate > 0.0 && self.next_f64() < rate
+    }
+
+    pub fn random_delay(&self) -> Duration {
+        if !self.config.enabled || self.config.max_delay_ms == 0 {
+            return Duration::ZERO;
+        }
+        let millis = (self.next_f64() * self.config.max_delay_ms as f64) as u64;
+        Duration::from_millis(millis)
+    }
+
+    // Called by synchronous handlers before doing their real work
+    pub fn before_handler(&self, task_id: u32) -> Result<()> {
+        let delay = self.random_delay();
+        if !delay.is_zero() {
+            crate::log_event!(Warn, "CHAOS: delaying task {} by {:?}", task_id, delay);
+            thread::sleep(delay);
+        }
+        self.maybe_fail_handler(task_id)
+    }
+
+    pub fn maybe_fail_handler(&self, task_id: u32) -> Result<()> {
+        if self.roll(self.config.handler_failure_rate) {
+            crate::log_event!(Warn, "CHAOS: injecting failure into task {}", task_id);
+            Err(TaskMasterError::InvalidOperation(format!(
+                "Injected chaos failure for task {}",
+                task_id
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn maybe_fail_storage(&self, operation: &str) -> Result<()> {
+        if self.roll(self.config.storage_failure_rate) {
+            crate::log_event!(Warn, "CHAOS: injecting storage failure into {}", operation);
+            Err(TaskMasterError::StorageError(format!(
+                "Injected chaos failure during {}",
+                operation
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Storage decorator that randomly fails operations before delegating
+pub struct ChaosStorage<S: Storage> {
+    inner: S,
+    chaos: Arc<ChaosMonkey>,
+}
+
+impl<S: Storage> ChaosStorage<S> {
+    pub fn new(inner: S, chaos: Arc<ChaosMonkey>) -> Self {
+        ChaosStorage { inner, chaos }
+    }
+}
+
+impl<S: Storage> Storage for ChaosStorage<S> {
+    fn save_project(&mut self, project: &Project) -> Result<()> {
+        self.chaos.maybe_fail_storage("save_project")?;
+        self.inner.save_project(project)
+    }
+
+    fn load_project(&self, id: u32) -> Result<Project> {
+        self.chaos.maybe_fail_storage("load_project")?;
+        self.inner.load_project(id)
+    }
+
+    fn list_projects(&self) -> Result<Vec<Project>> {
+        self.chaos.maybe_fail_storage("list_projects")?;
+        self.inner.list_projects()
+    }
+
+    fn delete_project(&mut self, id: u32) -> Result<()> {
+        self.chaos.maybe_fail_storage("delete_project")?;
+        self.inner.delete_project(id)
+    }
+
+    fn save_task(&self, project_id: u32, task: &Task) -> Result<()> {
+        self.chaos.maybe_fail_storage("save_task")?;
+        self.inner.save_task(project_id, task)
+    }
+
+    fn load_task(&self, project_id: u32, task_id: u32) -> Result<Task> {
+        self.chaos.maybe_fail_storage("load_task")?;
+        self.inner.load_task(project_id, task_id)
+    }
+
+    fn delete_task(&self, project_id: u32, task_id: u32) -> Result<()> {
+        self.chaos.maybe_fail_storage("delete_task")?;
+        self.inner.delete_task(project_id, task_id)
+    }
+}
@@ -1,11 +1,79 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, CommandFactory, ErrorKind, Parser, Subcommand, ValueEnum};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::error::Result;
+use chrono::{DateTime, Local, Utc};
+use tokio::sync::mpsc;
+
+use crate::appearance::{self, ProjectColor};
+use crate::async_executor::TaskEvent;
+use crate::attachments::AttachmentStore;
+use crate::autoschedule::AutoSchedule;
+use crate::blob_store::{self, BlobBackend};
+use crate::brief::{self, Briefing};
+use crate::bulk::{TaskChange, TaskFilter};
+use crate::burndown;
+#[cfg(feature = "chaos")]
+use crate::chaos::{ChaosMonkey, ChaosStorage};
+use crate::completions;
+use crate::config::Config;
+use crate::dates;
+use crate::dedupe;
+use crate::diff::{self, ProjectDiff};
+use crate::editing;
+use crate::error::{self, Result, TaskMasterError};
+use crate::estimation::{self, CalibrationReport};
+use crate::event_log::EventLog;
+use crate::export;
+use crate::fields::{self, FieldDefinition, FieldKind};
 use crate::file_storage::FileStorage;
+use crate::follow_ups::FollowUpRule;
+use crate::goals::{GoalProgress, GoalTarget, Goals, Quarter};
+use crate::graph;
+use crate::habits;
+use crate::hierarchy;
+use crate::import;
+use crate::locale;
+use crate::logs;
+use crate::notification::NotificationSystem;
+use crate::output::{self, ProjectListing, ProjectRecord, SearchHit, TaskRecord};
+use crate::periodic_tasks::RecurrencePattern;
+use crate::planner::Planner;
+use crate::priority::PriorityScale;
 use crate::project::Project;
+use crate::prompting;
+use crate::query::{self, Query};
+use crate::ready_queue::{CriticalPathFirst, PriorityFirst, ReadyQueuePolicy, ShortestJobFirst};
+use crate::recurring_projects::RecurringProjects;
+use crate::reminders;
+use crate::report::ProjectReport;
+use crate::report_plugins;
+use crate::review::ReviewQueue;
+use crate::routing::{self, Capture, RoutingRule};
+use crate::schedule::{DatedSchedule, ImpactReport, Scenario, WorkCalendar};
+use crate::split::{self, SplitPlan, SplitStrategy};
+use crate::stats::ProjectStats;
 use crate::storage::Storage;
+use crate::summary::{self, SummaryCache};
+use crate::table::{Cell, Table};
 use crate::task::{Task, TaskPriority, TaskStatus};
+use crate::task_dependencies::{self, Dependency, DependencyKind, OrphanPolicy, Propagation};
+use crate::task_executor::TaskExecutor;
+use crate::task_handler::{SimulatedTaskHandler, TaskHandlerRegistry};
+use crate::templates::{DependencyRule, ProjectTemplateLibrary, TaskTemplate, TemplateLibrary};
+use crate::timesheet::{Rounding, Timesheet};
+use crate::triage::{self, TriageVotes, Vote};
+use crate::urgency;
+use crate::validation::ValidationRule;
+use crate::vault::{self, VaultState};
+use crate::watch;
+use crate::worker_pool::JobResult;
+use crate::workflow::Workflow;
 
 #[derive(Parser)]
 #[clap(author, version, about = "TaskMaster - A task management system")]
@@ -15,40 +83,272 @@ pub struct Cli {
 
     #[clap(long, default_value = "./data", help = "Path to data directory")]
     data_dir: PathBuf,
+
+    #[clap(
+        long = "output",
+        value_enum,
+        default_value = "table",
+        help = "How list-projects, show-project, show-task and search print: table, json or yaml"
+    )]
+    output_style: OutputStyle,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Fail on missing arguments instead of asking for them"
+    )]
+    no_prompt: bool,
+
+    #[clap(long, global = true, help = "Print listings without colors")]
+    no_color: bool,
 }
 
-#[derive(ValueEnum, Clone, Debug)]
-enum CliTaskStatus {
-    Todo,
-    InProgress,
-    Done,
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputStyle {
+    // The human-readable listing
+    Table,
+    Json,
+    Yaml,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
-enum CliTaskPriority {
-    Low,
-    Medium,
-    High,
+enum ReplaySink {
+    Log,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    Csv,
+    Md,
+    Ics,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ImportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    Md,
+    Html,
+    // Report scripts only
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TimesheetFormat {
+    Csv,
+    // A printable page, to save as PDF from the browser
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum TaskSort {
+    // The project's manual order
+    Manual,
+    Id,
+    Priority,
+    // Soonest first, tasks without a due date last
+    Due,
+    Title,
+    // Computed urgency score, configured under `urgency` in config.json
+    Urgency,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RunPolicy {
+    Priority,
+    Shortest,
+    CriticalPath,
+}
+
+#[derive(Subcommand)]
+enum RankAction {
+    /// Move a task to just before or after another, or to the top or bottom
+    #[clap(group(ArgGroup::new("place").required(true).args(&["before", "after", "top", "bottom"])))]
+    Move {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(long, help = "Put it just before this task")]
+        before: Option<u32>,
+
+        #[clap(long, help = "Put it just after this task")]
+        after: Option<u32>,
+
+        #[clap(long, help = "Put it first")]
+        top: bool,
+
+        #[clap(long, help = "Put it last")]
+        bottom: bool,
+    },
+
+    /// List a project's tasks in manual order with their ranks
+    List {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+    },
+
+    /// Spread a project's ranks out evenly again, keeping the order
+    Rebalance {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+    },
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Create a new project
+    #[clap(allow_missing_positional = true)]
     CreateProject {
-        #[clap(help = "Project ID")]
-        id: u32,
+        #[clap(help = "Project ID (next free ID if omitted)")]
+        id: Option<u32>,
 
         #[clap(help = "Project name")]
         name: String,
+
+        #[clap(long, help = "Parent project (workspace) to create it under")]
+        parent: Option<u32>,
     },
 
     /// List all projects
-    ListProjects,
+    ListProjects {
+        #[clap(long, help = "Include archived projects")]
+        all: bool,
+
+        #[clap(long, help = "Show projects nested under their workspaces")]
+        tree: bool,
+    },
+
+    /// Move a project under a parent project (workspace), or back to the top level
+    SetParent {
+        #[clap(help = "Project ID")]
+        id: u32,
+
+        #[clap(help = "Parent project ID, or none for the top level")]
+        parent: String,
+    },
 
     /// Show project details
     ShowProject {
         #[clap(help = "Project ID")]
         id: u32,
+
+        #[clap(long, help = "List tasks from most to least urgent priority")]
+        by_priority: bool,
+
+        #[clap(
+            long,
+            value_enum,
+            help = "Order tasks by manual order, ID, priority, due date, title or urgency score"
+        )]
+        sort: Option<TaskSort>,
+
+        #[clap(
+            long = "field",
+            help = "Only tasks whose custom field has this value, as key=value (repeatable)"
+        )]
+        fields: Vec<String>,
+
+        #[clap(long, help = "Only tasks with this status")]
+        status: Option<String>,
+
+        #[clap(long, help = "Only tasks with this priority")]
+        priority: Option<String>,
+
+        #[clap(long = "tag", help = "Only tasks with this tag (repeatable)")]
+        tags: Vec<String>,
+
+        #[clap(long, help = "Only unfinished tasks past their due date")]
+        overdue: bool,
+
+        #[clap(long, help = "Show at most this many tasks")]
+        limit: Option<usize>,
+    },
+
+    /// Update a project's name, description, owner or deadline
+    UpdateProject {
+        #[clap(help = "Project ID")]
+        id: u32,
+
+        #[clap(long, help = "New project name")]
+        name: Option<String>,
+
+        #[clap(long, help = "Project description")]
+        description: Option<String>,
+
+        #[clap(long, help = "Project owner (empty to clear)")]
+        owner: Option<String>,
+
+        #[clap(
+            long,
+            help = "Deadline (same forms as add-task's --due), or none to clear it"
+        )]
+        deadline: Option<String>,
+
+        #[clap(
+            long,
+            help = "Color shown in listings: red, green, yellow, blue, magenta, cyan, white or gray, or none to clear it"
+        )]
+        color: Option<String>,
+
+        #[clap(
+            long,
+            help = "Short icon or emoji shown before the name, or none to clear it"
+        )]
+        icon: Option<String>,
+    },
+
+    /// Show a single task with its timestamps and status history
+    ShowTask {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        id: u32,
+    },
+
+    /// Search tasks across all projects, e.g. "status:todo priority:high due.before:2024-07-01 tag:backend login"
+    Search {
+        #[clap(
+            required = true,
+            allow_hyphen_values = true,
+            help = "Terms that must all match: status:, priority:, tag:, field:name=value, project:, milestone:, due:none|any|overdue, due.before:, due.after:, created.before:/after:, updated.before:/after:, -term to negate, and plain words or \"quoted phrases\" to find in titles"
+        )]
+        query: Vec<String>,
+
+        #[clap(long, help = "Include archived projects")]
+        all: bool,
+    },
+
+    /// List the tasks that can be worked on now across all projects, most urgent first
+    Next {
+        #[clap(long, default_value = "10", help = "Show at most this many tasks")]
+        limit: usize,
+
+        #[clap(long, help = "Include archived projects")]
+        all: bool,
     },
 
     /// Delete a project
@@ -62,17 +362,27 @@ enum Commands {
         #[clap(help = "Project ID")]
         project_id: u32,
 
-        #[clap(help = "Task ID")]
-        id: u32,
+        // The task ID comes before the title and may be left out, which clap can't express
+        // with further optional arguments after the title; see `split_task_args`
+        #[clap(
+            required = true,
+            max_values = 4,
+            value_name = "[ID] TITLE [STATUS] [PRIORITY]",
+            help = "Task ID (next free ID in the project if omitted), title, status (the workflow's initial state if omitted) and priority (Low/Medium/High or a level on the project scale; the project's default if omitted)"
+        )]
+        args: Vec<String>,
 
-        #[clap(help = "Task title")]
-        title: String,
+        #[clap(long, help = "Estimated effort in hours")]
+        estimate: Option<f64>,
 
-        #[clap(value_enum, default_value_t = CliTaskStatus::Todo, help = "Task status")]
-        status: CliTaskStatus,
+        #[clap(
+            long,
+            help = "Due date, e.g. 2024-06-01, \"2024-06-01 17:00\", tomorrow, \"in 3 days\" or \"next friday\""
+        )]
+        due: Option<String>,
 
-        #[clap(value_enum, default_value_t = CliTaskPriority::Medium, help = "Task priority")]
-        priority: CliTaskPriority,
+        #[clap(long = "field", help = "Custom field as key=value (repeatable)")]
+        fields: Vec<String>,
     },
 
     /// Update a task
@@ -86,11 +396,50 @@ enum Commands {
         #[clap(help = "New task title")]
         title: String,
 
-        #[clap(value_enum, help = "New task status")]
-        status: CliTaskStatus,
+        #[clap(help = "New task status (any state in the project workflow)")]
+        status: String,
+
+        #[clap(help = "New task priority (Low/Medium/High or a level on the project scale)")]
+        priority: String,
+
+        #[clap(
+            long,
+            help = "New due date (same forms as add-task), or none to clear it"
+        )]
+        due: Option<String>,
+
+        #[clap(
+            long = "field",
+            help = "Set a custom field as key=value, or clear it with key= (repeatable)"
+        )]
+        fields: Vec<String>,
+    },
+
+    /// Mark a task as done
+    Done {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        id: u32,
+    },
+
+    /// Mark a task as in progress
+    Start {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        id: u32,
+    },
+
+    /// Move a finished task back to the workflow's initial status
+    Reopen {
+        #[clap(help = "Project ID")]
+        project_id: u32,
 
-        #[clap(value_enum, help = "New task priority")]
-        priority: CliTaskPriority,
+        #[clap(help = "Task ID")]
+        id: u32,
     },
 
     /// Delete a task
@@ -100,135 +449,5209 @@ enum Commands {
 
         #[clap(help = "Task ID")]
         id: u32,
+
+        #[clap(
+            long,
+            conflicts_with = "transfer",
+            help = "Delete even if other tasks depend on it, dropping their dependency on it"
+        )]
+        force: bool,
+
+        #[clap(
+            long,
+            conflicts_with = "force",
+            help = "Delete even if other tasks depend on it; they take over its dependencies"
+        )]
+        transfer: bool,
     },
-}
 
-pub fn run_cli() -> Result<()> {
-    let cli = Cli::parse();
-    let mut storage = FileStorage::new(&cli.data_dir)?;
+    /// Change every task in a project that matches a filter
+    Bulk {
+        #[clap(long = "project", help = "Project ID")]
+        project_id: u32,
 
-    match &cli.command {
-        Commands::CreateProject { id, name } => {
-            let project = Project::new(*id, name.clone());
-            storage.save_project(&project)?;
-            println!("Project created: {} (ID: {})", name, id);
-        }
+        #[clap(
+            long = "where",
+            help = "Only tasks where key=value, for status, priority, tag or a custom field (repeatable; all must match)"
+        )]
+        conditions: Vec<String>,
 
-        Commands::ListProjects => {
-            let projects = storage.list_projects()?;
-            if projects.is_empty() {
-                println!("No projects found");
-            } else {
-                println!("Projects:");
-                for project in projects {
-                    println!("  ID: {}, Name: {}", project.id, project.name);
-                }
-            }
-        }
+        #[clap(
+            long = "set",
+            required = true,
+            help = "Change to make as key=value: status, priority, due, tag (adds), untag (removes) or a custom field (repeatable)"
+        )]
+        assignments: Vec<String>,
 
-        Commands::ShowProject { id } => match storage.load_project(*id) {
-            Ok(project) => {
-                println!("Project: {} (ID: {})", project.name, project.id);
-                if project.tasks.is_empty() {
-                    println!("  No tasks");
-                } else {
-                    println!("  Tasks:");
-                    for task in &project.tasks {
-                        println!(
-                            "    ID: {}, Title: {}, Status: {:?}, Priority: {:?}",
-                            task.id, task.title, task.status, task.priority
-                        );
-                    }
-                }
-            }
-            Err(e) => println!("Error: {}", e),
-        },
+        #[clap(long, help = "List the matching tasks without changing them")]
+        dry_run: bool,
+    },
 
-        Commands::DeleteProject { id } => match storage.delete_project(*id) {
-            Ok(_) => println!("Project deleted: {}", id),
-            Err(e) => println!("Error: {}", e),
-        },
+    /// Show or change a project's defaults for new tasks
+    ProjectSettings {
+        #[clap(help = "Project ID")]
+        project_id: u32,
 
-        Commands::AddTask {
-            project_id,
-            id,
-            title,
-            status,
-            priority,
-        } => {
-            // Convert the CLI enums to our internal types
-            let task_status = cli_status_to_task_status(status);
-            let task_priority = cli_priority_to_task_priority(priority);
+        #[clap(long, help = "Priority for new tasks that don't give one, or none")]
+        default_priority: Option<String>,
 
-            // Create the task
-            let task = Task::new(*id, title.clone(), task_status, task_priority);
+        #[clap(
+            long,
+            help = "Prefix shown before task IDs, e.g. WEB- (empty to clear)"
+        )]
+        id_prefix: Option<String>,
 
-            // Load the project, add the task, and save it back
-            match storage.load_project(*project_id) {
-                Ok(mut project) => {
-                    project.add_task(task);
-                    storage.save_project(&project)?;
-                    println!("Task added to project {}: {}", project_id, title);
-                }
-                Err(e) => println!("Error: {}", e),
-            }
-        }
+        #[clap(long, help = "Comma-separated tags added to every new task, or none")]
+        default_tags: Option<String>,
 
-        Commands::UpdateTask {
-            project_id,
-            id,
-            title,
-            status,
-            priority,
-        } => {
-            // Convert the CLI enums to our internal types
-            let task_status = cli_status_to_task_status(status);
-            let task_priority = cli_priority_to_task_priority(priority);
+        #[clap(
+            long,
+            help = "When a task is done: off, notify (announce dependents that can start) or unblock (also move them out of a Blocked status)"
+        )]
+        propagation: Option<String>,
 
-            // Load the project, update the task, and save it back
-            match storage.load_project(*project_id) {
-                Ok(mut project) => {
-                    match project.update_task(*id, title.clone(), task_status, task_priority) {
-                        Ok(_) => {
-                            storage.save_project(&project)?;
-                            println!("Task updated: {}", id);
-                        }
-                        Err(e) => println!("Error updating task: {}", e),
-                    }
-                }
-                Err(e) => println!("Error loading project: {}", e),
-            }
-        }
+        #[clap(
+            long,
+            help = "Most of the project's tasks run at once by run --all, or none for no limit"
+        )]
+        max_concurrent: Option<String>,
+    },
 
-        Commands::DeleteTask { project_id, id } => {
-            // Load the project, remove the task, and save it back
-            match storage.load_project(*project_id) {
-                Ok(mut project) => {
-                    project.remove_task(*id);
-                    storage.save_project(&project)?;
-                    println!("Task removed: {}", id);
-                }
-                Err(e) => println!("Error: {}", e),
-            }
-        }
-    }
+    /// Show the statuses and transitions a project allows
+    ShowWorkflow {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+    },
 
-    Ok(())
-}
+    /// Replace a project's workflow
+    SetWorkflow {
+        #[clap(help = "Project ID")]
+        project_id: u32,
 
-// Convert from CLI enums to our internal types
-fn cli_status_to_task_status(status: &CliTaskStatus) -> TaskStatus {
-    match status {
-        CliTaskStatus::Todo => TaskStatus::ToDo,
-        CliTaskStatus::InProgress => TaskStatus::InProgress,
-        CliTaskStatus::Done => TaskStatus::Done,
-    }
-}
+        #[clap(
+            long,
+            value_delimiter = ',',
+            help = "Comma-separated list of statuses, e.g. ToDo,InReview,Done"
+        )]
+        states: Vec<String>,
 
-fn cli_priority_to_task_priority(priority: &CliTaskPriority) -> TaskPriority {
-    match priority {
-        CliTaskPriority::Low => TaskPriority::Low,
-        CliTaskPriority::Medium => TaskPriority::Medium,
-        CliTaskPriority::High => TaskPriority::High,
-    }
+        #[clap(
+            long = "transition",
+            help = "Allowed transition as FROM:TO (repeatable)"
+        )]
+        transitions: Vec<String>,
+    },
+
+    /// Configure the numeric priority scale of a project (e.g. P0-P4)
+    SetPriorityScale {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Lowest level on the scale")]
+        min: u8,
+
+        #[clap(help = "Highest level on the scale")]
+        max: u8,
+
+        #[clap(long, default_value = "", help = "Label prefix, e.g. P")]
+        prefix: String,
+
+        #[clap(long, help = "Treat the lowest number as the most urgent")]
+        lower_is_urgent: bool,
+    },
+
+    /// Attach a file or link to a task
+    Attach {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "File path or URL")]
+        source: String,
+
+        #[clap(long, help = "Name to list the attachment under")]
+        name: Option<String>,
+    },
+
+    /// Remove an attachment from a task
+    Detach {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "Attachment name")]
+        name: String,
+    },
+
+    /// Open a task attachment with the system's default application
+    OpenAttachment {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "Attachment name")]
+        name: String,
+    },
+
+    /// Delete stored attachment files no task refers to any more
+    Prune {
+        #[clap(long, help = "List what would be deleted without deleting it")]
+        dry_run: bool,
+    },
+
+    /// Set the estimated effort of a task
+    Estimate {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "Estimated effort in hours")]
+        hours: f64,
+    },
+
+    /// Log time spent on a task
+    LogTime {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "Hours spent")]
+        hours: f64,
+
+        #[clap(long, help = "Day the work was done (defaults to today)")]
+        date: Option<String>,
+
+        #[clap(long, help = "Who did the work (defaults to $USER)")]
+        user: Option<String>,
+    },
+
+    /// Compare estimates with tracked time and suggest an adjustment factor
+    Calibration {
+        #[clap(help = "Project ID (all projects if omitted)")]
+        project_id: Option<u32>,
+    },
+
+    /// List a project's milestones with their progress and tasks
+    Milestones {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "Print the milestones and their progress as JSON")]
+        json: bool,
+    },
+
+    /// Add a milestone that tasks can be grouped under
+    AddMilestone {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Milestone name")]
+        name: String,
+
+        #[clap(long, help = "Due date (same forms as add-task's --due)")]
+        due: Option<String>,
+    },
+
+    /// Delete a milestone; its tasks are kept without one
+    DeleteMilestone {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Milestone ID or name")]
+        milestone: String,
+    },
+
+    /// Assign a task to a milestone
+    SetMilestone {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "Milestone ID or name, or none to take the task out of its milestone")]
+        milestone: String,
+    },
+
+    /// Show task counts by status and priority, overdue tasks and completion
+    Stats {
+        #[clap(help = "Project ID (all active projects if omitted)")]
+        project_id: Option<u32>,
+    },
+
+    /// Show open and closed task counts per day, rebuilt from task history
+    Burndown {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(
+            long,
+            default_value = "30",
+            help = "Number of days to show, ending today"
+        )]
+        days: u32,
+
+        #[clap(long, help = "Print CSV instead of a table")]
+        csv: bool,
+    },
+
+    /// Write a shareable status report for a project, or run a report script
+    Report {
+        #[clap(help = "Project ID (for scripts, all active projects if omitted)")]
+        project_id: Option<u32>,
+
+        #[clap(long, help = "Report script in <data-dir>/reports to run instead")]
+        name: Option<String>,
+
+        #[clap(long, help = "List the report scripts")]
+        list: bool,
+
+        #[clap(long, value_enum, default_value_t = ReportFormat::Md, help = "Report format")]
+        format: ReportFormat,
+
+        #[clap(long, help = "File to write (defaults to stdout)")]
+        output: Option<PathBuf>,
+    },
+
+    /// Summarize today's agenda, overdue tasks and yesterday's completions
+    Brief {
+        #[clap(help = "Project ID (all active projects if omitted)")]
+        project_id: Option<u32>,
+
+        #[clap(long, help = "Speak-friendly sentences for a text-to-speech engine")]
+        tts: bool,
+
+        #[clap(
+            long,
+            help = "Read the briefing aloud by piping it to this command, e.g. \"say\" or \"espeak\" (implies --tts)"
+        )]
+        speak: Option<String>,
+    },
+
+    /// Export per-day, per-task time and completions for invoicing or compliance
+    Timesheet {
+        #[clap(long, help = "First day to include")]
+        from: String,
+
+        #[clap(long, help = "Last day to include (defaults to today)")]
+        to: Option<String>,
+
+        #[clap(long, help = "Only time logged by this person")]
+        user: Option<String>,
+
+        #[clap(long, help = "Only this project (all active projects if omitted)")]
+        project: Option<u32>,
+
+        #[clap(long, help = "Round each day's time on a task to this many minutes")]
+        round: Option<u32>,
+
+        #[clap(long, help = "Rounding direction: up, nearest or down")]
+        rounding: Option<String>,
+
+        #[clap(long, value_enum, default_value_t = TimesheetFormat::Csv, help = "Output format")]
+        format: TimesheetFormat,
+
+        #[clap(long, help = "File to write (defaults to stdout)")]
+        output: Option<PathBuf>,
+    },
+
+    /// Print a one-line summary (open, running and overdue tasks) for tmux or shell prompts
+    Statusline {
+        #[clap(long, help = "Only this project (all active projects if omitted)")]
+        project: Option<u32>,
+    },
+
+    /// Cast a hidden priority and/or estimate vote on a task for the next triage
+    TriageVote {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(long, help = "Who is voting (defaults to $USER)")]
+        user: Option<String>,
+
+        #[clap(
+            long,
+            help = "Priority vote (Low/Medium/High or a level on the project scale)"
+        )]
+        priority: Option<String>,
+
+        #[clap(long, help = "Estimate vote in hours")]
+        estimate: Option<f64>,
+    },
+
+    /// List unestimated tasks with their vote counts; reveal the votes and apply consensus
+    Triage {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "Show every vote and the consensus")]
+        reveal: bool,
+
+        #[clap(
+            long,
+            help = "Reveal, then set the consensus priority and estimate on each task"
+        )]
+        apply: bool,
+
+        #[clap(long, help = "Apply without asking for confirmation")]
+        yes: bool,
+    },
+
+    /// Make a task wait for another task to be done
+    AddDependency {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "ID of the task it depends on")]
+        dependency_id: u32,
+
+        #[clap(
+            long,
+            default_value = "fs",
+            help = "Dependency type: fs (finish-to-start), ss (start-to-start), ff (finish-to-finish) or sf (start-to-finish)"
+        )]
+        kind: String,
+
+        #[clap(
+            long,
+            help = "Extra wait after the dependency starts or finishes, e.g. 2d or 4h"
+        )]
+        lag: Option<String>,
+    },
+
+    /// Stop a task waiting for another task
+    RemoveDependency {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "ID of the task it no longer depends on")]
+        dependency_id: u32,
+    },
+
+    /// Show what a task depends on and which tasks depend on it
+    ShowDependencies {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+    },
+
+    /// List a project's tasks in an order that respects their dependencies
+    ExecutionOrder {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "Group open tasks into waves that can run in parallel")]
+        waves: bool,
+    },
+
+    /// List every dependency cycle in a project, e.g. after hand-editing its file
+    Cycles {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+    },
+
+    /// Export the dependency graph for Graphviz or Mermaid, colored by status
+    Graph {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, value_enum, default_value_t = GraphFormat::Dot, help = "Output format")]
+        format: GraphFormat,
+
+        #[clap(long, help = "File to write (defaults to stdout)")]
+        output: Option<PathBuf>,
+    },
+
+    /// Mark a task and the upstream dependencies only it needs as Done
+    CloseChain {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID at the end of the chain")]
+        task_id: u32,
+
+        #[clap(long, help = "Close without asking for confirmation")]
+        yes: bool,
+    },
+
+    /// Save a named task template for reuse
+    CreateTemplate {
+        #[clap(help = "Template name")]
+        name: String,
+
+        #[clap(help = "Title pattern; {date}, {project} and {n} are filled in when used")]
+        title: String,
+
+        #[clap(long, default_value = "medium", help = "Priority for created tasks")]
+        priority: String,
+
+        #[clap(long = "tag", help = "Tag to apply (repeatable)")]
+        tags: Vec<String>,
+
+        #[clap(long = "check", help = "Checklist item (repeatable)")]
+        checklist: Vec<String>,
+
+        #[clap(long, help = "Estimated effort in hours")]
+        estimate: Option<f64>,
+    },
+
+    /// List saved task templates
+    ListTemplates,
+
+    /// Show a task template
+    ShowTemplate {
+        #[clap(help = "Template name")]
+        name: String,
+    },
+
+    /// Delete a task template
+    DeleteTemplate {
+        #[clap(help = "Template name")]
+        name: String,
+    },
+
+    /// Add a task to a project from a named template
+    AddFromTemplate {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Template name")]
+        template: String,
+
+        #[clap(
+            default_value = "todo",
+            help = "Task status (any state in the project workflow)"
+        )]
+        status: String,
+
+        #[clap(long, help = "Task ID (next free ID in the project if omitted)")]
+        id: Option<u32>,
+    },
+
+    /// Copy a project and all its tasks under a new ID and name
+    CloneProject {
+        #[clap(help = "Project to copy")]
+        source_id: u32,
+
+        #[clap(help = "ID for the copy")]
+        new_id: u32,
+
+        #[clap(help = "Name for the copy")]
+        new_name: String,
+
+        #[clap(
+            long,
+            help = "Start every task over in the workflow's initial state, without history or tracked time"
+        )]
+        reset: bool,
+
+        #[clap(
+            long,
+            help = "Number the copied tasks 1..n, keeping dependencies intact"
+        )]
+        renumber: bool,
+    },
+
+    /// Save a project, reset to its initial state, as a named project template
+    SaveProjectTemplate {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Template name")]
+        name: String,
+    },
+
+    /// List saved project templates
+    ListProjectTemplates,
+
+    /// Delete a saved project template
+    DeleteProjectTemplate {
+        #[clap(help = "Template name")]
+        name: String,
+    },
+
+    /// Create a project from a saved project template
+    CreateFromProjectTemplate {
+        #[clap(help = "Template name")]
+        template: String,
+
+        #[clap(help = "Project name")]
+        name: String,
+
+        #[clap(long, help = "Project ID (next free ID if omitted)")]
+        id: Option<u32>,
+
+        #[clap(
+            long = "component",
+            value_delimiter = ',',
+            help = "Component to copy {component} tasks for (repeatable or comma-separated)"
+        )]
+        components: Vec<String>,
+    },
+
+    /// Declare a dependency in a project template by task titles, e.g. "Deploy {component}"
+    /// after "Test {component}"
+    TemplateDependency {
+        #[clap(help = "Template name")]
+        template: String,
+
+        #[clap(help = "Title of the waiting task; may contain {component}")]
+        task: String,
+
+        #[clap(help = "Title of the task it depends on; may contain {component}")]
+        depends_on: String,
+
+        #[clap(long, default_value = "fs", help = "Dependency type: fs, ss, ff or sf")]
+        kind: String,
+
+        #[clap(long, help = "Lag after the dependency, e.g. 2h or 1d")]
+        lag: Option<String>,
+
+        #[clap(long, help = "Remove the rule instead of adding it")]
+        remove: bool,
+    },
+
+    /// Compute start and finish dates for every task from its dependencies and estimate
+    Schedule {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "Project start date (today if omitted)")]
+        start: Option<String>,
+
+        #[clap(long, default_value_t = 8.0, help = "Working hours per day")]
+        hours_per_day: f64,
+
+        #[clap(long, help = "Schedule work on Saturdays and Sundays too")]
+        weekends: bool,
+
+        #[clap(long, help = "Print the schedule as JSON, e.g. for Gantt rendering")]
+        json: bool,
+    },
+
+    /// Suggest start and due dates for tasks without a due date, fitted to free working hours
+    AutoSchedule {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "First day to schedule from (today if omitted)")]
+        start: Option<String>,
+
+        #[clap(long, default_value_t = 8.0, help = "Working hours per day")]
+        hours_per_day: f64,
+
+        #[clap(long, help = "Schedule work on Saturdays and Sundays too")]
+        weekends: bool,
+
+        #[clap(long, help = "Accept proposals, asking about each task")]
+        apply: bool,
+
+        #[clap(long, help = "Accept every proposal without asking")]
+        yes: bool,
+    },
+
+    /// Print a daily or weekly planner page as HTML, ready for paper planning
+    Planner {
+        #[clap(help = "Project ID (all active projects if omitted)")]
+        project_id: Option<u32>,
+
+        #[clap(
+            long,
+            help = "Plan the whole week, from the locale's first day of the week, instead of one day"
+        )]
+        week: bool,
+
+        #[clap(
+            long,
+            help = "Day to plan, or a day in the week to plan (today if omitted)"
+        )]
+        date: Option<String>,
+
+        #[clap(long, default_value_t = 8.0, help = "Working hours per day")]
+        hours_per_day: f64,
+
+        #[clap(long, help = "Treat Saturdays and Sundays as working days")]
+        weekends: bool,
+
+        #[clap(long, help = "HTML file to write (defaults to stdout)")]
+        output: Option<PathBuf>,
+    },
+
+    /// Show what slips if a task is delayed or removed, without changing anything
+    Impact {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(long, help = "Delay to evaluate, e.g. 4h, 5d or 2w (default 1d)")]
+        delay: Option<String>,
+
+        #[clap(long, conflicts_with = "delay", help = "Evaluate removing the task")]
+        remove: bool,
+    },
+
+    /// List tasks whose estimate or checklist is too big for a single task
+    Oversized {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, default_value_t = split::DEFAULT_MAX_HOURS, help = "Largest reasonable estimate in hours")]
+        max_hours: f64,
+
+        #[clap(long, default_value_t = split::DEFAULT_MAX_CHECKLIST_ITEMS, help = "Largest reasonable checklist")]
+        max_items: usize,
+    },
+
+    /// Find open tasks with near-identical titles and optionally merge them
+    Dedupe {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(
+            long,
+            default_value_t = dedupe::DEFAULT_THRESHOLD,
+            help = "How similar titles must be, from 0 to 1"
+        )]
+        threshold: f64,
+
+        #[clap(long, help = "Merge each pair into the older task")]
+        merge: bool,
+
+        #[clap(long, help = "Merge without asking about each pair")]
+        yes: bool,
+    },
+
+    /// Split a task into subtasks that the original task then depends on
+    SplitTask {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(
+            long,
+            help = "Split into this many equal parts instead of by checklist"
+        )]
+        parts: Option<u32>,
+
+        #[clap(
+            long,
+            conflicts_with = "parts",
+            help = "Create one subtask per open checklist item"
+        )]
+        by_checklist: bool,
+
+        #[clap(long, help = "Split without asking for confirmation")]
+        yes: bool,
+    },
+
+    /// Make a task recur, so completing it adds the next occurrence to the project
+    SetRecurrence {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "daily, weekly, monthly, every:<duration> (e.g. every:3d), or none")]
+        recurrence: String,
+    },
+
+    /// Track a recurring task as a habit, with streaks instead of overdue escalation
+    SetHabit {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(
+            long,
+            default_value = "0",
+            help = "Missed periods a streak survives before it is broken"
+        )]
+        skips: u32,
+
+        #[clap(long, help = "Stop tracking the task as a habit")]
+        off: bool,
+    },
+
+    /// Show habit streaks and a calendar of recent periods
+    Habits {
+        #[clap(help = "Project ID (all active projects if omitted)")]
+        project_id: Option<u32>,
+
+        #[clap(long, default_value = "14", help = "Number of recent periods to show")]
+        periods: u32,
+    },
+
+    /// Change a task's position in the project's manual order
+    MoveTask {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "up, down, or a 1-based position")]
+        to: String,
+    },
+
+    /// Show or change tasks' manual ranks
+    Rank {
+        #[clap(subcommand)]
+        action: RankAction,
+    },
+
+    /// Re-create a project from a template project on a schedule
+    CreateRecurringProject {
+        #[clap(help = "Name for the instances, numbered as \"<name> #<n>\"")]
+        name: String,
+
+        #[clap(help = "ID of the project to copy tasks and settings from")]
+        template_project: u32,
+
+        #[clap(help = "daily, weekly, monthly or every:<duration>")]
+        recurrence: String,
+
+        #[clap(long, help = "When the first instance is due (default: now)")]
+        start: Option<String>,
+    },
+
+    /// List recurring projects and their next run
+    ListRecurringProjects,
+
+    /// Stop re-creating a recurring project (existing instances are kept)
+    DeleteRecurringProject {
+        #[clap(help = "Recurring project ID")]
+        id: u32,
+    },
+
+    /// Create instances for every recurring project that is due, archiving older ones
+    RunRecurringProjects,
+
+    /// Add a quarterly goal that projects and tasks can be linked to
+    AddGoal {
+        #[clap(help = "Goal name")]
+        name: String,
+
+        #[clap(long, help = "Quarter, e.g. 2024-Q3 (the current quarter if omitted)")]
+        quarter: Option<String>,
+    },
+
+    /// Link a project, or one of its tasks, to a goal; linking again changes the weight
+    LinkGoal {
+        #[clap(help = "Goal ID")]
+        goal_id: u32,
+
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(
+            long,
+            help = "Link this task of the project instead of the whole project"
+        )]
+        task: Option<u32>,
+
+        #[clap(
+            long,
+            default_value_t = 1.0,
+            help = "Share of the goal, relative to its other links"
+        )]
+        weight: f64,
+    },
+
+    /// Remove a project or task link from a goal
+    UnlinkGoal {
+        #[clap(help = "Goal ID")]
+        goal_id: u32,
+
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "Task ID, if the link is to a task")]
+        task: Option<u32>,
+    },
+
+    /// Delete a goal (linked projects and tasks are untouched)
+    DeleteGoal {
+        #[clap(help = "Goal ID")]
+        id: u32,
+    },
+
+    /// List goals with progress rolled up from their linked projects and tasks
+    Goals {
+        #[clap(long, help = "Only goals for this quarter, e.g. 2024-Q3")]
+        quarter: Option<String>,
+
+        #[clap(long, help = "Print the goals and their progress as JSON")]
+        json: bool,
+    },
+
+    /// Sync a folder of Markdown notes (e.g. an Obsidian vault) with projects, both ways
+    VaultSync {
+        #[clap(help = "Vault folder; each note maps to a project, each checkbox to a task")]
+        vault: PathBuf,
+    },
+
+    /// Set or clear a task's due date
+    SetDue {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(
+            help = "Due date (e.g. \"2024-06-01 17:00\", tomorrow or \"next friday\"), or none"
+        )]
+        due: String,
+    },
+
+    /// Give a task its own reminder schedule
+    SetReminders {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(
+            help = "Comma-separated rules such as 1w,1d,due,every:1h, or default to use the global schedule"
+        )]
+        schedule: String,
+    },
+
+    /// Print reminders that have come due and mark them as sent
+    Remind {
+        #[clap(help = "Project ID (all projects if omitted)")]
+        project_id: Option<u32>,
+    },
+
+    /// List tasks waiting for review, such as work in progress for too long
+    Review,
+
+    /// Take a task off the review queue
+    DismissReview {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+    },
+
+    /// Mark a task as blocked for a reason outside the project
+    Block {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(help = "Why the task is blocked, e.g. \"waiting on customer\"")]
+        reason: String,
+    },
+
+    /// Clear a manual block
+    Unblock {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+    },
+
+    /// Edit a whole project as a YAML document in $EDITOR
+    EditProject {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+    },
+
+    /// Show added, removed and changed tasks and dependencies between two projects, or
+    /// between a project and its state at an earlier time
+    Diff {
+        #[clap(help = "Project ID")]
+        project_a: u32,
+
+        #[clap(help = "Project to compare against", conflicts_with = "at")]
+        project_b: Option<u32>,
+
+        #[clap(long, help = "Compare against the project as it stood at this time")]
+        at: Option<String>,
+
+        #[clap(long, help = "Print the diff as JSON")]
+        json: bool,
+    },
+
+    /// Move all tasks from one project into another, then archive or delete the source
+    #[clap(alias = "merge-projects")]
+    Merge {
+        #[clap(help = "Project to move tasks out of")]
+        source_id: u32,
+
+        #[clap(help = "Project to move tasks into")]
+        destination_id: u32,
+
+        #[clap(
+            long,
+            help = "Prefix the moved tasks' tags with this (and tag them with it)"
+        )]
+        tag_prefix: Option<String>,
+
+        #[clap(long, help = "Delete the source project instead of archiving it")]
+        delete: bool,
+    },
+
+    /// Show or replace the rules tasks in a project must satisfy
+    SetRules {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(
+            help = "Comma-separated rules such as non-empty-title,due-required:High,max-in-progress:3, or none; omit to show the current rules"
+        )]
+        rules: Option<String>,
+    },
+
+    /// Quickly add a task, filing it by the routing rules in config.json
+    Capture {
+        #[clap(
+            required = true,
+            help = "Task title; words starting with # become tags, e.g. \"Fix login #bug\""
+        )]
+        text: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Don't warn about similar open tasks already in any project"
+        )]
+        no_duplicate_check: bool,
+    },
+
+    /// List the routing rules used by capture
+    Routes,
+
+    /// Show how dates, times and numbers are formatted, as set in config.json
+    Locale,
+
+    /// Show what changed on a task and when
+    History {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Task ID")]
+        task_id: u32,
+
+        #[clap(long, help = "Show each change as removed/added lines")]
+        diff: bool,
+    },
+
+    /// Declare, change or remove a custom field in a project's schema
+    DefineField {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "Field name")]
+        name: String,
+
+        #[clap(help = "Field type: text, number, date, enum:a|b|c, or none to remove it")]
+        kind: String,
+    },
+
+    /// Run tasks through the executor, reporting progress as each one finishes
+    Run {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(help = "IDs of the tasks to run")]
+        task_ids: Vec<u32>,
+
+        #[clap(long, help = "Choose interactively from the tasks that can run now")]
+        pick: bool,
+
+        #[clap(
+            long,
+            conflicts_with_all = &["task-ids", "pick"],
+            help = "Run every open task, each as soon as the tasks it depends on have finished"
+        )]
+        all: bool,
+
+        #[clap(long, default_value_t = 4, help = "Number of worker threads")]
+        workers: usize,
+
+        #[clap(
+            long,
+            default_value_t = 0,
+            help = "Run a task whose handler fails up to this many more times"
+        )]
+        retries: u32,
+
+        #[clap(
+            long,
+            value_enum,
+            default_value = "priority",
+            requires = "all",
+            help = "Which ready task starts first when workers are short: priority, shortest (by estimate) or critical-path"
+        )]
+        policy: RunPolicy,
+
+        #[clap(
+            long,
+            requires = "all",
+            help = "Also run every open task of this project on the same workers (repeatable)"
+        )]
+        also: Vec<u32>,
+    },
+
+    /// Re-emit logged task events through the notification pipeline
+    Replay {
+        #[clap(long, help = "Replay events recorded at or after this time")]
+        from: Option<String>,
+
+        #[clap(long, help = "Replay events recorded at or before this time")]
+        to: Option<String>,
+
+        #[clap(long, value_enum, default_value_t = ReplaySink::Log, help = "Where replayed events are delivered")]
+        sink: ReplaySink,
+
+        #[clap(long, help = "File for the json sink (defaults to stdout)")]
+        output: Option<PathBuf>,
+    },
+
+    /// Keep a live view of a project's due dates, runs and events on screen
+    Watch {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, default_value_t = 1, help = "Seconds between redraws")]
+        interval: u64,
+    },
+
+    /// Export a project's tasks as CSV, a Markdown checklist or an iCalendar file of to-dos
+    Export {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(
+            long,
+            value_enum,
+            default_value_t = ExportFormat::Csv,
+            help = "csv, md (a checklist) or ics (a to-do for each task with a due date)"
+        )]
+        format: ExportFormat,
+
+        #[clap(long, help = "File to write (defaults to stdout)")]
+        output: Option<PathBuf>,
+    },
+
+    /// Create tasks in a project from the rows of a CSV file or a JSON array
+    Import {
+        #[clap(help = "Project ID")]
+        project_id: u32,
+
+        #[clap(long, help = "File to read, or - for standard input")]
+        file: PathBuf,
+
+        #[clap(
+            long,
+            value_enum,
+            help = "csv (with a header row) or json; guessed from the file extension if omitted"
+        )]
+        format: Option<ImportFormat>,
+
+        #[clap(
+            long = "map",
+            help = "Read a column as title, status, priority, due, tags, estimate or field:<name>, e.g. Summary=title (repeatable)"
+        )]
+        mappings: Vec<String>,
+
+        #[clap(long, help = "Show what would be created without saving anything")]
+        dry_run: bool,
+
+        #[clap(
+            long,
+            help = "Don't warn about similar open tasks already in any project"
+        )]
+        no_duplicate_check: bool,
+    },
+
+    /// Print a shell completion script, e.g. `taskmaster completions bash > /etc/bash_completion.d/taskmaster`
+    Completions {
+        #[clap(value_enum, help = "Shell to complete for")]
+        shell: CompletionShell,
+    },
+
+    /// Answer a completion script: candidates for the last of the words typed so far
+    #[clap(hide = true)]
+    Complete {
+        #[clap(allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+}
+
+pub async fn run_cli() -> Result<()> {
+    let args: Vec<String> = env::args().collect();
+    let cli = match Cli::try_parse_from(&args) {
+        Ok(cli) => cli,
+        Err(e) if e.kind() == ErrorKind::MissingRequiredArgument && prompting::enabled(&args) => {
+            match prompting::fill_missing(Cli::command(), &args) {
+                Ok(args) => Cli::try_parse_from(args).unwrap_or_else(|e| e.exit()),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(e) => e.exit(),
+    };
+    if cli.no_color {
+        appearance::disable_colors();
+    }
+
+    // Completion runs on every Tab press, so it skips loading config and opening storage
+    match &cli.command {
+        Commands::Completions { shell } => {
+            let script = match shell {
+                CompletionShell::Bash => completions::bash(),
+                CompletionShell::Zsh => completions::zsh(),
+                CompletionShell::Fish => completions::fish(),
+                CompletionShell::Powershell => completions::powershell(),
+            };
+            print!("{}", script);
+            return Ok(());
+        }
+        Commands::Complete { words } => {
+            for (value, description) in completions::candidates(Cli::command(), words) {
+                if description.is_empty() {
+                    println!("{}", value);
+                } else {
+                    println!("{}\t{}", value, description);
+                }
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let config = Config::load(&cli.data_dir)?;
+    locale::install_settings(&config.locale);
+    let mut storage = open_storage(&cli.data_dir, &config)?;
+
+    match &cli.command {
+        Commands::CreateProject { name, id, parent } => {
+            if let Some(parent) = parent {
+                storage.load_project(*parent)?;
+            }
+            let id = match id {
+                // Saving would replace the existing project and all of its tasks
+                Some(id) if storage.project_ids()?.contains(id) => {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Project {} already exists",
+                        id
+                    )));
+                }
+                Some(id) => *id,
+                None => storage.next_project_id()?,
+            };
+            let mut project = Project::new(id, name.clone());
+            project.parent_id = *parent;
+            storage.save_project(&project)?;
+            println!("Project created: {} (ID: {})", name, id);
+        }
+
+        Commands::ListProjects { all, tree } => {
+            let projects: Vec<Project> = storage
+                .list_projects()?
+                .into_iter()
+                .filter(|p| *all || !p.archived)
+                .collect();
+            if cli.output_style != OutputStyle::Table {
+                let records: Vec<ProjectListing> =
+                    projects.iter().map(ProjectListing::new).collect();
+                print_structured(cli.output_style, "projects", &records)?;
+            } else if projects.is_empty() {
+                println!("No projects found");
+            } else if *tree {
+                println!("Projects:");
+                for line in hierarchy::tree_lines(&projects) {
+                    println!("  {}", line);
+                }
+            } else {
+                let mut table = Table::new(&["ID", "Name", "Open", "Tasks", ""]).with_shrinking(1);
+                for project in projects {
+                    let listing = ProjectListing::new(&project);
+                    table.row(
+                        vec![
+                            project.id.to_string().into(),
+                            Cell::colored(project.label(), project.color),
+                            listing.open_task_count.to_string().into(),
+                            listing.task_count.to_string().into(),
+                            if project.archived { "archived" } else { "" }.into(),
+                        ],
+                        project.archived,
+                    );
+                }
+                table.print();
+            }
+        }
+
+        Commands::SetParent { id, parent } => {
+            let projects = storage.list_projects()?;
+            let Some(mut project) = projects.iter().find(|p| p.id == *id).cloned() else {
+                return Err(TaskMasterError::ProjectNotFound(*id));
+            };
+
+            let parent_id = if parent.trim().eq_ignore_ascii_case("none") {
+                None
+            } else {
+                let parsed = parent.trim().parse::<u32>().map_err(|_| {
+                    TaskMasterError::InvalidOperation(format!(
+                        "Expected a project ID or none, got '{}'",
+                        parent
+                    ))
+                });
+                match parsed.and_then(|p| hierarchy::check_parent(&projects, *id, p).map(|_| p)) {
+                    Ok(parent_id) => Some(parent_id),
+                    Err(e) => return Err(e),
+                }
+            };
+
+            project.parent_id = parent_id;
+            storage.save_project(&project)?;
+            match parent_id {
+                Some(parent_id) => println!("Project {} moved under project {}", id, parent_id),
+                None => println!("Project {} moved to the top level", id),
+            }
+        }
+
+        Commands::ShowProject {
+            id,
+            by_priority,
+            sort,
+            fields,
+            status,
+            priority,
+            tags,
+            overdue,
+            limit,
+        } => match storage.load_project(*id) {
+            Ok(project) => {
+                let filters = fields
+                    .iter()
+                    .map(|f| fields::parse_assignment(f))
+                    .collect::<Result<Vec<_>>>()?;
+                let status = status
+                    .as_deref()
+                    .map(|s| project.settings.workflow.parse_status(s))
+                    .transpose()?;
+                let priority = priority
+                    .as_deref()
+                    .map(|p| project.settings.priority_scale.parse(p))
+                    .transpose()?;
+                let now = Utc::now();
+
+                let sort = match sort {
+                    Some(sort) => *sort,
+                    None if *by_priority => TaskSort::Priority,
+                    None => TaskSort::Manual,
+                };
+                let by = |order: fn(&Task, &Task) -> std::cmp::Ordering| {
+                    let mut tasks: Vec<(&Task, Option<f64>)> =
+                        project.tasks.iter().map(|t| (t, None)).collect();
+                    tasks.sort_by(|a, b| order(a.0, b.0));
+                    tasks
+                };
+                let tasks: Vec<(&Task, Option<f64>)> = match sort {
+                    TaskSort::Manual => project.tasks.iter().map(|t| (t, None)).collect(),
+                    TaskSort::Id => by(|a, b| a.id.cmp(&b.id)),
+                    TaskSort::Due => by(|a, b| match (a.due, b.due) {
+                        (Some(a), Some(b)) => a.cmp(&b),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => std::cmp::Ordering::Equal,
+                    }),
+                    TaskSort::Title => {
+                        by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+                    }
+                    TaskSort::Priority => project
+                        .tasks_by_priority()
+                        .into_iter()
+                        .map(|t| (t, None))
+                        .collect(),
+                    TaskSort::Urgency => {
+                        let config = Config::load(&cli.data_dir)?;
+                        project
+                            .tasks_by_urgency(&config.urgency)
+                            .into_iter()
+                            .map(|(t, score)| (t, Some(score)))
+                            .collect()
+                    }
+                };
+                let mut matching: Vec<(&Task, Option<f64>)> = tasks
+                    .into_iter()
+                    .filter(|(task, _)| {
+                        filters
+                            .iter()
+                            .all(|(name, value)| task.field_matches(name, value))
+                            && status.as_ref().is_none_or(|s| &task.status == s)
+                            && priority.as_ref().is_none_or(|p| &task.priority == p)
+                            && tags
+                                .iter()
+                                .all(|tag| task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+                            && (!*overdue || task.is_overdue(now))
+                    })
+                    .collect();
+                let total = matching.len();
+                if let Some(limit) = limit {
+                    matching.truncate(*limit);
+                }
+
+                if cli.output_style != OutputStyle::Table {
+                    let tasks = matching
+                        .iter()
+                        .map(|(task, urgency)| TaskRecord::new(&project, task, *urgency))
+                        .collect();
+                    let record = ProjectRecord::new(&project, tasks);
+                    print_structured(cli.output_style, "project", &record)?;
+                    return Ok(());
+                }
+
+                println!(
+                    "Project: {} (ID: {})",
+                    appearance::paint(&project.label(), project.color),
+                    project.id
+                );
+                println!("  UUID: {}", project.uuid);
+                if let Some(color) = project.color {
+                    println!("  Color: {}", color);
+                }
+                if !project.description.is_empty() {
+                    println!("  Description: {}", project.description);
+                }
+                if let Some(owner) = &project.owner {
+                    println!("  Owner: {}", owner);
+                }
+                if let Some(parent_id) = project.parent_id {
+                    println!("  Parent project: {}", parent_id);
+                }
+                println!("  Created: {}", dates::format_local(&project.created_at));
+                if let Some(deadline) = &project.deadline {
+                    println!("  Deadline: {}", dates::format_local(deadline));
+                }
+                if project.tasks.is_empty() {
+                    println!("  No tasks");
+                } else if total == 0 {
+                    println!("  No matching tasks");
+                } else {
+                    println!("  Tasks:");
+                    let mut headers = TASK_COLUMNS.to_vec();
+                    if sort == TaskSort::Urgency {
+                        headers.push("Urgency");
+                    }
+                    let mut table = Table::new(&headers).with_indent(4).with_shrinking(1);
+                    let shown = matching.len();
+                    for (task, urgency) in matching {
+                        let mut cells = task_cells(&project, task, now);
+                        if let Some(score) = urgency {
+                            cells.push(locale::number(score, 1).into());
+                        }
+                        table.row(cells, task.status == TaskStatus::Done);
+                        for attachment in &task.attachments {
+                            table.note(format!("Attachment: {}", attachment.describe()));
+                        }
+                    }
+                    table.print();
+                    if shown < total {
+                        println!("    ... and {} more", total - shown);
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::UpdateProject {
+            id,
+            name,
+            description,
+            owner,
+            deadline,
+            color,
+            icon,
+        } => match storage.load_project(*id) {
+            Ok(mut project) => {
+                let deadline = deadline.as_deref().map(dates::parse_due).transpose()?;
+                if let Some(name) = name {
+                    project.name = name.clone();
+                }
+                if let Some(description) = description {
+                    project.description = description.trim().to_string();
+                }
+                if let Some(owner) = owner {
+                    let owner = owner.trim();
+                    project.owner = (!owner.is_empty()).then(|| owner.to_string());
+                }
+                if let Some(deadline) = deadline {
+                    project.deadline = deadline;
+                }
+                if let Some(color) = color {
+                    project.color = if color.trim().eq_ignore_ascii_case("none") {
+                        None
+                    } else {
+                        match ProjectColor::parse(color) {
+                            Ok(color) => Some(color),
+                            Err(e) => return Err(e),
+                        }
+                    };
+                }
+                if let Some(icon) = icon {
+                    project.icon = if icon.trim().eq_ignore_ascii_case("none") {
+                        None
+                    } else {
+                        match appearance::parse_icon(icon) {
+                            Ok(icon) => Some(icon),
+                            Err(e) => return Err(e),
+                        }
+                    };
+                }
+                storage.save_project(&project)?;
+                println!(
+                    "Project updated: {} (ID: {})",
+                    appearance::paint(&project.label(), project.color),
+                    project.id
+                );
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::ShowTask { project_id, id } => match storage.load_project(*project_id) {
+            Ok(project) => match project.get_task(*id) {
+                Ok(task) if cli.output_style != OutputStyle::Table => {
+                    let urgency = Config::load(&cli.data_dir)?.urgency.score(
+                        task,
+                        &project.tasks,
+                        &project.settings.priority_scale,
+                        Utc::now(),
+                    );
+                    let record = TaskRecord::new(&project, task, Some(urgency));
+                    print_structured(cli.output_style, "task", &record)?;
+                }
+                Ok(task) => {
+                    println!("Task: {} (ID: {})", task.title, project.task_ref(task.id));
+                    println!("  UUID: {}", task.uuid);
+                    println!("  Status: {}", task.status_label(&project.tasks));
+                    println!("  Priority: {}", project.priority_label(task));
+                    let urgency = Config::load(&cli.data_dir)?.urgency.score(
+                        task,
+                        &project.tasks,
+                        &project.settings.priority_scale,
+                        Utc::now(),
+                    );
+                    println!("  Urgency: {}", locale::number(urgency, 1));
+                    println!("  Created: {}", dates::format_local(&task.created_at));
+                    println!("  Updated: {}", dates::format_local(&task.updated_at));
+                    if let Some(start) = &task.planned_start {
+                        println!("  Planned start: {}", dates::format_local(start));
+                    }
+                    if let Some(due) = &task.due {
+                        println!("  Due: {}", dates::format_local(due));
+                    }
+                    if let Some(name) = task.milestone.and_then(|id| project.milestone_name(id)) {
+                        println!("  Milestone: {}", name);
+                    }
+                    if let Some(rules) = &task.reminders {
+                        let rules: Vec<String> = rules.iter().map(|r| r.to_string()).collect();
+                        println!("  Reminders: {}", rules.join(", "));
+                    }
+                    if let Some(estimate) = task.estimate_hours {
+                        println!("  Estimate: {}", locale::hours(estimate, 1));
+                    }
+                    let actual = task.actual_hours();
+                    if actual > 0.0 {
+                        println!("  Tracked: {}", locale::hours(actual, 1));
+                    }
+                    if let Some(ratio) = task.estimate_ratio {
+                        println!("  Actual/estimate: {}", locale::number(ratio, 2));
+                    }
+                    if !task.tags.is_empty() {
+                        println!("  Tags: {}", task.tags.join(", "));
+                    }
+                    if let Some(parent) = task.parent {
+                        println!("  Subtask of: {}", parent);
+                    }
+                    if let Some(origin) = &task.follow_up_of {
+                        println!(
+                            "  Follow-up of: task {} [{}]",
+                            origin.task_id, origin.run_id
+                        );
+                    }
+                    if let Some(recurrence) = &task.recurrence {
+                        println!("  Repeats: {}", recurrence);
+                    }
+                    if let Some(deps) = &task.dependencies {
+                        println!("  Dependencies: {}", task_dependencies::format_list(deps));
+                    }
+                    for attachment in &task.attachments {
+                        println!("  Attachment: {}", attachment.describe());
+                    }
+                    for item in &task.checklist {
+                        println!("  [{}] {}", if item.done { "x" } else { " " }, item.text);
+                    }
+                    let mut custom: Vec<_> = task.custom_fields.iter().collect();
+                    custom.sort();
+                    for (name, value) in custom {
+                        println!("  {}: {}", name, value);
+                    }
+                    if task.history.is_empty() {
+                        println!("  No status changes");
+                    } else {
+                        println!("  History:");
+                        for change in &task.history {
+                            println!(
+                                "    {}  {} -> {}",
+                                dates::format_local(&change.changed_at),
+                                change.from,
+                                change.to
+                            );
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::Search { query, all } => {
+            let query = Query::parse(&query.join(" "))?;
+            let projects: Vec<Project> = storage
+                .list_projects()?
+                .into_iter()
+                .filter(|p| *all || !p.archived)
+                .collect();
+            let hits = query::search(&projects, &query, Utc::now());
+
+            if cli.output_style != OutputStyle::Table {
+                let records: Vec<SearchHit> = hits
+                    .iter()
+                    .map(|(project, task)| SearchHit::new(project, task, None))
+                    .collect();
+                print_structured(cli.output_style, "tasks", &records)?;
+            } else if hits.is_empty() {
+                println!("No matching tasks");
+            } else {
+                let mut headers = vec!["Project"];
+                headers.extend(TASK_COLUMNS);
+                let mut table = Table::new(&headers).with_indent(2).with_shrinking(2);
+                let now = Utc::now();
+                for (project, task) in &hits {
+                    let mut cells = vec![Cell::colored(project.label(), project.color)];
+                    cells.extend(task_cells(project, task, now));
+                    table.row(cells, task.status == TaskStatus::Done);
+                }
+                table.print();
+                println!(
+                    "{} matching task{}",
+                    hits.len(),
+                    if hits.len() == 1 { "" } else { "s" }
+                );
+            }
+        }
+
+        Commands::Next { limit, all } => {
+            let projects: Vec<Project> = storage
+                .list_projects()?
+                .into_iter()
+                .filter(|p| *all || !p.archived)
+                .collect();
+            let config = Config::load(&cli.data_dir)?;
+            let now = Utc::now();
+            let ranked = urgency::actionable(&projects, &config.urgency, now);
+            let total = ranked.len();
+            let shown = &ranked[..total.min(*limit)];
+
+            if cli.output_style != OutputStyle::Table {
+                let records: Vec<SearchHit> = shown
+                    .iter()
+                    .map(|(project, task, score)| SearchHit::new(project, task, Some(*score)))
+                    .collect();
+                print_structured(cli.output_style, "tasks", &records)?;
+            } else if shown.is_empty() {
+                println!("Nothing to work on right now");
+            } else {
+                let mut headers = vec!["Project"];
+                headers.extend(TASK_COLUMNS);
+                headers.push("Urgency");
+                let mut table = Table::new(&headers).with_indent(2).with_shrinking(2);
+                for (project, task, score) in shown {
+                    let mut cells = vec![Cell::colored(project.label(), project.color)];
+                    cells.extend(task_cells(project, task, now));
+                    cells.push(locale::number(*score, 1).into());
+                    table.row(cells, false);
+                }
+                table.print();
+                if shown.len() < total {
+                    println!("  ... and {} more", total - shown.len());
+                }
+            }
+        }
+
+        Commands::DeleteProject { id } => match storage.load_project(*id) {
+            Ok(project) => {
+                // Subprojects move up to the deleted project's parent
+                let projects = storage.list_projects()?;
+                for child in hierarchy::children(&projects, *id) {
+                    let mut child = child.clone();
+                    child.parent_id = project.parent_id;
+                    storage.save_project(&child)?;
+                }
+                storage.delete_project(*id)?;
+                println!("Project deleted: {}", id);
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::AddTask {
+            project_id,
+            args,
+            estimate,
+            due,
+            fields,
+        } => {
+            let (id, title, status, priority) = split_task_args(args);
+            let due = due.as_deref().map(dates::parse_datetime).transpose()?;
+
+            // Load the project, add the task, and save it back
+            match storage.load_project(*project_id) {
+                Ok(mut project) => {
+                    let id = match id {
+                        Some(id) => id,
+                        None => storage.next_task_id(*project_id)?,
+                    };
+                    let mut task = project.new_task(id, title.to_string())?;
+                    let result = status
+                        .map(|status| project.settings.workflow.parse_status(status))
+                        .transpose()
+                        .and_then(|task_status| {
+                            if let Some(task_status) = task_status {
+                                task.status = task_status;
+                            }
+                            if let Some(priority) = priority {
+                                task.priority = project.settings.priority_scale.parse(priority)?;
+                            }
+                            task.estimate_hours = *estimate;
+                            task.due = due;
+                            for assignment in fields {
+                                let (name, value) = fields::parse_assignment(assignment)?;
+                                let (name, value) = fields::normalize_field(
+                                    &project.settings.fields,
+                                    &name,
+                                    &value,
+                                )?;
+                                task.custom_fields.insert(name, value);
+                            }
+                            project.add_task(task)
+                        });
+                    match result {
+                        Ok(_) => {
+                            storage.save_project(&project)?;
+                            println!(
+                                "Task added to project {}: {} (ID: {})",
+                                project_id,
+                                title,
+                                project.task_ref(id)
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::UpdateTask {
+            project_id,
+            id,
+            title,
+            status,
+            priority,
+            due,
+            fields,
+        } => {
+            let due = due.as_deref().map(dates::parse_due).transpose()?;
+
+            // Load the project, update the task, and save it back
+            match storage.load_project(*project_id) {
+                Ok(mut project) => {
+                    let result = parse_task_fields(&project, status, priority).and_then(
+                        |(task_status, task_priority)| {
+                            if let Some(due) = due {
+                                project.set_task_due(*id, due)?;
+                            }
+                            for assignment in fields {
+                                let (name, value) = fields::parse_assignment(assignment)?;
+                                let value = Some(value.as_str()).filter(|v| !v.is_empty());
+                                project.set_task_field(*id, &name, value)?;
+                            }
+                            project.update_task(*id, title.clone(), task_status, task_priority)
+                        },
+                    );
+                    match result {
+                        Ok(next) => {
+                            storage.save_project(&project)?;
+                            println!("Task updated: {}", id);
+                            if let Some(next) = next {
+                                println!("Next occurrence created: task {}", next);
+                            }
+                            announce_events(&cli.data_dir, &mut project)?;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::Done { project_id, id }
+        | Commands::Start { project_id, id }
+        | Commands::Reopen { project_id, id } => {
+            let mut project = storage.load_project(*project_id)?;
+            let current = project.get_task(*id)?.status.clone();
+            let status = match &cli.command {
+                Commands::Done { .. } => TaskStatus::Done,
+                Commands::Start { .. } => TaskStatus::InProgress,
+                _ => {
+                    if current != TaskStatus::Done {
+                        return Err(TaskMasterError::InvalidOperation(format!(
+                            "Task {} is not done; it is {}",
+                            id, current
+                        )));
+                    }
+                    project.settings.workflow.initial_state()?
+                }
+            };
+            if current == status {
+                println!("Task {} is already {}", id, status);
+                return Ok(());
+            }
+
+            match project.set_task_status(*id, status.clone()) {
+                Ok(next) => {
+                    storage.save_project(&project)?;
+                    println!("Task {}: {} -> {}", id, current, status);
+                    if let Some(next) = next {
+                        println!("Next occurrence created: task {}", next);
+                    }
+                    announce_events(&cli.data_dir, &mut project)?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::DeleteTask {
+            project_id,
+            id,
+            force,
+            transfer,
+        } => {
+            let policy = if *transfer {
+                OrphanPolicy::Transfer
+            } else if *force {
+                OrphanPolicy::Cascade
+            } else {
+                OrphanPolicy::Refuse
+            };
+
+            // Load the project, remove the task, and save it back
+            let mut project = storage.load_project(*project_id)?;
+            let attachments = project
+                .get_task(*id)
+                .map(|t| t.attachments.clone())
+                .unwrap_or_default();
+            let has_dependents = project.tasks.iter().any(|t| t.depends_on(*id));
+            match project.remove_task(*id, policy) {
+                Ok(dependents) => {
+                    storage.save_project(&project)?;
+                    AttachmentStore::new(&cli.data_dir).remove_task(&attachments)?;
+                    println!("Task removed: {}", id);
+                    if !dependents.is_empty() {
+                        let refs: Vec<String> =
+                            dependents.iter().map(|d| project.task_ref(*d)).collect();
+                        let action = if *transfer {
+                            "took over its dependencies"
+                        } else {
+                            "no longer depend on it"
+                        };
+                        println!("Task(s) {} {}", refs.join(", "), action);
+                    }
+                }
+                Err(e) => {
+                    // Only a refusal over dependents can be overridden
+                    if policy == OrphanPolicy::Refuse && has_dependents {
+                        eprintln!(
+                            "Use --force to drop those dependencies or --transfer to pass them on"
+                        );
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Commands::Bulk {
+            project_id,
+            conditions,
+            assignments,
+            dry_run,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let parsed = TaskFilter::parse(&project, conditions).and_then(|filter| {
+                    TaskChange::parse(&project, assignments).map(|change| (filter, change))
+                });
+                let (filter, change) = parsed?;
+
+                if *dry_run {
+                    let matching: Vec<&Task> =
+                        project.tasks.iter().filter(|t| filter.matches(t)).collect();
+                    println!("{} task(s) would be updated:", matching.len());
+                    for task in matching {
+                        println!("  {} - {}", project.task_ref(task.id), task.title);
+                    }
+                    return Ok(());
+                }
+
+                match project.bulk_update(&filter, &change) {
+                    Ok(ids) if ids.is_empty() => println!("No tasks matched"),
+                    Ok(ids) => {
+                        storage.save_project(&project)?;
+                        let refs: Vec<String> =
+                            ids.iter().map(|id| project.task_ref(*id)).collect();
+                        println!("Updated {} task(s): {}", ids.len(), refs.join(", "));
+                        announce_events(&cli.data_dir, &mut project)?;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::ProjectSettings {
+            project_id,
+            default_priority,
+            id_prefix,
+            default_tags,
+            propagation,
+            max_concurrent,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let changed = default_priority.is_some()
+                    || id_prefix.is_some()
+                    || default_tags.is_some()
+                    || propagation.is_some()
+                    || max_concurrent.is_some();
+
+                if let Some(priority) = default_priority {
+                    project.settings.default_priority =
+                        if priority.trim().eq_ignore_ascii_case("none") {
+                            None
+                        } else {
+                            match project.settings.priority_scale.parse(priority) {
+                                Ok(priority) => Some(priority),
+                                Err(e) => return Err(e),
+                            }
+                        };
+                }
+                if let Some(prefix) = id_prefix {
+                    project.settings.id_prefix = prefix.trim().to_string();
+                }
+                if let Some(tags) = default_tags {
+                    project.settings.default_tags = if tags.trim().eq_ignore_ascii_case("none") {
+                        Vec::new()
+                    } else {
+                        tags.split(',')
+                            .map(|tag| tag.trim().to_string())
+                            .filter(|tag| !tag.is_empty())
+                            .collect()
+                    };
+                }
+                if let Some(propagation) = propagation {
+                    match Propagation::parse(propagation) {
+                        Ok(propagation) => project.settings.propagation = propagation,
+                        Err(e) => return Err(e),
+                    }
+                }
+                if let Some(limit) = max_concurrent {
+                    project.settings.max_concurrent = if limit.trim().eq_ignore_ascii_case("none") {
+                        None
+                    } else {
+                        match limit.trim().parse::<usize>() {
+                            Ok(limit) if limit > 0 => Some(limit),
+                            _ => {
+                                return Err(TaskMasterError::InvalidOperation(format!(
+                                    "Expected a number of tasks above 0 or none, got '{}'",
+                                    limit
+                                )))
+                            }
+                        }
+                    };
+                }
+                if changed {
+                    storage.save_project(&project)?;
+                    println!("Settings updated for project {}", project_id);
+                }
+
+                let settings = &project.settings;
+                println!("Settings for {} (ID: {}):", project.name, project.id);
+                println!(
+                    "  Default priority: {}",
+                    settings
+                        .default_priority
+                        .as_ref()
+                        .map_or("Medium".to_string(), |p| settings.priority_scale.label(p))
+                );
+                println!("  Initial status: {}", settings.workflow.initial_state()?);
+                if settings.id_prefix.is_empty() {
+                    println!("  Task ID prefix: none");
+                } else {
+                    println!("  Task ID prefix: {}", settings.id_prefix);
+                }
+                if settings.default_tags.is_empty() {
+                    println!("  Default tags: none");
+                } else {
+                    println!("  Default tags: {}", settings.default_tags.join(", "));
+                }
+                println!("  Propagation to dependents: {}", settings.propagation);
+                match settings.max_concurrent {
+                    Some(limit) => println!("  Tasks run at once: at most {}", limit),
+                    None => println!("  Tasks run at once: no limit"),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::ShowWorkflow { project_id } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                println!("Workflow for project {}:", project.name);
+                project.settings.workflow.display();
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SetWorkflow {
+            project_id,
+            states,
+            transitions,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match build_workflow(&project, states, transitions) {
+                Ok(workflow) => {
+                    project.settings.workflow = workflow;
+                    storage.save_project(&project)?;
+                    println!("Workflow updated for project {}", project_id);
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::SetPriorityScale {
+            project_id,
+            min,
+            max,
+            prefix,
+            lower_is_urgent,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match PriorityScale::new(*min, *max, prefix, *lower_is_urgent) {
+                Ok(scale) => {
+                    project.settings.priority_scale = scale;
+                    storage.save_project(&project)?;
+                    println!(
+                        "Priority scale for project {} set to {}{}-{}{}",
+                        project_id, prefix, min, prefix, max
+                    );
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::Attach {
+            project_id,
+            task_id,
+            source,
+            name,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                // Make sure the task exists before copying anything
+                project.get_task(*task_id)?;
+
+                let store =
+                    AttachmentStore::with_backend(&cli.data_dir, config.attachment_backend.clone());
+                let attachment = store.attach(*project_id, *task_id, source, name.as_deref())?;
+                let description = attachment.describe();
+                match project.attach_to_task(*task_id, attachment.clone()) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        println!("Attached to task {}: {}", task_id, description);
+                    }
+                    Err(e) => {
+                        store.remove(&attachment)?;
+                        return Err(e);
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Detach {
+            project_id,
+            task_id,
+            name,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match project.detach_from_task(*task_id, name) {
+                Ok(attachment) => {
+                    storage.save_project(&project)?;
+                    AttachmentStore::new(&cli.data_dir).remove(&attachment)?;
+                    println!("Detached from task {}: {}", task_id, name);
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::OpenAttachment {
+            project_id,
+            task_id,
+            name,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let task = project.get_task(*task_id)?;
+                match task.attachments.iter().find(|a| &a.name == name) {
+                    Some(attachment) => {
+                        let store = AttachmentStore::new(&cli.data_dir);
+                        println!("Opening {}", store.location(attachment)?);
+                        store.open(attachment)?;
+                    }
+                    None => {
+                        return Err(TaskMasterError::InvalidOperation(format!(
+                            "Task {} has no attachment named '{}'",
+                            task_id, name
+                        )))
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Prune { dry_run } => {
+            // Archived projects count too: their attachments come back if they are restored
+            let mut referenced: BTreeMap<BlobBackend, BTreeSet<String>> = BTreeMap::new();
+            referenced.entry(BlobBackend::Local).or_default();
+            referenced.entry(BlobBackend::Dedup).or_default();
+            referenced
+                .entry(config.attachment_backend.clone())
+                .or_default();
+            // Not list_projects, which skips unreadable files: their attachments would look
+            // unreferenced and be deleted
+            for id in storage.project_ids()? {
+                let project = storage.load_project(id).map_err(|e| {
+                    TaskMasterError::StorageError(format!(
+                        "Nothing pruned: project {} could not be read: {}",
+                        id, e
+                    ))
+                })?;
+                for task in &project.tasks {
+                    for (backend, key) in task.attachments.iter().filter_map(|a| a.blob()) {
+                        referenced.entry(backend).or_default().insert(key);
+                    }
+                }
+            }
+
+            let mut total = 0;
+            // Stores that couldn't be cleaned up; the others still are
+            let mut failed = Vec::new();
+            for (backend, keys) in &referenced {
+                let store = backend.open(&cli.data_dir);
+                let removed = match blob_store::collect_garbage(store.as_ref(), keys, *dry_run) {
+                    Ok(removed) => removed,
+                    Err(e) => {
+                        failed.push(format!("{} store: {}", backend, e));
+                        continue;
+                    }
+                };
+                for key in &removed {
+                    println!("  {}: {}", backend, key);
+                }
+                total += removed.len();
+            }
+            if *dry_run {
+                println!("{} unreferenced attachment file(s) would be deleted", total);
+            } else {
+                println!("Deleted {} unreferenced attachment file(s)", total);
+            }
+            if !failed.is_empty() {
+                return Err(TaskMasterError::StorageError(failed.join("; ")));
+            }
+        }
+
+        Commands::Estimate {
+            project_id,
+            task_id,
+            hours,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match project.set_task_estimate(*task_id, *hours) {
+                Ok(_) => {
+                    storage.save_project(&project)?;
+                    println!("Task {} estimated at {}", task_id, locale::hours(*hours, 1));
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::LogTime {
+            project_id,
+            task_id,
+            hours,
+            date,
+            user,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let date = match date.as_deref().map(dates::parse_datetime).transpose() {
+                    Ok(date) => date
+                        .map_or_else(Local::now, |d| d.with_timezone(&Local))
+                        .date_naive(),
+                    Err(e) => return Err(e),
+                };
+                match project.log_task_time(*task_id, *hours, date, current_user(user)) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        println!(
+                            "Logged {} on task {} for {}",
+                            locale::hours(*hours, 1),
+                            task_id,
+                            locale::date(date)
+                        );
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Calibration { project_id } => {
+            let projects = match project_id {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage.list_projects()?,
+            };
+
+            for project in &projects {
+                let report = CalibrationReport::for_project(project);
+                report.display();
+
+                let remaining = estimation::remaining_estimate(project, None);
+                if remaining > 0.0 {
+                    println!(
+                        "  Remaining work: {} estimated, {} calibrated",
+                        locale::hours(remaining, 1),
+                        locale::hours(estimation::remaining_estimate(project, Some(&report)), 1)
+                    );
+                }
+            }
+
+            if projects.len() > 1 {
+                CalibrationReport::from_projects("all projects", &projects).display();
+            }
+        }
+
+        Commands::Milestones { project_id, json } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let progress = project.milestone_progress();
+                if *json {
+                    println!("{}", serde_json::to_string_pretty(&progress)?);
+                    return Ok(());
+                }
+
+                if progress.is_empty() {
+                    println!("No milestones in project {}", project.id);
+                    return Ok(());
+                }
+                println!("Milestones for {}:", project.name);
+                for milestone in &progress {
+                    println!("  {}", milestone.describe(Utc::now()));
+                    for task in project
+                        .tasks
+                        .iter()
+                        .filter(|t| t.milestone == Some(milestone.id))
+                    {
+                        println!(
+                            "    {} - {} [{}]",
+                            project.task_ref(task.id),
+                            task.title,
+                            task.status_label(&project.tasks)
+                        );
+                    }
+                }
+                let unassigned = project
+                    .tasks
+                    .iter()
+                    .filter(|t| t.milestone.is_none())
+                    .count();
+                if unassigned > 0 {
+                    println!("  {} task(s) without a milestone", unassigned);
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::AddMilestone {
+            project_id,
+            name,
+            due,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let result = due
+                    .as_deref()
+                    .map(dates::parse_datetime)
+                    .transpose()
+                    .and_then(|due| project.add_milestone(name, due));
+                match result {
+                    Ok(id) => {
+                        storage.save_project(&project)?;
+                        println!("Milestone added: {} (ID: {})", name.trim(), id);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::DeleteMilestone {
+            project_id,
+            milestone,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let result = project
+                    .find_milestone(milestone)
+                    .map(|m| m.id)
+                    .and_then(|id| project.remove_milestone(id));
+                match result {
+                    Ok(removed) => {
+                        storage.save_project(&project)?;
+                        println!("Milestone deleted: {}", removed.name);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SetMilestone {
+            project_id,
+            task_id,
+            milestone,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let target = if milestone.trim().eq_ignore_ascii_case("none") {
+                    Ok(None)
+                } else {
+                    project.find_milestone(milestone).map(|m| Some(m.id))
+                };
+                match target.and_then(|target| project.set_task_milestone(*task_id, target)) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        match project.get_task(*task_id)?.milestone {
+                            Some(id) => println!(
+                                "Task {} is now in milestone {}",
+                                task_id,
+                                project.milestone_name(id).unwrap_or_default()
+                            ),
+                            None => println!("Task {} no longer has a milestone", task_id),
+                        }
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Stats { project_id } => {
+            let projects: Vec<Project> = match project_id {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage
+                    .list_projects()?
+                    .into_iter()
+                    .filter(|p| !p.archived)
+                    .collect(),
+            };
+
+            if projects.is_empty() {
+                println!("No projects found");
+            }
+            let all_projects = storage.list_projects()?;
+            for project in &projects {
+                project.stats().display();
+
+                // Workspaces also get totals across everything beneath them
+                let descendants = hierarchy::descendants(&all_projects, project.id);
+                if !descendants.is_empty() {
+                    let mut workspace = vec![project.clone()];
+                    workspace.extend(descendants.into_iter().cloned());
+                    let label = format!("{} and its subprojects", project.name);
+                    ProjectStats::from_projects(&label, &workspace, Utc::now()).display();
+                }
+            }
+            if projects.len() > 1 {
+                ProjectStats::from_projects("all projects", &projects, Utc::now()).display();
+            }
+
+            // Goals the shown projects count towards
+            let goals = Goals::load(&cli.data_dir)?;
+            let linked: Vec<GoalProgress> = goals
+                .iter()
+                .filter(|g| projects.iter().any(|p| g.links_project(p.id)))
+                .map(|g| g.progress(&all_projects))
+                .collect();
+            if !linked.is_empty() {
+                println!("Goals:");
+                for goal in &linked {
+                    println!("  {}", goal.describe());
+                }
+            }
+        }
+
+        Commands::Burndown {
+            project_id,
+            days,
+            csv,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let rows = burndown::burndown(&project, *days, Local::now().date_naive());
+                if *csv {
+                    print!("{}", burndown::to_csv(&rows));
+                } else {
+                    println!("Burndown for {} (last {} days):", project.name, days);
+                    println!(
+                        "  {:<10}  {:>5}  {:>5}  {:>6}",
+                        "Date", "Open", "Done", "Closed"
+                    );
+                    for row in &rows {
+                        println!(
+                            "  {:<10}  {:>5}  {:>5}  {:>6}",
+                            locale::date(row.date),
+                            row.open,
+                            row.done,
+                            row.closed
+                        );
+                    }
+                    println!("  Trend: {}", burndown::sparkline(&rows));
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Statusline { project } => {
+            let mut cache = SummaryCache::load(&cli.data_dir)?;
+            if cache.refresh(storage.as_ref())? {
+                cache.save(&cli.data_dir)?;
+            }
+            println!(
+                "{}",
+                summary::statusline(&cache.summaries(*project), Utc::now())
+            );
+        }
+
+        Commands::TriageVote {
+            project_id,
+            task_id,
+            user,
+            priority,
+            estimate,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                project.get_task(*task_id)?;
+                let priority = match priority
+                    .as_deref()
+                    .map(|p| project.settings.priority_scale.parse(p))
+                    .transpose()
+                {
+                    Ok(priority) => priority,
+                    Err(e) => return Err(e),
+                };
+                let Some(user) = current_user(user) else {
+                    return Err(TaskMasterError::InvalidOperation(
+                        "Could not tell who is voting; pass --user".to_string(),
+                    ));
+                };
+
+                let mut votes = TriageVotes::load(&cli.data_dir)?;
+                let vote = Vote {
+                    user: user.clone(),
+                    priority,
+                    estimate_hours: *estimate,
+                    at: Utc::now(),
+                };
+                match votes.vote(*project_id, *task_id, vote) {
+                    Ok(count) => {
+                        votes.save(&cli.data_dir)?;
+                        println!(
+                            "Vote from {} recorded for task {} ({} vote(s) so far)",
+                            user, task_id, count
+                        );
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Triage {
+            project_id,
+            reveal,
+            apply,
+            yes,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let mut votes = TriageVotes::load(&cli.data_dir)?;
+                // Open tasks still to estimate, plus any others someone has voted on
+                let tasks: Vec<(u32, String)> = project
+                    .tasks
+                    .iter()
+                    .filter(|t| t.status != TaskStatus::Done)
+                    .filter(|t| {
+                        t.estimate_hours.is_none() || !votes.votes(project.id, t.id).is_empty()
+                    })
+                    .map(|t| (t.id, t.title.clone()))
+                    .collect();
+                if tasks.is_empty() {
+                    println!("Nothing to triage in {}", project.name);
+                    return Ok(());
+                }
+
+                let scale = project.settings.priority_scale.clone();
+                let mut decisions = Vec::new();
+                println!("Triage for {}:", project.name);
+                for (id, title) in &tasks {
+                    let task_votes = votes.votes(project.id, *id);
+                    println!("  {} - {} ({} vote(s))", id, title, task_votes.len());
+                    if !(*reveal || *apply) || task_votes.is_empty() {
+                        continue;
+                    }
+                    for vote in task_votes {
+                        let priority = vote
+                            .priority
+                            .as_ref()
+                            .map_or("-".to_string(), |p| scale.label(p));
+                        let estimate = vote
+                            .estimate_hours
+                            .map_or("-".to_string(), |h| locale::hours(h, 1));
+                        println!("      {}: {}, {}", vote.user, priority, estimate);
+                    }
+                    let consensus = triage::consensus(task_votes, &scale);
+                    println!(
+                        "      Consensus: {}, {}{}",
+                        consensus
+                            .priority
+                            .as_ref()
+                            .map_or("-".to_string(), |p| scale.label(p)),
+                        consensus
+                            .estimate_hours
+                            .map_or("-".to_string(), |h| locale::hours(h, 1)),
+                        if consensus.agreed {
+                            ""
+                        } else {
+                            " (votes differ; worth discussing)"
+                        }
+                    );
+                    decisions.push((*id, consensus));
+                }
+
+                if !*apply {
+                    if !*reveal {
+                        println!("Votes stay hidden until --reveal or --apply");
+                    }
+                    return Ok(());
+                }
+                if decisions.is_empty() {
+                    println!("No votes to apply");
+                    return Ok(());
+                }
+                if !*yes && !confirm("Apply the consensus values?")? {
+                    println!("Cancelled");
+                    return Ok(());
+                }
+
+                let mut applied = 0;
+                let mut failed = 0;
+                for (id, consensus) in decisions {
+                    let result = project.get_task(id).cloned().and_then(|task| {
+                        if let Some(priority) = consensus.priority {
+                            project.update_task(id, task.title, task.status, priority)?;
+                        }
+                        if let Some(hours) = consensus.estimate_hours {
+                            project.set_task_estimate(id, hours)?;
+                        }
+                        Ok(())
+                    });
+                    match result {
+                        Ok(_) => {
+                            votes.clear(project.id, id);
+                            applied += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("Task {}: {}", id, e);
+                            failed += 1;
+                        }
+                    }
+                }
+                storage.save_project(&project)?;
+                votes.save(&cli.data_dir)?;
+                println!("Applied consensus to {} task(s)", applied);
+                if failed > 0 {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Consensus could not be applied to {} task(s)",
+                        failed
+                    )));
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::AddDependency {
+            project_id,
+            task_id,
+            dependency_id,
+            kind,
+            lag,
+        } => {
+            let kind = DependencyKind::parse(kind);
+            let lag = lag.as_deref().map(dates::parse_duration).transpose();
+            let dependency = match (kind, lag) {
+                (Ok(kind), Ok(lag)) => Dependency::new(*dependency_id)
+                    .with_kind(kind)
+                    .with_lag(lag),
+                (Err(e), _) | (_, Err(e)) => {
+                    return Err(e);
+                }
+            };
+            match storage.load_project(*project_id) {
+                Ok(mut project) => match project.add_typed_dependency(*task_id, dependency) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        println!("Task {} now depends on task {}", task_id, dependency);
+                    }
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::RemoveDependency {
+            project_id,
+            task_id,
+            dependency_id,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let depends = project
+                    .get_task(*task_id)
+                    .map(|task| task.depends_on(*dependency_id));
+                match depends {
+                    Ok(false) => {
+                        return Err(TaskMasterError::InvalidOperation(format!(
+                            "Task {} does not depend on task {}",
+                            task_id, dependency_id
+                        )))
+                    }
+                    Ok(true) => match project.remove_task_dependency(*task_id, *dependency_id) {
+                        Ok(_) => {
+                            storage.save_project(&project)?;
+                            println!(
+                                "Task {} no longer depends on task {}",
+                                task_id, dependency_id
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    },
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::ShowDependencies {
+            project_id,
+            task_id,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => match project.get_task(*task_id) {
+                Ok(task) => {
+                    let describe = |id: u32| match project.get_task(id) {
+                        Ok(t) => format!("{} - {} [{}]", id, t.title, t.status),
+                        Err(_) => format!("{} (missing)", id),
+                    };
+                    println!("Task {} - {} [{}]", task.id, task.title, task.status);
+
+                    let deps = task.dependencies.clone().unwrap_or_default();
+                    if deps.is_empty() {
+                        println!("  Depends on nothing");
+                    } else {
+                        let blocking = task.blocking_dependencies(&project.tasks);
+                        let finish_blockers = task.finish_blockers(&project.tasks);
+                        println!("  Depends on:");
+                        for dep in deps {
+                            let marker = if blocking.contains(&dep.task) {
+                                " (blocking)"
+                            } else if finish_blockers.contains(&dep.task) {
+                                " (blocks finishing)"
+                            } else {
+                                ""
+                            };
+                            let label = dep.label().map(|l| format!(" {}", l)).unwrap_or_default();
+                            println!("    {}{}{}", describe(dep.task), label, marker);
+                        }
+                    }
+
+                    let dependents: Vec<u32> = project
+                        .tasks
+                        .iter()
+                        .filter(|t| t.depends_on(*task_id))
+                        .map(|t| t.id)
+                        .collect();
+                    if dependents.is_empty() {
+                        println!("  No tasks depend on it");
+                    } else {
+                        println!("  Needed by:");
+                        for id in dependents {
+                            println!("    {}", describe(id));
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::ExecutionOrder {
+            project_id,
+            waves: true,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => match project.execution_levels() {
+                Ok(waves) if waves.is_empty() => {
+                    println!("No open tasks in project {}", project_id)
+                }
+                Ok(waves) => {
+                    println!("Execution waves for {}:", project.name);
+                    for (index, wave) in waves.iter().enumerate() {
+                        println!("  Wave {}:", index + 1);
+                        for id in wave {
+                            if let Ok(task) = project.get_task(*id) {
+                                println!("    {} - {} [{}]", task.id, task.title, task.status);
+                            }
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::ExecutionOrder { project_id, .. } => match storage.load_project(*project_id) {
+            Ok(project) => match project.get_task_execution_order() {
+                Ok(order) if order.is_empty() => println!("No tasks in project {}", project_id),
+                Ok(order) => {
+                    println!("Execution order for {}:", project.name);
+                    for (position, task) in order.iter().enumerate() {
+                        let deps = task.dependencies.clone().unwrap_or_default();
+                        if deps.is_empty() {
+                            println!(
+                                "  {}. {} - {} [{}]",
+                                position + 1,
+                                task.id,
+                                task.title,
+                                task.status
+                            );
+                        } else {
+                            println!(
+                                "  {}. {} - {} [{}] after {}",
+                                position + 1,
+                                task.id,
+                                task.title,
+                                task.status,
+                                task_dependencies::format_list(&deps)
+                            );
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::Cycles { project_id } => {
+            match storage.load_project(*project_id) {
+                Ok(project) => {
+                    // Enough to see what needs fixing without flooding the terminal
+                    let limit = 100;
+                    let cycles = task_dependencies::find_cycles(&project.tasks, limit);
+                    if cycles.is_empty() {
+                        println!("No dependency cycles in project {}", project_id);
+                        return Ok(());
+                    }
+
+                    println!(
+                        "Dependency cycles in {} (each task depends on the next):",
+                        project.name
+                    );
+                    for cycle in &cycles {
+                        println!("  {}", error::format_cycle(cycle));
+                    }
+                    if cycles.len() == limit {
+                        println!("Showing the first {} cycles; there may be more", limit);
+                    }
+                    println!("Break a cycle with remove-dependency <project_id> <task_id> <dependency_id>");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::Graph {
+            project_id,
+            format,
+            output,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let rendered = match format {
+                    GraphFormat::Dot => graph::to_dot(&project),
+                    GraphFormat::Mermaid => graph::to_mermaid(&project),
+                };
+                match (rendered, output) {
+                    (Ok(document), Some(path)) => {
+                        fs::write(path, document)?;
+                        println!("Graph written to {}", path.display());
+                    }
+                    (Ok(document), None) => print!("{}", document),
+                    (Err(e), _) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::CloseChain {
+            project_id,
+            task_id,
+            yes,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let chain: Vec<(u32, String)> = match project.completion_chain(*task_id) {
+                    Ok(chain) => chain.iter().map(|t| (t.id, t.title.clone())).collect(),
+                    Err(e) => return Err(e),
+                };
+
+                if chain.is_empty() {
+                    println!("Nothing to close; task {} is already Done", task_id);
+                    return Ok(());
+                }
+
+                println!("The following tasks will be marked Done:");
+                for (id, title) in &chain {
+                    println!("  ID: {}, Title: {}", id, title);
+                }
+
+                if !*yes && !confirm("Close these tasks?")? {
+                    println!("Cancelled");
+                    return Ok(());
+                }
+
+                match project.close_chain(*task_id) {
+                    Ok(closed) => {
+                        storage.save_project(&project)?;
+                        println!("Closed {} task(s)", closed.len());
+                        announce_events(&cli.data_dir, &mut project)?;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::CreateTemplate {
+            name,
+            title,
+            priority,
+            tags,
+            checklist,
+            estimate,
+        } => {
+            let mut library = TemplateLibrary::load(&cli.data_dir)?;
+            let mut template = TaskTemplate::new(name, title);
+            template.priority = priority.clone();
+            template.tags = tags.clone();
+            template.checklist = checklist.clone();
+            template.estimate_hours = *estimate;
+
+            let replaced = library.insert(template).is_some();
+            library.save(&cli.data_dir)?;
+            if replaced {
+                println!("Template updated: {}", name);
+            } else {
+                println!("Template created: {}", name);
+            }
+        }
+
+        Commands::ListTemplates => {
+            let library = TemplateLibrary::load(&cli.data_dir)?;
+            let mut templates = library.iter().peekable();
+            if templates.peek().is_none() {
+                println!("No templates found");
+            } else {
+                println!("Templates:");
+                for template in templates {
+                    println!("  {}: {}", template.name, template.title_pattern);
+                }
+            }
+        }
+
+        Commands::ShowTemplate { name } => match TemplateLibrary::load(&cli.data_dir)?.get(name) {
+            Ok(template) => template.display(),
+            Err(e) => return Err(e),
+        },
+
+        Commands::DeleteTemplate { name } => {
+            let mut library = TemplateLibrary::load(&cli.data_dir)?;
+            match library.remove(name) {
+                Ok(_) => {
+                    library.save(&cli.data_dir)?;
+                    println!("Template deleted: {}", name);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::AddFromTemplate {
+            project_id,
+            template,
+            status,
+            id,
+        } => {
+            let library = TemplateLibrary::load(&cli.data_dir)?;
+            match storage.load_project(*project_id) {
+                Ok(mut project) => {
+                    let id = match id {
+                        Some(id) => *id,
+                        None => project.next_task_id()?,
+                    };
+                    let task = library.get(template).and_then(|template| {
+                        template.instantiate(
+                            id,
+                            project.settings.workflow.parse_status(status)?,
+                            &project.settings.priority_scale,
+                            &[("project", project.name.clone())],
+                        )
+                    });
+
+                    let title = task.as_ref().map(|t| t.title.clone()).unwrap_or_default();
+                    match task.and_then(|task| project.add_task(task)) {
+                        Ok(_) => {
+                            storage.save_project(&project)?;
+                            println!(
+                                "Task added to project {}: {} (ID: {})",
+                                project_id, title, id
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::CloneProject {
+            source_id,
+            new_id,
+            new_name,
+            reset,
+            renumber,
+        } => match storage.load_project(*source_id) {
+            Ok(source) => {
+                if storage.load_project(*new_id).is_ok() {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Project {} already exists",
+                        new_id
+                    )));
+                }
+                let copy = source.clone_as(*new_id, new_name.clone(), *reset, *renumber)?;
+                storage.save_project(&copy)?;
+                println!(
+                    "Project {} copied to {} (ID: {}) with {} tasks",
+                    source_id,
+                    new_name,
+                    new_id,
+                    copy.tasks.len()
+                );
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SaveProjectTemplate { project_id, name } => {
+            match storage.load_project(*project_id) {
+                Ok(project) => {
+                    let mut library = ProjectTemplateLibrary::load(&cli.data_dir)?;
+                    let replaced = library.insert(name, &project)?.is_some();
+                    library.save(&cli.data_dir)?;
+                    if replaced {
+                        println!("Project template updated: {}", name);
+                    } else {
+                        println!("Project template saved: {}", name);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::ListProjectTemplates => {
+            let library = ProjectTemplateLibrary::load(&cli.data_dir)?;
+            let mut templates = library.iter().peekable();
+            if templates.peek().is_none() {
+                println!("No project templates found");
+            } else {
+                println!("Project templates:");
+                for (name, project) in templates {
+                    println!("  {}: {} tasks", name, project.tasks.len());
+                    for rule in &project.settings.dependency_rules {
+                        println!("    {}", rule);
+                    }
+                }
+            }
+        }
+
+        Commands::DeleteProjectTemplate { name } => {
+            let mut library = ProjectTemplateLibrary::load(&cli.data_dir)?;
+            match library.remove(name) {
+                Ok(_) => {
+                    library.save(&cli.data_dir)?;
+                    println!("Project template deleted: {}", name);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::CreateFromProjectTemplate {
+            template,
+            name,
+            id,
+            components,
+        } => {
+            let library = ProjectTemplateLibrary::load(&cli.data_dir)?;
+            match library.get(template) {
+                Ok(template) => {
+                    let id = match id {
+                        Some(id) => *id,
+                        None => storage.next_project_id()?,
+                    };
+                    let components: Vec<String> = components
+                        .iter()
+                        .map(|c| c.trim().to_string())
+                        .filter(|c| !c.is_empty())
+                        .collect();
+                    let project =
+                        template.instantiate_with_components(id, name.clone(), &components)?;
+                    storage.save_project(&project)?;
+                    println!(
+                        "Project created: {} (ID: {}) with {} tasks",
+                        name,
+                        id,
+                        project.tasks.len()
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::TemplateDependency {
+            template,
+            task,
+            depends_on,
+            kind,
+            lag,
+            remove,
+        } => {
+            let kind = DependencyKind::parse(kind);
+            let lag = lag.as_deref().map(dates::parse_duration).transpose();
+            let rule = match (kind, lag) {
+                (Ok(kind), Ok(lag)) => DependencyRule::new(task, depends_on, kind, lag),
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            };
+            let rule = rule?;
+
+            let mut library = ProjectTemplateLibrary::load(&cli.data_dir)?;
+            let project = library.get_mut(template)?;
+            let rules = &mut project.settings.dependency_rules;
+            let existing = rules.iter().position(|r| {
+                r.task.eq_ignore_ascii_case(&rule.task)
+                    && r.depends_on.eq_ignore_ascii_case(&rule.depends_on)
+            });
+            match (existing, *remove) {
+                (Some(index), true) => {
+                    let removed = rules.remove(index);
+                    println!("Dependency rule removed: {}", removed);
+                }
+                (None, true) => {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Template {} has no rule {}",
+                        template, rule
+                    )));
+                }
+                (Some(index), false) => {
+                    println!("Dependency rule updated: {}", rule);
+                    rules[index] = rule;
+                }
+                (None, false) => {
+                    println!("Dependency rule added: {}", rule);
+                    rules.push(rule);
+                }
+            }
+            library.save(&cli.data_dir)?;
+        }
+
+        Commands::Schedule {
+            project_id,
+            start,
+            hours_per_day,
+            weekends,
+            json,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let start = match start.as_deref().map(dates::parse_datetime).transpose() {
+                    Ok(start) => start.unwrap_or_else(Utc::now),
+                    Err(e) => return Err(e),
+                };
+                let calendar = WorkCalendar {
+                    hours_per_day: *hours_per_day,
+                    weekends: *weekends,
+                };
+                let start = start.with_timezone(&chrono::Local).date_naive();
+                match DatedSchedule::plan(&project, start, &calendar) {
+                    Ok(schedule) if *json => {
+                        println!("{}", serde_json::to_string_pretty(&schedule)?)
+                    }
+                    Ok(schedule) => schedule.display(),
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::AutoSchedule {
+            project_id,
+            start,
+            hours_per_day,
+            weekends,
+            apply,
+            yes,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let start = match start.as_deref().map(dates::parse_datetime).transpose() {
+                    Ok(start) => start.unwrap_or_else(Utc::now),
+                    Err(e) => return Err(e),
+                };
+                let calendar = WorkCalendar {
+                    hours_per_day: *hours_per_day,
+                    weekends: *weekends,
+                };
+                let start = start.with_timezone(&chrono::Local).date_naive();
+                let plan = AutoSchedule::propose(&project, start, &calendar)?;
+                if plan.proposals.is_empty() && plan.unplaced.is_empty() {
+                    println!("Every open task already has a due date");
+                    return Ok(());
+                }
+
+                println!("Proposed schedule from {}:", locale::date(plan.start));
+                println!(
+                    "{:<5} {:<30} {:<12} {:<12} {:>6}",
+                    "ID", "Title", "Start", "Due", "Hours"
+                );
+                for proposal in &plan.proposals {
+                    println!(
+                        "{:<5} {:<30} {:<12} {:<12} {:>6}",
+                        proposal.task_id,
+                        proposal.title,
+                        locale::date(proposal.start),
+                        locale::date(proposal.due),
+                        locale::number(proposal.hours, 1)
+                    );
+                }
+                if !plan.unestimated.is_empty() {
+                    println!(
+                        "No estimate, scheduled as taking no time: {:?}",
+                        plan.unestimated
+                    );
+                }
+                if !plan.unplaced.is_empty() {
+                    println!("No free capacity within two years: {:?}", plan.unplaced);
+                }
+                if !*apply && !*yes {
+                    println!("Run with --apply to accept proposals task by task");
+                    return Ok(());
+                }
+
+                let mut accepted = 0;
+                for proposal in &plan.proposals {
+                    let question = format!(
+                        "Schedule task {} from {} to {}?",
+                        proposal.task_id,
+                        locale::date(proposal.start),
+                        locale::date(proposal.due)
+                    );
+                    if !*yes && !confirm(&question)? {
+                        continue;
+                    }
+                    let result = dates::start_of_workday(proposal.start)
+                        .and_then(|begin| {
+                            project.set_task_planned_start(proposal.task_id, Some(begin))
+                        })
+                        .and_then(|_| dates::end_of_workday(proposal.due))
+                        .and_then(|due| project.set_task_due(proposal.task_id, Some(due)));
+                    match result {
+                        Ok(()) => accepted += 1,
+                        Err(e) => return Err(e),
+                    }
+                }
+                if accepted > 0 {
+                    storage.save_project(&project)?;
+                }
+                println!("Scheduled {} task(s)", accepted);
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Report {
+            project_id,
+            name,
+            list,
+            format,
+            output,
+        } => {
+            if *list {
+                let scripts = report_plugins::list(&cli.data_dir)?;
+                if scripts.is_empty() {
+                    println!(
+                        "No report scripts in {}",
+                        cli.data_dir.join(report_plugins::REPORTS_DIR).display()
+                    );
+                } else {
+                    println!("Report scripts:");
+                    for script in scripts {
+                        println!("  {}", script);
+                    }
+                }
+                return Ok(());
+            }
+
+            let document = match (name, project_id) {
+                (Some(name), _) => {
+                    let projects: Vec<Project> = match project_id {
+                        Some(id) => vec![storage.load_project(*id)?],
+                        None => storage
+                            .list_projects()?
+                            .into_iter()
+                            .filter(|p| !p.archived)
+                            .collect(),
+                    };
+                    let format = match format {
+                        ReportFormat::Md => report_plugins::OutputFormat::Markdown,
+                        ReportFormat::Html => report_plugins::OutputFormat::Html,
+                        ReportFormat::Text => report_plugins::OutputFormat::Text,
+                        ReportFormat::Json => report_plugins::OutputFormat::Json,
+                    };
+                    let snapshot = report_plugins::Snapshot::new(&projects, Utc::now());
+                    let result = report_plugins::find(&cli.data_dir, name)
+                        .and_then(|script| report_plugins::run(&script, &snapshot, format));
+                    result?
+                }
+                (None, Some(id)) => match storage.load_project(*id) {
+                    Ok(project) => {
+                        let report = ProjectReport::build(&project, Utc::now());
+                        match format {
+                            ReportFormat::Md => report.to_markdown(),
+                            ReportFormat::Html => report.to_html(),
+                            ReportFormat::Text | ReportFormat::Json => {
+                                return Err(TaskMasterError::InvalidOperation(
+                                    "The built-in report is written as md or html".to_string(),
+                                ));
+                            }
+                        }
+                    }
+                    Err(e) => return Err(e),
+                },
+                (None, None) => {
+                    return Err(TaskMasterError::InvalidOperation(
+                        "Give a project ID, or --name to run a report script".to_string(),
+                    ));
+                }
+            };
+            match output {
+                Some(path) => {
+                    fs::write(path, document)?;
+                    println!("Report written to {}", path.display());
+                }
+                None => print!("{}", document),
+            }
+        }
+
+        Commands::Brief {
+            project_id,
+            tts,
+            speak,
+        } => {
+            let projects: Vec<Project> = match project_id {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage
+                    .list_projects()?
+                    .into_iter()
+                    .filter(|p| !p.archived)
+                    .collect(),
+            };
+            let briefing = Briefing::build(&projects, Utc::now());
+            match speak {
+                Some(command) => {
+                    brief::speak(command, &briefing.to_speech())?;
+                }
+                None if *tts => print!("{}", briefing.to_speech()),
+                None => print!("{}", briefing.to_text()),
+            }
+        }
+
+        Commands::Timesheet {
+            from,
+            to,
+            user,
+            project,
+            round,
+            rounding,
+            format,
+            output,
+        } => {
+            let day = |input: &str| {
+                dates::parse_datetime(input).map(|d| d.with_timezone(&Local).date_naive())
+            };
+            let range = day(from).and_then(|from| {
+                let to = match to.as_deref() {
+                    Some(to) => day(to)?,
+                    None => Local::now().date_naive(),
+                };
+                Ok((from, to))
+            });
+            let mut rule = Config::load(&cli.data_dir)?.timesheet_rounding;
+            if let Some(minutes) = round {
+                rule.increment_minutes = *minutes;
+            }
+            let mode = rounding.as_deref().map(Rounding::parse).transpose();
+            let ((from, to), mode) = match (range, mode) {
+                (Ok(range), Ok(mode)) => (range, mode),
+                (Err(e), _) | (_, Err(e)) => {
+                    return Err(e);
+                }
+            };
+            if let Some(mode) = mode {
+                rule.mode = mode;
+            }
+
+            let projects: Vec<Project> = match project {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage
+                    .list_projects()?
+                    .into_iter()
+                    .filter(|p| !p.archived)
+                    .collect(),
+            };
+            match Timesheet::build(&projects, from, to, user.as_deref(), rule, Utc::now()) {
+                Ok(timesheet) => {
+                    let document = match format {
+                        TimesheetFormat::Csv => timesheet.to_csv(),
+                        TimesheetFormat::Html => timesheet.to_html(),
+                    };
+                    match output {
+                        Some(path) => {
+                            fs::write(path, document)?;
+                            println!(
+                                "Timesheet written to {} ({})",
+                                path.display(),
+                                locale::hours(timesheet.total_hours(), 2)
+                            );
+                        }
+                        None => print!("{}", document),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::Planner {
+            project_id,
+            week,
+            date,
+            hours_per_day,
+            weekends,
+            output,
+        } => {
+            let date = match date.as_deref().map(dates::parse_datetime).transpose() {
+                Ok(date) => date
+                    .unwrap_or_else(Utc::now)
+                    .with_timezone(&Local)
+                    .date_naive(),
+                Err(e) => return Err(e),
+            };
+            let projects: Vec<Project> = match project_id {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage
+                    .list_projects()?
+                    .into_iter()
+                    .filter(|p| !p.archived)
+                    .collect(),
+            };
+            let calendar = WorkCalendar {
+                hours_per_day: *hours_per_day,
+                weekends: *weekends,
+            };
+            let planner = if *week {
+                let first = locale::current().week_start_of(date);
+                Planner::build(&projects, first, 7, &calendar)
+            } else {
+                Planner::build(&projects, date, 1, &calendar)
+            };
+            match output {
+                Some(path) => {
+                    fs::write(path, planner.to_html())?;
+                    println!("Planner written to {}", path.display());
+                }
+                None => print!("{}", planner.to_html()),
+            }
+        }
+
+        Commands::Impact {
+            project_id,
+            task_id,
+            delay,
+            remove,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let scenario = if *remove {
+                    Ok(Scenario::Remove(*task_id))
+                } else {
+                    dates::parse_duration(delay.as_deref().unwrap_or("1d"))
+                        .map(|delay| Scenario::Delay(*task_id, delay.num_minutes() as f64 / 60.0))
+                };
+
+                match scenario.and_then(|scenario| ImpactReport::analyze(&project, scenario)) {
+                    Ok(report) => report.display(&project),
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Oversized {
+            project_id,
+            max_hours,
+            max_items,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let oversized = split::find_oversized(&project, *max_hours, *max_items);
+                if oversized.is_empty() {
+                    println!("No oversized tasks");
+                }
+                for candidate in oversized {
+                    let task = project.get_task(candidate.task_id)?;
+                    println!("  ID: {}, Title: {}", task.id, task.title);
+                    if let Some(hours) = candidate.estimate_hours {
+                        println!("    Estimate: {}", locale::hours(hours, 1));
+                    }
+                    if candidate.checklist_items > 0 {
+                        println!("    Checklist items: {}", candidate.checklist_items);
+                    }
+                    let suggestion = match SplitStrategy::suggest(task, *max_hours) {
+                        SplitStrategy::Checklist => "--by-checklist".to_string(),
+                        SplitStrategy::Parts(parts) => format!("--parts {}", parts),
+                    };
+                    println!(
+                        "    Suggested: split-task {} {} {}",
+                        project_id, task.id, suggestion
+                    );
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Dedupe {
+            project_id,
+            threshold,
+            merge,
+            yes,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let candidates = dedupe::find_duplicates(&project, *threshold);
+                if candidates.is_empty() {
+                    println!("No likely duplicates found");
+                    return Ok(());
+                }
+
+                let title = |project: &Project, id: u32| {
+                    project
+                        .get_task(id)
+                        .map(|t| t.title.clone())
+                        .unwrap_or_default()
+                };
+                println!("Likely duplicates in {}:", project.name);
+                for candidate in &candidates {
+                    println!(
+                        "  {} '{}' ~ {} '{}' ({:.0}% similar)",
+                        candidate.keep,
+                        title(&project, candidate.keep),
+                        candidate.duplicate,
+                        title(&project, candidate.duplicate),
+                        candidate.similarity * 100.0
+                    );
+                }
+                if !*merge {
+                    println!("Run with --merge to merge them into the older task");
+                    return Ok(());
+                }
+
+                let mut merged = 0;
+                for candidate in &candidates {
+                    // An earlier merge may already have removed one of the pair
+                    if project.get_task(candidate.keep).is_err()
+                        || project.get_task(candidate.duplicate).is_err()
+                    {
+                        continue;
+                    }
+                    let question = format!(
+                        "Merge task {} into task {}?",
+                        candidate.duplicate, candidate.keep
+                    );
+                    if !*yes && !confirm(&question)? {
+                        continue;
+                    }
+                    match project.merge_tasks(candidate.keep, candidate.duplicate) {
+                        Ok(_) => {
+                            merged += 1;
+                            println!(
+                                "Merged task {} into task {}",
+                                candidate.duplicate, candidate.keep
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                if merged > 0 {
+                    storage.save_project(&project)?;
+                }
+                println!("Merged {} task(s)", merged);
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SplitTask {
+            project_id,
+            task_id,
+            parts,
+            by_checklist,
+            yes,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let strategy = match (parts, by_checklist) {
+                    (Some(parts), _) => SplitStrategy::Parts(*parts),
+                    (None, true) => SplitStrategy::Checklist,
+                    (None, false) => match project.get_task(*task_id) {
+                        Ok(task) => SplitStrategy::suggest(task, split::DEFAULT_MAX_HOURS),
+                        Err(e) => return Err(e),
+                    },
+                };
+
+                let plan = SplitPlan::new(&project, *task_id, strategy)?;
+
+                println!("Task {} will be split into:", task_id);
+                for subtask in &plan.subtasks {
+                    match subtask.estimate_hours {
+                        Some(hours) => println!(
+                            "  ID: {}, Title: {} ({})",
+                            subtask.id,
+                            subtask.title,
+                            locale::hours(hours, 1)
+                        ),
+                        None => println!("  ID: {}, Title: {}", subtask.id, subtask.title),
+                    }
+                }
+
+                if !*yes && !confirm("Create these subtasks?")? {
+                    println!("Cancelled");
+                    return Ok(());
+                }
+
+                let created = plan.apply(&mut project)?;
+                storage.save_project(&project)?;
+                println!(
+                    "Created {} subtasks; task {} now depends on them",
+                    created.len(),
+                    task_id
+                );
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SetRecurrence {
+            project_id,
+            task_id,
+            recurrence,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let pattern = if recurrence.eq_ignore_ascii_case("none") {
+                    Ok(None)
+                } else {
+                    recurrence.parse::<RecurrencePattern>().map(Some)
+                };
+
+                match pattern.and_then(|p| project.set_task_recurrence(*task_id, p)) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        println!("Recurrence for task {} set to {}", task_id, recurrence);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SetHabit {
+            project_id,
+            task_id,
+            skips,
+            off,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match project.set_task_habit(*task_id, (!*off).then_some(*skips)) {
+                Ok(_) => {
+                    storage.save_project(&project)?;
+                    if *off {
+                        println!("Task {} is no longer tracked as a habit", task_id);
+                    } else {
+                        println!(
+                            "Task {} is tracked as a habit ({} skip(s) allowed)",
+                            task_id, skips
+                        );
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::Habits {
+            project_id,
+            periods,
+        } => {
+            let projects: Vec<Project> = match project_id {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage
+                    .list_projects()?
+                    .into_iter()
+                    .filter(|p| !p.archived)
+                    .collect(),
+            };
+
+            let today = Local::now().date_naive();
+            let mut found = false;
+            for project in &projects {
+                // The open occurrence carries each habit's record
+                let habits: Vec<_> = project
+                    .tasks
+                    .iter()
+                    .filter(|t| t.status != TaskStatus::Done)
+                    .filter_map(|t| Some((t, t.habit.as_ref()?, t.recurrence.as_ref()?)))
+                    .collect();
+                if habits.is_empty() {
+                    continue;
+                }
+                found = true;
+                println!(
+                    "Habits in {}:",
+                    appearance::paint(&project.label(), project.color)
+                );
+                for (task, habit, pattern) in habits {
+                    let streak = habit.streak(pattern, today);
+                    let unit = habits::period_name(pattern);
+                    println!("  {} - {} ({})", task.id, task.title, pattern);
+                    println!(
+                        "      Streak: {} {}(s), best {}; {}/{} skip(s) used",
+                        streak.current, unit, streak.best, streak.skips_used, habit.skips
+                    );
+                    println!(
+                        "      Last {} {}s: {}",
+                        periods,
+                        unit,
+                        habit.calendar(pattern, today, *periods)
+                    );
+                }
+            }
+            if !found {
+                println!("No habits found; track a recurring task with set-habit");
+            }
+        }
+
+        Commands::MoveTask {
+            project_id,
+            task_id,
+            to,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let result = match to.to_lowercase().as_str() {
+                    "up" => project.move_task_up(*task_id),
+                    "down" => project.move_task_down(*task_id),
+                    position => match position.parse::<usize>() {
+                        Ok(position) if position > 0 => {
+                            project.move_task_to(*task_id, position - 1)
+                        }
+                        _ => Err(TaskMasterError::InvalidOperation(format!(
+                            "Invalid position '{}'; use up, down or a number from 1",
+                            to
+                        ))),
+                    },
+                };
+
+                match result.and_then(|_| project.task_position(*task_id)) {
+                    Ok(position) => {
+                        storage.save_project(&project)?;
+                        println!("Task {} is now at position {}", task_id, position + 1);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Rank { action } => match action {
+            RankAction::Move {
+                project_id,
+                task_id,
+                before,
+                after,
+                top,
+                bottom: _,
+            } => match storage.load_project(*project_id) {
+                Ok(mut project) => {
+                    let result = match (before, after) {
+                        (Some(other), _) => project.move_task_next_to(*task_id, *other, false),
+                        (_, Some(other)) => project.move_task_next_to(*task_id, *other, true),
+                        _ if *top => project.move_task_to(*task_id, 0),
+                        _ => project.move_task_to(*task_id, usize::MAX),
+                    };
+                    match result.and_then(|_| project.task_position(*task_id)) {
+                        Ok(position) => {
+                            storage.save_project(&project)?;
+                            let rank = project.get_task(*task_id)?.rank.clone();
+                            println!(
+                                "Task {} is now at position {} (rank {})",
+                                project.task_ref(*task_id),
+                                position + 1,
+                                rank
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            RankAction::List { project_id } => match storage.load_project(*project_id) {
+                Ok(project) => {
+                    if project.tasks.is_empty() {
+                        println!("No tasks");
+                    }
+                    for (position, task) in project.tasks.iter().enumerate() {
+                        println!(
+                            "  {:>3}. {:<10} {} - {}",
+                            position + 1,
+                            task.rank,
+                            project.task_ref(task.id),
+                            task.title
+                        );
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            RankAction::Rebalance { project_id } => match storage.load_project(*project_id) {
+                Ok(mut project) => {
+                    project.rerank_tasks();
+                    storage.save_project(&project)?;
+                    println!(
+                        "Re-ranked {} task(s) in project {}",
+                        project.tasks.len(),
+                        project_id
+                    );
+                }
+                Err(e) => return Err(e),
+            },
+        },
+
+        Commands::CreateRecurringProject {
+            name,
+            template_project,
+            recurrence,
+            start,
+        } => {
+            storage.load_project(*template_project)?;
+
+            let pattern = recurrence.parse::<RecurrencePattern>();
+            let first_run = match start {
+                Some(start) => dates::parse_datetime(start).map(SystemTime::from),
+                None => Ok(SystemTime::now()),
+            };
+
+            match (pattern, first_run) {
+                (Ok(pattern), Ok(first_run)) => {
+                    let mut recurring = RecurringProjects::load(&cli.data_dir)?;
+                    let id = recurring
+                        .add(name, *template_project, pattern, first_run)?
+                        .id;
+                    recurring.save(&cli.data_dir)?;
+                    println!("Recurring project created: {} (ID: {})", name, id);
+                }
+                (Err(e), _) | (_, Err(e)) => return Err(e),
+            }
+        }
+
+        Commands::ListRecurringProjects => {
+            let recurring = RecurringProjects::load(&cli.data_dir)?;
+            let mut entries = recurring.iter().peekable();
+            if entries.peek().is_none() {
+                println!("No recurring projects found");
+            }
+            for entry in entries {
+                println!(
+                    "  ID: {}, Name: {}, Template: {}, Repeats: {}, Next run: {}, Instances: {:?}",
+                    entry.id,
+                    entry.name,
+                    entry.template_project,
+                    entry.pattern,
+                    dates::format_local(&DateTime::<Utc>::from(entry.next_run)),
+                    entry.instances
+                );
+            }
+        }
+
+        Commands::DeleteRecurringProject { id } => {
+            let mut recurring = RecurringProjects::load(&cli.data_dir)?;
+            match recurring.remove(*id) {
+                Ok(entry) => {
+                    recurring.save(&cli.data_dir)?;
+                    println!("Recurring project deleted: {}", entry.name);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::RunRecurringProjects => {
+            let mut recurring = RecurringProjects::load(&cli.data_dir)?;
+            let result = recurring.run_due(storage.as_mut());
+            // Save progress even if a later entry failed
+            recurring.save(&cli.data_dir)?;
+
+            match result {
+                Ok(created) if created.is_empty() => println!("No recurring projects are due"),
+                Ok(created) => {
+                    for project in created {
+                        println!("Project created: {} (ID: {})", project.name, project.id);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::AddGoal { name, quarter } => {
+            let quarter = match quarter {
+                Some(quarter) => Quarter::parse(quarter),
+                None => Ok(Quarter::containing(Local::now().date_naive())),
+            };
+            match quarter {
+                Ok(quarter) => {
+                    let mut goals = Goals::load(&cli.data_dir)?;
+                    let id = goals.add(name, quarter)?.id;
+                    goals.save(&cli.data_dir)?;
+                    println!("Goal created: {} for {} (ID: {})", name, quarter, id);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::LinkGoal {
+            goal_id,
+            project_id,
+            task,
+            weight,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let target = match task {
+                    Some(task_id) => {
+                        project.get_task(*task_id)?;
+                        GoalTarget::Task {
+                            project: *project_id,
+                            task: *task_id,
+                        }
+                    }
+                    None => GoalTarget::Project(*project_id),
+                };
+                let mut goals = Goals::load(&cli.data_dir)?;
+                match goals.link(*goal_id, target, *weight) {
+                    Ok(_) => {
+                        goals.save(&cli.data_dir)?;
+                        println!("Goal {} linked to {} (weight {})", goal_id, target, weight);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::UnlinkGoal {
+            goal_id,
+            project_id,
+            task,
+        } => {
+            let target = match task {
+                Some(task_id) => GoalTarget::Task {
+                    project: *project_id,
+                    task: *task_id,
+                },
+                None => GoalTarget::Project(*project_id),
+            };
+            let mut goals = Goals::load(&cli.data_dir)?;
+            match goals.unlink(*goal_id, target) {
+                Ok(_) => {
+                    goals.save(&cli.data_dir)?;
+                    println!("Goal {} unlinked from {}", goal_id, target);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::DeleteGoal { id } => {
+            let mut goals = Goals::load(&cli.data_dir)?;
+            match goals.remove(*id) {
+                Ok(goal) => {
+                    goals.save(&cli.data_dir)?;
+                    println!("Goal deleted: {}", goal.name);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::Goals { quarter, json } => {
+            let quarter = quarter.as_deref().map(Quarter::parse).transpose()?;
+            let goals = Goals::load(&cli.data_dir)?;
+            let projects = storage.list_projects()?;
+            let progress: Vec<GoalProgress> = goals
+                .iter()
+                .filter(|g| quarter.is_none_or(|q| g.quarter == q))
+                .map(|g| g.progress(&projects))
+                .collect();
+
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&progress)?);
+            } else if progress.is_empty() {
+                println!("No goals found");
+            } else {
+                for goal in &progress {
+                    println!("{}", goal.describe());
+                    if goal.links.is_empty() {
+                        println!("    Nothing linked yet; use link-goal");
+                    }
+                    for link in &goal.links {
+                        let done = link
+                            .progress
+                            .map_or("-".to_string(), |p| format!("{:.0}%", p * 100.0));
+                        println!("    {} (weight {}): {}", link.label, link.weight, done);
+                    }
+                }
+            }
+        }
+
+        Commands::VaultSync { vault } => {
+            let vault = match vault.canonicalize() {
+                Ok(vault) => vault,
+                Err(e) => {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "{}: {}",
+                        vault.display(),
+                        e
+                    )));
+                }
+            };
+            let mut state = VaultState::load(&cli.data_dir)?;
+            let result = vault::sync_vault(&vault, storage.as_mut(), &mut state);
+            // Remember what was synced even if a later note failed
+            state.save(&cli.data_dir)?;
+
+            match result {
+                Ok(reports) if reports.is_empty() => println!("No notes found"),
+                Ok(reports) => {
+                    for report in reports {
+                        let note = report.note.strip_prefix(&vault).unwrap_or(&report.note);
+                        if report.changes.is_empty() {
+                            println!(
+                                "{} (project {}): up to date",
+                                note.display(),
+                                report.project
+                            );
+                            continue;
+                        }
+                        println!("{} (project {}):", note.display(), report.project);
+                        for change in report.changes {
+                            println!("  {}", change);
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::SetDue {
+            project_id,
+            task_id,
+            due,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                match dates::parse_due(due).and_then(|due| project.set_task_due(*task_id, due)) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        println!("Due date for task {} updated", task_id);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::SetReminders {
+            project_id,
+            task_id,
+            schedule,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let rules = if schedule.eq_ignore_ascii_case("default") {
+                    Ok(None)
+                } else {
+                    reminders::parse_schedule(schedule).map(Some)
+                };
+
+                match rules.and_then(|rules| project.set_task_reminders(*task_id, rules)) {
+                    Ok(_) => {
+                        storage.save_project(&project)?;
+                        println!("Reminder schedule for task {} updated", task_id);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Remind { project_id } => {
+            let projects = match project_id {
+                Some(id) => vec![storage.load_project(*id)?],
+                None => storage.list_projects()?,
+            };
+
+            let now = Utc::now();
+            let mut sent = 0;
+            let mut urgent = false;
+            let mut review = ReviewQueue::load(&cli.data_dir)?;
+            let wip_limit = chrono::Duration::hours(config.wip_age_limit_hours);
+            for mut project in projects {
+                for (task_id, age) in review.flag_aging_wip(&project, wip_limit, now) {
+                    let title = project.get_task(task_id).map_or("", |t| t.title.as_str());
+                    println!(
+                        "[{}] Task {} '{}' has been in progress for {}; added to the review queue",
+                        appearance::paint(&project.label(), project.color),
+                        task_id,
+                        title,
+                        dates::format_age(age)
+                    );
+                    sent += 1;
+                }
+
+                let due = reminders::collect_reminders(&mut project, &config.reminders, now);
+                if due.is_empty() {
+                    continue;
+                }
+                let scale = &project.settings.priority_scale;
+                for reminder in &due {
+                    println!(
+                        "[{}] {}",
+                        appearance::paint(&project.label(), project.color),
+                        reminder.message()
+                    );
+                    urgent |= !reminder.habit
+                        && project.get_task(reminder.task_id).is_ok_and(|task| {
+                            scale.weight(&task.priority) >= scale.weight(&TaskPriority::High)
+                        });
+                }
+                sent += due.len();
+                storage.save_project(&project)?;
+            }
+
+            review.save(&cli.data_dir)?;
+
+            if sent == 0 {
+                println!("No reminders due");
+            } else if urgent && config.bell {
+                ring_bell();
+            }
+        }
+
+        Commands::Review => {
+            let review = ReviewQueue::load(&cli.data_dir)?;
+            if review.items().is_empty() {
+                println!("Nothing to review");
+                return Ok(());
+            }
+
+            let now = Utc::now();
+            for item in review.items() {
+                let (label, task) = match storage.load_project(item.project) {
+                    Ok(project) => {
+                        let task = project.get_task(item.task).ok().cloned();
+                        (appearance::paint(&project.label(), project.color), task)
+                    }
+                    Err(_) => (format!("project {}", item.project), None),
+                };
+                let title = task.as_ref().map_or("(deleted)", |t| t.title.as_str());
+                let mut line = format!(
+                    "[{}] Task {} '{}': {}",
+                    label,
+                    item.task,
+                    title,
+                    item.reason.describe()
+                );
+                if let Some(age) = task.and_then(|t| t.wip_age(now)) {
+                    line.push_str(&format!(" ({})", dates::format_age(age)));
+                }
+                line.push_str(&format!(", queued {}", dates::format_local(&item.added)));
+                println!("{}", line);
+            }
+        }
+
+        Commands::DismissReview {
+            project_id,
+            task_id,
+        } => {
+            let mut review = ReviewQueue::load(&cli.data_dir)?;
+            if review.dismiss(*project_id, *task_id) == 0 {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Task {} in project {} is not in the review queue",
+                    task_id, project_id
+                )));
+            }
+            review.save(&cli.data_dir)?;
+            println!("Task {} taken off the review queue", task_id);
+        }
+
+        Commands::Block {
+            project_id,
+            task_id,
+            reason,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match project.set_task_blocked(*task_id, Some(reason.clone())) {
+                Ok(_) => {
+                    storage.save_project(&project)?;
+                    println!("Task {} blocked: {}", task_id, reason);
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::Unblock {
+            project_id,
+            task_id,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => match project.set_task_blocked(*task_id, None) {
+                Ok(_) => {
+                    storage.save_project(&project)?;
+                    println!("Task {} unblocked", task_id);
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        },
+
+        Commands::EditProject { project_id } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let buffer = editing::EditBuffer::create(&project)?;
+
+                // Keep re-opening the document until it is valid or the user gives up
+                let outcome = loop {
+                    if let Err(e) = editing::open_editor(buffer.path()) {
+                        buffer.remove()?;
+                        return Err(e);
+                    }
+                    match editing::apply_edit(&project, &buffer.read()?) {
+                        Ok(result) => break Some(result),
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            if !confirm("Re-open the editor to fix it?")? {
+                                break None;
+                            }
+                        }
+                    }
+                };
+
+                match outcome {
+                    Some((_, summary)) if summary.is_empty() => {
+                        buffer.remove()?;
+                        println!("No changes to project {}", project_id)
+                    }
+                    Some((edited, summary)) => {
+                        // Saving now would undo whatever was saved while the editor was open
+                        let current = storage.load_project(*project_id)?;
+                        if serde_json::to_value(&current)? != serde_json::to_value(&project)? {
+                            return Err(TaskMasterError::InvalidOperation(format!(
+                                "Project {} changed while it was being edited, so nothing was saved; the edited document is kept at {}",
+                                project_id,
+                                buffer.path().display()
+                            )));
+                        }
+                        storage.save_project(&edited)?;
+                        buffer.remove()?;
+                        println!("Project {} updated", project_id);
+                        summary.display();
+                    }
+                    None => {
+                        buffer.remove()?;
+                        println!("Edit discarded; project {} is unchanged", project_id)
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Diff {
+            project_a,
+            project_b,
+            at,
+            json,
+        } => {
+            let current = storage.load_project(*project_a)?;
+
+            let diff = match (project_b, at) {
+                (Some(other_id), _) => match storage.load_project(*other_id) {
+                    Ok(other) => ProjectDiff::compare(
+                        &current,
+                        &other,
+                        format!("project {} ({})", current.id, current.name),
+                        format!("project {} ({})", other.id, other.name),
+                    ),
+                    Err(e) => return Err(e),
+                },
+                (None, Some(at)) => match dates::parse_datetime(at) {
+                    Ok(at) => ProjectDiff::compare(
+                        &diff::project_as_of(&current, at),
+                        &current,
+                        format!("project {} at {}", current.id, dates::format_local(&at)),
+                        format!("project {} now", current.id),
+                    ),
+                    Err(e) => return Err(e),
+                },
+                (None, None) => {
+                    return Err(TaskMasterError::InvalidOperation(
+                        "Give a second project ID or --at <time>".to_string(),
+                    ));
+                }
+            };
+
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                diff.display();
+            }
+        }
+
+        Commands::Merge {
+            source_id,
+            destination_id,
+            tag_prefix,
+            delete,
+        } => {
+            let loaded = storage
+                .load_project(*source_id)
+                .and_then(|source| Ok((source, storage.load_project(*destination_id)?)));
+            match loaded {
+                Ok((mut source, mut destination)) => {
+                    let moved = source.tasks.len();
+                    match destination.merge_from(&mut source, tag_prefix.as_deref()) {
+                        Ok(renumbered) => {
+                            // Save the destination first so a failure can't lose the tasks
+                            storage.save_project(&destination)?;
+                            let projects = storage.list_projects()?;
+                            let children = hierarchy::children(&projects, source.id);
+                            for child in &children {
+                                let mut child = (*child).clone();
+                                child.parent_id = Some(destination.id);
+                                storage.save_project(&child)?;
+                            }
+                            if *delete {
+                                storage.delete_project(source.id)?;
+                            } else {
+                                source.archived = true;
+                                storage.save_project(&source)?;
+                            }
+
+                            println!(
+                                "Moved {} task(s) from project {} into project {}",
+                                moved, source_id, destination_id
+                            );
+                            for (old, new) in renumbered {
+                                println!("  Task {} is now task {}", old, new);
+                            }
+                            for child in children {
+                                println!(
+                                    "  Project {} moved under project {}",
+                                    child.id, destination_id
+                                );
+                            }
+                            if *delete {
+                                println!("Project {} deleted", source_id);
+                            } else {
+                                println!("Project {} archived", source_id);
+                            }
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::SetRules { project_id, rules } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                if let Some(rules) = rules {
+                    let parsed = if rules.trim().eq_ignore_ascii_case("none") {
+                        Ok(Vec::new())
+                    } else {
+                        rules
+                            .split(',')
+                            .map(|rule| {
+                                ValidationRule::parse(rule, &project.settings.priority_scale)
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    };
+                    match parsed {
+                        Ok(parsed) => {
+                            project.settings.rules = parsed;
+                            storage.save_project(&project)?;
+                            println!("Rules for project {} updated", project_id);
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+
+                if project.settings.rules.is_empty() {
+                    println!("Project {} has no rules", project_id);
+                } else {
+                    println!("Rules for project {}:", project_id);
+                    for rule in &project.settings.rules {
+                        println!("  {}", rule);
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Capture {
+            text,
+            no_duplicate_check,
+        } => {
+            let config = Config::load(&cli.data_dir)?;
+            let capture = Capture::parse(&text.join(" "));
+            if capture.title.is_empty() {
+                return Err(TaskMasterError::InvalidOperation(
+                    "The task needs a title besides its tags".to_string(),
+                ));
+            }
+
+            let rule = routing::route(&config.routes, &capture);
+            let Some(project_id) = rule.map(|r| r.project).or(config.inbox) else {
+                return Err(TaskMasterError::InvalidOperation(
+                    "No route matched and no inbox project is set in config.json".to_string(),
+                ));
+            };
+
+            match storage.load_project(project_id) {
+                Ok(mut project) => {
+                    let id = project.next_task_id()?;
+                    let result = captured_task(&project, id, &capture, rule)
+                        .and_then(|task| project.add_task(task));
+
+                    match result {
+                        Ok(_) => {
+                            let mut others = storage.list_projects()?;
+                            storage.save_project(&project)?;
+                            let how = if rule.is_some() { "routed" } else { "inbox" };
+                            println!(
+                                "Task added to project {} ({}): {} (ID: {})",
+                                project_id, how, capture.title, id
+                            );
+                            if !*no_duplicate_check {
+                                let tags = &project.get_task(id)?.tags;
+                                others.retain(|p| p.id != project_id);
+                                others.push(project.clone());
+                                warn_similar(&others, &capture.title, tags, Some((project_id, id)));
+                            }
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Commands::Locale => {
+            let locale = locale::current();
+            let source = match &config.locale.preset {
+                Some(_) => "config.json",
+                None => "the environment",
+            };
+            println!("Locale: {} (preset from {})", locale.name, source);
+            println!("  Date format: {}", locale.date_format);
+            println!("  Clock: {}", locale.clock);
+            println!("  Week starts on: {}", locale.week_start);
+            println!("  Decimal separator: '{}'", locale.decimal_separator);
+            match locale.thousands_separator {
+                Some(separator) => println!("  Thousands separator: '{}'", separator),
+                None => println!("  Thousands separator: none"),
+            }
+            println!(
+                "  Example: {}, {}",
+                locale.datetime(&Utc::now()),
+                locale.hours(1234.5, 1)
+            );
+        }
+
+        Commands::Routes => {
+            let config = Config::load(&cli.data_dir)?;
+            if config.routes.is_empty() {
+                println!("No routing rules configured");
+            } else {
+                println!("Routing rules (first match wins):");
+                for (position, rule) in config.routes.iter().enumerate() {
+                    println!("  {}. {}", position + 1, rule.describe());
+                }
+            }
+            match config.inbox {
+                Some(inbox) => println!("Unmatched tasks go to project {}", inbox),
+                None => println!("No inbox project set; unmatched captures are rejected"),
+            }
+        }
+
+        Commands::History {
+            project_id,
+            task_id,
+            diff,
+        } => match storage
+            .load_project(*project_id)
+            .and_then(|project| project.get_task(*task_id).cloned())
+        {
+            Ok(task) => {
+                println!("History of task {}: {}", task.id, task.title);
+                println!("  {}  created", dates::format_local(&task.created_at));
+
+                for edit in task.timeline() {
+                    let when = dates::format_local(&edit.changed_at);
+                    if *diff {
+                        println!("@@ {} @@", when);
+                        println!("- {}: {}", edit.field, edit.before);
+                        println!("+ {}: {}", edit.field, edit.after);
+                    } else {
+                        println!(
+                            "  {}  {}: {} -> {}",
+                            when, edit.field, edit.before, edit.after
+                        );
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::DefineField {
+            project_id,
+            name,
+            kind,
+        } => match storage.load_project(*project_id) {
+            Ok(mut project) => {
+                let schema = &mut project.settings.fields;
+                schema.retain(|field| !field.name.eq_ignore_ascii_case(name));
+                if !kind.eq_ignore_ascii_case("none") {
+                    match kind.parse::<FieldKind>() {
+                        Ok(kind) => schema.push(FieldDefinition {
+                            name: name.clone(),
+                            kind,
+                        }),
+                        Err(e) => return Err(e),
+                    }
+                }
+                storage.save_project(&project)?;
+
+                if project.settings.fields.is_empty() {
+                    println!("Project {} accepts any custom field as text", project_id);
+                } else {
+                    println!("Custom fields for project {}:", project_id);
+                    for field in &project.settings.fields {
+                        println!("  {}: {}", field.name, field.kind);
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Run {
+            project_id,
+            task_ids,
+            pick,
+            all,
+            workers,
+            retries,
+            policy,
+            also,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let registry = default_handlers();
+                let log = EventLog::new(&cli.data_dir);
+                if *all {
+                    let executor =
+                        TaskExecutor::new((*workers).max(1), 3600).with_retries(*retries);
+                    let started = Instant::now();
+                    let policy: Box<dyn ReadyQueuePolicy> = match policy {
+                        RunPolicy::Priority => Box::new(PriorityFirst),
+                        RunPolicy::Shortest => Box::new(ShortestJobFirst),
+                        RunPolicy::CriticalPath => Box::new(CriticalPathFirst),
+                    };
+                    let mut projects = vec![project];
+                    for id in also.iter().filter(|id| *id != project_id) {
+                        projects.push(storage.load_project(*id)?);
+                    }
+                    let shared: Vec<&Project> = projects.iter().collect();
+                    let results =
+                        executor.execute_projects_with(&shared, &registry, policy.as_ref())?;
+                    let mut failed = 0;
+                    for (id, result) in &results {
+                        log_result(&log, *id, result)?;
+                        let Some(project) = projects.iter().find(|p| p.id == *id) else {
+                            continue;
+                        };
+                        let title = project
+                            .get_task(result.task_id)
+                            .map(|t| t.title.clone())
+                            .unwrap_or_default();
+                        if !result.outcome.is_success() {
+                            failed += 1;
+                        }
+                        // Which project a task belongs to only needs saying once there are several
+                        let owner = if projects.len() > 1 {
+                            format!("{}: ", appearance::paint(&project.label(), project.color))
+                        } else {
+                            String::new()
+                        };
+                        println!(
+                            "[{}] {}Task {} '{}' {}",
+                            result.run_id,
+                            owner,
+                            project.task_ref(result.task_id),
+                            title,
+                            result.outcome
+                        );
+                    }
+                    println!(
+                        "Ran {} task(s) in {}s: {} succeeded, {} failed",
+                        results.len(),
+                        locale::number(started.elapsed().as_secs_f64(), 1),
+                        results.len() - failed,
+                        failed
+                    );
+                    for project in projects.iter_mut() {
+                        let project_id = project.id;
+                        let mut added = false;
+                        for (_, result) in results.iter().filter(|(id, _)| *id == project_id) {
+                            added |= add_follow_ups(project, result, &config.follow_ups);
+                        }
+                        if added {
+                            storage.save_project(project)?;
+                        }
+                    }
+                    if failed > 0 && config.bell {
+                        ring_bell();
+                    }
+                    return Ok(());
+                }
+
+                let runnable: Vec<&Task> = project
+                    .ready_tasks()
+                    .into_iter()
+                    .filter(|t| registry.has_handler(t))
+                    .collect();
+
+                let selected = if *pick {
+                    if runnable.is_empty() {
+                        println!("No tasks can run right now");
+                        return Ok(());
+                    }
+                    println!("Tasks ready to run:");
+                    for task in &runnable {
+                        println!(
+                            "  ID: {}, Title: {}, Priority: {}",
+                            task.id,
+                            task.title,
+                            project.priority_label(task)
+                        );
+                    }
+                    let ids: Vec<u32> = runnable.iter().map(|t| t.id).collect();
+                    prompt_selection(&ids)??
+                } else {
+                    for id in task_ids {
+                        if !runnable.iter().any(|t| t.id == *id) {
+                            let task = project.get_task(*id)?;
+                            return Err(TaskMasterError::InvalidOperation(format!(
+                                "Task {} cannot run now ({})",
+                                id,
+                                task.status_label(&project.tasks)
+                            )));
+                        }
+                    }
+                    task_ids.clone()
+                };
+
+                if selected.is_empty() {
+                    println!("Nothing selected");
+                    return Ok(());
+                }
+
+                let executor = TaskExecutor::new((*workers).max(1), 3600).with_retries(*retries);
+                for id in &selected {
+                    let task = project.get_task(*id)?.clone();
+                    let handler = registry
+                        .get_handler_for_task(&task)
+                        .map(|h| h.clone_box())
+                        .ok_or_else(|| {
+                            TaskMasterError::InvalidOperation(format!(
+                                "No handler available for task: {}",
+                                id
+                            ))
+                        })?;
+                    let run_id = executor.execute_with_handler(task, handler)?;
+                    println!("[{}] Queued task {}", run_id, id);
+                    log.append_for_project(
+                        *project_id,
+                        &TaskEvent::Started {
+                            task_id: *id,
+                            run_id,
+                        },
+                    )?;
+                }
+
+                let started = Instant::now();
+                let mut failed = 0;
+                let mut results = Vec::new();
+                for finished in 1..=selected.len() {
+                    let result = executor.wait_for_result()?;
+                    log_result(&log, *project_id, &result)?;
+                    let title = project
+                        .get_task(result.task_id)
+                        .map(|t| t.title.clone())
+                        .unwrap_or_default();
+                    if result.outcome.is_success() {
+                        println!(
+                            "[{}/{}] [{}] Task {} '{}' finished after {}s",
+                            finished,
+                            selected.len(),
+                            result.run_id,
+                            result.task_id,
+                            title,
+                            locale::number(started.elapsed().as_secs_f64(), 1)
+                        );
+                    } else {
+                        failed += 1;
+                        println!(
+                            "[{}/{}] [{}] Task {} '{}' {}",
+                            finished,
+                            selected.len(),
+                            result.run_id,
+                            result.task_id,
+                            title,
+                            result.outcome
+                        );
+                    }
+                    results.push(result);
+                }
+
+                println!(
+                    "Ran {} task(s): {} succeeded, {} failed",
+                    selected.len(),
+                    selected.len() - failed,
+                    failed
+                );
+                let mut project = project;
+                let mut added = false;
+                for result in &results {
+                    added |= add_follow_ups(&mut project, result, &config.follow_ups);
+                }
+                if added {
+                    storage.save_project(&project)?;
+                }
+                if failed > 0 && config.bell {
+                    ring_bell();
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Replay {
+            from,
+            to,
+            sink,
+            output,
+        } => {
+            replay_events(&cli.data_dir, from.as_deref(), to.as_deref(), sink, output).await?;
+        }
+
+        Commands::Watch {
+            project_id,
+            interval,
+        } => {
+            storage.load_project(*project_id)?;
+            // Storage messages would scroll the view
+            logs::set_echo(false);
+            watch::watch(
+                storage.as_ref(),
+                &EventLog::new(&cli.data_dir),
+                *project_id,
+                Duration::from_secs((*interval).max(1)),
+            )?;
+        }
+
+        Commands::Export {
+            project_id,
+            format,
+            output,
+        } => match storage.load_project(*project_id) {
+            Ok(project) => {
+                let document = match format {
+                    ExportFormat::Csv => export::to_csv(&project),
+                    ExportFormat::Md => export::to_markdown(&project),
+                    ExportFormat::Ics => export::to_ical(&project, Utc::now()),
+                };
+                match output {
+                    Some(path) => {
+                        fs::write(path, document)?;
+                        println!("Project {} exported to {}", project_id, path.display());
+                    }
+                    None => print!("{}", document),
+                }
+            }
+            Err(e) => return Err(e),
+        },
+
+        Commands::Import {
+            project_id,
+            file,
+            format,
+            mappings,
+            dry_run,
+            no_duplicate_check,
+        } => {
+            let mut project = storage.load_project(*project_id)?;
+            let text = if file.as_os_str() == "-" {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text)?;
+                text
+            } else {
+                match fs::read_to_string(file) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        return Err(TaskMasterError::InvalidOperation(format!(
+                            "Could not read {}: {}",
+                            file.display(),
+                            e
+                        )));
+                    }
+                }
+            };
+            let json = file
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+            let format = format.unwrap_or(if json {
+                ImportFormat::Json
+            } else {
+                ImportFormat::Csv
+            });
+            let rows = match format {
+                ImportFormat::Csv => import::parse_csv(&text),
+                ImportFormat::Json => import::parse_json(&text),
+            };
+            let report = rows.and_then(|mut rows| {
+                import::apply_mappings(&mut rows, mappings)?;
+                import::import_rows(&mut project, &rows)
+            });
+            let report = report?;
+
+            let row_name = if format == ImportFormat::Csv {
+                "Line"
+            } else {
+                "Task"
+            };
+            if !report.ignored.is_empty() {
+                println!(
+                    "Ignoring column(s) {}; use --map to read them",
+                    report.ignored.join(", ")
+                );
+            }
+            let existing = if *no_duplicate_check {
+                Vec::new()
+            } else {
+                storage.list_projects()?
+            };
+            // In file order, whether the row worked or not
+            let mut outcomes: Vec<(usize, std::result::Result<u32, &String>)> = report
+                .created
+                .iter()
+                .map(|(row, id)| (*row, Ok(*id)))
+                .chain(report.failed.iter().map(|(row, e)| (*row, Err(e))))
+                .collect();
+            outcomes.sort_by_key(|(row, _)| *row);
+            for (row, outcome) in outcomes {
+                match outcome {
+                    Ok(id) => {
+                        let task = project.get_task(id)?;
+                        println!(
+                            "{} {}: {} task {}: {}",
+                            row_name,
+                            row,
+                            if *dry_run { "would create" } else { "created" },
+                            project.task_ref(id),
+                            task.title
+                        );
+                        if !*no_duplicate_check {
+                            warn_similar(&existing, &task.title, &task.tags, None);
+                        }
+                    }
+                    Err(error) => println!("{} {}: error: {}", row_name, row, error),
+                }
+            }
+
+            let total = report.created.len() + report.failed.len();
+            if *dry_run {
+                println!(
+                    "Dry run: {} of {} row(s) would be imported into project {}; nothing was saved",
+                    report.created.len(),
+                    total,
+                    project_id
+                );
+            } else {
+                if !report.created.is_empty() {
+                    storage.save_project(&project)?;
+                }
+                println!(
+                    "Imported {} of {} row(s) into project {}",
+                    report.created.len(),
+                    total,
+                    project_id
+                );
+            }
+            // The rows that worked are kept, but scripts need to know some didn't
+            if !report.failed.is_empty() {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "{} of {} row(s) could not be imported",
+                    report.failed.len(),
+                    total
+                )));
+            }
+        }
+
+        // Handled before storage is opened
+        Commands::Completions { .. } | Commands::Complete { .. } => {}
+    }
+
+    Ok(())
+}
+
+// Feed events from the event log back through a NotificationSystem wired to the chosen sink
+async fn replay_events(
+    data_dir: &PathBuf,
+    from: Option<&str>,
+    to: Option<&str>,
+    sink: &ReplaySink,
+    output: &Option<PathBuf>,
+) -> Result<()> {
+    let from = from.map(dates::parse_datetime).transpose()?;
+    let to = to.map(dates::parse_datetime).transpose()?;
+
+    // With the json sink stdout may carry the events, so everything else goes to stderr
+    let json = matches!(sink, ReplaySink::Json);
+    let status = |line: String| {
+        if json {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    };
+
+    let events = EventLog::new(data_dir).read_range(from, to)?;
+    if events.is_empty() {
+        status("No events to replay".to_string());
+        return Ok(());
+    }
+
+    status(format!(
+        "Replaying {} events recorded between {} and {}",
+        events.len(),
+        dates::format_local(&events[0].recorded_at),
+        dates::format_local(&events[events.len() - 1].recorded_at)
+    ));
+
+    // The channel holds every event, so all sends complete before the system starts
+    let (event_tx, event_rx) = mpsc::channel(events.len());
+    let mut notification_system = NotificationSystem::new(event_rx);
+    if json {
+        notification_system = notification_system.quiet();
+    }
+
+    match sink {
+        ReplaySink::Log => {
+            notification_system.register_callback("replay_log", |event| {
+                println!("REPLAY: {}", event);
+            });
+        }
+        ReplaySink::Json => {
+            let writer: Box<dyn Write + Send> = match output {
+                Some(path) => Box::new(File::create(path)?),
+                None => Box::new(io::stdout()),
+            };
+            let writer = Mutex::new(writer);
+            notification_system.register_callback("replay_json", move |event| {
+                let mut writer = writer.lock().unwrap();
+                match serde_json::to_string(event) {
+                    Ok(json) => {
+                        if let Err(e) = writeln!(writer, "{}", json) {
+                            eprintln!("Failed to write replayed event: {}", e);
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to serialize replayed event: {}", e),
+                }
+            });
+        }
+    }
+
+    for logged in events {
+        event_tx
+            .send(logged.event)
+            .await
+            .map_err(|_| TaskMasterError::ChannelError("Replay channel closed".to_string()))?;
+    }
+    drop(event_tx);
+
+    notification_system.start().await
+}
+
+// Record how a run ended in the event log, for `watch` and `replay`
+fn log_result(log: &EventLog, project_id: u32, result: &JobResult) -> Result<()> {
+    let event = result.outcome.event(result.task_id, result.run_id.clone());
+    log.append_for_project(project_id, &event)
+}
+
+// Add the follow-up tasks the handler and config rules ask for after a run to the project,
+// saying so and reporting any that couldn't be added; true if any were added
+fn add_follow_ups(project: &mut Project, result: &JobResult, rules: &[FollowUpRule]) -> bool {
+    let mut added = false;
+    for (title, outcome) in project.add_follow_ups(result, rules) {
+        match outcome {
+            Ok(id) => {
+                added = true;
+                println!(
+                    "Follow-up task {} '{}' added after task {} [{}]",
+                    project.task_ref(id),
+                    title,
+                    result.task_id,
+                    result.run_id
+                );
+            }
+            Err(e) => eprintln!(
+                "Follow-up '{}' after task {} [{}] not added: {}",
+                title, result.task_id, result.run_id, e
+            ),
+        }
+    }
+    added
+}
+
+// Print the events a change to the project raised and record them in the event log, so
+// they can be replayed through the notification system like those from runs
+fn announce_events(data_dir: &PathBuf, project: &mut Project) -> Result<()> {
+    let log = EventLog::new(data_dir);
+    for event in project.take_events() {
+        log.append_for_project(project.id, &event)?;
+        println!("{}", event);
+    }
+    Ok(())
+}
+
+// Open the data directory, layering on any storage decorators the config asks for
+#[cfg_attr(not(feature = "chaos"), allow(unused_variables))]
+fn open_storage(data_dir: &PathBuf, config: &Config) -> Result<Box<dyn Storage>> {
+    let storage = FileStorage::new(data_dir)?;
+
+    #[cfg(feature = "chaos")]
+    if config.chaos.enabled {
+        let chaos = ChaosMonkey::shared(config.chaos.clone());
+        return Ok(Box::new(ChaosStorage::new(storage, chaos)));
+    }
+
+    Ok(Box::new(storage))
+}
+
+// Build a workflow from CLI input, making sure existing tasks still fit in it
+fn build_workflow(
+    project: &Project,
+    states: &[String],
+    transitions: &[String],
+) -> Result<Workflow> {
+    let states = states
+        .iter()
+        .map(|s| s.parse::<TaskStatus>())
+        .collect::<Result<Vec<_>>>()?;
+
+    // Parse transitions against the new states rather than the current workflow
+    let draft = Workflow::new(states.clone(), Vec::new())?;
+    let transitions = transitions
+        .iter()
+        .map(|t| draft.parse_transition(t))
+        .collect::<Result<Vec<_>>>()?;
+
+    let workflow = Workflow::new(states, transitions)?;
+
+    if let Some(task) = project.tasks.iter().find(|t| !workflow.contains(&t.status)) {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Task {} is in status {} which the new workflow does not include",
+            task.id, task.status
+        )));
+    }
+
+    Ok(workflow)
+}
+
+// Resolve CLI status and priority strings against the project's workflow and scale
+// Split add-task's positional arguments into the optional task ID, the title, status and
+// priority. A leading number is the ID only when something follows it, so a task can still
+// be titled with just a number.
+fn split_task_args(args: &[String]) -> (Option<u32>, &str, Option<&str>, Option<&str>) {
+    let (id, rest) = match args.split_first() {
+        Some((first, rest)) if !rest.is_empty() => match first.parse() {
+            Ok(id) => (Some(id), rest),
+            Err(_) => (None, args),
+        },
+        _ => (None, args),
+    };
+    let arg = |i: usize| rest.get(i).map(String::as_str);
+    (id, arg(0).unwrap_or_default(), arg(1), arg(2))
+}
+
+fn parse_task_fields(
+    project: &Project,
+    status: &str,
+    priority: &str,
+) -> Result<(TaskStatus, TaskPriority)> {
+    let status = project.settings.workflow.parse_status(status)?;
+    let priority = project.settings.priority_scale.parse(priority)?;
+    Ok((status, priority))
+}
+
+// Build a captured task, applying the defaults of the rule that routed it
+// Point out open tasks that look like the one just added, with the command to see each.
+// `added` is the new task itself, which is left out.
+fn warn_similar(projects: &[Project], title: &str, tags: &[String], added: Option<(u32, u32)>) {
+    let similar: Vec<dedupe::SimilarTask> =
+        dedupe::find_similar(projects, title, tags, dedupe::DEFAULT_THRESHOLD)
+            .into_iter()
+            .filter(|s| Some((s.project, s.task)) != added)
+            .take(3)
+            .collect();
+    for candidate in similar {
+        let Some(project) = projects.iter().find(|p| p.id == candidate.project) else {
+            continue;
+        };
+        let Ok(task) = project.get_task(candidate.task) else {
+            continue;
+        };
+        println!(
+            "Warning: similar open task {} \"{}\" in {} ({:.0}% alike); see: taskmaster show-task {} {}",
+            project.task_ref(task.id),
+            task.title,
+            appearance::paint(&project.label(), project.color),
+            candidate.similarity * 100.0,
+            project.id,
+            task.id
+        );
+    }
+}
+
+fn captured_task(
+    project: &Project,
+    id: u32,
+    capture: &Capture,
+    rule: Option<&RoutingRule>,
+) -> Result<Task> {
+    let mut task = project.new_task(id, capture.title.clone())?;
+    if let Some(priority) = rule.and_then(|r| r.priority.as_deref()) {
+        task.priority = project.settings.priority_scale.parse(priority)?;
+    }
+    for tag in &capture.tags {
+        if !task.tags.contains(tag) {
+            task.tags.push(tag.clone());
+        }
+    }
+
+    if let Some(rule) = rule {
+        for tag in &rule.add_tags {
+            if !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+        if let Some(due_in) = &rule.due_in {
+            let due = Utc::now().checked_add_signed(dates::parse_duration(due_in)?);
+            task.due = Some(due.ok_or_else(|| {
+                TaskMasterError::InvalidOperation(format!("Due date too far away: {}", due_in))
+            })?);
+        }
+    }
+    Ok(task)
+}
+
+// The given name, or the logged-in user's
+fn current_user(given: &Option<String>) -> Option<String> {
+    given
+        .clone()
+        .or_else(|| env::var("USER").ok())
+        .or_else(|| env::var("USERNAME").ok())
+}
+
+fn ring_bell() {
+    print!("\x07");
+    let _ = io::stdout().flush();
+}
+
+// Handlers available to `run`
+fn default_handlers() -> TaskHandlerRegistry {
+    let mut registry = TaskHandlerRegistry::new();
+    registry.register_handler(Box::new(SimulatedTaskHandler::new(
+        "simulated",
+        Duration::from_secs(2),
+    )));
+    registry
+}
+
+// Ask which of `choices` to use, accepting "all", IDs and ranges such as "2,5-7"
+fn prompt_selection(choices: &[u32]) -> Result<Result<Vec<u32>>> {
+    print!("Select tasks to run (e.g. 2,5-7 or all): ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(parse_selection(answer.trim(), choices))
+}
+
+fn parse_selection(input: &str, choices: &[u32]) -> Result<Vec<u32>> {
+    if input.eq_ignore_ascii_case("all") {
+        return Ok(choices.to_vec());
+    }
+
+    let invalid =
+        |part: &str| TaskMasterError::InvalidOperation(format!("Invalid selection '{}'", part));
+
+    let mut selected = Vec::new();
+    for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (part, part),
+        };
+        let start: u32 = start.parse().map_err(|_| invalid(part))?;
+        let end: u32 = end.parse().map_err(|_| invalid(part))?;
+
+        for id in start..=end {
+            if !choices.contains(&id) {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Task {} is not one of the runnable tasks",
+                    id
+                )));
+            }
+            if !selected.contains(&id) {
+                selected.push(id);
+            }
+        }
+    }
+    Ok(selected)
+}
+
+// Ask a yes/no question on stdin, defaulting to no
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Print a record as JSON or YAML, wrapped with the schema version
+// Columns of the cells `task_cells` gives
+const TASK_COLUMNS: [&str; 5] = ["ID", "Title", "Status", "Priority", "Due"];
+
+// A task as a row of a listing, with its priority colored and an overdue due date marked
+fn task_cells(project: &Project, task: &Task, now: DateTime<Utc>) -> Vec<Cell> {
+    let weight = project.settings.priority_scale.weight(&task.priority);
+    let due = match task.due {
+        Some(due) if task.is_overdue(now) => Cell::colored(
+            format!("{} (overdue)", dates::format_local(&due)),
+            Some(ProjectColor::Red),
+        ),
+        Some(due) => dates::format_local(&due).into(),
+        None => "".into(),
+    };
+    vec![
+        project.task_ref(task.id).into(),
+        task.title.clone().into(),
+        task.status_label(&project.tasks).into(),
+        Cell::colored(
+            project.priority_label(task),
+            Some(appearance::priority_color(weight)),
+        ),
+        due,
+    ]
+}
+
+fn print_structured<T: serde::Serialize>(style: OutputStyle, kind: &str, record: &T) -> Result<()> {
+    let value = output::envelope(kind, record)?;
+    match style {
+        OutputStyle::Yaml => print!("{}", output::to_yaml(&value)),
+        _ => println!("{}", output::to_json(&value)?),
+    }
+    Ok(())
 }
@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Arg, Command};
+
+use crate::file_storage::FileStorage;
+use crate::storage::Storage;
+
+// Completion scripts hand the words typed so far to the hidden `complete` command, which
+// answers from the command definitions and the data directory, one "value<TAB>description"
+// per line. An empty answer lets the shell fall back to completing file names.
+
+pub fn bash() -> String {
+    r#"# bash completion for taskmaster
+_taskmaster() {
+    local IFS=$'\n'
+    local candidates
+    candidates=($(taskmaster complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null | cut -f1))
+    COMPREPLY=("${candidates[@]}")
+}
+complete -o bashdefault -o default -F _taskmaster taskmaster
+"#
+    .to_string()
+}
+
+pub fn zsh() -> String {
+    r#"#compdef taskmaster
+_taskmaster() {
+    local -a candidates
+    local line value
+    for line in "${(@f)$(taskmaster complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}"; do
+        [[ -z $line ]] && continue
+        value=${line%%$'\t'*}
+        if [[ $line == *$'\t'* ]]; then
+            candidates+=("${value//:/\\:}:${line#*$'\t'}")
+        else
+            candidates+=("${value//:/\\:}")
+        fi
+    done
+    if (( ${#candidates} )); then
+        _describe 'taskmaster' candidates
+    else
+        _files
+    fi
+}
+compdef _taskmaster taskmaster
+"#
+    .to_string()
+}
+
+pub fn fish() -> String {
+    r#"# fish completion for taskmaster
+function __taskmaster_complete
+    set -l tokens (commandline -opc) (commandline -ct)
+    set -l candidates (taskmaster complete -- $tokens[2..-1] 2>/dev/null)
+    if test (count $candidates) -gt 0
+        printf '%s\n' $candidates
+    else
+        __fish_complete_path (commandline -ct)
+    end
+end
+complete -c taskmaster -f -a '(__taskmaster_complete)'
+"#
+    .to_string()
+}
+
+pub fn powershell() -> String {
+    r#"# PowerShell completion for taskmaster
+Register-ArgumentCompleter -Native -CommandName taskmaster -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })
+    if ($wordToComplete -eq '') { $words += '""' }
+    taskmaster complete -- @words 2>$null | ForEach-Object {
+        $value, $description = $_ -split "`t", 2
+        if (-not $description) { $description = $value }
+        [System.Management.Automation.CompletionResult]::new($value, $value, 'ParameterValue', $description)
+    }
+}
+"#
+    .to_string()
+}
+
+// What a positional argument holds, judged by its name
+pub enum Slot {
+    Project,
+    // A task of the project given by the positional at this index
+    Task(usize),
+    Other,
+}
+
+// Where a command line has got to: the (sub)command being given arguments, the positionals
+// typed for it, and an option still waiting for its value
+pub struct CommandLine<'a, 'help> {
+    pub command: &'a Command<'help>,
+    pub positionals: Vec<&'a str>,
+    pub pending: Option<&'a Arg<'help>>,
+    pub data_dir: PathBuf,
+    pub only_positionals: bool,
+}
+
+// Follow `words` (the arguments after the program name) through the command definitions,
+// which must have been built. None if a word names no subcommand.
+pub fn walk<'a, 'help>(
+    root: &'a Command<'help>,
+    words: &'a [String],
+) -> Option<CommandLine<'a, 'help>> {
+    let mut line = CommandLine {
+        command: root,
+        positionals: Vec::new(),
+        pending: None,
+        data_dir: PathBuf::from("./data"),
+        only_positionals: false,
+    };
+
+    for word in words {
+        if let Some(arg) = line.pending.take() {
+            if arg.get_id() == "data-dir" {
+                line.data_dir = PathBuf::from(word);
+            }
+            continue;
+        }
+        if !line.only_positionals && word == "--" {
+            line.only_positionals = true;
+        } else if !line.only_positionals && word.starts_with("--") {
+            let (name, value) = match word[2..].split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (&word[2..], None),
+            };
+            let arg = line
+                .command
+                .get_arguments()
+                .find(|a| a.get_long() == Some(name));
+            match (arg, value) {
+                (Some(arg), Some(value)) if arg.get_id() == "data-dir" => {
+                    line.data_dir = PathBuf::from(value)
+                }
+                (Some(arg), None) if arg.is_takes_value_set() => line.pending = Some(arg),
+                _ => {}
+            }
+        } else if !line.only_positionals && word.starts_with('-') && word.len() > 1 {
+            let short = word.chars().nth(1);
+            let arg = line
+                .command
+                .get_arguments()
+                .find(|a| a.get_short() == short);
+            if let Some(arg) = arg.filter(|a| a.is_takes_value_set() && word.len() == 2) {
+                line.pending = Some(arg);
+            }
+        } else if line.positionals.is_empty() && line.command.has_subcommands() {
+            line.command = line.command.find_subcommand(word)?;
+        } else {
+            line.positionals.push(word);
+        }
+    }
+    Some(line)
+}
+
+// Candidates for the last of `words` (the arguments typed after the program name, the last
+// one being completed), as (value, description) pairs
+pub fn candidates(mut root: Command, words: &[String]) -> Vec<(String, String)> {
+    // Fills in what the definitions leave implicit, such as which options take values
+    root.build();
+    let root = &root;
+
+    let (current, typed) = match words.split_last() {
+        Some((current, typed)) => (current.as_str(), typed),
+        None => ("", words),
+    };
+    // PowerShell passes an empty word as two quotes
+    let current = if current == "\"\"" { "" } else { current };
+
+    let Some(CommandLine {
+        command,
+        positionals,
+        pending,
+        data_dir,
+        only_positionals,
+    }) = walk(root, typed)
+    else {
+        return Vec::new();
+    };
+
+    let project_arg = |at: usize| {
+        positionals
+            .get(at)
+            .and_then(|id| id.parse::<u32>().ok())
+            .map(|id| task_ids(&data_dir, id))
+            .unwrap_or_default()
+    };
+
+    let mut found: Vec<(String, String)> = if let Some(arg) = pending {
+        let values = possible_values(arg);
+        // Options such as --before and --parent take IDs too
+        let args: Vec<&Arg> = command.get_positionals().collect();
+        let first_project = (0..args.len()).find(|at| is_project(command, args[*at]));
+        match arg.get_id() {
+            _ if !values.is_empty() => values,
+            "parent" | "project" => project_ids(&data_dir),
+            "before" | "after" | "task" | "depends-on" => {
+                first_project.map(project_arg).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        }
+    } else if current.starts_with('-') && !only_positionals {
+        command
+            .get_arguments()
+            .filter(|a| !a.is_positional() && !a.is_hide_set())
+            .filter_map(|a| {
+                let help = a.get_help().unwrap_or_default().to_string();
+                a.get_long().map(|long| (format!("--{}", long), help))
+            })
+            .collect()
+    } else if positionals.is_empty() && command.has_subcommands() {
+        command
+            .get_subcommands()
+            .filter(|c| !c.is_hide_set())
+            .map(|c| {
+                let about = c.get_about().unwrap_or_default();
+                (c.get_name().to_string(), about.to_string())
+            })
+            .collect()
+    } else {
+        let args: Vec<&Arg> = command.get_positionals().collect();
+        let index = positionals.len();
+        let arg = args.get(index).or_else(|| {
+            args.last()
+                .filter(|a| a.is_multiple_values_set() || a.is_multiple_occurrences_set())
+        });
+        match arg {
+            Some(arg) => {
+                let values = possible_values(arg);
+                if !values.is_empty() {
+                    values
+                } else {
+                    match slot(command, &args, index.min(args.len().saturating_sub(1))) {
+                        Slot::Project => project_ids(&data_dir),
+                        Slot::Task(at) => project_arg(at),
+                        Slot::Other => Vec::new(),
+                    }
+                }
+            }
+            None => Vec::new(),
+        }
+    };
+
+    found.retain(|(value, _)| value.starts_with(current));
+    found
+}
+
+pub fn possible_values(arg: &Arg) -> Vec<(String, String)> {
+    if !arg.is_takes_value_set() && !arg.is_positional() {
+        return Vec::new();
+    }
+    // Value enums list their values on the argument; value parsers on the parser
+    let values: Vec<_> = match arg.get_possible_values() {
+        Some(values) => values.to_vec(),
+        None => arg
+            .get_value_parser()
+            .possible_values()
+            .map(|values| values.collect())
+            .unwrap_or_default(),
+    };
+    values
+        .into_iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| {
+            let help = v.get_help().unwrap_or_default();
+            (v.get_name().to_string(), help.to_string())
+        })
+        .collect()
+}
+
+// Project IDs are named project-id, project-a, source-id and so on, or plain `id` in
+// commands about projects. Task IDs follow a project ID.
+pub fn slot(command: &Command, args: &[&Arg], index: usize) -> Slot {
+    let Some(arg) = args.get(index) else {
+        return Slot::Other;
+    };
+    if is_project(command, arg) {
+        return Slot::Project;
+    }
+    let taskish = matches!(
+        arg.get_id(),
+        "id" | "task-id" | "dependency-id" | "depends-on" | "task"
+    );
+    match (0..index).rev().find(|at| is_project(command, args[*at])) {
+        Some(project) if taskish => Slot::Task(project),
+        _ => Slot::Other,
+    }
+}
+
+fn is_project(command: &Command, arg: &Arg) -> bool {
+    let id = arg.get_id();
+    id.starts_with("project")
+        || matches!(id, "source-id" | "destination-id")
+        || (id == "id" && command.get_name().contains("project"))
+}
+
+fn storage(data_dir: &Path) -> Option<FileStorage> {
+    // Completing must never create a data directory as a side effect
+    if !data_dir.is_dir() {
+        return None;
+    }
+    FileStorage::new(data_dir).ok()
+}
+
+pub fn project_ids(data_dir: &Path) -> Vec<(String, String)> {
+    let Some(storage) = storage(data_dir) else {
+        return Vec::new();
+    };
+    storage
+        .list_projects()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| (p.id.to_string(), p.label()))
+        .collect()
+}
+
+pub fn task_ids(data_dir: &Path, project_id: u32) -> Vec<(String, String)> {
+    let Some(project) = storage(data_dir).and_then(|s| s.load_project(project_id).ok()) else {
+        return Vec::new();
+    };
+    project
+        .tasks
+        .iter()
+        .map(|t| (t.id.to_string(), t.title.clone()))
+        .collect()
+}
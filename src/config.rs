@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::blob_store::BlobBackend;
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosConfig;
+use crate::error::Result;
+use crate::follow_ups::FollowUpRule;
+use crate::locale::LocaleSettings;
+use crate::reminders::ReminderRule;
+use crate::review;
+use crate::routing::RoutingRule;
+use crate::timesheet::RoundingRule;
+use crate::urgency::UrgencyCoefficients;
+
+pub const CONFIG_FILE: &str = "config.json";
+
+// Application-wide settings, stored as `config.json` in the data directory
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Config {
+    #[cfg(feature = "chaos")]
+    #[serde(default)]
+    pub chaos: ChaosConfig,
+    // Reminder schedule for tasks that don't define their own
+    #[serde(default = "ReminderRule::defaults")]
+    pub reminders: Vec<ReminderRule>,
+    // Where `capture` files tasks, tried in order
+    #[serde(default)]
+    pub routes: Vec<RoutingRule>,
+    // Project for captured tasks no route matches
+    #[serde(default)]
+    pub inbox: Option<u32>,
+    #[serde(default)]
+    pub urgency: UrgencyCoefficients,
+    // Ring the terminal bell for reminders about High-priority tasks and for failed runs
+    #[serde(default)]
+    pub bell: bool,
+    // How `timesheet` rounds each day's time on a task
+    #[serde(default)]
+    pub timesheet_rounding: RoundingRule,
+    // `remind` puts tasks in progress for longer than this up for review
+    #[serde(default = "review::default_wip_age_limit_hours")]
+    pub wip_age_limit_hours: i64,
+    // Where newly attached files are stored
+    #[serde(default)]
+    pub attachment_backend: BlobBackend,
+    // How dates, times and numbers are shown
+    #[serde(default)]
+    pub locale: LocaleSettings,
+    // Tasks `run` adds when a task ends a certain way
+    #[serde(default)]
+    pub follow_ups: Vec<FollowUpRule>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            #[cfg(feature = "chaos")]
+            chaos: ChaosConfig::default(),
+            reminders: ReminderRule::defaults(),
+            routes: Vec::new(),
+            inbox: None,
+            urgency: UrgencyCoefficients::default(),
+            bell: false,
+            timesheet_rounding: RoundingRule::default(),
+            wip_age_limit_hours: review::default_wip_age_limit_hours(),
+            attachment_backend: BlobBackend::default(),
+            locale: LocaleSettings::default(),
+            follow_ups: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    // Load the config from the data directory, falling back to defaults if absent
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
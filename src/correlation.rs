@@ -0,0 +1,41 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+// Process-local sequence so IDs minted in the same instant stay unique
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(1);
+
+// Identifies a single execution of a task so it can be traced across the
+// worker pool, executors, events, and notifications
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RunId(String);
+
+impl RunId {
+    pub fn new() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let sequence = NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+        RunId(format!("run-{:x}-{:04x}", millis, sequence))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RunId {
+    fn default() -> Self {
+        RunId::new()
+    }
+}
+
+impl fmt::Display for RunId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
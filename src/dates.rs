@@ -0,0 +1,204 @@
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+
+use crate::error::{Result, TaskMasterError};
+use crate::locale;
+
+// Parse a user-supplied point in time, interpreting dates without an offset as local time.
+// Accepts RFC 3339, "YYYY-MM-DD HH:MM[:SS]", "YYYY-MM-DD" (local midnight), and the
+// relative forms understood by parse_relative, such as "tomorrow" or "next friday 17:00".
+pub fn parse_datetime(input: &str) -> Result<DateTime<Utc>> {
+    let input = input.trim();
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(input) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(input, format) {
+            return local_to_utc(naive, input);
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        if let Some(midnight) = date.and_hms_opt(0, 0, 0) {
+            return local_to_utc(midnight, input);
+        }
+    }
+
+    if let Some(naive) = parse_relative(input, Local::now().naive_local()) {
+        return local_to_utc(naive, input);
+    }
+
+    Err(TaskMasterError::InvalidOperation(format!(
+        "Could not understand date '{}'; use YYYY-MM-DD, YYYY-MM-DD HH:MM, or phrases like \
+         \"tomorrow\", \"in 3 days\" or \"next friday\"",
+        input
+    )))
+}
+
+// Resolve a relative phrase against `now`:
+//   "now", "today", "tomorrow", "yesterday", "next week"
+//   "in 3 days", "in 2h", "in 90 minutes"
+//   "friday" or "this fri" (the next such day after today), "next friday" (in next week)
+// Day phrases may end with a time, e.g. "tomorrow 9:30", "friday at 5pm"; without one
+// they mean local midnight, like a plain date.
+pub fn parse_relative(input: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let input = input.trim().to_lowercase();
+
+    if input == "now" {
+        return Some(now);
+    }
+    if let Some(span) = input.strip_prefix("in ") {
+        return parse_duration(span)
+            .ok()
+            .and_then(|span| now.checked_add_signed(span));
+    }
+
+    let (day, time) = split_time(&input)?;
+    let today = now.date();
+    let date = match day {
+        "today" => today,
+        "tomorrow" => today.succ_opt()?,
+        "yesterday" => today.pred_opt()?,
+        "next week" => today + Duration::days(7),
+        _ => match day.strip_prefix("next ") {
+            // "next friday" is the Friday of next week
+            Some(name) => {
+                let weekday: Weekday = name.parse().ok()?;
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                monday + Duration::days(7 + weekday.num_days_from_monday() as i64)
+            }
+            // "friday" or "this friday" is the next Friday after today
+            None => {
+                let weekday: Weekday = day.strip_prefix("this ").unwrap_or(day).parse().ok()?;
+                let ahead = (weekday.num_days_from_monday() + 7
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                today + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+            }
+        },
+    };
+
+    Some(date.and_time(time))
+}
+
+// Split a trailing "[at] HH:MM" or "[at] 5pm" off a day phrase
+fn split_time(input: &str) -> Option<(&str, NaiveTime)> {
+    let midnight = NaiveTime::from_hms_opt(0, 0, 0)?;
+    let Some((day, last)) = input.rsplit_once(' ') else {
+        return Some((input, midnight));
+    };
+
+    match parse_time_of_day(last) {
+        Some(time) => Some((day.strip_suffix(" at").unwrap_or(day).trim(), time)),
+        None => Some((input, midnight)),
+    }
+}
+
+fn parse_time_of_day(input: &str) -> Option<NaiveTime> {
+    if let Ok(time) = NaiveTime::parse_from_str(input, "%H:%M") {
+        return Some(time);
+    }
+
+    let (hour, afternoon) = match (input.strip_suffix("am"), input.strip_suffix("pm")) {
+        (Some(hour), _) => (hour, false),
+        (_, Some(hour)) => (hour, true),
+        _ => return None,
+    };
+    let (hour, minute) = match hour.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse().ok()?),
+        None => (hour.parse::<u32>().ok()?, 0),
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+    NaiveTime::from_hms_opt(hour % 12 + if afternoon { 12 } else { 0 }, minute, 0)
+}
+
+// A due date argument: any form parse_datetime accepts, or "none" to clear it
+pub fn parse_due(input: &str) -> Result<Option<DateTime<Utc>>> {
+    if input.trim().eq_ignore_ascii_case("none") {
+        Ok(None)
+    } else {
+        parse_datetime(input).map(Some)
+    }
+}
+
+// Start of the working day (09:00 local) on a date, used for planned starts
+pub fn start_of_workday(date: NaiveDate) -> Result<DateTime<Utc>> {
+    let naive = date.and_time(NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default());
+    local_to_utc(naive, &date.to_string())
+}
+
+// Close of business (17:00 local) on a date, used when a task is planned for a day
+pub fn end_of_workday(date: NaiveDate) -> Result<DateTime<Utc>> {
+    let naive = date.and_time(NaiveTime::from_hms_opt(17, 0, 0).unwrap_or_default());
+    local_to_utc(naive, &date.to_string())
+}
+
+fn local_to_utc(naive: NaiveDateTime, input: &str) -> Result<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .ok_or_else(|| {
+            TaskMasterError::InvalidOperation(format!("'{}' does not exist in local time", input))
+        })
+}
+
+// Render a stored timestamp in the user's local time
+pub fn format_local(timestamp: &DateTime<Utc>) -> String {
+    locale::datetime(timestamp)
+}
+
+// The inverse of parse_duration, in the largest unit that divides the span evenly
+pub fn format_duration(span: Duration) -> String {
+    let minutes = span.num_minutes();
+    for (unit, size) in [("w", 60 * 24 * 7), ("d", 60 * 24), ("h", 60)] {
+        if minutes != 0 && minutes % size == 0 {
+            return format!("{}{}", minutes / size, unit);
+        }
+    }
+    format!("{}m", minutes)
+}
+
+// A rough length of time for display, in its two largest units: "3d 4h", "5h 20m", "45m"
+pub fn format_age(span: Duration) -> String {
+    let minutes = span.num_minutes().max(0);
+    let (days, hours, mins) = (minutes / (60 * 24), minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", mins),
+        (0, _) => format!("{}h {}m", hours, mins),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+// Parse a span such as "90m", "4h", "5d" or "2w"
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let invalid = || {
+        TaskMasterError::InvalidOperation(format!(
+            "Could not understand duration '{}'; use a number followed by m, h, d or w",
+            input
+        ))
+    };
+
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .ok_or_else(invalid)?;
+    let (amount, unit) = input.split_at(split);
+    let amount: f64 = amount.parse().map_err(|_| invalid())?;
+
+    let minutes = match unit.trim().to_lowercase().as_str() {
+        "m" | "min" | "mins" | "minute" | "minutes" => amount,
+        "h" | "hr" | "hrs" | "hour" | "hours" => amount * 60.0,
+        "d" | "day" | "days" => amount * 60.0 * 24.0,
+        "w" | "wk" | "week" | "weeks" => amount * 60.0 * 24.0 * 7.0,
+        _ => return Err(invalid()),
+    };
+
+    Duration::try_minutes(minutes.round() as i64).ok_or_else(invalid)
+}
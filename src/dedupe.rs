@@ -0,0 +1,138 @@
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// Titles at least this similar are reported as likely duplicates
+pub const DEFAULT_THRESHOLD: f64 = 0.85;
+
+// Words that do not tell two titles apart
+const FILLER_WORDS: [&str; 5] = ["a", "an", "the", "to", "and"];
+
+// Two open tasks that look like the same piece of work
+#[derive(Debug, Clone)]
+pub struct DuplicateCandidate {
+    // The older task, which a merge keeps
+    pub keep: u32,
+    pub duplicate: u32,
+    // 1.0 for titles that are identical once normalized
+    pub similarity: f64,
+}
+
+// Lowercase words with punctuation and filler words dropped, so "Fix the login bug!" and
+// "fix login-bug" compare equal
+pub fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !FILLER_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Similarity of two titles from 0.0 to 1.0: the edit distance between their normalized
+// forms relative to the longer one
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalize_title(a).chars().collect();
+    let b: Vec<char> = normalize_title(b).chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+// Levenshtein distance, keeping a single row of the table
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// Pairs of open tasks whose titles are at least `threshold` similar, most similar first.
+// Done tasks are left out, so past occurrences of a recurring task are not flagged.
+pub fn find_duplicates(project: &Project, threshold: f64) -> Vec<DuplicateCandidate> {
+    let mut open: Vec<&Task> = project
+        .tasks
+        .iter()
+        .filter(|t| t.status != TaskStatus::Done)
+        .collect();
+    open.sort_by_key(|t| (t.created_at, t.id));
+
+    let mut candidates = Vec::new();
+    for (i, keep) in open.iter().enumerate() {
+        for duplicate in &open[i + 1..] {
+            let similarity = title_similarity(&keep.title, &duplicate.title);
+            if similarity >= threshold {
+                candidates.push(DuplicateCandidate {
+                    keep: keep.id,
+                    duplicate: duplicate.id,
+                    similarity,
+                });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    candidates
+}
+
+// An open task, possibly in another project, that a task about to be added may repeat
+#[derive(Debug, Clone)]
+pub struct SimilarTask {
+    pub project: u32,
+    pub task: u32,
+    pub similarity: f64,
+}
+
+// How alike a new task is to an existing one: the title similarity, with a fifth of the
+// weight going to how many tags they share when both have tags
+pub fn task_similarity(title: &str, tags: &[String], other: &Task) -> f64 {
+    let title = title_similarity(title, &other.title);
+    if tags.is_empty() || other.tags.is_empty() {
+        return title;
+    }
+    let shared = tags
+        .iter()
+        .filter(|tag| other.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        .count();
+    let union = tags.len() + other.tags.len() - shared;
+    0.8 * title + 0.2 * shared as f64 / union as f64
+}
+
+// Open tasks across unarchived `projects` at least `threshold` alike to a task with this
+// title and tags, most similar first
+pub fn find_similar(
+    projects: &[Project],
+    title: &str,
+    tags: &[String],
+    threshold: f64,
+) -> Vec<SimilarTask> {
+    let mut similar: Vec<SimilarTask> = projects
+        .iter()
+        .filter(|p| !p.archived)
+        .flat_map(|project| {
+            project
+                .tasks
+                .iter()
+                .filter(|t| t.status != TaskStatus::Done)
+                .map(move |task| SimilarTask {
+                    project: project.id,
+                    task: task.id,
+                    similarity: task_similarity(title, tags, task),
+                })
+        })
+        .filter(|s| s.similarity >= threshold)
+        .collect();
+    similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    similar
+}
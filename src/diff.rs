@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::dates;
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TaskSummary {
+    pub id: u32,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TaskChange {
+    pub id: u32,
+    pub title: String,
+    pub changes: Vec<FieldChange>,
+}
+
+// A dependency edge: `task` depends on `depends_on`
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub task: u32,
+    pub depends_on: u32,
+}
+
+// Differences between two versions of a project, with tasks matched by ID
+#[derive(Debug, Serialize, Clone)]
+pub struct ProjectDiff {
+    pub from: String,
+    pub to: String,
+    pub added: Vec<TaskSummary>,
+    pub removed: Vec<TaskSummary>,
+    pub changed: Vec<TaskChange>,
+    pub added_dependencies: Vec<Edge>,
+    pub removed_dependencies: Vec<Edge>,
+}
+
+impl ProjectDiff {
+    pub fn compare(before: &Project, after: &Project, from: String, to: String) -> Self {
+        let old: BTreeMap<u32, &Task> = before.tasks.iter().map(|t| (t.id, t)).collect();
+        let new: BTreeMap<u32, &Task> = after.tasks.iter().map(|t| (t.id, t)).collect();
+
+        let summary = |task: &Task| TaskSummary {
+            id: task.id,
+            title: task.title.clone(),
+        };
+        let added = new
+            .values()
+            .filter(|t| !old.contains_key(&t.id))
+            .map(|t| summary(t))
+            .collect();
+        let removed = old
+            .values()
+            .filter(|t| !new.contains_key(&t.id))
+            .map(|t| summary(t))
+            .collect();
+
+        let changed = new
+            .values()
+            .filter_map(|task| {
+                let previous = old.get(&task.id)?;
+                let changes = field_changes(before, previous, after, task);
+                (!changes.is_empty()).then(|| TaskChange {
+                    id: task.id,
+                    title: task.title.clone(),
+                    changes,
+                })
+            })
+            .collect();
+
+        let old_edges = edges(before);
+        let new_edges = edges(after);
+
+        ProjectDiff {
+            from,
+            to,
+            added,
+            removed,
+            changed,
+            added_dependencies: new_edges.difference(&old_edges).copied().collect(),
+            removed_dependencies: old_edges.difference(&new_edges).copied().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added_dependencies.is_empty()
+            && self.removed_dependencies.is_empty()
+    }
+
+    pub fn display(&self) {
+        println!("--- {}", self.from);
+        println!("+++ {}", self.to);
+
+        if self.is_empty() {
+            println!("No differences");
+            return;
+        }
+
+        for task in &self.added {
+            println!("+ Task {}: {}", task.id, task.title);
+        }
+        for task in &self.removed {
+            println!("- Task {}: {}", task.id, task.title);
+        }
+        for task in &self.changed {
+            println!("~ Task {}: {}", task.id, task.title);
+            for change in &task.changes {
+                println!(
+                    "    {}: {} -> {}",
+                    change.field, change.before, change.after
+                );
+            }
+        }
+        for edge in &self.added_dependencies {
+            println!("+ Dependency: {} -> {}", edge.task, edge.depends_on);
+        }
+        for edge in &self.removed_dependencies {
+            println!("- Dependency: {} -> {}", edge.task, edge.depends_on);
+        }
+    }
+}
+
+fn edges(project: &Project) -> BTreeSet<Edge> {
+    project
+        .tasks
+        .iter()
+        .flat_map(|task| {
+            task.dependency_ids().map(|dep| Edge {
+                task: task.id,
+                depends_on: dep,
+            })
+        })
+        .collect()
+}
+
+fn field_changes(
+    before_project: &Project,
+    before: &Task,
+    after_project: &Project,
+    after: &Task,
+) -> Vec<FieldChange> {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let fields = |project: &Project, task: &Task| {
+        let done = task.checklist.iter().filter(|item| item.done).count();
+        [
+            ("title", task.title.clone()),
+            ("status", task.status.to_string()),
+            ("priority", project.priority_label(task)),
+            ("due", optional(task.due.as_ref().map(dates::format_local))),
+            (
+                "estimate",
+                optional(task.estimate_hours.map(|h| format!("{}h", h))),
+            ),
+            ("tags", task.tags.join(", ")),
+            (
+                "checklist",
+                format!("{}/{} done", done, task.checklist.len()),
+            ),
+            ("blocked", optional(task.blocked_reason.clone())),
+            (
+                "recurrence",
+                optional(task.recurrence.as_ref().map(|r| r.to_string())),
+            ),
+            ("attachments", task.attachments.len().to_string()),
+        ]
+    };
+
+    fields(before_project, before)
+        .into_iter()
+        .zip(fields(after_project, after))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, before), (_, after))| FieldChange {
+            field: field.to_string(),
+            before,
+            after,
+        })
+        .collect()
+}
+
+// Best-effort reconstruction of a project as it stood at `at`, from creation times and status
+// history. Edits other than status changes, and deleted tasks, are not recorded, so those
+// show up as they are now.
+pub fn project_as_of(project: &Project, at: DateTime<Utc>) -> Project {
+    let mut past = project.clone();
+    past.tasks.retain(|task| task.created_at <= at);
+    past.invalidate_dependency_graph();
+    for task in &mut past.tasks {
+        task.status = status_at(task, at);
+    }
+    past
+}
+
+fn status_at(task: &Task, at: DateTime<Utc>) -> TaskStatus {
+    match task.history.iter().rev().find(|c| c.changed_at <= at) {
+        Some(change) => change.to.clone(),
+        None => task
+            .history
+            .first()
+            .map(|change| change.from.clone())
+            .unwrap_or_else(|| task.status.clone()),
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Result, TaskMasterError};
+use crate::ids;
+use crate::output;
+use crate::project::Project;
+use crate::ranking;
+
+const DOCUMENT_HEADER: &str = "# Edit the project below and save to apply the changes.\n\
+    # Lines starting with # are ignored.\n";
+
+// What an edited project document changed, by task ID
+#[derive(Debug, Default)]
+pub struct EditSummary {
+    pub added: Vec<u32>,
+    pub removed: Vec<u32>,
+    pub modified: Vec<u32>,
+    // Name, settings or other project-level fields
+    pub project_changed: bool,
+    pub reordered: bool,
+}
+
+impl EditSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && !self.project_changed
+            && !self.reordered
+    }
+
+    pub fn display(&self) {
+        if self.project_changed {
+            println!("  Project details updated");
+        }
+        if self.reordered {
+            println!("  Task order updated");
+        }
+        for (label, ids) in [
+            ("Added", &self.added),
+            ("Modified", &self.modified),
+            ("Removed", &self.removed),
+        ] {
+            if !ids.is_empty() {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                println!("  {} tasks: {}", label, ids.join(", "));
+            }
+        }
+    }
+}
+
+// A project as the YAML document shown in the editor
+pub fn to_document(project: &Project) -> Result<String> {
+    let value = serde_json::to_value(project)?;
+    Ok(format!("{}{}", DOCUMENT_HEADER, output::to_yaml(&value)))
+}
+
+// The document being edited, in a new directory of its own that only the current user can
+// open, so nobody else on the machine can read the project or put a link in its place
+pub struct EditBuffer {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl EditBuffer {
+    pub fn create(project: &Project) -> Result<Self> {
+        let dir = env::temp_dir().join(format!("taskmaster-edit-{}", ids::new_uuid()));
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        builder.mode(0o700);
+        // Fails rather than reusing anything already at that path
+        builder.create(&dir)?;
+
+        let buffer = EditBuffer {
+            path: dir.join(format!("project-{}.yaml", project.id)),
+            dir,
+        };
+        let written = to_document(project).and_then(|document| {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&buffer.path)?;
+            file.write_all(document.as_bytes())?;
+            Ok(())
+        });
+        match written {
+            Ok(()) => Ok(buffer),
+            Err(e) => {
+                buffer.remove()?;
+                Err(e)
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn read(&self) -> Result<String> {
+        Ok(fs::read_to_string(&self.path)?)
+    }
+
+    pub fn remove(self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)?;
+        Ok(())
+    }
+}
+
+// Open `path` in $VISUAL or $EDITOR (which may carry arguments, e.g. "code --wait")
+pub fn open_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| default_editor().to_string());
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or_else(|| {
+        TaskMasterError::InvalidOperation("The EDITOR variable is empty".to_string())
+    })?;
+
+    let status = Command::new(program).args(parts).arg(path).status()?;
+    if !status.success() {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        )));
+    }
+    Ok(())
+}
+
+fn default_editor() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "notepad"
+    } else {
+        "vi"
+    }
+}
+
+// Parse and validate an edited copy of `original`. Nothing is applied unless the whole
+// document is valid: status changes must be allowed by the workflow and changed tasks must
+// pass the project's rules. The returned project is meant to be saved in one write.
+pub fn apply_edit(original: &Project, document: &str) -> Result<(Project, EditSummary)> {
+    let mut edited: Project = serde_json::from_value(output::from_yaml(document)?)?;
+    if edited.id != original.id {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "The project ID cannot be changed (expected {}, found {})",
+            original.id, edited.id
+        )));
+    }
+    if edited.uuid != original.uuid {
+        return Err(TaskMasterError::InvalidOperation(
+            "The project UUID cannot be changed".to_string(),
+        ));
+    }
+    edited.validate()?;
+
+    edited.assign_missing_uuids();
+
+    let before: HashMap<u32, _> = original.tasks.iter().map(|t| (t.id, t)).collect();
+    let mut summary = EditSummary::default();
+
+    // The document's task order becomes the manual order
+    let kept_order = |project: &Project| -> Vec<u32> {
+        project
+            .tasks
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| before.contains_key(id))
+            .collect()
+    };
+    summary.reordered = kept_order(&edited) != kept_order(original);
+    // Ranks are kept while they still read in document order
+    let in_order = edited.tasks.iter().all(|t| ranking::is_valid(&t.rank))
+        && edited.tasks.windows(2).all(|w| w[0].rank < w[1].rank);
+    if !in_order {
+        edited.rerank_tasks();
+    }
+
+    for task in &mut edited.tasks {
+        let Some(old) = before.get(&task.id) else {
+            summary.added.push(task.id);
+            continue;
+        };
+        // Position is reported separately, so compare everything else
+        let mut unmoved = task.clone();
+        unmoved.rank = old.rank.clone();
+        if serde_json::to_value(&unmoved)? == serde_json::to_value(old)? {
+            continue;
+        }
+
+        // Route status changes through set_status so they land in the task's history
+        if task.status != old.status {
+            edited
+                .settings
+                .workflow
+                .validate_transition(&old.status, &task.status)
+                .map_err(|e| in_task(task.id, e))?;
+            let status = task.status.clone();
+            task.status = old.status.clone();
+            task.set_status(status);
+        }
+        task.touch();
+        summary.modified.push(task.id);
+    }
+
+    for id in summary.added.iter().chain(&summary.modified) {
+        edited
+            .check_rules(edited.get_task(*id)?)
+            .map_err(|e| in_task(*id, e))?;
+    }
+
+    summary.removed = original
+        .tasks
+        .iter()
+        .map(|t| t.id)
+        .filter(|id| !edited.tasks.iter().any(|t| t.id == *id))
+        .collect();
+
+    summary.project_changed = edited.name != original.name
+        || edited.archived != original.archived
+        || serde_json::to_value(&edited.settings)? != serde_json::to_value(&original.settings)?;
+
+    Ok((edited, summary))
+}
+
+fn in_task(id: u32, e: TaskMasterError) -> TaskMasterError {
+    TaskMasterError::InvalidOperation(format!("Task {}: {}", id, e))
+}
@@ -1,27 +1,97 @@
 use std::error::Error as StdError;
 use std::{fmt, io};
 
+use crate::task::TaskStatus;
+use crate::validation::Violation;
+
 #[derive(Debug)]
 pub enum TaskMasterError {
     TaskNotFound(u32),
+    DuplicateTaskId(u32),
     ProjectNotFound(u32),
     InvalidOperation(String),
     StorageError(String),
     IoError(io::Error),
     SerializationError(String),
     ChannelError(String),
+    InvalidTransition(TaskStatus, TaskStatus),
+    TemplateNotFound(String),
+    MilestoneNotFound(String),
+    ValidationFailed(Vec<Violation>),
+    // Task IDs around the cycle, each depending on the next, ending where it started
+    DependencyCycle(Vec<u32>),
 }
 
 impl fmt::Display for TaskMasterError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TaskMasterError::TaskNotFound(id) => write!(f, "Task with ID {} not found", id),
+            TaskMasterError::DuplicateTaskId(id) => {
+                write!(f, "Task with ID {} already exists", id)
+            }
             TaskMasterError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
             TaskMasterError::ProjectNotFound(id) => write!(f, "Project with ID {} not found", id),
             TaskMasterError::StorageError(msg) => write!(f, "Storage error: {}", msg),
             TaskMasterError::IoError(err) => write!(f, "I/O error: {}", err),
             TaskMasterError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             TaskMasterError::ChannelError(msg) => write!(f, "Channel error: {}", msg),
+            TaskMasterError::InvalidTransition(from, to) => {
+                write!(f, "Invalid status transition: {} -> {}", from, to)
+            }
+            TaskMasterError::TemplateNotFound(name) => write!(f, "Template '{}' not found", name),
+            TaskMasterError::MilestoneNotFound(name) => {
+                write!(f, "Milestone '{}' not found", name)
+            }
+            TaskMasterError::ValidationFailed(violations) => {
+                let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(f, "Validation failed: {}", violations.join("; "))
+            }
+            TaskMasterError::DependencyCycle(path) => {
+                write!(f, "Dependency cycle: {}", format_cycle(path))
+            }
+        }
+    }
+}
+
+impl TaskMasterError {
+    // What the CLI exits with, so scripts can tell failures apart: 3 when something asked
+    // for doesn't exist, 4 when a change is refused, 5 when reading or writing data fails,
+    // and 1 for anything else. Mistyped command lines exit with 2, from clap.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TaskMasterError::TaskNotFound(_)
+            | TaskMasterError::ProjectNotFound(_)
+            | TaskMasterError::TemplateNotFound(_)
+            | TaskMasterError::MilestoneNotFound(_) => 3,
+            TaskMasterError::InvalidOperation(_)
+            | TaskMasterError::DuplicateTaskId(_)
+            | TaskMasterError::InvalidTransition(_, _)
+            | TaskMasterError::ValidationFailed(_)
+            | TaskMasterError::DependencyCycle(_) => 4,
+            TaskMasterError::StorageError(_)
+            | TaskMasterError::IoError(_)
+            | TaskMasterError::SerializationError(_) => 5,
+            TaskMasterError::ChannelError(_) => 1,
+        }
+    }
+
+    // A stable name for the variant, for records that need to tell failures apart after
+    // the error itself is gone
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskMasterError::TaskNotFound(_) => "task_not_found",
+            TaskMasterError::DuplicateTaskId(_) => "duplicate_task_id",
+            TaskMasterError::ProjectNotFound(_) => "project_not_found",
+            TaskMasterError::InvalidOperation(_) => "invalid_operation",
+            TaskMasterError::StorageError(_) => "storage",
+            TaskMasterError::IoError(_) => "io",
+            TaskMasterError::SerializationError(_) => "serialization",
+            TaskMasterError::ChannelError(_) => "channel",
+            TaskMasterError::InvalidTransition(_, _) => "invalid_transition",
+            TaskMasterError::TemplateNotFound(_) => "template_not_found",
+            TaskMasterError::MilestoneNotFound(_) => "milestone_not_found",
+            TaskMasterError::ValidationFailed(_) => "validation_failed",
+            TaskMasterError::DependencyCycle(_) => "dependency_cycle",
         }
     }
 }
@@ -47,4 +117,10 @@ impl From<serde_json::Error> for TaskMasterError {
     }
 }
 
+// e.g. "4 -> 3 -> 2 -> 4"
+pub fn format_cycle(path: &[u32]) -> String {
+    let ids: Vec<String> = path.iter().map(|id| id.to_string()).collect();
+    ids.join(" -> ")
+}
+
 pub type Result<T> = std::result::Result<T, TaskMasterError>;
@@ -0,0 +1,119 @@
+use crate::locale;
+use crate::project::Project;
+use crate::task::TaskStatus;
+
+// How completed estimates compared with tracked effort for a set of tasks
+#[derive(Debug, Clone)]
+pub struct CalibrationReport {
+    pub label: String,
+    pub samples: usize,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+    pub mean_ratio: Option<f64>,
+    pub median_ratio: Option<f64>,
+}
+
+impl CalibrationReport {
+    pub fn for_project(project: &Project) -> Self {
+        Self::from_projects(&project.name, std::slice::from_ref(project))
+    }
+
+    pub fn from_projects(label: &str, projects: &[Project]) -> Self {
+        let mut ratios = Vec::new();
+        let mut estimated_hours = 0.0;
+        let mut actual_hours = 0.0;
+
+        for task in projects.iter().flat_map(|p| &p.tasks) {
+            if let (Some(ratio), Some(estimate)) = (task.estimate_ratio, task.estimate_hours) {
+                ratios.push(ratio);
+                estimated_hours += estimate;
+                actual_hours += estimate * ratio;
+            }
+        }
+
+        ratios.sort_by(|a, b| a.total_cmp(b));
+        let mean_ratio = if ratios.is_empty() {
+            None
+        } else {
+            Some(ratios.iter().sum::<f64>() / ratios.len() as f64)
+        };
+
+        CalibrationReport {
+            label: label.to_string(),
+            samples: ratios.len(),
+            estimated_hours,
+            actual_hours,
+            mean_ratio,
+            median_ratio: median(&ratios),
+        }
+    }
+
+    // Factor to multiply future estimates by; the median resists a few wild outliers
+    pub fn adjustment_factor(&self) -> f64 {
+        self.median_ratio.unwrap_or(1.0)
+    }
+
+    pub fn adjust(&self, estimate_hours: f64) -> f64 {
+        estimate_hours * self.adjustment_factor()
+    }
+
+    pub fn display(&self) {
+        println!("Calibration for {}:", self.label);
+        if self.samples == 0 {
+            println!("  No completed tasks with both an estimate and tracked time");
+            return;
+        }
+
+        println!("  Completed tasks measured: {}", self.samples);
+        println!(
+            "  Estimated: {}, actual: {}",
+            locale::hours(self.estimated_hours, 1),
+            locale::hours(self.actual_hours, 1)
+        );
+        if let (Some(mean), Some(median)) = (self.mean_ratio, self.median_ratio) {
+            println!(
+                "  Actual/estimate ratio: mean {}, median {}",
+                locale::number(mean, 2),
+                locale::number(median, 2)
+            );
+        }
+
+        let factor = self.adjustment_factor();
+        if (factor - 1.0).abs() < 0.05 {
+            println!("  Estimates are well calibrated; no adjustment suggested");
+        } else {
+            println!(
+                "  Suggestion: multiply future estimates by {} ({})",
+                locale::number(factor, 2),
+                if factor > 1.0 {
+                    "work tends to take longer than estimated"
+                } else {
+                    "work tends to finish faster than estimated"
+                }
+            );
+        }
+    }
+}
+
+// Hours still estimated for unfinished tasks, optionally calibrated
+pub fn remaining_estimate(project: &Project, calibration: Option<&CalibrationReport>) -> f64 {
+    project
+        .tasks
+        .iter()
+        .filter(|t| t.status != TaskStatus::Done)
+        .filter_map(|t| t.estimate_hours)
+        .map(|hours| calibration.map_or(hours, |c| c.adjust(hours)))
+        .sum()
+}
+
+fn median(sorted: &[f64]) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
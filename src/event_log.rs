@@ -0,0 +1,103 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::async_executor::TaskEvent;
+use crate::error::Result;
+
+pub const EVENT_LOG_FILE: &str = "events.jsonl";
+
+// A TaskEvent together with the moment it was observed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoggedEvent {
+    pub recorded_at: DateTime<Utc>,
+    // The project the task belongs to, when the writer knew it
+    #[serde(default)]
+    pub project_id: Option<u32>,
+    pub event: TaskEvent,
+}
+
+// Append-only JSON-lines log of task events kept in the data directory
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    path: PathBuf,
+}
+
+impl EventLog {
+    pub fn new<P: AsRef<Path>>(data_dir: P) -> Self {
+        EventLog {
+            path: data_dir.as_ref().join(EVENT_LOG_FILE),
+        }
+    }
+
+    pub fn append(&self, event: &TaskEvent) -> Result<()> {
+        self.record(None, event)
+    }
+
+    // Append an event about a task in the given project
+    pub fn append_for_project(&self, project_id: u32, event: &TaskEvent) -> Result<()> {
+        self.record(Some(project_id), event)
+    }
+
+    fn record(&self, project_id: Option<u32>, event: &TaskEvent) -> Result<()> {
+        let entry = LoggedEvent {
+            recorded_at: Utc::now(),
+            project_id,
+            event: event.clone(),
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+
+    // When the log was last written to, so watchers can tell when to read it again
+    pub fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).ok()?.modified().ok()
+    }
+
+    // Events recorded within [from, to], oldest first. Unreadable lines are skipped.
+    pub fn read_range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LoggedEvent>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut events = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: LoggedEvent = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    println!("Skipping unreadable event log entry: {}", e);
+                    continue;
+                }
+            };
+
+            let after_start = from.is_none_or(|from| entry.recorded_at >= from);
+            let before_end = to.is_none_or(|to| entry.recorded_at <= to);
+            if after_start && before_end {
+                events.push(entry);
+            }
+        }
+
+        events.sort_by_key(|e| e.recorded_at);
+        Ok(events)
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, Utc};
+
+use crate::dates;
+use crate::project::Project;
+use crate::report::markdown_escape;
+use crate::task::{Task, TaskPriority, TaskStatus};
+
+// Columns before the custom fields; `import` reads the same names back
+const CSV_COLUMNS: [&str; 11] = [
+    "id",
+    "title",
+    "status",
+    "priority",
+    "due",
+    "tags",
+    "estimate",
+    "tracked",
+    "milestone",
+    "parent",
+    "completed",
+];
+
+// One row per task in the manual order. Custom fields follow as `field:<name>` columns.
+pub fn to_csv(project: &Project) -> String {
+    let mut fields: Vec<&String> = project
+        .tasks
+        .iter()
+        .flat_map(|t| t.custom_fields.keys())
+        .collect();
+    fields.sort();
+    fields.dedup();
+
+    let mut header: Vec<String> = CSV_COLUMNS.iter().map(|c| c.to_string()).collect();
+    header.extend(
+        fields
+            .iter()
+            .map(|name| csv_field(&format!("field:{}", name))),
+    );
+    let mut csv = format!("{}\n", header.join(","));
+
+    for task in ordered(project) {
+        let timestamp =
+            |at: Option<DateTime<Utc>>| at.map(|at| at.to_rfc3339()).unwrap_or_default();
+        let mut row = vec![
+            task.id.to_string(),
+            csv_field(&task.title),
+            task.status.to_string(),
+            csv_field(&project.priority_label(task)),
+            timestamp(task.due),
+            csv_field(&task.tags.join(",")),
+            task.estimate_hours
+                .map(|h| h.to_string())
+                .unwrap_or_default(),
+            task.actual_hours().to_string(),
+            csv_field(
+                task.milestone
+                    .and_then(|id| project.milestone_name(id))
+                    .unwrap_or_default(),
+            ),
+            task.parent.map(|id| id.to_string()).unwrap_or_default(),
+            timestamp(task.completed_at()),
+        ];
+        row.extend(fields.iter().map(|name| {
+            task.custom_fields
+                .get(*name)
+                .map(|v| csv_field(v))
+                .unwrap_or_default()
+        }));
+        csv.push_str(&format!("{}\n", row.join(",")));
+    }
+    csv
+}
+
+// Quote a CSV field when it holds a comma, quote or line break
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// A checklist to paste into notes or an issue, with subtasks indented under their parents
+// and each task's own checklist below it
+pub fn to_markdown(project: &Project) -> String {
+    let mut md = format!("# {}\n\n", markdown_escape(&project.name));
+    if !project.description.is_empty() {
+        md.push_str(&format!("{}\n\n", project.description));
+    }
+    let tasks = ordered(project);
+    if tasks.is_empty() {
+        md.push_str("_No tasks_\n");
+    }
+    for task in tasks.iter().filter(|t| !has_parent_in(project, t)) {
+        markdown_task(project, &tasks, task, 0, &mut md);
+    }
+    md
+}
+
+fn has_parent_in(project: &Project, task: &Task) -> bool {
+    task.parent
+        .is_some_and(|parent| project.tasks.iter().any(|t| t.id == parent))
+}
+
+fn markdown_task(project: &Project, tasks: &[&Task], task: &Task, depth: usize, md: &mut String) {
+    let indent = "  ".repeat(depth);
+    let mut details = vec![project.priority_label(task)];
+    if let Some(due) = &task.due {
+        details.push(format!("due {}", dates::format_local(due)));
+    }
+    details.extend(
+        task.tags
+            .iter()
+            .map(|tag| format!("#{}", markdown_escape(tag))),
+    );
+    md.push_str(&format!(
+        "{}- [{}] {} ({})\n",
+        indent,
+        if task.status == TaskStatus::Done {
+            "x"
+        } else {
+            " "
+        },
+        markdown_escape(&task.title),
+        details.join(", ")
+    ));
+    for item in &task.checklist {
+        md.push_str(&format!(
+            "{}  - [{}] {}\n",
+            indent,
+            if item.done { "x" } else { " " },
+            markdown_escape(&item.text)
+        ));
+    }
+    for child in tasks.iter().filter(|t| t.parent == Some(task.id)) {
+        markdown_task(project, tasks, child, depth + 1, md);
+    }
+}
+
+// An iCalendar file with a VTODO for every task that has a due date. Calendar apps that
+// subscribe to the file match entries across exports by the tasks' UUIDs.
+pub fn to_ical(project: &Project, now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//TaskMaster//TaskMaster//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", ical_text(&project.name)),
+    ];
+    for task in ordered(project) {
+        let Some(due) = task.due else {
+            continue;
+        };
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}@taskmaster", task.uuid));
+        lines.push(format!("DTSTAMP:{}", ical_time(now)));
+        lines.push(format!("CREATED:{}", ical_time(task.created_at)));
+        lines.push(format!("LAST-MODIFIED:{}", ical_time(task.updated_at)));
+        lines.push(format!("SUMMARY:{}", ical_text(&task.title)));
+        if let Some(start) = task.planned_start.filter(|start| *start <= due) {
+            lines.push(format!("DTSTART:{}", ical_time(start)));
+        }
+        lines.push(format!("DUE:{}", ical_time(due)));
+        lines.push(format!(
+            "STATUS:{}",
+            match task.status {
+                TaskStatus::Done => "COMPLETED",
+                TaskStatus::InProgress => "IN-PROCESS",
+                _ => "NEEDS-ACTION",
+            }
+        ));
+        if let Some(completed) = task.completed_at() {
+            lines.push(format!("COMPLETED:{}", ical_time(completed)));
+        }
+        lines.push(format!("PRIORITY:{}", ical_priority(project, task)));
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|t| ical_text(t)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if !task.checklist.is_empty() {
+            let items: Vec<String> = task
+                .checklist
+                .iter()
+                .map(|item| format!("[{}] {}", if item.done { "x" } else { " " }, item.text))
+                .collect();
+            lines.push(format!("DESCRIPTION:{}", ical_text(&items.join("\n"))));
+        }
+        if let Some(parent) = task.parent.and_then(|id| project.get_task(id).ok()) {
+            lines.push(format!("RELATED-TO:{}@taskmaster", parent.uuid));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+fn ordered(project: &Project) -> Vec<&Task> {
+    let mut tasks: Vec<&Task> = project.tasks.iter().collect();
+    tasks.sort_by(|a, b| a.rank.cmp(&b.rank));
+    tasks
+}
+
+fn ical_time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// iCalendar runs from 1 (most urgent) to 9, with 5 in the middle; levels on a numeric
+// scale are spread over that range
+fn ical_priority(project: &Project, task: &Task) -> u8 {
+    match task.priority {
+        TaskPriority::High => 1,
+        TaskPriority::Medium => 5,
+        TaskPriority::Low => 9,
+        TaskPriority::Level(_) => {
+            let weight = project.settings.priority_scale.weight(&task.priority) as f64;
+            9 - (weight * 8.0 / 100.0).round() as u8
+        }
+    }
+}
+
+fn ical_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Lines end in CRLF and are folded to at most 75 bytes, continuing with a space, without
+// splitting a character
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+
+// The type of a custom field declared in a project's schema
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    // Stored as YYYY-MM-DD
+    Date,
+    // One of a fixed set of values
+    Enum(Vec<String>),
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldKind::Text => write!(f, "text"),
+            FieldKind::Number => write!(f, "number"),
+            FieldKind::Date => write!(f, "date"),
+            FieldKind::Enum(values) => write!(f, "enum:{}", values.join("|")),
+        }
+    }
+}
+
+impl FromStr for FieldKind {
+    type Err = TaskMasterError;
+
+    // "text", "number", "date" or "enum:a|b|c"
+    fn from_str(s: &str) -> Result<Self> {
+        let input = s.trim();
+        match input.to_lowercase().as_str() {
+            "text" => return Ok(FieldKind::Text),
+            "number" => return Ok(FieldKind::Number),
+            "date" => return Ok(FieldKind::Date),
+            _ => {}
+        }
+
+        let values: Vec<String> = input
+            .strip_prefix("enum:")
+            .ok_or_else(|| {
+                TaskMasterError::InvalidOperation(format!(
+                    "Unknown field type '{}'; use text, number, date or enum:a|b|c",
+                    s
+                ))
+            })?
+            .split('|')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if values.is_empty() {
+            return Err(TaskMasterError::InvalidOperation(
+                "An enum field needs at least one value".to_string(),
+            ));
+        }
+        Ok(FieldKind::Enum(values))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldDefinition {
+    pub name: String,
+    pub kind: FieldKind,
+}
+
+impl FieldDefinition {
+    // Check a value against the field's type, returning it in canonical form
+    pub fn normalize(&self, value: &str) -> Result<String> {
+        let value = value.trim();
+        let invalid = |expected: &str| {
+            TaskMasterError::InvalidOperation(format!(
+                "Field '{}' expects {}, got '{}'",
+                self.name, expected, value
+            ))
+        };
+
+        match &self.kind {
+            FieldKind::Text => Ok(value.to_string()),
+            FieldKind::Number => value
+                .parse::<f64>()
+                .map(|_| value.to_string())
+                .map_err(|_| invalid("a number")),
+            FieldKind::Date => dates::parse_datetime(value)
+                .map(|date| {
+                    date.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d")
+                        .to_string()
+                })
+                .map_err(|_| invalid("a date")),
+            FieldKind::Enum(values) => values
+                .iter()
+                .find(|v| v.eq_ignore_ascii_case(value))
+                .cloned()
+                .ok_or_else(|| invalid(&format!("one of {}", values.join(", ")))),
+        }
+    }
+}
+
+// Validate a custom field against a project's schema, returning the field's declared name and
+// the canonical value. Projects without a schema accept any field as text; once fields are
+// declared, only those can be set.
+pub fn normalize_field(
+    schema: &[FieldDefinition],
+    name: &str,
+    value: &str,
+) -> Result<(String, String)> {
+    if schema.is_empty() {
+        return Ok((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let field = schema
+        .iter()
+        .find(|field| field.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names: Vec<&str> = schema.iter().map(|f| f.name.as_str()).collect();
+            TaskMasterError::InvalidOperation(format!(
+                "Unknown field '{}'; this project defines {}",
+                name,
+                names.join(", ")
+            ))
+        })?;
+    Ok((field.name.clone(), field.normalize(value)?))
+}
+
+// Split a "key=value" argument
+pub fn parse_assignment(input: &str) -> Result<(String, String)> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(TaskMasterError::InvalidOperation(format!(
+            "Expected key=value, got '{}'",
+            input
+        ))),
+    }
+}
@@ -1,8 +1,7 @@
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-
-use serde_json;
+use std::time::SystemTime;
 
 use crate::error::{Result, TaskMasterError};
 use crate::project::Project;
@@ -36,8 +35,13 @@ impl Storage for FileStorage {
         let json = serde_json::to_string(project)
             .map_err(|e| TaskMasterError::SerializationError(e.to_string()))?;
 
-        let mut file = File::create(path)?;
+        // Write to a temporary file first so a failed save never leaves a half-written project
+        let tmp_path = path.with_extension("json.tmp");
+        let mut file = File::create(&tmp_path)?;
         file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        crate::log_event!(Debug, "Saved project {}", project.id);
         Ok(())
     }
 
@@ -48,12 +52,36 @@ impl Storage for FileStorage {
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
-        serde_json::from_str(&contents)
-            .map_err(|e| TaskMasterError::SerializationError(e.to_string()))
+        let mut project: Project = serde_json::from_str(&contents)
+            .map_err(|e| TaskMasterError::SerializationError(e.to_string()))?;
+        let reranked = project.assign_missing_ranks();
+        project.sort_tasks();
+
+        // Persist UUIDs and ranks for older files straight away so they stay stable across loads
+        if project.assign_missing_uuids() | reranked {
+            let json = serde_json::to_string(&project)
+                .map_err(|e| TaskMasterError::SerializationError(e.to_string()))?;
+            fs::write(&path, json)?;
+        }
+
+        crate::log_event!(Debug, "Loaded project {}", id);
+        Ok(project)
     }
 
     fn list_projects(&self) -> Result<Vec<Project>> {
         let mut projects = Vec::new();
+        for id in self.project_ids()? {
+            match self.load_project(id) {
+                Ok(project) => projects.push(project),
+                // Skip invalid projects
+                Err(e) => crate::log_event!(Debug, "Skipped unreadable project {}: {}", id, e),
+            }
+        }
+        Ok(projects)
+    }
+
+    fn project_ids(&self) -> Result<Vec<u32>> {
+        let mut ids = Vec::new();
 
         for entry in fs::read_dir(&self.base_path)? {
             let entry = entry?;
@@ -71,17 +99,18 @@ impl Storage for FileStorage {
                             .unwrap_or("")
                             .parse::<u32>()
                         {
-                            match self.load_project(id) {
-                                Ok(project) => projects.push(project),
-                                Err(_) => continue, // Skip invalid projects
-                            }
+                            ids.push(id);
                         }
                     }
                 }
             }
         }
 
-        Ok(projects)
+        Ok(ids)
+    }
+
+    fn project_modified(&self, id: u32) -> Option<SystemTime> {
+        fs::metadata(self.project_path(id)).ok()?.modified().ok()
     }
 
     fn delete_project(&mut self, id: u32) -> Result<()> {
@@ -89,6 +118,7 @@ impl Storage for FileStorage {
 
         if path.exists() {
             fs::remove_file(path)?;
+            crate::log_event!(Debug, "Deleted project {}", id);
             Ok(())
         } else {
             Err(TaskMasterError::ProjectNotFound(id))
@@ -130,7 +160,8 @@ impl Storage for FileStorage {
 
 impl Drop for FileStorage {
     fn drop(&mut self) {
-        // Any cleanup logic here
-        println!("FileStorage resources cleaned up");
+        // Any cleanup logic here. Logged at debug level so scripted output such as
+        // `statusline` stays a single clean line.
+        crate::log_event!(Debug, "FileStorage resources cleaned up");
     }
 }
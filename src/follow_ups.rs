@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::correlation::RunId;
+use crate::error::Result;
+use crate::project::Project;
+use crate::task::{Task, TaskPriority};
+use crate::worker_pool::JobOutcome;
+
+// A task asked for once a run is over, e.g. "Fix flaky test" after the tests failed.
+// Handlers return these from `TaskHandler::follow_ups` and the executor hands them back on
+// the run's JobResult; rules in config.json add more. `Project::add_follow_ups` turns both
+// into tasks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FollowUp {
+    pub title: String,
+    // The project's default priority if unset
+    pub priority: Option<TaskPriority>,
+    // Added to the project's default tags
+    pub tags: Vec<String>,
+}
+
+impl FollowUp {
+    pub fn new(title: &str) -> Self {
+        FollowUp {
+            title: title.to_string(),
+            priority: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+}
+
+// How a run ended, as follow-up rules name it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutcomeKind {
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled,
+    Panicked,
+}
+
+impl OutcomeKind {
+    pub fn of(outcome: &JobOutcome) -> Self {
+        match outcome {
+            JobOutcome::Success { .. } => OutcomeKind::Succeeded,
+            JobOutcome::Failed(_) => OutcomeKind::Failed,
+            JobOutcome::TimedOut => OutcomeKind::TimedOut,
+            JobOutcome::Cancelled => OutcomeKind::Cancelled,
+            JobOutcome::Panicked(_) => OutcomeKind::Panicked,
+        }
+    }
+}
+
+// A follow-up `run` adds whenever a matching task ends a certain way, listed in
+// `config.json` under `follow_ups`, e.g.
+// {"on": ["failed"], "tags": ["tests"], "title": "Fix {title}", "priority": "High"}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FollowUpRule {
+    // Outcomes that trigger the rule; any outcome but success if empty
+    #[serde(default)]
+    pub on: Vec<OutcomeKind>,
+    // Only for tasks with any of these tags; every task if empty
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Title of the new task, where {title} and {id} stand for the task that ran
+    pub title: String,
+    // On the project's priority scale; the project's default if unset
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+}
+
+impl FollowUpRule {
+    pub fn matches(&self, task: &Task, outcome: &JobOutcome) -> bool {
+        let kind = OutcomeKind::of(outcome);
+        let on = if self.on.is_empty() {
+            kind != OutcomeKind::Succeeded
+        } else {
+            self.on.contains(&kind)
+        };
+        let tagged = self.tags.is_empty()
+            || self
+                .tags
+                .iter()
+                .any(|tag| task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+        on && tagged
+    }
+
+    pub fn follow_up(&self, project: &Project, task: &Task) -> Result<FollowUp> {
+        let title = self
+            .title
+            .replace("{title}", &task.title)
+            .replace("{id}", &task.id.to_string());
+        let mut follow_up = FollowUp::new(&title);
+        if let Some(priority) = &self.priority {
+            follow_up = follow_up.priority(project.settings.priority_scale.parse(priority)?);
+        }
+        for tag in &self.add_tags {
+            follow_up = follow_up.tag(tag);
+        }
+        Ok(follow_up)
+    }
+}
+
+// The run a follow-up task was created after, to find it in the event log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FollowUpOrigin {
+    pub task_id: u32,
+    pub run_id: RunId,
+}
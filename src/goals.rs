@@ -0,0 +1,283 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::ids;
+use crate::project::Project;
+use crate::task::TaskStatus;
+
+pub const GOALS_FILE: &str = "goals.json";
+
+// A calendar quarter such as 2024-Q3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Quarter {
+    pub year: i32,
+    // 1 to 4
+    pub number: u32,
+}
+
+impl Quarter {
+    pub fn containing(date: NaiveDate) -> Self {
+        Quarter {
+            year: date.year(),
+            number: (date.month() - 1) / 3 + 1,
+        }
+    }
+
+    // "2024-Q3", "2024Q3" or "Q3-2024" (any case, space or dash)
+    pub fn parse(input: &str) -> Result<Self> {
+        let compact: String = input
+            .trim()
+            .to_uppercase()
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-'))
+            .collect();
+        let (year, number) = match compact.split_once('Q') {
+            Some(("", rest)) if rest.len() > 1 => (&rest[1..], &rest[..1]),
+            Some((year, number)) => (year, number),
+            None => ("", ""),
+        };
+        match (year.parse::<i32>(), number.parse::<u32>()) {
+            (Ok(year), Ok(number)) if (1..=4).contains(&number) => Ok(Quarter { year, number }),
+            _ => Err(TaskMasterError::InvalidOperation(format!(
+                "Invalid quarter '{}'; use e.g. 2024-Q3",
+                input
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Quarter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-Q{}", self.year, self.number)
+    }
+}
+
+// What a goal is measured by: a whole project, or a single task in one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GoalTarget {
+    Project(u32),
+    Task { project: u32, task: u32 },
+}
+
+impl fmt::Display for GoalTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalTarget::Project(id) => write!(f, "project {}", id),
+            GoalTarget::Task { project, task } => write!(f, "task {}/{}", project, task),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalLink {
+    pub target: GoalTarget,
+    // Share of the goal relative to the other links
+    pub weight: f64,
+}
+
+// A quarterly objective whose progress is the weighted completion of its links
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: u32,
+    pub name: String,
+    pub quarter: Quarter,
+    #[serde(default)]
+    pub links: Vec<GoalLink>,
+}
+
+// How far along one link is
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkProgress {
+    pub target: GoalTarget,
+    pub label: String,
+    pub weight: f64,
+    // None when the project or task no longer exists, or the project has no tasks
+    pub progress: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalProgress {
+    pub id: u32,
+    pub name: String,
+    pub quarter: Quarter,
+    // Weighted over the links that can be measured; None if there are none
+    pub percentage: Option<f64>,
+    pub links: Vec<LinkProgress>,
+}
+
+impl GoalProgress {
+    // e.g. "Launch v2 (2024-Q3, ID: 1): 45%"
+    pub fn describe(&self) -> String {
+        let progress = match self.percentage {
+            Some(percentage) => format!("{:.0}%", percentage),
+            None => "no progress to measure".to_string(),
+        };
+        format!(
+            "{} ({}, ID: {}): {}",
+            self.name, self.quarter, self.id, progress
+        )
+    }
+}
+
+impl Goal {
+    pub fn links_project(&self, project_id: u32) -> bool {
+        self.links.iter().any(|link| match link.target {
+            GoalTarget::Project(id) => id == project_id,
+            GoalTarget::Task { project, .. } => project == project_id,
+        })
+    }
+
+    // Projects count the share of their tasks that are Done; tasks count fully once Done
+    pub fn progress(&self, projects: &[Project]) -> GoalProgress {
+        let links: Vec<LinkProgress> = self
+            .links
+            .iter()
+            .map(|link| {
+                let (label, progress) = match link.target {
+                    GoalTarget::Project(id) => match projects.iter().find(|p| p.id == id) {
+                        Some(project) => (
+                            project.label(),
+                            project
+                                .stats()
+                                .completion_percentage()
+                                .map(|percentage| percentage / 100.0),
+                        ),
+                        None => (format!("project {} (missing)", id), None),
+                    },
+                    GoalTarget::Task { project, task } => match projects
+                        .iter()
+                        .find(|p| p.id == project)
+                        .and_then(|p| p.get_task(task).ok())
+                    {
+                        Some(found) => (
+                            format!("{} (task {}/{})", found.title, project, task),
+                            Some(if found.status == TaskStatus::Done {
+                                1.0
+                            } else {
+                                0.0
+                            }),
+                        ),
+                        None => (format!("task {}/{} (missing)", project, task), None),
+                    },
+                };
+                LinkProgress {
+                    target: link.target,
+                    label,
+                    weight: link.weight,
+                    progress,
+                }
+            })
+            .collect();
+
+        let measured: Vec<&LinkProgress> = links.iter().filter(|l| l.progress.is_some()).collect();
+        let total_weight: f64 = measured.iter().map(|l| l.weight).sum();
+        let percentage = (total_weight > 0.0).then(|| {
+            measured
+                .iter()
+                .map(|l| l.weight * l.progress.unwrap_or(0.0))
+                .sum::<f64>()
+                * 100.0
+                / total_weight
+        });
+
+        GoalProgress {
+            id: self.id,
+            name: self.name.clone(),
+            quarter: self.quarter,
+            percentage,
+            links,
+        }
+    }
+}
+
+// Goals stored as `goals.json` in the data directory; they span projects, so they live
+// outside any one project file
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Goals {
+    entries: Vec<Goal>,
+}
+
+impl Goals {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(GOALS_FILE);
+        if !path.exists() {
+            return Ok(Goals::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(GOALS_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, name: &str, quarter: Quarter) -> Result<&Goal> {
+        let id = ids::next_id(self.entries.iter().map(|g| g.id))?;
+        self.entries.push(Goal {
+            id,
+            name: name.to_string(),
+            quarter,
+            links: Vec::new(),
+        });
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+
+    pub fn remove(&mut self, id: u32) -> Result<Goal> {
+        let position = self
+            .entries
+            .iter()
+            .position(|g| g.id == id)
+            .ok_or_else(|| TaskMasterError::InvalidOperation(format!("No goal with ID {}", id)))?;
+        Ok(self.entries.remove(position))
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Result<&mut Goal> {
+        self.entries
+            .iter_mut()
+            .find(|g| g.id == id)
+            .ok_or_else(|| TaskMasterError::InvalidOperation(format!("No goal with ID {}", id)))
+    }
+
+    // Link a project or task, or change the weight of an existing link
+    pub fn link(&mut self, id: u32, target: GoalTarget, weight: f64) -> Result<()> {
+        if weight <= 0.0 || !weight.is_finite() {
+            return Err(TaskMasterError::InvalidOperation(
+                "Weight must be a positive number".to_string(),
+            ));
+        }
+        let goal = self.get_mut(id)?;
+        match goal.links.iter_mut().find(|l| l.target == target) {
+            Some(link) => link.weight = weight,
+            None => goal.links.push(GoalLink { target, weight }),
+        }
+        Ok(())
+    }
+
+    pub fn unlink(&mut self, id: u32, target: GoalTarget) -> Result<()> {
+        let goal = self.get_mut(id)?;
+        let before = goal.links.len();
+        goal.links.retain(|l| l.target != target);
+        if goal.links.len() == before {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Goal {} is not linked to {}",
+                id, target
+            )));
+        }
+        Ok(())
+    }
+
+    // Sorted by quarter, then ID
+    pub fn iter(&self) -> impl Iterator<Item = &Goal> {
+        let mut goals: Vec<&Goal> = self.entries.iter().collect();
+        goals.sort_by_key(|g| (g.quarter, g.id));
+        goals.into_iter()
+    }
+}
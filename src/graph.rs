@@ -0,0 +1,163 @@
+use crate::error::Result;
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// Fill colors by status, shared by both formats
+const DONE_COLOR: &str = "#b7e1a1";
+const IN_PROGRESS_COLOR: &str = "#ffe08a";
+const TODO_COLOR: &str = "#e0e0e0";
+const CUSTOM_COLOR: &str = "#a9cce3";
+const BLOCKED_COLOR: &str = "#f4a6a6";
+
+// How a task is drawn; open tasks waiting on others or blocked by hand stand out
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NodeKind {
+    Done,
+    InProgress,
+    ToDo,
+    Custom,
+    Blocked,
+}
+
+impl NodeKind {
+    fn of(task: &Task, tasks: &[Task]) -> Self {
+        match &task.status {
+            TaskStatus::Done => NodeKind::Done,
+            _ if task.blocked_reason.is_some() || !task.blocking_dependencies(tasks).is_empty() => {
+                NodeKind::Blocked
+            }
+            TaskStatus::InProgress => NodeKind::InProgress,
+            TaskStatus::ToDo => NodeKind::ToDo,
+            TaskStatus::Custom(_) => NodeKind::Custom,
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            NodeKind::Done => DONE_COLOR,
+            NodeKind::InProgress => IN_PROGRESS_COLOR,
+            NodeKind::ToDo => TODO_COLOR,
+            NodeKind::Custom => CUSTOM_COLOR,
+            NodeKind::Blocked => BLOCKED_COLOR,
+        }
+    }
+
+    // Mermaid class name
+    fn class(self) -> &'static str {
+        match self {
+            NodeKind::Done => "done",
+            NodeKind::InProgress => "inprogress",
+            NodeKind::ToDo => "todo",
+            NodeKind::Custom => "custom",
+            NodeKind::Blocked => "blocked",
+        }
+    }
+
+    const ALL: [NodeKind; 5] = [
+        NodeKind::Done,
+        NodeKind::InProgress,
+        NodeKind::ToDo,
+        NodeKind::Custom,
+        NodeKind::Blocked,
+    ];
+}
+
+// From each dependency to the task waiting on it, labelled with the dependency type and
+// lag unless it is a plain finish-to-start
+fn edges(tasks: &[&Task]) -> Vec<(u32, u32, Option<String>)> {
+    tasks
+        .iter()
+        .flat_map(|task| {
+            task.dependencies
+                .iter()
+                .flatten()
+                .map(move |dep| (dep.task, task.id, dep.label()))
+        })
+        .collect()
+}
+
+// Graphviz source; render with e.g. `dot -Tsvg`
+pub fn to_dot(project: &Project) -> Result<String> {
+    let tasks = project.get_task_execution_order()?;
+    let mut dot = format!("digraph \"{}\" {{\n", dot_escape(&project.name));
+    dot.push_str("  rankdir=LR;\n");
+    dot.push_str("  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+    for task in &tasks {
+        dot.push_str(&format!(
+            "  t{} [label=\"{}: {}\\n{}\", fillcolor=\"{}\"];\n",
+            task.id,
+            task.id,
+            dot_escape(&task.title),
+            dot_escape(&task.status.to_string()),
+            NodeKind::of(task, &project.tasks).color()
+        ));
+    }
+    for (from, to, label) in edges(&tasks) {
+        match label {
+            Some(label) => dot.push_str(&format!(
+                "  t{} -> t{} [label=\"{}\"];\n",
+                from,
+                to,
+                dot_escape(&label)
+            )),
+            None => dot.push_str(&format!("  t{} -> t{};\n", from, to)),
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+// A Mermaid flowchart, ready to paste into Markdown docs
+pub fn to_mermaid(project: &Project) -> Result<String> {
+    let tasks = project.get_task_execution_order()?;
+    let mut mermaid = String::from("flowchart LR\n");
+    for task in &tasks {
+        mermaid.push_str(&format!(
+            "  t{}[\"{}: {}<br/>{}\"]\n",
+            task.id,
+            task.id,
+            mermaid_escape(&task.title),
+            mermaid_escape(&task.status.to_string())
+        ));
+    }
+    for (from, to, label) in edges(&tasks) {
+        match label {
+            Some(label) => mermaid.push_str(&format!(
+                "  t{} -->|\"{}\"| t{}\n",
+                from,
+                mermaid_escape(&label),
+                to
+            )),
+            None => mermaid.push_str(&format!("  t{} --> t{}\n", from, to)),
+        }
+    }
+    for kind in NodeKind::ALL {
+        let ids: Vec<String> = tasks
+            .iter()
+            .filter(|t| NodeKind::of(t, &project.tasks) == kind)
+            .map(|t| format!("t{}", t.id))
+            .collect();
+        if ids.is_empty() {
+            continue;
+        }
+        mermaid.push_str(&format!(
+            "  classDef {} fill:{},stroke:#555\n  class {} {}\n",
+            kind.class(),
+            kind.color(),
+            ids.join(","),
+            kind.class()
+        ));
+    }
+    Ok(mermaid)
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Mermaid labels are quoted; quotes and angle brackets go in as entity codes
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
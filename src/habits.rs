@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::periodic_tasks::RecurrencePattern;
+
+// Habit tracking for a recurring task. The state moves along to each new occurrence, so
+// the open occurrence always holds the full record.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Habit {
+    // First day counted; periods are measured from here
+    pub since: NaiveDate,
+    // Missed periods a streak survives before it is broken
+    pub skips: u32,
+    // Local days the habit was done on
+    #[serde(default)]
+    pub completions: Vec<NaiveDate>,
+}
+
+// Where a habit stands, in periods of its recurrence (days, weeks, ...)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Streak {
+    pub current: u32,
+    pub best: u32,
+    // Skips the current streak has used up
+    pub skips_used: u32,
+}
+
+impl Habit {
+    pub fn new(since: NaiveDate, skips: u32) -> Self {
+        Habit {
+            since,
+            skips,
+            completions: Vec::new(),
+        }
+    }
+
+    pub fn record_completion(&mut self, date: NaiveDate) {
+        self.completions.push(date);
+    }
+
+    // Index of the period a date falls in, counting from `since`
+    fn period(&self, pattern: &RecurrencePattern, date: NaiveDate) -> i64 {
+        let days = (date - self.since).num_days();
+        match pattern {
+            RecurrencePattern::Daily => days,
+            RecurrencePattern::Weekly => days.div_euclid(7),
+            RecurrencePattern::Monthly => {
+                (date.year() - self.since.year()) as i64 * 12 + date.month() as i64
+                    - self.since.month() as i64
+            }
+            RecurrencePattern::Custom(interval) => {
+                days.div_euclid((interval.as_secs() / 86_400).max(1) as i64)
+            }
+        }
+    }
+
+    fn done_periods(&self, pattern: &RecurrencePattern) -> HashSet<i64> {
+        self.completions
+            .iter()
+            .map(|&date| self.period(pattern, date))
+            .collect()
+    }
+
+    // Walk the periods up to today. A period without a completion uses up a skip; once a
+    // streak has missed more periods than allowed it starts again from zero. The current
+    // period is still open, so it is never counted as missed.
+    pub fn streak(&self, pattern: &RecurrencePattern, today: NaiveDate) -> Streak {
+        let done = self.done_periods(pattern);
+        let now = self.period(pattern, today);
+        let mut streak = Streak::default();
+        for period in 0..=now {
+            if done.contains(&period) {
+                streak.current += 1;
+                streak.best = streak.best.max(streak.current);
+            } else if period < now && streak.current > 0 {
+                streak.skips_used += 1;
+                if streak.skips_used > self.skips {
+                    streak.current = 0;
+                    streak.skips_used = 0;
+                }
+            }
+        }
+        streak
+    }
+
+    // The last `count` periods, oldest first: '✓' done, '·' missed, '○' the open period
+    // not done yet
+    pub fn calendar(&self, pattern: &RecurrencePattern, today: NaiveDate, count: u32) -> String {
+        let done = self.done_periods(pattern);
+        let now = self.period(pattern, today);
+        (now + 1 - count as i64..=now)
+            .filter(|period| *period >= 0)
+            .map(|period| {
+                if done.contains(&period) {
+                    '✓'
+                } else if period == now {
+                    '○'
+                } else {
+                    '·'
+                }
+            })
+            .collect()
+    }
+}
+
+// "day", "week", ... for describing streaks of a pattern
+pub fn period_name(pattern: &RecurrencePattern) -> &'static str {
+    match pattern {
+        RecurrencePattern::Daily => "day",
+        RecurrencePattern::Weekly => "week",
+        RecurrencePattern::Monthly => "month",
+        RecurrencePattern::Custom(_) => "period",
+    }
+}
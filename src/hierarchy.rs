@@ -0,0 +1,82 @@
+use crate::appearance;
+use crate::error::{Result, TaskMasterError};
+use crate::project::Project;
+
+// Projects can sit under a parent project, which then acts as a workspace for them.
+// The hierarchy is stored as `parent_id` on each project.
+
+// Check that `project_id` can move under `parent_id` without creating a cycle
+pub fn check_parent(projects: &[Project], project_id: u32, parent_id: u32) -> Result<()> {
+    if !projects.iter().any(|p| p.id == parent_id) {
+        return Err(TaskMasterError::ProjectNotFound(parent_id));
+    }
+
+    let mut current = Some(parent_id);
+    while let Some(id) = current {
+        if id == project_id {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Project {} cannot be placed under {}, which is inside it",
+                project_id, parent_id
+            )));
+        }
+        current = projects
+            .iter()
+            .find(|p| p.id == id)
+            .and_then(|p| p.parent_id);
+    }
+    Ok(())
+}
+
+// Direct subprojects, in ID order
+pub fn children(projects: &[Project], parent_id: u32) -> Vec<&Project> {
+    let mut children: Vec<&Project> = projects
+        .iter()
+        .filter(|p| p.parent_id == Some(parent_id))
+        .collect();
+    children.sort_by_key(|p| p.id);
+    children
+}
+
+// Every project below `project_id`, depth first
+pub fn descendants(projects: &[Project], project_id: u32) -> Vec<&Project> {
+    let mut found = Vec::new();
+    for child in children(projects, project_id) {
+        found.push(child);
+        found.extend(descendants(projects, child.id));
+    }
+    found
+}
+
+// Indented listing of the hierarchy. Projects whose parent is missing from `projects`
+// (e.g. hidden because it is archived) are shown at the top level.
+pub fn tree_lines(projects: &[Project]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut roots: Vec<&Project> = projects
+        .iter()
+        .filter(|p| match p.parent_id {
+            Some(parent) => !projects.iter().any(|other| other.id == parent),
+            None => true,
+        })
+        .collect();
+    roots.sort_by_key(|p| p.id);
+    for root in roots {
+        push_subtree(projects, root, 0, &mut lines);
+    }
+    lines
+}
+
+fn push_subtree(projects: &[Project], project: &Project, depth: usize, lines: &mut Vec<String>) {
+    let mut line = format!(
+        "{}{} (ID: {})",
+        "  ".repeat(depth),
+        appearance::paint(&project.label(), project.color),
+        project.id
+    );
+    if project.archived {
+        line.push_str(" (archived)");
+    }
+    lines.push(line);
+    for child in children(projects, project.id) {
+        push_subtree(projects, child, depth + 1, lines);
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Result, TaskMasterError};
+
+static UUID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Random (version 4) UUID used as a stable identity for projects and tasks
+// across data directories, independent of their user-facing numeric IDs
+pub fn new_uuid() -> String {
+    let high = random_u64();
+    let low = random_u64();
+
+    // Set the version (4) and RFC 4122 variant bits
+    let high = (high & 0xffff_ffff_ffff_0fff) | 0x0000_0000_0000_4000;
+    let low = (low & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+// std seeds every RandomState from OS randomness; mixing in the clock and a
+// counter keeps successive values distinct within one process
+fn random_u64() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(nanos);
+    hasher.write_u64(UUID_COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+// Smallest ID above every existing one, starting at 1
+pub fn next_id<I: IntoIterator<Item = u32>>(existing: I) -> Result<u32> {
+    existing.into_iter().max().map_or(Ok(1), after)
+}
+
+// The ID after `id`, or an error once every ID has been used
+pub fn after(id: u32) -> Result<u32> {
+    id.checked_add(1)
+        .ok_or_else(|| TaskMasterError::InvalidOperation(format!("No IDs left after {}", id)))
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+use crate::fields;
+use crate::project::Project;
+use crate::task::Task;
+
+// Columns that fill in a task's own properties; `field:<name>` columns set custom fields
+pub const COLUMNS: [&str; 6] = ["title", "status", "priority", "due", "tags", "estimate"];
+
+// One task's worth of input: the row or array position it came from, and its values by
+// lowercase column name
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    pub number: usize,
+    pub values: BTreeMap<String, String>,
+}
+
+// What importing did, row by row
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    // (row, task ID)
+    pub created: Vec<(usize, u32)>,
+    // (row, why it was skipped)
+    pub failed: Vec<(usize, String)>,
+    // Columns that matched nothing and were left out
+    pub ignored: Vec<String>,
+}
+
+// Rows of a CSV file whose first line names the columns. Quoted values may hold commas,
+// doubled quotes and line breaks. Rows are numbered by the line they start on.
+pub fn parse_csv(text: &str) -> Result<Vec<ImportRow>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = csv_records(text)?;
+    if records.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (_, header) = records.remove(0);
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+
+    Ok(records
+        .into_iter()
+        .filter(|(_, cells)| cells.iter().any(|c| !c.trim().is_empty()))
+        .map(|(line, cells)| ImportRow {
+            number: line,
+            values: header
+                .iter()
+                .cloned()
+                .zip(cells)
+                .filter(|(name, _)| !name.is_empty())
+                .collect(),
+        })
+        .collect())
+}
+
+fn csv_records(text: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if cell.is_empty() => in_quotes = true,
+            ',' if !in_quotes => cells.push(std::mem::take(&mut cell)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                cells.push(std::mem::take(&mut cell));
+                records.push((start, std::mem::take(&mut cells)));
+                line += 1;
+                start = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                cell.push(c);
+            }
+        }
+    }
+    if in_quotes {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Unclosed quote in the row starting on line {}",
+            start
+        )));
+    }
+    if !cell.is_empty() || !cells.is_empty() {
+        cells.push(cell);
+        records.push((start, cells));
+    }
+    Ok(records)
+}
+
+// Rows from a JSON array of objects, or an object with such an array under "tasks" (as
+// `--output json` prints). Tags may be an array and custom fields an object under
+// "fields"; rows are numbered from 1.
+pub fn parse_json(text: &str) -> Result<Vec<ImportRow>> {
+    let value: Value = serde_json::from_str(text)?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(map) => match map.get("tasks") {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Err(TaskMasterError::InvalidOperation(
+                    "Expected an array of tasks, or an object with a \"tasks\" array".to_string(),
+                ))
+            }
+        },
+        _ => {
+            return Err(TaskMasterError::InvalidOperation(
+                "Expected an array of tasks".to_string(),
+            ))
+        }
+    };
+
+    let mut rows = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let Value::Object(map) = item else {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Task {} is not an object",
+                index + 1
+            )));
+        };
+        let mut values = BTreeMap::new();
+        for (key, value) in map {
+            let key = match key.trim().to_lowercase() {
+                // As `--output json` names it
+                key if key == "estimate_hours" => "estimate".to_string(),
+                key => key,
+            };
+            match value {
+                Value::Object(custom) if key == "fields" => {
+                    for (name, value) in custom {
+                        if let Some(text) = json_text(value) {
+                            values.insert(format!("field:{}", name.to_lowercase()), text);
+                        }
+                    }
+                }
+                value => {
+                    if let Some(text) = json_text(value) {
+                        values.insert(key, text);
+                    }
+                }
+            }
+        }
+        rows.push(ImportRow {
+            number: index + 1,
+            values,
+        });
+    }
+    Ok(rows)
+}
+
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null | Value::Object(_) => None,
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(json_text)
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+// Read columns under other names, from `column=target` pairs such as "Summary=title" or
+// "Owner=field:owner"
+pub fn apply_mappings(rows: &mut [ImportRow], mappings: &[String]) -> Result<()> {
+    let mut renames = Vec::new();
+    for mapping in mappings {
+        let (column, target) = fields::parse_assignment(mapping)?;
+        let target = target.to_lowercase();
+        if !COLUMNS.contains(&target.as_str()) && !target.starts_with("field:") {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Can't map '{}' to '{}'; use one of {} or field:<name>",
+                column,
+                target,
+                COLUMNS.join(", ")
+            )));
+        }
+        renames.push((column.to_lowercase(), target));
+    }
+    for row in rows {
+        for (column, target) in &renames {
+            if let Some(value) = row.values.remove(column) {
+                row.values.insert(target.clone(), value);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Add a task to `project` for each row. A row that can't become a valid task is reported
+// and skipped without stopping the others. Columns naming a field in the project's schema
+// set that field, like `field:<name>` columns do.
+pub fn import_rows(project: &mut Project, rows: &[ImportRow]) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let schema = project.settings.fields.clone();
+    let field_name = |column: &str| -> Option<String> {
+        if let Some(name) = column.strip_prefix("field:") {
+            return Some(name.trim().to_string());
+        }
+        schema
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(column))
+            .map(|f| f.name.clone())
+    };
+
+    let mut ignored: Vec<String> = rows
+        .iter()
+        .flat_map(|row| row.values.keys())
+        .filter(|column| !COLUMNS.contains(&column.as_str()) && field_name(column).is_none())
+        .cloned()
+        .collect();
+    ignored.sort();
+    ignored.dedup();
+    report.ignored = ignored;
+
+    for row in rows {
+        let result = project.next_task_id().and_then(|id| {
+            let task = row_task(project, row, id, &field_name)?;
+            project.add_task(task)?;
+            Ok(id)
+        });
+        match result {
+            Ok(id) => report.created.push((row.number, id)),
+            Err(e) => report.failed.push((row.number, e.to_string())),
+        }
+    }
+    Ok(report)
+}
+
+fn row_task(
+    project: &Project,
+    row: &ImportRow,
+    id: u32,
+    field_name: &dyn Fn(&str) -> Option<String>,
+) -> Result<Task> {
+    let value = |column: &str| {
+        row.values
+            .get(column)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    };
+    let title = value("title")
+        .ok_or_else(|| TaskMasterError::InvalidOperation("The row has no title".to_string()))?;
+
+    let mut task = project.new_task(id, title.to_string())?;
+    if let Some(status) = value("status") {
+        task.status = project.settings.workflow.parse_status(status)?;
+    }
+    if let Some(priority) = value("priority") {
+        task.priority = project.settings.priority_scale.parse(priority)?;
+    }
+    if let Some(due) = value("due") {
+        task.due = Some(dates::parse_datetime(due)?);
+    }
+    if let Some(tags) = value("tags") {
+        for tag in tags
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        {
+            if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                task.tags.push(tag.to_string());
+            }
+        }
+    }
+    if let Some(estimate) = value("estimate") {
+        let hours = estimate
+            .trim_end_matches('h')
+            .parse::<f64>()
+            .ok()
+            .filter(|h| *h >= 0.0)
+            .ok_or_else(|| {
+                TaskMasterError::InvalidOperation(format!(
+                    "Estimate should be a number of hours, got '{}'",
+                    estimate
+                ))
+            })?;
+        task.estimate_hours = Some(hours);
+    }
+
+    let schema = &project.settings.fields;
+    for column in row.values.keys() {
+        let (Some(name), Some(raw)) = (field_name(column), value(column)) else {
+            continue;
+        };
+        let (name, value) = fields::normalize_field(schema, &name, raw)?;
+        task.custom_fields.insert(name, value);
+    }
+    Ok(task)
+}
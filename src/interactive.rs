@@ -1,23 +1,57 @@
 use std::io::{self, Write};
 use std::path::PathBuf;
 
+use chrono::Utc;
+
+use crate::appearance;
+use crate::config::Config;
+use crate::dates;
 use crate::error::Result;
+use crate::event_log::EventLog;
 use crate::file_storage::FileStorage;
+use crate::locale;
 use crate::project::Project;
+use crate::query::{self, Query};
 use crate::storage::Storage;
-use crate::task::{Task, TaskPriority, TaskStatus};
+use crate::task_dependencies::OrphanPolicy;
+
+// The shell's commands as (usage, description), for help and completion
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("help", "Show this help message"),
+    ("exit, quit", "Exit the shell"),
+    ("list", "List all projects"),
+    ("new <id> <name>", "Create a new project"),
+    ("open <id>", "Open a project (make it current)"),
+    ("delete <id>", "Delete a project"),
+    ("tasks", "List tasks in the current project"),
+    ("workflow", "Show the current project's workflow"),
+    ("add <id> <title>", "Add a task to the current project"),
+    ("update <id> <title> <status> <priority>", "Update a task"),
+    (
+        "due <id> <when>",
+        "Set a task's due date (e.g. tomorrow, next friday, none)",
+    ),
+    ("remove <id>", "Remove a task from the current project"),
+    (
+        "search <query>",
+        "Find tasks in all projects (e.g. status:todo tag:backend login)",
+    ),
+];
 
 pub struct InteractiveShell {
     storage: FileStorage,
     current_project: Option<Project>,
+    events: EventLog,
 }
 
 impl InteractiveShell {
     pub fn new(data_dir: &PathBuf) -> Result<Self> {
         let storage = FileStorage::new(data_dir)?;
+        locale::install_settings(&Config::load(data_dir)?.locale);
         Ok(InteractiveShell {
             storage,
             current_project: None,
+            events: EventLog::new(data_dir),
         })
     }
 
@@ -31,50 +65,14 @@ impl InteractiveShell {
 
             let mut input = String::new();
             io::stdin().read_line(&mut input)?;
-            let input = input.trim();
 
-            if input.is_empty() {
-                continue;
+            let mut out = Vec::new();
+            let keep_going = self.execute(&input, &mut out)?;
+            for line in out {
+                println!("{}", line);
             }
-
-            let parts: Vec<&str> = input.split_whitespace().collect();
-            let command = parts[0];
-
-            match command {
-                "help" => self.show_help(),
-                "exit" | "quit" => break,
-                "list" => self.list_projects()?,
-                "new" if parts.len() >= 3 => {
-                    let id = parts[1].parse::<u32>().unwrap_or(0);
-                    let name = parts[2..].join(" ");
-                    self.create_project(id, &name)?;
-                }
-                "open" if parts.len() >= 2 => {
-                    let id = parts[1].parse::<u32>().unwrap_or(0);
-                    self.open_project(id)?;
-                }
-                "delete" if parts.len() >= 2 => {
-                    let id = parts[1].parse::<u32>().unwrap_or(0);
-                    self.delete_project(id)?;
-                }
-                "tasks" => self.list_tasks()?,
-                "add" if parts.len() >= 3 => {
-                    let id = parts[1].parse::<u32>().unwrap_or(0);
-                    let title = parts[2..].join(" ");
-                    self.add_task(id, &title)?;
-                }
-                "update" if parts.len() >= 5 => {
-                    let id = parts[1].parse::<u32>().unwrap_or(0);
-                    let title = parts[2].to_string();
-                    let status = parts[3].to_string();
-                    let priority = parts[4].to_string();
-                    self.update_task(id, &title, &status, &priority)?;
-                }
-                "remove" if parts.len() >= 2 => {
-                    let id = parts[1].parse::<u32>().unwrap_or(0);
-                    self.remove_task(id)?;
-                }
-                _ => println!("Unknown command or invalid format. Type 'help' for help."),
+            if !keep_going {
+                break;
             }
         }
 
@@ -82,55 +80,167 @@ impl InteractiveShell {
         Ok(())
     }
 
-    fn show_help(&self) {
-        println!("Available commands:");
-        println!("  help                          Show this help message");
-        println!("  exit, quit                    Exit the shell");
-        println!("  list                          List all projects");
-        println!("  new <id> <name>               Create a new project");
-        println!("  open <id>                     Open a project (make it current)");
-        println!("  delete <id>                   Delete a project");
-        println!("  tasks                         List tasks in the current project");
-        println!("  add <id> <title>              Add a task to the current project");
-        println!("  update <id> <title> <status> <priority>  Update a task");
-        println!("  remove <id>                   Remove a task from the current project");
+    // Run one command line, writing what it reports to `out`. Returns false when the
+    // command asks to leave the shell.
+    pub fn execute(&mut self, input: &str, out: &mut Vec<String>) -> Result<bool> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(true);
+        }
+
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        let command = parts[0];
+
+        match command {
+            "help" => self.show_help(out),
+            "exit" | "quit" => return Ok(false),
+            "list" => self.list_projects(out)?,
+            "new" if parts.len() >= 3 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                let name = parts[2..].join(" ");
+                self.create_project(id, &name, out)?;
+            }
+            "open" if parts.len() >= 2 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                self.open_project(id, out)?;
+            }
+            "delete" if parts.len() >= 2 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                self.delete_project(id, out)?;
+            }
+            "tasks" => self.list_tasks(out)?,
+            "workflow" => self.show_workflow(out),
+            "add" if parts.len() >= 3 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                let title = parts[2..].join(" ");
+                self.add_task(id, &title, out)?;
+            }
+            "update" if parts.len() >= 5 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                let title = parts[2].to_string();
+                let status = parts[3].to_string();
+                let priority = parts[4].to_string();
+                self.update_task(id, &title, &status, &priority, out)?;
+            }
+            "due" if parts.len() >= 3 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                let when = parts[2..].join(" ");
+                self.set_due(id, &when, out)?;
+            }
+            "remove" if parts.len() >= 2 => {
+                let id = parts[1].parse::<u32>().unwrap_or(0);
+                self.remove_task(id, out)?;
+            }
+            "search" if parts.len() >= 2 => {
+                let query = input[command.len()..].trim();
+                self.search(query, out)?;
+            }
+            _ => out.push("Unknown command or invalid format. Type 'help' for help.".to_string()),
+        }
+        Ok(true)
+    }
+
+    // Make a project current without reporting it, e.g. to follow a selection elsewhere
+    pub fn set_current_project(&mut self, id: u32) {
+        self.current_project = self.storage.load_project(id).ok();
+    }
+
+    pub fn current_project_id(&self) -> Option<u32> {
+        self.current_project.as_ref().map(|p| p.id)
+    }
+
+    // Command names starting with the first word of `input`
+    pub fn complete(input: &str) -> Vec<&'static str> {
+        let prefix = input.trim_start();
+        COMMANDS
+            .iter()
+            .flat_map(|(usage, _)| {
+                let names = usage.split(' ').next().unwrap_or_default();
+                names.split(',').map(str::trim)
+            })
+            .filter(|name| !name.is_empty() && name.starts_with(prefix))
+            .collect()
+    }
+
+    fn show_help(&self, out: &mut Vec<String>) {
+        out.push("Available commands:".to_string());
+        for (usage, description) in COMMANDS {
+            out.push(format!("  {:<28}  {}", usage, description));
+        }
     }
 
-    fn list_projects(&self) -> Result<()> {
+    fn list_projects(&self, out: &mut Vec<String>) -> Result<()> {
         let projects = self.storage.list_projects()?;
         if projects.is_empty() {
-            println!("No projects found");
+            out.push("No projects found".to_string());
         } else {
-            println!("Projects:");
+            out.push("Projects:".to_string());
             for project in projects {
-                println!("  ID: {}, Name: {}", project.id, project.name);
+                out.push(format!(
+                    "  ID: {}, Name: {}",
+                    project.id,
+                    appearance::paint(&project.label(), project.color)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn search(&self, query: &str, out: &mut Vec<String>) -> Result<()> {
+        let query = match Query::parse(query) {
+            Ok(query) => query,
+            Err(e) => {
+                out.push(format!("Error: {}", e));
+                return Ok(());
             }
+        };
+        let projects: Vec<Project> = self
+            .storage
+            .list_projects()?
+            .into_iter()
+            .filter(|p| !p.archived)
+            .collect();
+        let hits = query::search(&projects, &query, Utc::now());
+        if hits.is_empty() {
+            out.push("No matching tasks".to_string());
+        }
+        for (project, task) in hits {
+            out.push(format!(
+                "  [{}] ID: {}, Title: {}, Status: {}",
+                project.label(),
+                project.task_ref(task.id),
+                task.title,
+                task.status_label(&project.tasks)
+            ));
         }
         Ok(())
     }
 
-    fn create_project(&mut self, id: u32, name: &str) -> Result<()> {
+    fn create_project(&mut self, id: u32, name: &str, out: &mut Vec<String>) -> Result<()> {
         let project = Project::new(id, name.to_string());
         self.storage.save_project(&project)?;
-        println!("Project created: {} (ID: {})", name, id);
+        out.push(format!("Project created: {} (ID: {})", name, id));
         Ok(())
     }
 
-    fn open_project(&mut self, id: u32) -> Result<()> {
+    fn open_project(&mut self, id: u32, out: &mut Vec<String>) -> Result<()> {
         match self.storage.load_project(id) {
             Ok(project) => {
-                println!("Opened project: {} (ID: {})", project.name, project.id);
+                out.push(format!(
+                    "Opened project: {} (ID: {})",
+                    project.name, project.id
+                ));
                 self.current_project = Some(project);
                 Ok(())
             }
             Err(e) => {
-                println!("Error opening project: {}", e);
+                out.push(format!("Error opening project: {}", e));
                 Ok(())
             }
         }
     }
 
-    fn delete_project(&mut self, id: u32) -> Result<()> {
+    fn delete_project(&mut self, id: u32, out: &mut Vec<String>) -> Result<()> {
         // If the project to delete is the current project, clear it
         if let Some(proj) = &self.current_project {
             if proj.id == id {
@@ -140,94 +250,139 @@ impl InteractiveShell {
 
         match self.storage.delete_project(id) {
             Ok(_) => {
-                println!("Project deleted: {}", id);
+                out.push(format!("Project deleted: {}", id));
                 Ok(())
             }
             Err(e) => {
-                println!("Error deleting project: {}", e);
+                out.push(format!("Error deleting project: {}", e));
                 Ok(())
             }
         }
     }
 
-    fn list_tasks(&self) -> Result<()> {
+    fn list_tasks(&self, out: &mut Vec<String>) -> Result<()> {
         if let Some(project) = &self.current_project {
             if project.tasks.is_empty() {
-                println!("No tasks in project");
+                out.push("No tasks in project".to_string());
             } else {
-                println!("Tasks in project {}:", project.name);
+                out.push(format!("Tasks in project {}:", project.name));
                 for task in &project.tasks {
-                    println!(
-                        "  ID: {}, Title: {}, Status: {:?}, Priority: {:?}",
-                        task.id, task.title, task.status, task.priority
-                    );
+                    out.push(format!(
+                        "  ID: {}, Title: {}, Status: {}, Priority: {}",
+                        project.task_ref(task.id),
+                        task.title,
+                        task.status_label(&project.tasks),
+                        project.priority_label(task)
+                    ));
                 }
             }
         } else {
-            println!("No project is currently open");
+            out.push("No project is currently open".to_string());
         }
         Ok(())
     }
 
-    fn add_task(&mut self, id: u32, title: &str) -> Result<()> {
+    fn show_workflow(&self, out: &mut Vec<String>) {
+        if let Some(project) = &self.current_project {
+            out.extend(project.settings.workflow.lines());
+        } else {
+            out.push("No project is currently open".to_string());
+        }
+    }
+
+    fn add_task(&mut self, id: u32, title: &str, out: &mut Vec<String>) -> Result<()> {
         if let Some(project) = &mut self.current_project {
-            let task = Task::new(
-                id,
-                title.to_string(),
-                TaskStatus::ToDo,
-                TaskPriority::Medium,
-            );
-            project.add_task(task);
-            self.storage.save_project(project)?;
-            println!("Task added: {} (ID: {})", title, id);
+            let added = project
+                .new_task(id, title.to_string())
+                .and_then(|task| project.add_task(task));
+            match added {
+                Ok(_) => {
+                    self.storage.save_project(project)?;
+                    out.push(format!(
+                        "Task added: {} (ID: {})",
+                        title,
+                        project.task_ref(id)
+                    ));
+                }
+                Err(e) => out.push(format!("Error adding task: {}", e)),
+            }
         } else {
-            println!("No project is currently open");
+            out.push("No project is currently open".to_string());
         }
         Ok(())
     }
 
-    fn update_task(&mut self, id: u32, title: &str, status: &str, priority: &str) -> Result<()> {
+    fn update_task(
+        &mut self,
+        id: u32,
+        title: &str,
+        status: &str,
+        priority: &str,
+        out: &mut Vec<String>,
+    ) -> Result<()> {
         if let Some(project) = &mut self.current_project {
-            let status = match status.to_lowercase().as_str() {
-                "todo" => TaskStatus::ToDo,
-                "in_progress" | "inprogress" => TaskStatus::InProgress,
-                "done" => TaskStatus::Done,
-                _ => {
-                    println!("Invalid status: {}", status);
+            let status = match project.settings.workflow.parse_status(status) {
+                Ok(status) => status,
+                Err(e) => {
+                    out.push(format!("Invalid status: {}", e));
                     return Ok(());
                 }
             };
 
-            let priority = match priority.to_lowercase().as_str() {
-                "low" => TaskPriority::Low,
-                "medium" => TaskPriority::Medium,
-                "high" => TaskPriority::High,
-                _ => {
-                    println!("Invalid priority: {}", priority);
+            let priority = match project.settings.priority_scale.parse(priority) {
+                Ok(priority) => priority,
+                Err(e) => {
+                    out.push(format!("Invalid priority: {}", e));
                     return Ok(());
                 }
             };
 
             match project.update_task(id, title.to_string(), status, priority) {
+                Ok(next) => {
+                    self.storage.save_project(project)?;
+                    out.push(format!("Task updated: {}", id));
+                    if let Some(next) = next {
+                        out.push(format!("Next occurrence created: task {}", next));
+                    }
+                    for event in project.take_events() {
+                        self.events.append(&event)?;
+                        out.push(event.to_string());
+                    }
+                }
+                Err(e) => out.push(format!("Error updating task: {}", e)),
+            }
+        } else {
+            out.push("No project is currently open".to_string());
+        }
+        Ok(())
+    }
+
+    fn set_due(&mut self, id: u32, when: &str, out: &mut Vec<String>) -> Result<()> {
+        if let Some(project) = &mut self.current_project {
+            match dates::parse_due(when).and_then(|due| project.set_task_due(id, due)) {
                 Ok(_) => {
                     self.storage.save_project(project)?;
-                    println!("Task updated: {}", id);
+                    out.push(format!("Due date for task {} updated", id));
                 }
-                Err(e) => println!("Error updating task: {}", e),
+                Err(e) => out.push(format!("Error: {}", e)),
             }
         } else {
-            println!("No project is currently open");
+            out.push("No project is currently open".to_string());
         }
         Ok(())
     }
 
-    fn remove_task(&mut self, id: u32) -> Result<()> {
+    fn remove_task(&mut self, id: u32, out: &mut Vec<String>) -> Result<()> {
         if let Some(project) = &mut self.current_project {
-            project.remove_task(id);
-            self.storage.save_project(project)?;
-            println!("Task removed: {}", id);
+            match project.remove_task(id, OrphanPolicy::Cascade) {
+                Ok(_) => {
+                    self.storage.save_project(project)?;
+                    out.push(format!("Task removed: {}", id));
+                }
+                Err(e) => out.push(format!("Error: {}", e)),
+            }
         } else {
-            println!("No project is currently open");
+            out.push("No project is currently open".to_string());
         }
         Ok(())
     }
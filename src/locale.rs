@@ -0,0 +1,316 @@
+use std::fmt;
+use std::sync::RwLock;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+
+// How dates, times and numbers are written in everything meant for people to read. Files
+// meant for other programs (JSON, YAML, CSV, the data directory itself) keep ISO dates and
+// plain numbers whatever the locale says.
+
+pub const PRESETS: [&str; 6] = ["iso", "en-US", "en-GB", "de-DE", "fr-FR", "ja-JP"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Clock {
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Clock::H24 => write!(f, "24h"),
+            Clock::H12 => write!(f, "12h"),
+        }
+    }
+}
+
+// The `locale` section of config.json. Every setting is optional: unset ones come from the
+// preset, which itself defaults to the one matching LC_ALL, LC_TIME or LANG, or "iso".
+// Each data directory has its own config, so each profile can have its own locale.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocaleSettings {
+    #[serde(default)]
+    pub preset: Option<String>,
+    // A strftime pattern such as "%d.%m.%Y"
+    #[serde(default)]
+    pub date_format: Option<String>,
+    #[serde(default)]
+    pub clock: Option<Clock>,
+    #[serde(default)]
+    pub week_start: Option<Weekday>,
+    #[serde(default)]
+    pub decimal_separator: Option<char>,
+    // Grouping of thousands; an empty string turns it off
+    #[serde(default)]
+    pub thousands_separator: Option<String>,
+}
+
+impl LocaleSettings {
+    pub fn resolve(&self) -> Result<Locale> {
+        let mut locale = match &self.preset {
+            Some(name) => Locale::preset(name)?,
+            None => Locale::from_env(),
+        };
+        if let Some(format) = &self.date_format {
+            check_date_format(format)?;
+            locale.date_format = format.clone();
+        }
+        if let Some(clock) = self.clock {
+            locale.clock = clock;
+        }
+        if let Some(day) = self.week_start {
+            locale.week_start = day;
+        }
+        if let Some(separator) = self.decimal_separator {
+            locale.decimal_separator = separator;
+        }
+        if let Some(separator) = &self.thousands_separator {
+            locale.thousands_separator = separator.chars().next();
+        }
+        if Some(locale.decimal_separator) == locale.thousands_separator {
+            return Err(TaskMasterError::InvalidOperation(
+                "The decimal and thousands separators must differ".to_string(),
+            ));
+        }
+        Ok(locale)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    pub name: String,
+    pub date_format: String,
+    pub clock: Clock,
+    pub week_start: Weekday,
+    pub decimal_separator: char,
+    pub thousands_separator: Option<char>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            name: "iso".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            clock: Clock::H24,
+            week_start: Weekday::Mon,
+            decimal_separator: '.',
+            thousands_separator: None,
+        }
+    }
+}
+
+impl Locale {
+    pub fn preset(name: &str) -> Result<Self> {
+        let (date_format, clock, week_start, decimal, thousands) = match normalize(name).as_str() {
+            "iso" | "c" | "posix" => return Ok(Locale::default()),
+            "en-us" => ("%m/%d/%Y", Clock::H12, Weekday::Sun, '.', Some(',')),
+            "en-gb" => ("%d/%m/%Y", Clock::H24, Weekday::Mon, '.', Some(',')),
+            "de-de" => ("%d.%m.%Y", Clock::H24, Weekday::Mon, ',', Some('.')),
+            "fr-fr" => ("%d/%m/%Y", Clock::H24, Weekday::Mon, ',', Some(' ')),
+            "ja-jp" => ("%Y/%m/%d", Clock::H24, Weekday::Sun, '.', Some(',')),
+            _ => {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Unknown locale '{}'; expected one of: {}",
+                    name,
+                    PRESETS.join(", ")
+                )))
+            }
+        };
+        Ok(Locale {
+            name: PRESETS
+                .iter()
+                .find(|p| normalize(p) == normalize(name))
+                .map_or_else(|| name.to_string(), |p| p.to_string()),
+            date_format: date_format.to_string(),
+            clock,
+            week_start,
+            decimal_separator: decimal,
+            thousands_separator: thousands,
+        })
+    }
+
+    // The preset for the environment's locale, such as LANG=de_DE.UTF-8. A language alone
+    // picks that language's preset (de_AT gets de-DE); anything unknown gets "iso".
+    pub fn from_env() -> Self {
+        let value = ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let wanted = normalize(&value);
+        let language = wanted.split('-').next().unwrap_or_default().to_string();
+        PRESETS
+            .iter()
+            .find(|p| normalize(p) == wanted)
+            .or_else(|| {
+                PRESETS.iter().find(|p| {
+                    p.contains('-') && normalize(p).starts_with(&format!("{}-", language))
+                })
+            })
+            .and_then(|p| Locale::preset(p).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn date(&self, date: NaiveDate) -> String {
+        date.format(&self.date_format).to_string()
+    }
+
+    // "Monday 2024-06-03" in the ISO locale
+    pub fn weekday_date(&self, date: NaiveDate) -> String {
+        format!("{} {}", date.format("%A"), self.date(date))
+    }
+
+    // "Mon 06/03/2024" in en-US
+    pub fn short_weekday_date(&self, date: NaiveDate) -> String {
+        format!("{} {}", date.format("%a"), self.date(date))
+    }
+
+    pub fn time(&self, time: NaiveTime) -> String {
+        match self.clock {
+            Clock::H24 => time.format("%H:%M").to_string(),
+            Clock::H12 => time.format("%-I:%M %p").to_string(),
+        }
+    }
+
+    pub fn time_with_seconds(&self, time: NaiveTime) -> String {
+        match self.clock {
+            Clock::H24 => time.format("%H:%M:%S").to_string(),
+            Clock::H12 => time.format("%-I:%M:%S %p").to_string(),
+        }
+    }
+
+    // A stored timestamp in local time
+    pub fn datetime(&self, at: &DateTime<Utc>) -> String {
+        let local = at.with_timezone(&Local).naive_local();
+        format!(
+            "{} {}",
+            self.date(local.date()),
+            self.time_with_seconds(local.time())
+        )
+    }
+
+    // `value` rounded to `decimals` places, with the locale's separators
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let text = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = match text.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (text.as_str(), None),
+        };
+
+        let mut grouped = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            let left = whole.len() - i;
+            if i > 0 && left % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    grouped.push(separator);
+                }
+            }
+            grouped.push(digit);
+        }
+        // Rounding may leave "-0.0"; only show the sign on something that isn't zero
+        let negative = value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0');
+        let sign = if negative { "-" } else { "" };
+        match fraction {
+            Some(fraction) => format!("{}{}{}{}", sign, grouped, self.decimal_separator, fraction),
+            None => format!("{}{}", sign, grouped),
+        }
+    }
+
+    // A number of hours such as "1.5h", or "1,5h" with a decimal comma
+    pub fn hours(&self, hours: f64, decimals: usize) -> String {
+        format!("{}h", self.number(hours, decimals))
+    }
+
+    // The first day of the week holding `date`
+    pub fn week_start_of(&self, date: NaiveDate) -> NaiveDate {
+        let back = (date.weekday().num_days_from_monday() + 7
+            - self.week_start.num_days_from_monday())
+            % 7;
+        date - chrono::Duration::days(back as i64)
+    }
+}
+
+fn normalize(name: &str) -> String {
+    let name = name.split(['.', '@']).next().unwrap_or_default();
+    name.trim().replace('_', "-").to_lowercase()
+}
+
+fn check_date_format(format: &str) -> Result<()> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "'{}' is not a valid date format",
+            format
+        )));
+    }
+    Ok(())
+}
+
+// The locale everything is currently shown in, installed once the config is loaded
+static CURRENT: RwLock<Option<Locale>> = RwLock::new(None);
+
+pub fn install(locale: Locale) {
+    if let Ok(mut current) = CURRENT.write() {
+        *current = Some(locale);
+    }
+}
+
+// Install the configured locale; settings that don't make sense are reported and the
+// environment's locale is used instead, so a typo in config.json never stops the app
+pub fn install_settings(settings: &LocaleSettings) {
+    match settings.resolve() {
+        Ok(locale) => install(locale),
+        Err(e) => {
+            crate::log_event!(Warn, "Warning: ignoring the locale in config.json: {}", e);
+            install(Locale::from_env());
+        }
+    }
+}
+
+// The installed locale, or the environment's before any config has been loaded
+pub fn current() -> Locale {
+    CURRENT
+        .read()
+        .ok()
+        .and_then(|current| current.clone())
+        .unwrap_or_else(Locale::from_env)
+}
+
+// Shorthands formatting with the installed locale
+
+pub fn date(date: NaiveDate) -> String {
+    current().date(date)
+}
+
+pub fn weekday_date(date: NaiveDate) -> String {
+    current().weekday_date(date)
+}
+
+pub fn short_weekday_date(date: NaiveDate) -> String {
+    current().short_weekday_date(date)
+}
+
+pub fn time(time: NaiveTime) -> String {
+    current().time(time)
+}
+
+pub fn time_with_seconds(time: NaiveTime) -> String {
+    current().time_with_seconds(time)
+}
+
+pub fn datetime(at: &DateTime<Utc>) -> String {
+    current().datetime(at)
+}
+
+pub fn number(value: f64, decimals: usize) -> String {
+    current().number(value, decimals)
+}
+
+pub fn hours(hours: f64, decimals: usize) -> String {
+    current().hours(hours, decimals)
+}
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+// How many events are kept; older ones are dropped first
+const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    // The next level up, wrapping back to Debug; used to cycle the TUI's level filter
+    pub fn next(self) -> Self {
+        match self {
+            Level::Debug => Level::Info,
+            Level::Info => Level::Warn,
+            Level::Warn => Level::Error,
+            Level::Error => Level::Debug,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub at: DateTime<Utc>,
+    pub level: Level,
+    // Module that recorded the event, e.g. "worker_pool"
+    pub module: String,
+    pub message: String,
+}
+
+struct Buffer {
+    entries: VecDeque<LogEntry>,
+    // Print Info and above to stdout as well; off while the TUI owns the terminal
+    echo: bool,
+}
+
+static BUFFER: Mutex<Buffer> = Mutex::new(Buffer {
+    entries: VecDeque::new(),
+    echo: true,
+});
+
+// Record an event from the executors, workers or storage, keeping the most recent in memory
+// for the TUI's Logs tab. Prefer the `log_event!` macro, which fills in the module.
+pub fn record(level: Level, module: &str, message: String) {
+    let Ok(mut buffer) = BUFFER.lock() else {
+        return;
+    };
+    if buffer.echo && level >= Level::Info {
+        println!("{}", message);
+    }
+    if buffer.entries.len() == CAPACITY {
+        buffer.entries.pop_front();
+    }
+    let module = module.strip_prefix("taskmaster::").unwrap_or(module);
+    buffer.entries.push_back(LogEntry {
+        at: Utc::now(),
+        level,
+        module: module.to_string(),
+        message,
+    });
+}
+
+pub fn set_echo(echo: bool) {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        buffer.echo = echo;
+    }
+}
+
+// Recorded events at or above `min_level` whose module or message contains `search`
+// (ignoring case), oldest first
+pub fn recent(min_level: Level, search: &str) -> Vec<LogEntry> {
+    let search = search.to_lowercase();
+    let Ok(buffer) = BUFFER.lock() else {
+        return Vec::new();
+    };
+    buffer
+        .entries
+        .iter()
+        .filter(|e| e.level >= min_level)
+        .filter(|e| {
+            search.is_empty()
+                || e.module.to_lowercase().contains(&search)
+                || e.message.to_lowercase().contains(&search)
+        })
+        .cloned()
+        .collect()
+}
+
+// Record an event at the given level from the calling module:
+// `log_event!(Info, "Worker {} got job {}", id, run_id)`
+#[macro_export]
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)*) => {
+        $crate::logs::record($crate::logs::Level::$level, module_path!(), format!($($arg)*))
+    };
+}
@@ -1,18 +1,77 @@
+mod appearance;
 mod async_executor;
+mod attachments;
+mod autoschedule;
+mod blob_store;
+mod brief;
+mod bulk;
+mod burndown;
+mod cancellation;
+#[cfg(feature = "chaos")]
+mod chaos;
 mod cli;
+mod completions;
+mod config;
+mod correlation;
+mod dates;
+mod dedupe;
+mod diff;
+mod editing;
 mod error;
+mod estimation;
+mod event_log;
+mod export;
+mod fields;
 mod file_storage;
+mod follow_ups;
+mod goals;
+mod graph;
+mod habits;
+mod hierarchy;
+mod ids;
+mod import;
 mod interactive;
+mod locale;
+mod logs;
+mod milestone;
 mod notification;
+mod output;
 mod periodic_tasks;
+mod planner;
+mod planning;
+mod pool_bench;
+mod priority;
 mod project;
+mod prompting;
+mod query;
+mod ranking;
+mod ready_queue;
+mod recurring_projects;
+mod reminders;
+mod report;
+mod report_plugins;
+mod review;
+mod routing;
+mod schedule;
+mod split;
+mod stats;
 mod storage;
+mod summary;
+mod table;
 mod task;
 mod task_dependencies;
 mod task_executor;
 mod task_handler;
+mod templates;
+mod timesheet;
+mod triage;
 mod tui;
+mod urgency;
+mod validation;
+mod vault;
+mod watch;
 mod worker_pool;
+mod workflow;
 
 use crate::error::Result;
 use crate::file_storage::FileStorage;
@@ -34,6 +93,15 @@ async fn main() -> Result<()> {
                 // Run tests
                 run_sync_tests()?;
                 test_async().await?;
+                #[cfg(feature = "chaos")]
+                {
+                    test_chaos()?;
+                    test_chaos_async().await?;
+                }
+            }
+            "--bench" => {
+                // Worker pool throughput, old design against new
+                pool_bench::run(args.get(2).and_then(|jobs| jobs.parse().ok()))?;
             }
             "--interactive" => {
                 // Run in interactive mode
@@ -47,17 +115,25 @@ async fn main() -> Result<()> {
             }
             _ => {
                 // Run in CLI mode
-                cli::run_cli()?;
+                run_cli().await;
             }
         }
     } else {
         // Run in CLI mode
-        cli::run_cli()?;
+        run_cli().await;
     }
 
     Ok(())
 }
 
+// Errors go to stderr, and the exit code tells scripts what kind of failure it was
+async fn run_cli() {
+    if let Err(e) = cli::run_cli().await {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
 fn run_sync_tests() -> Result<()> {
     // Basic project demonstration
     let mut project = Project::new(1, String::from("Project 1"));
@@ -75,8 +151,8 @@ fn run_sync_tests() -> Result<()> {
         TaskPriority::Medium,
     );
 
-    project.add_task(task1);
-    project.add_task(task2);
+    project.add_task(task1)?;
+    project.add_task(task2)?;
 
     println!("Initial project:");
     project.display();
@@ -106,6 +182,21 @@ fn run_sync_tests() -> Result<()> {
         println!("Periodic tasks test failed: {}", e);
     }
 
+    println!("\nTesting custom workflows:");
+    if let Err(e) = test_workflow() {
+        println!("Workflow test failed: {}", e);
+    }
+
+    println!("\nTesting ready-queue policies:");
+    if let Err(e) = test_ready_queue_policies() {
+        println!("Ready-queue policy test failed: {}", e);
+    }
+
+    println!("\nTesting follow-up tasks:");
+    if let Err(e) = test_follow_ups() {
+        println!("Follow-up test failed: {}", e);
+    }
+
     Ok(())
 }
 
@@ -126,8 +217,8 @@ fn test_storage() -> Result<()> {
         .priority(TaskPriority::Medium)
         .build();
 
-    project.add_task(task1);
-    project.add_task(task2);
+    project.add_task(task1)?;
+    project.add_task(task2)?;
 
     // Initialize storage
     let mut storage = FileStorage::new("./data")?;
@@ -155,6 +246,21 @@ fn test_storage() -> Result<()> {
     println!("Loaded project:");
     loaded_project.display();
 
+    // The document edit-project opens reads back as the same project
+    let document = crate::editing::to_document(&loaded_project)?;
+    let (_, summary) = crate::editing::apply_edit(&loaded_project, &document)?;
+    assert!(summary.is_empty());
+
+    // Save, load and delete a single task on its own
+    println!("Round-tripping a single task...");
+    storage.save_task(42, &project.tasks[0])?;
+    let loaded_task = storage.load_task(42, 101)?;
+    assert_eq!(loaded_task.title, project.tasks[0].title);
+    assert_eq!(loaded_task.status, TaskStatus::Done);
+    storage.delete_task(42, 101)?;
+    assert!(storage.load_task(42, 101).is_err());
+    assert!(storage.delete_task(42, 101).is_err());
+
     // Test non-existent project
     println!("Testing error handling with non-existent project...");
     match storage.load_project(999) {
@@ -171,8 +277,10 @@ fn test_concurrency() -> Result<()> {
 
     println!("Testing concurrency...");
 
-    // Create a task executor with 4 worker threads and 10-second timeout
-    let executor = TaskExecutor::new(4, 10);
+    // Create a task executor with 4 worker threads and 10-second timeout, announcing each
+    // outcome on an event channel too
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(16);
+    let executor = TaskExecutor::new(4, 10).with_event_bus(event_tx);
 
     // Create some tasks
     let task1 = Task::new(
@@ -195,29 +303,46 @@ fn test_concurrency() -> Result<()> {
     );
 
     // Execute tasks
-    executor.execute_task(task1)?;
-    executor.execute_task(task2)?;
-    executor.execute_task(task3)?;
+    let run1 = executor.execute_task(task1)?;
+    let run2 = executor.execute_task(task2)?;
+    let run3 = executor.execute_task(task3)?;
+    println!("Submitted runs: {}, {}, {}", run1, run2, run3);
+    println!("Results pending: {}", executor.pending_results());
 
     // Check if tasks are running
     println!("Is task 1 running? {}", executor.is_task_running(1));
     println!("Is task 2 running? {}", executor.is_task_running(2));
     println!("Is task 3 running? {}", executor.is_task_running(3));
 
-    // Wait a bit for some tasks to complete
-    std::thread::sleep(std::time::Duration::from_secs(3));
+    // Cancelling stops task 3's job instead of leaving it to run out its two seconds
+    executor.cancel_task(3)?;
+    match executor.wait_for(3, std::time::Duration::from_millis(500)) {
+        Some(result) => println!("Task 3 [{}] {}", result.run_id, result.outcome),
+        None => println!("Task 3 did not stop in time"),
+    }
 
-    // Collect and display results
-    let results = executor.collect_results();
+    // Task 2's result on its own; the others are kept for the collection below
+    match executor.wait_for(2, std::time::Duration::from_secs(5)) {
+        Some(result) => println!("Task 2 [{}] {}", result.run_id, result.outcome),
+        None => println!("Task 2 did not finish in time"),
+    }
+
+    // Collect and display the rest, waiting only as long as they need
+    let results = executor.collect_results_timeout(std::time::Duration::from_secs(5));
     println!("Collected {} results", results.len());
+    assert_eq!(executor.pending_results(), 0);
+    assert!(executor.collect_results().is_empty());
     for result in &results {
         println!(
-            "Task {}: {}",
-            result.task_id,
-            if result.success { "Success" } else { "Failed" }
+            "Task {} [{}] {}",
+            result.task_id, result.run_id, result.outcome
         );
     }
 
+    while let Ok(event) = event_rx.try_recv() {
+        println!("Event: {}", event);
+    }
+
     // Check for timeouts
     let timed_out = executor.check_timeouts();
     println!("Timed out tasks: {:?}", timed_out);
@@ -242,10 +367,18 @@ fn test_advanced_types() -> Result<()> {
         vec![TaskPriority::High, TaskPriority::Medium],
     );
 
+    // P0-P1 on a P0-P4 scale are urgent enough for the escalation handler
+    let escalation_handler = PriorityTaskHandler::at_least(
+        "EscalationHandler",
+        crate::priority::PriorityScale::new(0, 4, "P", true)?,
+        75,
+    );
+
     // Create registry and register handlers
     let mut registry = TaskHandlerRegistry::new();
     registry.register_handler(Box::new(basic_handler));
     registry.register_handler(Box::new(priority_handler));
+    registry.register_handler(Box::new(escalation_handler));
 
     // List available handlers
     println!("Available handlers: {:?}", registry.list_handlers());
@@ -272,6 +405,16 @@ fn test_advanced_types() -> Result<()> {
     println!("Executing urgent task:");
     registry.execute_task(&urgent_task)?;
 
+    let p1_task = Task::new(
+        104,
+        String::from("Customer outage follow-up"),
+        TaskStatus::ToDo,
+        TaskPriority::Level(1),
+    );
+
+    println!("Executing P1 task:");
+    registry.execute_task(&p1_task)?;
+
     // Try a task that no handler can process
     let unhandled_task = Task::new(
         103,
@@ -297,7 +440,7 @@ async fn test_async() -> Result<()> {
     println!("\nTesting async features:");
 
     // Create channels for notifications
-    let (_event_tx, event_rx) = mpsc::channel(100);
+    let (event_tx, event_rx) = mpsc::channel(100);
 
     // Create async executor
     let executor = AsyncTaskExecutor::new(10, 100);
@@ -307,20 +450,55 @@ async fn test_async() -> Result<()> {
 
     // Register callbacks
     notification_system.register_callback("log_events", |event| match event {
-        TaskEvent::Started { task_id } => println!("NOTIFICATION: Task {} started", task_id),
-        TaskEvent::Completed { task_id } => println!("NOTIFICATION: Task {} completed", task_id),
+        TaskEvent::Started { task_id, run_id } => {
+            println!("NOTIFICATION [{}]: Task {} started", run_id, task_id)
+        }
+        TaskEvent::Completed { task_id, run_id } => {
+            println!("NOTIFICATION [{}]: Task {} completed", run_id, task_id)
+        }
         TaskEvent::Failed {
             task_id,
+            run_id,
             error_message,
+            ..
+        } => {
+            println!(
+                "NOTIFICATION [{}]: Task {} failed: {}",
+                run_id, task_id, error_message
+            )
+        }
+        TaskEvent::Timeout { task_id, run_id } => {
+            println!("NOTIFICATION [{}]: Task {} timed out", run_id, task_id)
+        }
+        TaskEvent::Terminated { task_id, run_id } => {
+            println!("NOTIFICATION [{}]: Task {} terminated", run_id, task_id)
+        }
+        TaskEvent::Unblocked {
+            task_id,
+            run_id,
+            dependency,
+            ..
         } => {
-            println!("NOTIFICATION: Task {} failed: {}", task_id, error_message)
+            println!(
+                "NOTIFICATION [{}]: Task {} unblocked by task {}",
+                run_id, task_id, dependency
+            )
         }
-        TaskEvent::Timeout { task_id } => println!("NOTIFICATION: Task {} timed out", task_id),
-        TaskEvent::Terminated { task_id } => println!("NOTIFICATION: Task {} terminated", task_id),
     });
-    // Start notification system in background
+    // A callback can be taken off again by name
+    notification_system.register_callback("temporary", |_| {});
+    assert!(notification_system.unregister_callback("temporary"));
+    assert!(!notification_system.unregister_callback("temporary"));
+
+    // Start notification system in background, watching a deadline for a task that never
+    // gets submitted
+    let deadlines =
+        std::collections::HashMap::from([(3, time::Instant::now() + Duration::from_secs(3))]);
     tokio::spawn(async move {
-        notification_system.start().await.unwrap();
+        notification_system
+            .start_with_deadlines(deadlines)
+            .await
+            .unwrap();
     });
 
     // Create some tasks
@@ -338,17 +516,40 @@ async fn test_async() -> Result<()> {
     );
 
     // Execute tasks
-    executor.execute_task(task1).await?;
-    executor.execute_task(task2).await?;
-
-    // Wait to see the results
-    time::sleep(Duration::from_secs(5)).await;
+    let run1 = executor.execute_task(task1).await?;
+    let run2 = executor.execute_task(task2).await?;
+    println!("Submitted async runs: {}, {}", run1, run2);
+    assert!(executor.is_task_running(1) && executor.is_task_running(2));
+    assert!(executor.check_timeouts().is_empty());
+    assert!(executor.cancel_task(99).await.is_err());
+
+    // Pass the executor's events on to the notification system until both runs are over
+    let mut finished = 0;
+    while finished < 2 {
+        let Ok(Some(event)) = time::timeout(Duration::from_secs(5), executor.next_event()).await
+        else {
+            break;
+        };
+        if matches!(
+            event,
+            TaskEvent::Completed { .. } | TaskEvent::Failed { .. }
+        ) {
+            finished += 1;
+        }
+        event_tx.send(event).await.map_err(|_| {
+            error::TaskMasterError::ChannelError("Notification system stopped".to_string())
+        })?;
+    }
+    // Long enough for the deadline to pass
+    time::sleep(Duration::from_secs(2)).await;
 
     println!("Async test completed");
     Ok(())
 }
 
 fn test_task_dependencies() -> Result<()> {
+    use crate::task_dependencies::{Dependency, DependencyGraph, DependencyKind};
+
     println!("\nTesting task dependencies:");
 
     // Create a project with interdependent tasks
@@ -380,10 +581,10 @@ fn test_task_dependencies() -> Result<()> {
         TaskPriority::Medium,
     );
 
-    project.add_task(task1);
-    project.add_task(task2);
-    project.add_task(task3);
-    project.add_task(task4);
+    project.add_task(task1)?;
+    project.add_task(task2)?;
+    project.add_task(task3)?;
+    project.add_task(task4)?;
 
     // Add dependencies
     project.add_task_dependency(2, 1)?; // task2 depends on task1
@@ -418,7 +619,7 @@ fn test_task_dependencies() -> Result<()> {
     // Mark task1 as done and check again
     println!("\nMarking task 1 as done and checking again:");
     if let Some(task) = project.tasks.iter_mut().find(|t| t.id == 1) {
-        task.status = TaskStatus::Done;
+        task.set_status(TaskStatus::Done);
     }
 
     for task in &project.tasks {
@@ -430,12 +631,37 @@ fn test_task_dependencies() -> Result<()> {
         );
     }
 
+    // The graph answers the same as the tasks do, also for a finish-to-finish dependency,
+    // which lets a task start but not finish before the task it waits on
+    println!("\nChecking the dependency graph against the tasks:");
+    project.add_typed_dependency(
+        4,
+        Dependency::new(2).with_kind(DependencyKind::FinishToFinish),
+    )?;
+    let graph = DependencyGraph::from_tasks(&project.tasks)?;
+    let now = chrono::Utc::now();
+    for task in &project.tasks {
+        let can_start = graph.are_dependencies_met(task.id, &project.tasks, now);
+        let can_finish = graph.can_finish(task.id, &project.tasks, now);
+        assert_eq!(can_start, task.can_start(&project.tasks));
+        assert_eq!(can_finish, task.can_finish(&project.tasks));
+        println!(
+            "  Task {}: can start: {}, can finish: {}",
+            task.id, can_start, can_finish
+        );
+    }
+    assert!(graph.are_dependencies_met(2, &project.tasks, now));
+    assert!(!graph.can_finish(4, &project.tasks, now));
+
     println!("Task dependencies test completed");
     Ok(())
 }
 
 fn test_periodic_tasks() -> Result<()> {
-    use crate::periodic_tasks::{PeriodicTask, PeriodicTaskScheduler, RecurrencePattern};
+    use crate::periodic_tasks::{
+        PeriodicTask, PeriodicTaskScheduler, PeriodicTemplate, RecurrencePattern,
+    };
+    use crate::templates::{TaskTemplate, TemplateLibrary};
     println!("\nTesting periodic tasks:");
 
     // Create a task template
@@ -451,11 +677,20 @@ fn test_periodic_tasks() -> Result<()> {
 
     println!(
         "Created periodic task: {} (ID: {})",
-        periodic_task.template.title, periodic_task.id
+        periodic_task.title(),
+        periodic_task.id
     );
 
-    // Create a scheduler
-    let mut scheduler = PeriodicTaskScheduler::new();
+    // Create a scheduler that can resolve named templates
+    let mut retro = TaskTemplate::new("retro", "Sprint retro #{n}");
+    retro.tags = vec![String::from("ceremony")];
+    retro.checklist = vec![
+        String::from("Collect feedback"),
+        String::from("Pick actions"),
+    ];
+    let mut library = TemplateLibrary::default();
+    library.insert(retro);
+    let mut scheduler = PeriodicTaskScheduler::new().with_templates(library);
 
     // Add the periodic task
     scheduler.add_task(periodic_task);
@@ -488,15 +723,24 @@ fn test_periodic_tasks() -> Result<()> {
 
     scheduler.add_task(backup_task);
 
+    // A periodic task that references a named template instead of embedding a task
+    scheduler.add_task(PeriodicTask::new(
+        4,
+        PeriodicTemplate::Named(String::from("retro")),
+        RecurrencePattern::Weekly,
+    ));
+
     // Normally we'd wait for tasks to become due, but for testing,
     // we'll force all tasks to be "due" by modifying next_run to be in the past
-    for id in 1..=3 {
-        // Assuming task IDs 1, 2, 3
+    for id in 1..=4 {
+        // Assuming task IDs 1 to 4
         if let Some(task) = scheduler.get_task_mut(id) {
             task.next_run = std::time::SystemTime::now() - Duration::from_secs(1);
         }
     }
 
+    assert_eq!(scheduler.get_due_tasks().len(), 4);
+
     // Generate due tasks
     println!("Generating due tasks:");
     let generated = scheduler.generate_due_tasks();
@@ -505,6 +749,9 @@ fn test_periodic_tasks() -> Result<()> {
     for task in &generated {
         println!("  Generated: {} (ID: {})", task.title, task.id);
     }
+    assert!(generated
+        .iter()
+        .any(|t| t.title == "Sprint retro #1" && t.checklist.len() == 2));
 
     // Check that the next_run has been updated
     println!("\nNext scheduled runs:");
@@ -516,6 +763,495 @@ fn test_periodic_tasks() -> Result<()> {
         );
     }
 
+    // Stopping a recurrence
+    assert!(scheduler.remove_task(3).is_some());
+    assert!(scheduler.get_task(3).is_none());
+
     println!("Periodic tasks test completed");
     Ok(())
 }
+
+fn test_workflow() -> Result<()> {
+    use crate::workflow::{Transition, Workflow};
+
+    println!("\nTesting custom workflows:");
+
+    let mut project = Project::new(77, String::from("Review Workflow"));
+    project.add_task(Task::new(
+        1,
+        String::from("Write docs"),
+        TaskStatus::ToDo,
+        TaskPriority::Medium,
+    ))?;
+
+    // ToDo -> InReview -> Done, with review able to bounce back
+    let in_review = TaskStatus::Custom(String::from("InReview"));
+    project.settings.workflow = Workflow::new(
+        vec![TaskStatus::ToDo, in_review.clone(), TaskStatus::Done],
+        vec![
+            Transition {
+                from: TaskStatus::ToDo,
+                to: in_review.clone(),
+            },
+            Transition {
+                from: in_review.clone(),
+                to: TaskStatus::ToDo,
+            },
+            Transition {
+                from: in_review.clone(),
+                to: TaskStatus::Done,
+            },
+        ],
+    )?;
+    project.settings.workflow.display();
+
+    // Skipping review is not allowed
+    println!("\nTrying to move straight from ToDo to Done:");
+    match project.update_task(
+        1,
+        String::from("Write docs"),
+        TaskStatus::Done,
+        TaskPriority::Medium,
+    ) {
+        Ok(_) => println!("Unexpected success: illegal transition was accepted"),
+        Err(e) => println!("Expected error: {}", e),
+    }
+
+    let status = project.settings.workflow.parse_status("in-review")?;
+    project.update_task(1, String::from("Write docs"), status, TaskPriority::Medium)?;
+    project.update_task(
+        1,
+        String::from("Write docs"),
+        TaskStatus::Done,
+        TaskPriority::Medium,
+    )?;
+    assert_eq!(project.get_task(1)?.status, TaskStatus::Done);
+
+    // Loaded workflows are checked like constructed ones, so a broken file is refused
+    // instead of failing later when a task is created
+    let saved = serde_json::to_string(&project.settings.workflow)?;
+    assert_eq!(
+        serde_json::from_str::<Workflow>(&saved)?,
+        project.settings.workflow
+    );
+    for broken in [
+        r#"{"states": [], "transitions": []}"#,
+        r#"{"states": ["ToDo"], "transitions": []}"#,
+    ] {
+        match serde_json::from_str::<Workflow>(broken) {
+            Ok(_) => panic!("Invalid workflow was loaded: {}", broken),
+            Err(e) => println!("Expected error: {}", e),
+        }
+    }
+    assert_eq!(project.settings.workflow.initial_state()?, TaskStatus::ToDo);
+
+    println!("Workflow test completed");
+    Ok(())
+}
+
+fn test_ready_queue_policies() -> Result<()> {
+    use crate::ready_queue::{
+        self, CriticalPathFirst, PriorityFirst, ReadyQueuePolicy, ShortestJobFirst,
+    };
+
+    // A long chain behind a small, unimportant task, next to one big urgent job and two
+    // quick ones, on two workers
+    let mut project = Project::new(78, String::from("Policy Comparison"));
+    let tasks = [
+        (1, "Prepare migration", TaskPriority::Low, 1.0, None),
+        (2, "Run migration", TaskPriority::Low, 6.0, Some(1)),
+        (3, "Rewrite importer", TaskPriority::High, 4.0, None),
+        (4, "Fix typo", TaskPriority::Medium, 1.0, None),
+        (5, "Bump version", TaskPriority::Medium, 1.0, None),
+    ];
+    for (id, title, priority, estimate, dependency) in tasks {
+        let mut builder = TaskBuilder::new(id, String::from(title)).priority(priority);
+        if let Some(dependency) = dependency {
+            builder = builder.dependency(dependency);
+        }
+        let mut task = builder.build();
+        task.estimate_hours = Some(estimate);
+        project.add_task(task)?;
+    }
+
+    let policies: [&dyn ReadyQueuePolicy; 3] =
+        [&PriorityFirst, &ShortestJobFirst, &CriticalPathFirst];
+    let mut runs = Vec::new();
+    for policy in policies {
+        let run = ready_queue::simulate(&project, policy, 2)?;
+        let order: Vec<String> = run.slots.iter().map(|s| s.task_id.to_string()).collect();
+        let mean_finish = run.slots.iter().map(|s| s.finish).sum::<f64>() / run.slots.len() as f64;
+        println!(
+            "{}: starts {}, done after {}h, mean finish {:.1}h",
+            policy.name(),
+            order.join(", "),
+            run.makespan,
+            mean_finish
+        );
+        runs.push((run, mean_finish));
+    }
+    let [(priority, _), (shortest, shortest_mean), (critical, _)] = &runs[..] else {
+        unreachable!();
+    };
+
+    // The urgent job starts straight away only when priority decides
+    assert_eq!(priority.slots[0].task_id, 3);
+    assert_eq!(priority.finish_of(3), Some(4.0));
+    // Quick jobs first finish the most work soonest
+    assert!(runs.iter().all(|(_, mean)| mean >= shortest_mean));
+    // Starting the chain first finishes everything soonest
+    assert_eq!(critical.makespan, 7.0);
+    assert!(critical.makespan < shortest.makespan && shortest.makespan < priority.makespan);
+    for slot in &critical.slots {
+        println!(
+            "  worker {}: task {} from {}h to {}h",
+            slot.worker, slot.task_id, slot.start, slot.finish
+        );
+    }
+
+    // The executor runs the same queue for real, with every task ending up finished
+    let executor = task_executor::TaskExecutor::new(2, 10);
+    let results = executor.execute_project(&project, &CriticalPathFirst)?;
+    assert_eq!(results.len(), project.tasks.len());
+    assert!(results.iter().all(|r| r.outcome.is_success()));
+
+    // A handler that never returns is timed out instead of holding up the queue
+    let mut stuck = Project::new(78, String::from("Stuck"));
+    stuck.add_task(Task::new(
+        1,
+        String::from("Wait forever"),
+        TaskStatus::ToDo,
+        TaskPriority::Low,
+    ))?;
+    let mut registry = task_handler::TaskHandlerRegistry::new();
+    registry.register_handler(Box::new(StuckHandler));
+    let executor = task_executor::TaskExecutor::new(1, 1);
+    let started = std::time::Instant::now();
+    let results = executor.execute_projects_with(&[&stuck], &registry, &CriticalPathFirst)?;
+    assert_eq!(results.len(), 1);
+    assert!(matches!(
+        results[0].1.outcome,
+        worker_pool::JobOutcome::TimedOut
+    ));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    println!("  Stuck task {}", results[0].1.outcome);
+
+    println!("Ready-queue policy test completed");
+    Ok(())
+}
+
+// Ignores cancellation and never finishes, like a handler blocked on a dead connection
+#[derive(Debug, Clone)]
+struct StuckHandler;
+
+impl task_handler::TaskHandler for StuckHandler {
+    fn execute(&self, _task: &Task, _cancel: &cancellation::CancellationFlag) -> Result<()> {
+        loop {
+            std::thread::park();
+        }
+    }
+
+    fn name(&self) -> &str {
+        "StuckHandler"
+    }
+
+    fn can_handle(&self, _task: &Task) -> bool {
+        true
+    }
+
+    fn clone_box(&self) -> Box<dyn task_handler::TaskHandler> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+// Stands in for a CI job: test tasks fail, and each failure asks for a task to fix them
+#[derive(Debug, Clone)]
+struct FlakyTestHandler;
+
+impl task_handler::TaskHandler for FlakyTestHandler {
+    fn execute(&self, task: &Task, _cancel: &cancellation::CancellationFlag) -> Result<()> {
+        Err(error::TaskMasterError::InvalidOperation(format!(
+            "{} failed",
+            task.title
+        )))
+    }
+
+    fn name(&self) -> &str {
+        "FlakyTestHandler"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        task.tags.iter().any(|t| t == "tests")
+    }
+
+    fn clone_box(&self) -> Box<dyn task_handler::TaskHandler> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn follow_ups(
+        &self,
+        task: &Task,
+        outcome: &worker_pool::JobOutcome,
+    ) -> Vec<follow_ups::FollowUp> {
+        if outcome.is_success() {
+            return Vec::new();
+        }
+        vec![
+            follow_ups::FollowUp::new(&format!("Fix flaky test: {}", task.title))
+                .priority(TaskPriority::High)
+                .tag("flaky"),
+        ]
+    }
+}
+
+fn test_follow_ups() -> Result<()> {
+    use crate::follow_ups::{FollowUpRule, OutcomeKind};
+    use crate::task_executor::TaskExecutor;
+
+    let mut project = Project::new(79, String::from("Nightly Build"));
+    let mut suite = Task::new(
+        1,
+        String::from("Integration tests"),
+        TaskStatus::ToDo,
+        TaskPriority::Medium,
+    );
+    suite.tags.push(String::from("tests"));
+    project.add_task(suite.clone())?;
+
+    // Rules from config.json: one asks for a review of failed test runs, the other has a
+    // priority the project's scale doesn't know, so it can't be added
+    let rules: Vec<FollowUpRule> = serde_json::from_str(
+        r#"[
+            {"on": ["failed"], "tags": ["tests"], "title": "Review {title} (task {id})"},
+            {"title": "Page someone", "priority": "P0"}
+        ]"#,
+    )?;
+    assert_eq!(rules[0].on, vec![OutcomeKind::Failed]);
+
+    let executor = TaskExecutor::new(1, 60);
+    let mut first_run = None;
+    // Run the suite twice; the second failure shouldn't add another copy
+    for _ in 0..2 {
+        executor.execute_with_handler(suite.clone(), Box::new(FlakyTestHandler))?;
+        let result = executor.wait_for_result()?;
+        println!("Task {} {}", result.task_id, result.outcome);
+        let outcomes = project.add_follow_ups(&result, &rules);
+        for (title, outcome) in &outcomes {
+            println!("Follow-up '{}': {:?}", title, outcome);
+        }
+        // The bad rule fails alone, every run
+        assert_eq!(outcomes.iter().filter(|(_, o)| o.is_err()).count(), 1);
+        first_run.get_or_insert(result.run_id);
+    }
+
+    assert_eq!(project.tasks.len(), 3);
+    assert_eq!(
+        project.get_task(3)?.title,
+        "Review Integration tests (task 1)"
+    );
+    let fix = project.get_task(2)?;
+    assert_eq!(fix.title, "Fix flaky test: Integration tests");
+    assert_eq!(fix.priority, TaskPriority::High);
+    let origin = fix
+        .follow_up_of
+        .as_ref()
+        .expect("follow-up is linked to its run");
+    assert_eq!(origin.task_id, 1);
+    assert_eq!(Some(&origin.run_id), first_run.as_ref());
+
+    println!("Follow-up test completed");
+    Ok(())
+}
+
+#[cfg(feature = "chaos")]
+fn test_chaos() -> Result<()> {
+    use crate::chaos::{ChaosConfig, ChaosMonkey, ChaosStorage};
+    use crate::task_executor::TaskExecutor;
+    use crate::worker_pool::JobOutcome;
+
+    println!("\nTesting executor resilience under chaos:");
+
+    // Every handler fails: each job must still report a result, and none stays running
+    let always_fail = ChaosMonkey::shared(ChaosConfig {
+        enabled: true,
+        handler_failure_rate: 1.0,
+        seed: 42,
+        ..ChaosConfig::default()
+    });
+    let executor = TaskExecutor::new(2, 10).with_chaos(always_fail);
+    for id in 1..=3 {
+        executor.execute_task(Task::new(
+            id,
+            format!("Chaos Task {}", id),
+            TaskStatus::ToDo,
+            TaskPriority::Medium,
+        ))?;
+    }
+
+    let mut failures = 0;
+    while failures < 3 {
+        let result = executor.wait_for_result()?;
+        let JobOutcome::Failed(error) = &result.outcome else {
+            panic!("Expected an injected failure, got: {}", result.outcome);
+        };
+        let message = error.to_string();
+        assert!(message.contains("Injected chaos failure"));
+        assert!(!executor.is_task_running(result.task_id));
+        println!(
+            "  Task {} [{}] failed: {}",
+            result.task_id, result.run_id, message
+        );
+        failures += 1;
+    }
+
+    // Retries: every attempt meets the chaos again, so a handler that always fails is run
+    // once more per retry before failing, and one that fails half the time gets through
+    let retried = |task_id: u32| {
+        let injected = format!("injecting failure into task {}", task_id);
+        let retries = format!("task {} failed", task_id);
+        (
+            logs::recent(logs::Level::Warn, &injected).len(),
+            logs::recent(logs::Level::Warn, &retries).len(),
+        )
+    };
+    for (task_id, rate, seed, succeeds, expected) in
+        [(21, 1.0, 42, false, 5), (22, 0.5, 17, true, 2)]
+    {
+        let chaos = ChaosMonkey::shared(ChaosConfig {
+            enabled: true,
+            handler_failure_rate: rate,
+            seed,
+            ..ChaosConfig::default()
+        });
+        let executor = TaskExecutor::new(1, 10).with_chaos(chaos).with_retries(5);
+        executor.execute_with_handler(
+            Task::new(
+                task_id,
+                format!("Retried Task {}", task_id),
+                TaskStatus::ToDo,
+                TaskPriority::Medium,
+            ),
+            Box::new(task_handler::BasicTaskHandler::new("basic", Vec::new())),
+        )?;
+        let result = executor.wait_for_result()?;
+        let (injected, retries) = retried(task_id);
+        assert_eq!(result.outcome.is_success(), succeeds);
+        assert_eq!(retries, expected);
+        // A run that still fails had one attempt more than it had retries
+        assert_eq!(injected, retries + usize::from(!succeeds));
+        println!(
+            "  Task {} {} after {} retries",
+            task_id, result.outcome, retries
+        );
+    }
+
+    // Slow handlers: tasks that overrun the timeout must be reported
+    let slow = ChaosMonkey::shared(ChaosConfig {
+        enabled: true,
+        max_delay_ms: 500,
+        seed: 7,
+        ..ChaosConfig::default()
+    });
+    let executor = TaskExecutor::new(2, 1).with_chaos(slow);
+    for id in 10..=11 {
+        executor.execute_task(Task::new(
+            id,
+            format!("Slow Task {}", id),
+            TaskStatus::ToDo,
+            TaskPriority::Low,
+        ))?;
+    }
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    let mut timed_out = executor.check_timeouts();
+    timed_out.sort();
+    assert_eq!(timed_out, vec![10, 11]);
+    println!("  Timed out under injected delays: {:?}", timed_out);
+    for result in executor.collect_results_timeout(std::time::Duration::from_secs(5)) {
+        assert!(matches!(result.outcome, JobOutcome::TimedOut));
+    }
+
+    // Storage errors surface as StorageError instead of corrupting data
+    let flaky = ChaosMonkey::shared(ChaosConfig {
+        enabled: true,
+        storage_failure_rate: 1.0,
+        seed: 3,
+        ..ChaosConfig::default()
+    });
+    let dir = std::env::temp_dir().join("taskmaster_chaos");
+    let mut storage = ChaosStorage::new(FileStorage::new(&dir)?, flaky);
+    match storage.save_project(&Project::new(1, String::from("Chaos"))) {
+        Ok(_) => panic!("Injected storage failure was not reported"),
+        Err(e) => println!("  Expected storage error: {}", e),
+    }
+    assert!(storage.load_project(1).is_err());
+
+    println!("Chaos test completed");
+    Ok(())
+}
+
+#[cfg(feature = "chaos")]
+async fn test_chaos_async() -> Result<()> {
+    use crate::async_executor::{AsyncTaskExecutor, TaskEvent};
+    use crate::chaos::{ChaosConfig, ChaosMonkey};
+
+    println!("\nTesting async notifications under chaos:");
+
+    let chaos = ChaosMonkey::shared(ChaosConfig {
+        enabled: true,
+        handler_failure_rate: 1.0,
+        max_delay_ms: 100,
+        seed: 11,
+        ..ChaosConfig::default()
+    });
+    let executor = AsyncTaskExecutor::new(10, 100).with_chaos(chaos);
+
+    let run1 = executor
+        .execute_task(Task::new(
+            1,
+            String::from("Async Chaos 1"),
+            TaskStatus::ToDo,
+            TaskPriority::High,
+        ))
+        .await?;
+    let run2 = executor
+        .execute_task(Task::new(
+            2,
+            String::from("Async Chaos 2"),
+            TaskStatus::ToDo,
+            TaskPriority::High,
+        ))
+        .await?;
+
+    // Each run must produce a Started event followed by a Failed event with the same run ID
+    let mut failed_runs = Vec::new();
+    while failed_runs.len() < 2 {
+        let event = time::timeout(Duration::from_secs(5), executor.next_event())
+            .await
+            .map_err(|_| {
+                crate::error::TaskMasterError::ChannelError(
+                    "Timed out waiting for chaos events".to_string(),
+                )
+            })?;
+        match event {
+            Some(TaskEvent::Failed { run_id, .. }) => failed_runs.push(run_id),
+            Some(TaskEvent::Completed { task_id, .. }) => {
+                panic!("Task {} completed despite injected failure", task_id)
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    assert!(failed_runs.contains(&run1) && failed_runs.contains(&run2));
+    assert!(!executor.is_task_running(1) && !executor.is_task_running(2));
+
+    println!("Async chaos test completed");
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dates;
+
+// A named checkpoint in a project that tasks can be grouped under
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Milestone {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+}
+
+// How far along a milestone is
+#[derive(Debug, Clone, Serialize)]
+pub struct MilestoneProgress {
+    pub id: u32,
+    pub name: String,
+    pub due: Option<DateTime<Utc>>,
+    pub total: usize,
+    pub done: usize,
+}
+
+impl MilestoneProgress {
+    // Share of the milestone's tasks that are Done; None while it has no tasks
+    pub fn percentage(&self) -> Option<f64> {
+        if self.total == 0 {
+            None
+        } else {
+            Some(self.done as f64 * 100.0 / self.total as f64)
+        }
+    }
+
+    // e.g. "Beta (ID: 2): 3/5 done (60%), due 2024-06-01 17:00:00, overdue"
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        let mut line = format!(
+            "{} (ID: {}): {}/{} done",
+            self.name, self.id, self.done, self.total
+        );
+        if let Some(percentage) = self.percentage() {
+            line.push_str(&format!(" ({:.0}%)", percentage));
+        }
+        if let Some(due) = &self.due {
+            line.push_str(&format!(", due {}", dates::format_local(due)));
+            if *due < now && self.done < self.total {
+                line.push_str(", overdue");
+            }
+        }
+        line
+    }
+}
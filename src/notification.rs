@@ -3,6 +3,7 @@ use tokio::sync::mpsc;
 use tokio::time::{self, Duration};
 
 use crate::async_executor::TaskEvent;
+use crate::correlation::RunId;
 use crate::error::Result;
 
 type CallbackFn = Box<dyn Fn(&TaskEvent) + Send + Sync + 'static>;
@@ -10,6 +11,8 @@ type CallbackFn = Box<dyn Fn(&TaskEvent) + Send + Sync + 'static>;
 pub struct NotificationSystem {
     event_rx: mpsc::Receiver<TaskEvent>,
     callbacks: HashMap<String, CallbackFn>,
+    // Leave stdout to the callbacks, e.g. when they write machine-readable output there
+    quiet: bool,
 }
 
 impl NotificationSystem {
@@ -17,9 +20,16 @@ impl NotificationSystem {
         NotificationSystem {
             event_rx,
             callbacks: HashMap::new(),
+            quiet: false,
         }
     }
 
+    // Don't print `start`'s progress lines
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
     pub fn register_callback<F>(&mut self, name: &str, callback: F)
     where
         F: Fn(&TaskEvent) + Send + Sync + 'static,
@@ -32,21 +42,35 @@ impl NotificationSystem {
     }
 
     pub async fn start(&mut self) -> Result<()> {
-        println!("Notification system started");
+        self.progress(format_args!("Notification system started"));
 
         while let Some(event) = self.event_rx.recv().await {
-            println!("Received event: {:?}", event);
+            self.progress(format_args!(
+                "[{}] Received event: {:?}",
+                event.run_id(),
+                event
+            ));
 
             for (name, callback) in &self.callbacks {
-                println!("Executing callback: {}", name);
+                self.progress(format_args!(
+                    "[{}] Executing callback: {}",
+                    event.run_id(),
+                    name
+                ));
                 callback(&event);
             }
         }
 
-        println!("Notification system stopped");
+        self.progress(format_args!("Notification system stopped"));
         Ok(())
     }
 
+    fn progress(&self, line: std::fmt::Arguments) {
+        if !self.quiet {
+            println!("{}", line);
+        }
+    }
+
     pub async fn start_with_deadlines(
         &mut self,
         tasks_with_deadlines: HashMap<u32, time::Instant>,
@@ -54,19 +78,27 @@ impl NotificationSystem {
         println!("Notification system with deadlines started");
 
         let mut deadline_tasks = tasks_with_deadlines.clone();
+        // Latest run of each watched task, so deadline events can be correlated
+        let mut latest_runs: HashMap<u32, RunId> = HashMap::new();
 
         loop {
             tokio::select! {
                 Some(event) = self.event_rx.recv() => {
-                    println!("Received event: {:?}", event);
+                    println!("[{}] Received event: {:?}", event.run_id(), event);
 
-                    // Handle task completion
-                    if let TaskEvent::Completed { task_id } = event {
-                        deadline_tasks.remove(&task_id);
+                    match &event {
+                        TaskEvent::Started { task_id, run_id } => {
+                            latest_runs.insert(*task_id, run_id.clone());
+                        }
+                        // Handle task completion
+                        TaskEvent::Completed { task_id, .. } => {
+                            deadline_tasks.remove(task_id);
+                        }
+                        _ => {}
                     }
 
                     for (name, callback) in &self.callbacks {
-                        println!("Executing callback: {}", name);
+                        println!("[{}] Executing callback: {}", event.run_id(), name);
                         callback(&event);
                     }
                 }
@@ -85,9 +117,11 @@ impl NotificationSystem {
                     for task_id in expired {
                         println!("Task {} deadline expired", task_id);
 
-                        let event = TaskEvent::Timeout { task_id };
+                        // Tasks that never started get a fresh ID for the deadline event
+                        let run_id = latest_runs.remove(&task_id).unwrap_or_default();
+                        let event = TaskEvent::Timeout { task_id, run_id };
                         for (name, callback) in &self.callbacks {
-                            println!("Executing deadline callback: {}", name);
+                            println!("[{}] Executing deadline callback: {}", event.run_id(), name);
                             callback(&event);
                         }
 
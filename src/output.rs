@@ -0,0 +1,499 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::error::{Result, TaskMasterError};
+use crate::follow_ups::FollowUpOrigin;
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// Bumped whenever a field is renamed or removed; new fields may be added without a bump
+pub const SCHEMA_VERSION: u32 = 1;
+
+// A project as listed by list-projects
+#[derive(Debug, Serialize)]
+pub struct ProjectListing {
+    pub id: u32,
+    pub uuid: String,
+    pub name: String,
+    pub archived: bool,
+    pub parent_id: Option<u32>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub task_count: usize,
+    pub open_task_count: usize,
+}
+
+impl ProjectListing {
+    pub fn new(project: &Project) -> Self {
+        ProjectListing {
+            id: project.id,
+            uuid: project.uuid.clone(),
+            name: project.name.clone(),
+            archived: project.archived,
+            parent_id: project.parent_id,
+            color: project.color.map(|c| c.to_string()),
+            icon: project.icon.clone(),
+            task_count: project.tasks.len(),
+            open_task_count: project
+                .tasks
+                .iter()
+                .filter(|t| t.status != TaskStatus::Done)
+                .count(),
+        }
+    }
+}
+
+// A project with its tasks, as shown by show-project
+#[derive(Debug, Serialize)]
+pub struct ProjectRecord {
+    #[serde(flatten)]
+    pub listing: ProjectListing,
+    pub description: String,
+    pub owner: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub tasks: Vec<TaskRecord>,
+}
+
+impl ProjectRecord {
+    pub fn new(project: &Project, tasks: Vec<TaskRecord>) -> Self {
+        ProjectRecord {
+            listing: ProjectListing::new(project),
+            description: project.description.clone(),
+            owner: project.owner.clone(),
+            created_at: project.created_at,
+            deadline: project.deadline,
+            tasks,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskRecord {
+    pub id: u32,
+    // The ID as shown to users, with the project's prefix
+    #[serde(rename = "ref")]
+    pub task_ref: String,
+    pub uuid: String,
+    pub title: String,
+    pub status: String,
+    pub blocked: bool,
+    pub priority: String,
+    pub urgency: Option<f64>,
+    // Place in the manual order; ranks compare as plain strings
+    pub rank: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub planned_start: Option<DateTime<Utc>>,
+    pub due: Option<DateTime<Utc>>,
+    pub milestone: Option<String>,
+    pub parent: Option<u32>,
+    // The run of another task this one was added after
+    pub follow_up_of: Option<FollowUpOrigin>,
+    pub tags: Vec<String>,
+    pub estimate_hours: Option<f64>,
+    pub tracked_hours: f64,
+    pub dependencies: Vec<DependencyRecord>,
+    pub attachments: Vec<String>,
+    pub checklist: Vec<ChecklistRecord>,
+    pub fields: BTreeMap<String, String>,
+    pub history: Vec<StatusChangeRecord>,
+}
+
+// A task found by search, with the project it belongs to
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub project_id: u32,
+    pub project: String,
+    #[serde(flatten)]
+    pub task: TaskRecord,
+}
+
+impl SearchHit {
+    pub fn new(project: &Project, task: &Task, urgency: Option<f64>) -> Self {
+        SearchHit {
+            project_id: project.id,
+            project: project.name.clone(),
+            task: TaskRecord::new(project, task, urgency),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyRecord {
+    pub task: u32,
+    // FS, SS, FF or SF
+    pub kind: String,
+    pub lag_minutes: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChecklistRecord {
+    pub text: String,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusChangeRecord {
+    pub at: DateTime<Utc>,
+    pub from: String,
+    pub to: String,
+}
+
+impl TaskRecord {
+    pub fn new(project: &Project, task: &Task, urgency: Option<f64>) -> Self {
+        TaskRecord {
+            id: task.id,
+            task_ref: project.task_ref(task.id),
+            uuid: task.uuid.clone(),
+            title: task.title.clone(),
+            status: task.status.to_string(),
+            blocked: task.is_blocked(&project.tasks),
+            priority: project.priority_label(task),
+            urgency,
+            rank: task.rank.clone(),
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+            planned_start: task.planned_start,
+            due: task.due,
+            milestone: task
+                .milestone
+                .and_then(|id| project.milestone_name(id))
+                .map(str::to_string),
+            parent: task.parent,
+            follow_up_of: task.follow_up_of.clone(),
+            tags: task.tags.clone(),
+            estimate_hours: task.estimate_hours,
+            tracked_hours: task.actual_hours(),
+            dependencies: task
+                .dependencies
+                .iter()
+                .flatten()
+                .map(|dep| DependencyRecord {
+                    task: dep.task,
+                    kind: dep.kind.to_string(),
+                    lag_minutes: dep.lag_minutes.unwrap_or(0),
+                })
+                .collect(),
+            attachments: task.attachments.iter().map(|a| a.describe()).collect(),
+            checklist: task
+                .checklist
+                .iter()
+                .map(|item| ChecklistRecord {
+                    text: item.text.clone(),
+                    done: item.done,
+                })
+                .collect(),
+            fields: task
+                .custom_fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            history: task
+                .history
+                .iter()
+                .map(|change| StatusChangeRecord {
+                    at: change.changed_at,
+                    from: change.from.to_string(),
+                    to: change.to.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// Wrap a record as {"version": 1, "<kind>": ...} so readers can check the schema
+pub fn envelope<T: Serialize>(kind: &str, record: &T) -> Result<Value> {
+    let mut map = serde_json::Map::new();
+    map.insert("version".to_string(), Value::from(SCHEMA_VERSION));
+    map.insert(kind.to_string(), serde_json::to_value(record)?);
+    Ok(Value::Object(map))
+}
+
+pub fn to_json(value: &Value) -> Result<String> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
+
+// Block-style YAML for the plain data the records produce. Strings are quoted whenever a
+// YAML reader could take them for something else.
+pub fn to_yaml(value: &Value) -> String {
+    match value {
+        Value::Array(items) if !items.is_empty() => yaml_block(value, 0),
+        Value::Object(map) if !map.is_empty() => yaml_block(value, 0),
+        _ => format!("{}\n", yaml_scalar(value)),
+    }
+}
+
+fn yaml_block(value: &Value, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let mut out = String::new();
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                out.push_str(&format!("{}{}:", pad, yaml_string(key)));
+                if is_nested(value) {
+                    out.push('\n');
+                    out.push_str(&yaml_block(value, indent + 2));
+                } else {
+                    out.push_str(&format!(" {}\n", yaml_scalar(value)));
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                if is_nested(item) {
+                    // The item's first line goes on the dash line
+                    let block = yaml_block(item, indent + 2);
+                    out.push_str(&format!("{}- {}", pad, &block[indent + 2..]));
+                } else {
+                    out.push_str(&format!("{}- {}\n", pad, yaml_scalar(item)));
+                }
+            }
+        }
+        _ => out.push_str(&format!("{}{}\n", pad, yaml_scalar(value))),
+    }
+    out
+}
+
+fn is_nested(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+        _ => false,
+    }
+}
+
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => yaml_string(s),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+fn yaml_string(s: &str) -> String {
+    let reserved = [
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n",
+    ];
+    let plain = !s.is_empty()
+        && !reserved.contains(&s.to_ascii_lowercase().as_str())
+        && s.parse::<f64>().is_err()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || " _-./()+".contains(c))
+        && !s.starts_with(['-', ' ', '.'])
+        && !s.ends_with(' ');
+    if plain {
+        s.to_string()
+    } else {
+        // JSON strings are valid double-quoted YAML
+        Value::String(s.to_string()).to_string()
+    }
+}
+
+// Read YAML back into plain data: the block style `to_yaml` writes, plus `#` comment lines,
+// single-quoted strings and flow lists such as `[a, b]`, which people tend to type when
+// editing it by hand. Anchors, tags and multi-line strings are not supported.
+pub fn from_yaml(text: &str) -> Result<Value> {
+    let mut lines = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+            continue;
+        }
+        let indent = raw.len() - raw.trim_start().len();
+        let line = YamlLine {
+            number: index + 1,
+            indent,
+            text: trimmed.to_string(),
+        };
+        if raw[..indent].contains('\t') {
+            return Err(yaml_error(&line, "indent with spaces, not tabs"));
+        }
+        lines.push(line);
+    }
+
+    let Some(indent) = lines.first().map(|line| line.indent) else {
+        return Ok(Value::Null);
+    };
+    let mut pos = 0;
+    let value = yaml_node(&mut lines, &mut pos, indent)?;
+    match lines.get(pos) {
+        Some(line) => Err(yaml_error(line, "unexpected indentation")),
+        None => Ok(value),
+    }
+}
+
+struct YamlLine {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+fn yaml_error(line: &YamlLine, message: &str) -> TaskMasterError {
+    TaskMasterError::SerializationError(format!("line {}: {}", line.number, message))
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// The node starting at `pos`, whose lines are indented by `indent`
+fn yaml_node(lines: &mut [YamlLine], pos: &mut usize, indent: usize) -> Result<Value> {
+    let line = &lines[*pos];
+    if is_item(&line.text) {
+        return yaml_sequence(lines, pos, indent);
+    }
+    if split_key(&line.text).is_some() {
+        return yaml_mapping(lines, pos, indent);
+    }
+    let value = parse_scalar(&line.text).map_err(|e| yaml_error(line, &e))?;
+    *pos += 1;
+    Ok(value)
+}
+
+fn yaml_mapping(lines: &mut [YamlLine], pos: &mut usize, indent: usize) -> Result<Value> {
+    let mut map = Map::new();
+    while let Some(line) = lines.get(*pos) {
+        if line.indent < indent {
+            break;
+        }
+        if line.indent > indent {
+            return Err(yaml_error(line, "unexpected indentation"));
+        }
+        let Some((key, rest)) = split_key(&line.text) else {
+            return Err(yaml_error(line, "expected 'key: value'"));
+        };
+        let value = if rest.is_empty() {
+            *pos += 1;
+            match lines.get(*pos) {
+                Some(next) if next.indent > indent => {
+                    let at = next.indent;
+                    yaml_node(lines, pos, at)?
+                }
+                // A list may sit at the same indentation as its key
+                Some(next) if next.indent == indent && is_item(&next.text) => {
+                    yaml_sequence(lines, pos, indent)?
+                }
+                _ => Value::Null,
+            }
+        } else {
+            let value = parse_scalar(rest).map_err(|e| yaml_error(line, &e))?;
+            *pos += 1;
+            value
+        };
+        if map.contains_key(&key) {
+            let line = &lines[*pos - 1];
+            return Err(yaml_error(line, &format!("'{}' is given twice", key)));
+        }
+        map.insert(key, value);
+    }
+    Ok(Value::Object(map))
+}
+
+fn yaml_sequence(lines: &mut [YamlLine], pos: &mut usize, indent: usize) -> Result<Value> {
+    let mut items = Vec::new();
+    while let Some(line) = lines.get_mut(*pos) {
+        if line.indent < indent || !is_item(&line.text) {
+            break;
+        }
+        if line.indent > indent {
+            return Err(yaml_error(line, "unexpected indentation"));
+        }
+        let rest = line.text[1..].trim_start().to_string();
+        if rest.is_empty() {
+            *pos += 1;
+            match lines.get(*pos) {
+                Some(next) if next.indent > indent => {
+                    let at = next.indent;
+                    items.push(yaml_node(lines, pos, at)?);
+                }
+                _ => items.push(Value::Null),
+            }
+        } else {
+            // Read the item's first line as if it started where the text after the dash does
+            line.indent += line.text.len() - rest.len();
+            line.text = rest;
+            let at = line.indent;
+            items.push(yaml_node(lines, pos, at)?);
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+// A mapping line's key and whatever follows its colon
+fn split_key(text: &str) -> Option<(String, &str)> {
+    if text.starts_with('"') {
+        let mut stream = serde_json::Deserializer::from_str(text).into_iter::<String>();
+        let key = stream.next()?.ok()?;
+        let rest = text[stream.byte_offset()..].strip_prefix(':')?;
+        return (rest.is_empty() || rest.starts_with(' ')).then(|| (key, rest.trim()));
+    }
+    if text.starts_with(['\'', '[', '{']) {
+        return None;
+    }
+    let (key, rest) = match text.find(": ") {
+        Some(at) => (&text[..at], &text[at + 2..]),
+        None => (text.strip_suffix(':')?, ""),
+    };
+    Some((key.trim().to_string(), rest.trim()))
+}
+
+fn parse_scalar(text: &str) -> std::result::Result<Value, String> {
+    match text {
+        "~" | "null" | "Null" | "NULL" => return Ok(Value::Null),
+        "true" | "True" | "TRUE" => return Ok(Value::Bool(true)),
+        "false" | "False" | "FALSE" => return Ok(Value::Bool(false)),
+        "[]" => return Ok(Value::Array(Vec::new())),
+        "{}" => return Ok(Value::Object(Map::new())),
+        _ => {}
+    }
+    if text.starts_with('"') {
+        return serde_json::from_str::<String>(text)
+            .map(Value::String)
+            .map_err(|_| format!("can't read the quoted string {}", text));
+    }
+    if let Some(quoted) = text.strip_prefix('\'') {
+        return quoted
+            .strip_suffix('\'')
+            .filter(|inner| !inner.replace("''", "").contains('\''))
+            .map(|inner| Value::String(inner.replace("''", "'")))
+            .ok_or_else(|| format!("can't read the quoted string {}", text));
+    }
+    if text.starts_with(['[', '{']) {
+        // JSON is valid flow YAML; otherwise only a list of plain values is understood
+        if let Ok(value) = serde_json::from_str(text) {
+            return Ok(value);
+        }
+        let inner = text
+            .strip_prefix('[')
+            .and_then(|t| t.strip_suffix(']'))
+            .ok_or_else(|| format!("write '{}' with one entry per line", text))?;
+        return inner
+            .split(',')
+            .map(|item| parse_scalar(item.trim()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map(Value::Array);
+    }
+    if let Ok(n) = text.parse::<i64>() {
+        return Ok(Value::from(n));
+    }
+    if let Ok(n) = text.parse::<u64>() {
+        return Ok(Value::from(n));
+    }
+    let digits = text.contains(|c: char| c.is_ascii_digit());
+    if let Some(n) = text
+        .parse::<f64>()
+        .ok()
+        .filter(|_| digits)
+        .and_then(Number::from_f64)
+    {
+        return Ok(Value::Number(n));
+    }
+    Ok(Value::String(text.to_string()))
+}
@@ -1,9 +1,14 @@
+use std::fmt;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
-use crate::task::{Task, TaskPriority, TaskStatus};
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+use crate::priority::PriorityScale;
+use crate::task::{Task, TaskStatus};
+use crate::templates::TemplateLibrary;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RecurrencePattern {
@@ -26,10 +31,71 @@ impl RecurrencePattern {
     }
 }
 
+impl fmt::Display for RecurrencePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecurrencePattern::Daily => write!(f, "daily"),
+            RecurrencePattern::Weekly => write!(f, "weekly"),
+            RecurrencePattern::Monthly => write!(f, "monthly"),
+            RecurrencePattern::Custom(duration) => {
+                write!(f, "every {}h", duration.as_secs_f64() / 3600.0)
+            }
+        }
+    }
+}
+
+impl FromStr for RecurrencePattern {
+    type Err = TaskMasterError;
+
+    // "daily", "weekly", "monthly", or "every:<duration>" such as "every:3d"
+    fn from_str(s: &str) -> Result<Self> {
+        let input = s.trim().to_lowercase();
+        match input.as_str() {
+            "daily" => Ok(RecurrencePattern::Daily),
+            "weekly" => Ok(RecurrencePattern::Weekly),
+            "monthly" => Ok(RecurrencePattern::Monthly),
+            _ => {
+                let spec = input.strip_prefix("every:").ok_or_else(|| {
+                    TaskMasterError::InvalidOperation(format!(
+                        "Unknown recurrence '{}'; use daily, weekly, monthly or every:<duration>",
+                        s
+                    ))
+                })?;
+                let duration = dates::parse_duration(spec)?.to_std().map_err(|_| {
+                    TaskMasterError::InvalidOperation(format!(
+                        "Recurrence interval must be positive: {}",
+                        s
+                    ))
+                })?;
+                if duration.is_zero() {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Recurrence interval must be positive: {}",
+                        s
+                    )));
+                }
+                Ok(RecurrencePattern::Custom(duration))
+            }
+        }
+    }
+}
+
+// What each occurrence is built from: an embedded task or a named template in the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PeriodicTemplate {
+    Task(Box<Task>),
+    Named(String),
+}
+
+impl From<Task> for PeriodicTemplate {
+    fn from(task: Task) -> Self {
+        PeriodicTemplate::Task(Box::new(task))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeriodicTask {
     pub id: u32,
-    pub template: Task,
+    pub template: PeriodicTemplate,
     pub pattern: RecurrencePattern,
     pub created_at: SystemTime,
     pub last_run: Option<SystemTime>,
@@ -38,13 +104,17 @@ pub struct PeriodicTask {
 }
 
 impl PeriodicTask {
-    pub fn new(id: u32, template: Task, pattern: RecurrencePattern) -> Self {
+    pub fn new<T: Into<PeriodicTemplate>>(
+        id: u32,
+        template: T,
+        pattern: RecurrencePattern,
+    ) -> Self {
         let now = SystemTime::now();
         let next_run = pattern.get_next_occurrence(now);
 
         PeriodicTask {
             id,
-            template,
+            template: template.into(),
             pattern,
             created_at: now,
             last_run: None,
@@ -58,40 +128,65 @@ impl PeriodicTask {
         now >= self.next_run
     }
 
-    pub fn generate_task(&mut self) -> Task {
+    pub fn title(&self) -> String {
+        match &self.template {
+            PeriodicTemplate::Task(task) => task.title.clone(),
+            PeriodicTemplate::Named(name) => format!("template '{}'", name),
+        }
+    }
+
+    // Named templates are looked up on every run so edits to them apply to later occurrences
+    pub fn generate_task(&mut self, templates: &TemplateLibrary) -> Result<Task> {
         let now = SystemTime::now();
+        let occurrence = self.occurrences + 1;
+
+        let task = match &self.template {
+            PeriodicTemplate::Task(template) => Task::new(
+                template.id * 1000 + occurrence,
+                format!(
+                    "{} (#{} on {})",
+                    template.title,
+                    occurrence,
+                    chrono::Local::now().format("%Y-%m-%d"),
+                ),
+                template.status.clone(),
+                template.priority.clone(),
+            ),
+            PeriodicTemplate::Named(name) => templates.get(name)?.instantiate(
+                self.id * 1000 + occurrence,
+                TaskStatus::ToDo,
+                &PriorityScale::default(),
+                &[("n", occurrence.to_string())],
+            )?,
+        };
 
         // Update periodic task state
         self.last_run = Some(now);
         self.next_run = self.pattern.get_next_occurrence(now);
-        self.occurrences += 1;
-
-        // Create a new task based on the template
-        let occurrence_id = self.template.id * 1000 + self.occurrences;
-        let title = format!(
-            "{} (#{} on {})",
-            self.template.title,
-            self.occurrences,
-            chrono::Local::now().format("%Y-%m-%d"),
-        );
-
-        Task::new(
-            occurrence_id,
-            title,
-            self.template.status.clone(),
-            self.template.priority.clone(),
-        )
+        self.occurrences = occurrence;
+
+        Ok(task)
     }
 }
 
 #[derive(Default)]
 pub struct PeriodicTaskScheduler {
     tasks: Vec<PeriodicTask>,
+    templates: TemplateLibrary,
 }
 
 impl PeriodicTaskScheduler {
     pub fn new() -> Self {
-        PeriodicTaskScheduler { tasks: Vec::new() }
+        PeriodicTaskScheduler {
+            tasks: Vec::new(),
+            templates: TemplateLibrary::default(),
+        }
+    }
+
+    // Library used to resolve periodic tasks that reference a named template
+    pub fn with_templates(mut self, templates: TemplateLibrary) -> Self {
+        self.templates = templates;
+        self
     }
 
     pub fn add_task(&mut self, task: PeriodicTask) {
@@ -125,7 +220,10 @@ impl PeriodicTaskScheduler {
         let mut generated = Vec::new();
 
         for task in self.tasks.iter_mut().filter(|t| t.is_due()) {
-            generated.push(task.generate_task());
+            match task.generate_task(&self.templates) {
+                Ok(generated_task) => generated.push(generated_task),
+                Err(e) => println!("Skipping periodic task {}: {}", task.id, e),
+            }
         }
 
         generated
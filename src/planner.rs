@@ -0,0 +1,315 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, Utc};
+
+use crate::appearance::escape_html;
+use crate::locale;
+use crate::periodic_tasks::RecurrencePattern;
+use crate::planning;
+use crate::project::Project;
+use crate::schedule::WorkCalendar;
+use crate::task::{Task, TaskStatus};
+
+// Agenda blocks are laid out from this hour on working days
+const DAY_START_HOUR: u32 = 9;
+
+// A task due on a planner day
+#[derive(Debug, Clone)]
+pub struct PlannerEntry {
+    pub project: String,
+    pub task_id: u32,
+    pub title: String,
+    pub due: DateTime<Utc>,
+    // Remaining estimated work
+    pub hours: f64,
+    pub done: bool,
+}
+
+// Time set aside for an estimated task, e.g. 09:00-11:30
+#[derive(Debug, Clone)]
+pub struct AgendaBlock {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub title: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannerDay {
+    pub date: NaiveDate,
+    pub working: bool,
+    pub entries: Vec<PlannerEntry>,
+    pub blocks: Vec<AgendaBlock>,
+    // Estimated hours that did not fit in the working day
+    pub overflow: f64,
+}
+
+// An open recurring task with a checkbox on each day it comes round
+#[derive(Debug, Clone)]
+pub struct RecurringItem {
+    pub project: String,
+    pub title: String,
+    pub pattern: String,
+    // One flag per planner day
+    pub days: Vec<bool>,
+}
+
+// Scheduled tasks, agenda blocks and recurring checkboxes for a run of days, ready to print
+#[derive(Debug, Clone)]
+pub struct Planner {
+    pub days: Vec<PlannerDay>,
+    pub recurring: Vec<RecurringItem>,
+}
+
+impl Planner {
+    pub fn build(
+        projects: &[Project],
+        start: NaiveDate,
+        day_count: usize,
+        calendar: &WorkCalendar,
+    ) -> Self {
+        let dates: Vec<NaiveDate> = (0..day_count)
+            .map(|offset| start + Duration::days(offset as i64))
+            .collect();
+
+        let mut days: Vec<PlannerDay> = dates
+            .iter()
+            .map(|&date| PlannerDay {
+                date,
+                working: calendar.is_working_day(date),
+                entries: Vec::new(),
+                blocks: Vec::new(),
+                overflow: 0.0,
+            })
+            .collect();
+        let mut recurring = Vec::new();
+
+        for project in projects {
+            let mut ordered: Vec<&Task> = project.tasks.iter().collect();
+            ordered.sort_by(|a, b| a.rank.cmp(&b.rank));
+            for task in ordered {
+                if let Some(due) = task.due {
+                    let date = due.with_timezone(&Local).date_naive();
+                    if let Some(day) = days.iter_mut().find(|d| d.date == date) {
+                        day.entries.push(PlannerEntry {
+                            project: project.label(),
+                            task_id: task.id,
+                            title: task.title.clone(),
+                            due,
+                            hours: planning::remaining_hours(task),
+                            done: task.status == TaskStatus::Done,
+                        });
+                    }
+                }
+                if let (Some(pattern), false) = (&task.recurrence, task.status == TaskStatus::Done)
+                {
+                    recurring.push(RecurringItem {
+                        project: project.label(),
+                        title: task.title.clone(),
+                        pattern: if task.habit.is_some() {
+                            format!("{} habit", pattern)
+                        } else {
+                            pattern.to_string()
+                        },
+                        days: dates.iter().map(|&d| recurs_on(task, pattern, d)).collect(),
+                    });
+                }
+            }
+        }
+
+        for day in &mut days {
+            day.entries.sort_by_key(|e| e.due);
+            if day.working {
+                lay_out_blocks(day, calendar.hours_per_day);
+            }
+        }
+
+        Planner { days, recurring }
+    }
+
+    // A self-contained page with print styles; one day per box, then the recurring grid
+    pub fn to_html(&self) -> String {
+        let title = match (self.days.first(), self.days.last()) {
+            (Some(first), Some(last)) if first.date != last.date => {
+                format!(
+                    "Planner: {} to {}",
+                    locale::date(first.date),
+                    locale::date(last.date)
+                )
+            }
+            (Some(first), _) => format!("Planner: {}", locale::weekday_date(first.date)),
+            _ => "Planner".to_string(),
+        };
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+        html.push_str(STYLE);
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+        html.push_str(if self.days.len() == 1 {
+            "<div class=\"days single\">\n"
+        } else {
+            "<div class=\"days\">\n"
+        });
+
+        for day in &self.days {
+            html.push_str(&format!(
+                "<section class=\"day{}\">\n<h2>{}</h2>\n",
+                if day.working { "" } else { " off" },
+                locale::weekday_date(day.date)
+            ));
+
+            html.push_str("<h3>Tasks</h3>\n<ul class=\"tasks\">\n");
+            if day.entries.is_empty() {
+                html.push_str("<li class=\"empty\">Nothing scheduled</li>\n");
+            }
+            for entry in &day.entries {
+                let mut details = vec![escape_html(&entry.project)];
+                details.push(format!(
+                    "due {}",
+                    locale::time(entry.due.with_timezone(&Local).time())
+                ));
+                if entry.hours > 0.0 {
+                    details.push(locale::hours(entry.hours, 1));
+                }
+                html.push_str(&format!(
+                    "<li><span class=\"box\">{}</span> #{} {} <small>{}</small></li>\n",
+                    if entry.done { "&#9746;" } else { "&#9744;" },
+                    entry.task_id,
+                    escape_html(&entry.title),
+                    details.join(" &middot; ")
+                ));
+            }
+            html.push_str("</ul>\n");
+
+            if day.working {
+                html.push_str("<h3>Agenda</h3>\n<table class=\"agenda\">\n");
+                for block in &day.blocks {
+                    html.push_str(&format!(
+                        "<tr><td class=\"time\">{}&ndash;{}</td><td>{}</td></tr>\n",
+                        locale::time(block.start),
+                        locale::time(block.end),
+                        escape_html(&block.title)
+                    ));
+                }
+                // Blank lines to write in the rest of the day by hand
+                for _ in day.blocks.len()..4 {
+                    html.push_str("<tr><td class=\"time\"></td><td></td></tr>\n");
+                }
+                html.push_str("</table>\n");
+                if day.overflow > 0.0 {
+                    html.push_str(&format!(
+                        "<p class=\"warning\">{} more than fits in the day</p>\n",
+                        locale::hours(day.overflow, 1)
+                    ));
+                }
+            }
+
+            html.push_str("<h3>Notes</h3>\n<div class=\"notes\"></div>\n</section>\n");
+        }
+        html.push_str("</div>\n");
+
+        if !self.recurring.is_empty() {
+            html.push_str(
+                "<section class=\"recurring\">\n<h2>Recurring</h2>\n<table>\n<tr><th></th>",
+            );
+            for day in &self.days {
+                html.push_str(&format!("<th>{}</th>", day.date.format("%a %e")));
+            }
+            html.push_str("</tr>\n");
+            for item in &self.recurring {
+                html.push_str(&format!(
+                    "<tr><td>{} <small>{} &middot; {}</small></td>",
+                    escape_html(&item.title),
+                    escape_html(&item.project),
+                    escape_html(&item.pattern)
+                ));
+                for &due in &item.days {
+                    html.push_str(if due {
+                        "<td class=\"box\">&#9744;</td>"
+                    } else {
+                        "<td></td>"
+                    });
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n</section>\n");
+        }
+
+        html.push_str(&format!(
+            "<footer>Printed {}</footer>\n</body>\n</html>\n",
+            locale::datetime(&Utc::now())
+        ));
+        html
+    }
+}
+
+// Back-to-back blocks for the day's open estimated tasks from DAY_START_HOUR, cut off
+// at the end of the working day
+fn lay_out_blocks(day: &mut PlannerDay, hours_per_day: f64) {
+    let day_start = NaiveTime::from_hms_opt(DAY_START_HOUR, 0, 0).unwrap_or_default();
+    let mut used = 0.0;
+    for entry in day.entries.iter().filter(|e| !e.done && e.hours > 0.0) {
+        let available = (hours_per_day - used).max(0.0);
+        let hours = entry.hours.min(available);
+        day.overflow += entry.hours - hours;
+        if hours <= 0.0 {
+            continue;
+        }
+        let start = day_start + Duration::minutes((used * 60.0).round() as i64);
+        used += hours;
+        day.blocks.push(AgendaBlock {
+            start,
+            end: day_start + Duration::minutes((used * 60.0).round() as i64),
+            title: entry.title.clone(),
+        });
+    }
+}
+
+// Whether a recurring task comes round on a date, counting from its due date, or from
+// when it was created if it has none
+fn recurs_on(task: &Task, pattern: &RecurrencePattern, date: NaiveDate) -> bool {
+    let anchor = task
+        .due
+        .unwrap_or(task.created_at)
+        .with_timezone(&Local)
+        .date_naive();
+    if date < anchor {
+        return false;
+    }
+    match pattern {
+        RecurrencePattern::Daily => true,
+        RecurrencePattern::Weekly => date.weekday() == anchor.weekday(),
+        RecurrencePattern::Monthly => date.day() == anchor.day(),
+        RecurrencePattern::Custom(interval) => {
+            let days = (interval.as_secs() / 86_400).max(1) as i64;
+            (date - anchor).num_days() % days == 0
+        }
+    }
+}
+
+const STYLE: &str = "<style>
+@page { size: A4; margin: 12mm; }
+body { font-family: Georgia, serif; color: #222; margin: 0 auto; max-width: 190mm; }
+h1 { font-size: 18pt; border-bottom: 2px solid #222; padding-bottom: 4px; }
+h2 { font-size: 13pt; margin: 0 0 6px; }
+h3 { font-size: 9pt; text-transform: uppercase; letter-spacing: 1px; color: #666; margin: 8px 0 2px; }
+.days { display: grid; grid-template-columns: 1fr 1fr; gap: 8px; }
+.days.single { grid-template-columns: 1fr; }
+.day { border: 1px solid #999; border-radius: 4px; padding: 8px; break-inside: avoid; }
+.day.off { background: #f4f4f4; }
+ul.tasks { list-style: none; padding: 0; margin: 0; }
+ul.tasks li { padding: 2px 0; border-bottom: 1px dotted #bbb; }
+li.empty { color: #999; font-style: italic; }
+small { color: #666; }
+.box { font-size: 12pt; }
+table { border-collapse: collapse; width: 100%; }
+.agenda td { border-bottom: 1px solid #ddd; height: 16px; font-size: 10pt; }
+.agenda td.time { width: 80px; color: #666; }
+.warning { color: #a00; font-size: 9pt; margin: 2px 0; }
+.notes { height: 40px; border-bottom: 1px solid #ddd; }
+.recurring { margin-top: 12px; break-inside: avoid; }
+.recurring th, .recurring td { border: 1px solid #ccc; padding: 3px; font-size: 10pt; }
+.recurring td.box { text-align: center; }
+footer { margin-top: 12px; font-size: 8pt; color: #999; }
+@media print { .day { page-break-inside: avoid; } }
+</style>
+";
@@ -0,0 +1,114 @@
+use chrono::{Duration, Local, NaiveDate};
+
+use crate::dates;
+use crate::error::Result;
+use crate::project::Project;
+use crate::schedule::WorkCalendar;
+use crate::task::{Task, TaskStatus};
+
+pub const PLANNING_DAYS: usize = 7;
+
+// One day of the planning board
+#[derive(Debug, Clone)]
+pub struct PlanDay {
+    pub date: NaiveDate,
+    // Working hours available; 0 on days off
+    pub capacity: f64,
+    // Remaining estimated work of the tasks due that day
+    pub hours: f64,
+    // In the project's manual order
+    pub tasks: Vec<u32>,
+}
+
+impl PlanDay {
+    pub fn over_capacity(&self) -> bool {
+        self.hours > self.capacity
+    }
+}
+
+// The open tasks of a project laid out over the week starting at `start`. Tasks without a
+// due date, or already overdue, wait in the backlog to be planned.
+#[derive(Debug, Clone)]
+pub struct WeekPlan {
+    pub backlog: Vec<u32>,
+    pub days: Vec<PlanDay>,
+}
+
+impl WeekPlan {
+    pub fn build(project: &Project, start: NaiveDate, calendar: &WorkCalendar) -> Self {
+        let mut days: Vec<PlanDay> = (0..PLANNING_DAYS)
+            .map(|offset| {
+                let date = start + Duration::days(offset as i64);
+                PlanDay {
+                    date,
+                    capacity: if calendar.is_working_day(date) {
+                        calendar.hours_per_day
+                    } else {
+                        0.0
+                    },
+                    hours: 0.0,
+                    tasks: Vec::new(),
+                }
+            })
+            .collect();
+
+        let mut ordered: Vec<&Task> = project.tasks.iter().collect();
+        ordered.sort_by(|a, b| a.rank.cmp(&b.rank));
+
+        let mut backlog = Vec::new();
+        for task in ordered {
+            if task.status == TaskStatus::Done {
+                continue;
+            }
+            let due = task.due.map(|due| due.with_timezone(&Local).date_naive());
+            match due {
+                Some(date) if date >= start => {
+                    // Due after the week: not part of this board
+                    if let Some(day) = days.iter_mut().find(|d| d.date == date) {
+                        day.hours += remaining_hours(task);
+                        day.tasks.push(task.id);
+                    }
+                }
+                _ => backlog.push(task.id),
+            }
+        }
+
+        WeekPlan { backlog, days }
+    }
+
+    // Index of the day a task is planned on, or None when it is in the backlog
+    pub fn day_of(&self, task_id: u32) -> Option<usize> {
+        self.days.iter().position(|d| d.tasks.contains(&task_id))
+    }
+}
+
+// Estimated hours still to do on a task; unestimated tasks count as nothing
+pub fn remaining_hours(task: &Task) -> f64 {
+    task.estimate_hours
+        .map_or(0.0, |estimate| (estimate - task.logged_hours).max(0.0))
+}
+
+// Plan a task for a day, due at the end of that working day, or send it back to the
+// backlog with None. The task moves in the manual order to just before the first task
+// due on a later day, so the order reads the same as the week.
+pub fn plan_task(project: &mut Project, task_id: u32, date: Option<NaiveDate>) -> Result<()> {
+    let due = date.map(dates::end_of_workday).transpose()?;
+    project.set_task_due(task_id, due)?;
+
+    let Some(date) = date else {
+        return Ok(());
+    };
+    project.sort_tasks();
+    let later = project
+        .tasks
+        .iter()
+        .filter(|t| t.id != task_id)
+        .position(|t| {
+            t.due
+                .is_some_and(|due| due.with_timezone(&Local).date_naive() > date)
+        });
+    if let Some(position) = later {
+        project.move_task_to(task_id, position)?;
+    }
+    Ok(())
+}
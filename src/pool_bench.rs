@@ -0,0 +1,177 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cancellation::CancellationFlag;
+use crate::correlation::RunId;
+use crate::error::Result;
+use crate::logs;
+use crate::task::{Task, TaskPriority, TaskStatus};
+use crate::worker_pool::{JobOutcome, JobResult, TaskJob, WorkerPool};
+
+// Throughput of the worker pool on many short jobs, next to the design it replaced: one
+// channel whose receiver all workers shared behind a Mutex, so taking a job meant queueing
+// for that lock. Run with `taskmaster --bench [jobs]`; use a release build for numbers
+// worth comparing.
+
+const DEFAULT_JOBS: usize = 200_000;
+const WORKER_COUNTS: [usize; 4] = [1, 2, 4, 8];
+// Best of this many runs, to smooth out scheduler noise
+const ROUNDS: usize = 3;
+
+pub fn run(jobs: Option<usize>) -> Result<()> {
+    let jobs = jobs.unwrap_or(DEFAULT_JOBS);
+    // Every job is logged; echoing them all would measure the terminal instead
+    logs::set_echo(false);
+
+    println!(
+        "Worker pool benchmark: {} short jobs, best of {}",
+        jobs, ROUNDS
+    );
+    println!(
+        "{:>7}  {:>16}  {:>16}  {:>7}",
+        "Workers", "Shared (jobs/s)", "Stealing (jobs/s)", "Speedup"
+    );
+    for workers in WORKER_COUNTS {
+        let shared = best_of(|| {
+            let pool = SharedQueuePool::new(workers);
+            time_jobs(jobs, |job| pool.execute(job), || pool.get_result())
+        })?;
+        let stealing = best_of(|| {
+            let pool = WorkerPool::new(workers);
+            time_jobs(jobs, |job| pool.execute(job), || pool.get_result())
+        })?;
+        println!(
+            "{:>7}  {:>16.0}  {:>17.0}  {:>6.2}x",
+            workers,
+            jobs as f64 / shared.as_secs_f64(),
+            jobs as f64 / stealing.as_secs_f64(),
+            shared.as_secs_f64() / stealing.as_secs_f64()
+        );
+    }
+    logs::set_echo(true);
+    Ok(())
+}
+
+fn best_of<F: FnMut() -> Result<Duration>>(mut run: F) -> Result<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        best = best.min(run()?);
+    }
+    Ok(best)
+}
+
+// Submit `jobs` jobs and wait for all their results
+fn time_jobs<S, R>(jobs: usize, submit: S, result: R) -> Result<Duration>
+where
+    S: Fn(TaskJob) -> Result<()>,
+    R: Fn() -> Result<JobResult>,
+{
+    let task = Arc::new(Task::new(
+        1,
+        "Benchmark".to_string(),
+        TaskStatus::ToDo,
+        TaskPriority::Medium,
+    ));
+    let started = Instant::now();
+    for _ in 0..jobs {
+        submit(TaskJob {
+            id: 1,
+            run_id: RunId::new(),
+            task: Arc::clone(&task),
+            cancel: CancellationFlag::new(),
+            handler: Box::new(short_job),
+        })?;
+    }
+    for _ in 0..jobs {
+        result()?;
+    }
+    Ok(started.elapsed())
+}
+
+// A few hundred nanoseconds of work the optimizer can't drop
+fn short_job(task: Arc<Task>, _cancel: CancellationFlag) -> JobOutcome {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for _ in 0..16 {
+        for byte in task.title.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    std::hint::black_box(hash);
+    Ok(()).into()
+}
+
+// The previous pool, kept only as the baseline
+enum Message {
+    NewTask(TaskJob),
+    Terminate,
+}
+
+struct SharedQueuePool {
+    threads: Vec<thread::JoinHandle<()>>,
+    sender: mpsc::Sender<Message>,
+    results: mpsc::Receiver<JobResult>,
+}
+
+impl SharedQueuePool {
+    fn new(size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Message>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (results_sender, results) = mpsc::channel();
+
+        let threads = (0..size)
+            .map(|id| {
+                let receiver = Arc::clone(&receiver);
+                let results_sender = results_sender.clone();
+                thread::spawn(move || loop {
+                    let message = receiver.lock().unwrap().recv().unwrap();
+                    match message {
+                        Message::NewTask(job) => {
+                            crate::log_event!(
+                                Info,
+                                "Worker {} got job {}; executing.",
+                                id,
+                                job.run_id
+                            );
+                            results_sender
+                                .send(JobResult {
+                                    task_id: job.id,
+                                    run_id: job.run_id,
+                                    outcome: (job.handler)(job.task, job.cancel),
+                                    follow_ups: Vec::new(),
+                                })
+                                .unwrap();
+                        }
+                        Message::Terminate => break,
+                    }
+                })
+            })
+            .collect();
+
+        SharedQueuePool {
+            threads,
+            sender,
+            results,
+        }
+    }
+
+    fn execute(&self, job: TaskJob) -> Result<()> {
+        self.sender.send(Message::NewTask(job)).unwrap();
+        Ok(())
+    }
+
+    fn get_result(&self) -> Result<JobResult> {
+        Ok(self.results.recv().unwrap())
+    }
+}
+
+impl Drop for SharedQueuePool {
+    fn drop(&mut self) {
+        for _ in &self.threads {
+            self.sender.send(Message::Terminate).unwrap();
+        }
+        for thread in self.threads.drain(..) {
+            thread.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::task::TaskPriority;
+
+// How a project's numeric priority levels are interpreted, e.g. P0-P4 or 1-100
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PriorityScale {
+    pub min: u8,
+    pub max: u8,
+    // Shown before the number, e.g. "P" for P0-P4
+    #[serde(default)]
+    pub prefix: String,
+    // True for scales like P0-P4 where the smallest number is the most urgent
+    #[serde(default)]
+    pub lower_is_urgent: bool,
+}
+
+impl Default for PriorityScale {
+    fn default() -> Self {
+        PriorityScale {
+            min: 1,
+            max: 100,
+            prefix: String::new(),
+            lower_is_urgent: false,
+        }
+    }
+}
+
+impl PriorityScale {
+    pub fn new(min: u8, max: u8, prefix: &str, lower_is_urgent: bool) -> Result<Self> {
+        if min >= max {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Priority scale minimum ({}) must be below its maximum ({})",
+                min, max
+            )));
+        }
+
+        Ok(PriorityScale {
+            min,
+            max,
+            prefix: prefix.to_string(),
+            lower_is_urgent,
+        })
+    }
+
+    // Urgency on a common 0-100 scale so named and numeric priorities compare
+    pub fn weight(&self, priority: &TaskPriority) -> u8 {
+        match priority {
+            TaskPriority::Low => 25,
+            TaskPriority::Medium => 50,
+            TaskPriority::High => 75,
+            TaskPriority::Level(level) => {
+                let level = (*level).clamp(self.min, self.max);
+                let span = (self.max - self.min) as f64;
+                let mut fraction = (level - self.min) as f64 / span;
+                if self.lower_is_urgent {
+                    fraction = 1.0 - fraction;
+                }
+                (fraction * 100.0).round() as u8
+            }
+        }
+    }
+
+    // Most urgent first
+    pub fn compare(&self, a: &TaskPriority, b: &TaskPriority) -> Ordering {
+        self.weight(b).cmp(&self.weight(a))
+    }
+
+    pub fn label(&self, priority: &TaskPriority) -> String {
+        match priority {
+            TaskPriority::Level(level) => format!("{}{}", self.prefix, level),
+            other => other.to_string(),
+        }
+    }
+
+    // Accepts Low/Medium/High or a level on this scale, with or without the prefix
+    pub fn parse(&self, input: &str) -> Result<TaskPriority> {
+        let input = input.trim();
+        match input.to_lowercase().as_str() {
+            "low" => return Ok(TaskPriority::Low),
+            "medium" => return Ok(TaskPriority::Medium),
+            "high" => return Ok(TaskPriority::High),
+            _ => {}
+        }
+
+        let digits = match input.get(..self.prefix.len()) {
+            Some(head) if !self.prefix.is_empty() && head.eq_ignore_ascii_case(&self.prefix) => {
+                &input[self.prefix.len()..]
+            }
+            _ => input,
+        };
+
+        let level = digits.parse::<u8>().map_err(|_| {
+            TaskMasterError::InvalidOperation(format!(
+                "Invalid priority '{}'; use Low, Medium, High, or {}{}-{}{}",
+                input, self.prefix, self.min, self.prefix, self.max
+            ))
+        })?;
+
+        if level < self.min || level > self.max {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Priority {} is outside the project scale {}{}-{}{}",
+                level, self.prefix, self.min, self.prefix, self.max
+            )));
+        }
+
+        Ok(TaskPriority::Level(level))
+    }
+}
@@ -1,14 +1,111 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::appearance::ProjectColor;
+use crate::async_executor::TaskEvent;
+use crate::attachments::Attachment;
+use crate::bulk::{TaskChange, TaskFilter};
+use crate::correlation::RunId;
+use crate::dates;
 use crate::error::{Result, TaskMasterError};
-use crate::task::{Task, TaskPriority, TaskStatus};
-use crate::task_dependencies::DependencyGraph;
+use crate::fields::{self, FieldDefinition};
+use crate::follow_ups::{FollowUp, FollowUpOrigin, FollowUpRule};
+use crate::habits::Habit;
+use crate::ids;
+use crate::milestone::{Milestone, MilestoneProgress};
+use crate::periodic_tasks::RecurrencePattern;
+use crate::priority::PriorityScale;
+use crate::ranking;
+use crate::reminders::ReminderRule;
+use crate::stats::ProjectStats;
+use crate::task::{Task, TaskPriority, TaskStatus, TimeEntry};
+use crate::task_dependencies::{
+    Dependency, DependencyGraph, GraphCache, OrphanPolicy, Propagation,
+};
+use crate::templates::{DependencyRule, COMPONENT_PLACEHOLDER};
+use crate::urgency::UrgencyCoefficients;
+use crate::validation::{self, ValidationRule};
+use crate::worker_pool::JobResult;
+use crate::workflow::Workflow;
+
+// Per-project configuration persisted alongside the tasks
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProjectSettings {
+    #[serde(default)]
+    pub workflow: Workflow,
+    #[serde(default)]
+    pub priority_scale: PriorityScale,
+    // Checked whenever a task is added or updated
+    #[serde(default)]
+    pub rules: Vec<ValidationRule>,
+    // Declared custom fields; empty means any field is accepted as text
+    #[serde(default)]
+    pub fields: Vec<FieldDefinition>,
+    // Used for new tasks that don't give a priority; Medium if unset
+    #[serde(default)]
+    pub default_priority: Option<TaskPriority>,
+    // Shown before task IDs, e.g. "WEB-" for WEB-12
+    #[serde(default)]
+    pub id_prefix: String,
+    // Added to every new task
+    #[serde(default)]
+    pub default_tags: Vec<String>,
+    // What finishing a task does to the tasks waiting on it
+    #[serde(default)]
+    pub propagation: Propagation,
+    // Dependencies by task title, added when a project is created from this one as a template
+    #[serde(default)]
+    pub dependency_rules: Vec<DependencyRule>,
+    // Most of the project's tasks an executor runs at once, however many workers it has, so
+    // a big project can't take every worker from the others; no limit if unset
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Project {
     pub id: u32,
     pub name: String,
+    // Stable identity for sync and merge; numeric IDs may collide across data directories
+    #[serde(default)]
+    pub uuid: String,
     pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub settings: ProjectSettings,
+    // Hidden from project listings, e.g. past instances of a recurring project
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    // The workspace project this one belongs to, if any
+    #[serde(default)]
+    pub parent_id: Option<u32>,
+    // Shown wherever the project is listed, so projects are easy to tell apart
+    #[serde(default)]
+    pub color: Option<ProjectColor>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    // In due-date order, undated ones last
+    #[serde(default)]
+    pub milestones: Vec<Milestone>,
+    // Lowest ID the next new task may take. It only grows, so IDs of deleted tasks are
+    // never handed out again; 0 in files saved before it existed.
+    #[serde(default)]
+    pub task_counter: u32,
+    // Built from the tasks' dependencies when first needed
+    #[serde(skip)]
+    graph: GraphCache,
+    // Raised by changes to the project, waiting to be announced
+    #[serde(skip)]
+    events: Vec<TaskEvent>,
 }
 
 impl Project {
@@ -16,34 +113,996 @@ impl Project {
         Project {
             id,
             name,
+            uuid: ids::new_uuid(),
             tasks: Vec::new(),
+            settings: ProjectSettings::default(),
+            archived: false,
+            description: String::new(),
+            owner: None,
+            created_at: Utc::now(),
+            deadline: None,
+            parent_id: None,
+            color: None,
+            icon: None,
+            milestones: Vec::new(),
+            task_counter: 0,
+            graph: GraphCache::default(),
+            events: Vec::new(),
+        }
+    }
+
+    // A fresh copy of this project's tasks and settings under a new ID, with every task
+    // back in the initial state. Task IDs are kept so dependencies still line up.
+    pub fn instantiate(&self, id: u32, name: String) -> Result<Project> {
+        let mut project = Project::new(id, name);
+        project.settings = self.settings.clone();
+        project.description = self.description.clone();
+        project.owner = self.owner.clone();
+        project.parent_id = self.parent_id;
+        project.color = self.color;
+        project.icon = self.icon.clone();
+        project.milestones = self.milestones.clone();
+
+        let initial = self.settings.workflow.initial_state()?;
+        for task in &self.tasks {
+            let mut task = task.next_occurrence(task.id, initial.clone());
+            // A new project starts its habits from scratch
+            if let Some(habit) = &mut task.habit {
+                *habit = Habit::new(Local::now().date_naive(), habit.skips);
+            }
+            project.push_task(task);
+        }
+        Ok(project)
+    }
+
+    // Like `instantiate`, but every task with `{component}` in its title is copied once per
+    // component, and the dependency rules are turned into edges between the tasks they name.
+    // Dependencies between templated tasks stay within a component; a plain task that
+    // depends on a templated one waits for all of its copies.
+    pub fn instantiate_with_components(
+        &self,
+        id: u32,
+        name: String,
+        components: &[String],
+    ) -> Result<Project> {
+        let mut project = self.instantiate(id, name)?;
+
+        // The copies of each templated task, by component
+        let mut copies: HashMap<u32, Vec<(&str, u32)>> = HashMap::new();
+        if !components.is_empty() {
+            let mut next_id = project.next_task_id()?;
+            for task in &self.tasks {
+                if !task.title.contains(COMPONENT_PLACEHOLDER) {
+                    continue;
+                }
+                for component in components {
+                    copies
+                        .entry(task.id)
+                        .or_default()
+                        .push((component.as_str(), next_id));
+                    next_id = ids::after(next_id)?;
+                }
+            }
+        }
+
+        if !copies.is_empty() {
+            let originals = std::mem::take(&mut project.tasks);
+            project.graph.clear();
+            for task in originals {
+                let instances: Vec<(Option<&str>, u32)> = match copies.get(&task.id) {
+                    Some(list) => list.iter().map(|(c, id)| (Some(*c), *id)).collect(),
+                    None => vec![(None, task.id)],
+                };
+                for (component, copy_id) in instances {
+                    let mut copy = task.clone();
+                    copy.id = copy_id;
+                    if let Some(component) = component {
+                        copy.uuid = ids::new_uuid();
+                        copy.title = task.title.replace(COMPONENT_PLACEHOLDER, component);
+                    }
+                    copy.dependencies = task.dependencies.as_ref().map(|deps| {
+                        deps.iter()
+                            .flat_map(|dep| match copies.get(&dep.task) {
+                                None => vec![*dep],
+                                Some(list) => list
+                                    .iter()
+                                    .filter(|(c, _)| component.is_none_or(|own| own == *c))
+                                    .map(|(_, id)| Dependency { task: *id, ..*dep })
+                                    .collect(),
+                            })
+                            .collect()
+                    });
+                    project.push_task(copy);
+                }
+            }
+        }
+
+        for rule in &self.settings.dependency_rules {
+            for (task_title, dependency_title) in rule.expand(components) {
+                let ids_titled = |title: &str| -> Vec<u32> {
+                    project
+                        .tasks
+                        .iter()
+                        .filter(|t| t.title.eq_ignore_ascii_case(title))
+                        .map(|t| t.id)
+                        .collect()
+                };
+                let dependents = ids_titled(&task_title);
+                let dependencies = ids_titled(&dependency_title);
+                for &task_id in &dependents {
+                    for &dependency_id in &dependencies {
+                        if project.get_task(task_id)?.depends_on(dependency_id) {
+                            continue;
+                        }
+                        project
+                            .add_typed_dependency(task_id, rule.dependency(dependency_id))
+                            .map_err(|e| {
+                                TaskMasterError::InvalidOperation(format!(
+                                    "Dependency rule {}: {}",
+                                    rule, e
+                                ))
+                            })?;
+                    }
+                }
+            }
+        }
+
+        Ok(project)
+    }
+
+    // The name with the project's icon in front, if it has one
+    pub fn label(&self) -> String {
+        match &self.icon {
+            Some(icon) => format!("{} {}", icon, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    // A deep copy under a new ID and name. With `reset`, tasks start over in the initial
+    // state as in `instantiate`; otherwise statuses, history and tracked time are kept.
+    // With `renumber`, tasks are numbered 1..n in manual order and dependencies follow.
+    pub fn clone_as(&self, id: u32, name: String, reset: bool, renumber: bool) -> Result<Project> {
+        let mut project = if reset {
+            self.instantiate(id, name)?
+        } else {
+            let mut project = self.clone();
+            project.id = id;
+            project.name = name;
+            project.uuid = ids::new_uuid();
+            project.created_at = Utc::now();
+            project.archived = false;
+            for task in &mut project.tasks {
+                task.uuid = ids::new_uuid();
+            }
+            project
+        };
+
+        if renumber {
+            project.renumber_tasks();
+        }
+        Ok(project)
+    }
+
+    // Number tasks 1..n in manual order, rewriting dependencies and subtask links.
+    // Returns the (old, new) ID pairs that changed.
+    pub fn renumber_tasks(&mut self) -> Vec<(u32, u32)> {
+        self.sort_tasks();
+        self.graph.clear();
+        let mapping: Vec<(u32, u32)> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| (task.id, i as u32 + 1))
+            .collect();
+        let remap = |id: u32| {
+            mapping
+                .iter()
+                .find(|(old, _)| *old == id)
+                .map_or(id, |(_, new)| *new)
+        };
+
+        for task in &mut self.tasks {
+            task.id = remap(task.id);
+            task.parent = task.parent.map(remap);
+            if let Some(deps) = task.dependencies.as_mut() {
+                for dep in deps.iter_mut() {
+                    dep.task = remap(dep.task);
+                }
+            }
+        }
+
+        // Numbering starts over, so the counter does too
+        self.task_counter = self.tasks.len() as u32 + 1;
+
+        mapping
+            .into_iter()
+            .filter(|(old, new)| old != new)
+            .collect()
+    }
+
+    // New tasks go to the end of the manual order. Fails if the ID is taken or the task
+    // breaks a project rule.
+    pub fn add_task(&mut self, task: Task) -> Result<()> {
+        if self.tasks.iter().any(|t| t.id == task.id) {
+            return Err(TaskMasterError::DuplicateTaskId(task.id));
         }
+        self.check_rules(&task)?;
+        self.push_task(task);
+        Ok(())
     }
 
-    pub fn add_task(&mut self, task: Task) {
+    // Add copies of existing tasks (instances, merges, recurrences) without re-checking rules
+    fn push_task(&mut self, mut task: Task) {
+        let last = self.tasks.iter().map(|t| t.rank.as_str()).max();
+        task.rank = ranking::between(last.filter(|r| !r.is_empty()), None);
+        self.task_counter = self.task_counter.max(task.id.saturating_add(1));
+        self.graph.update(|graph| graph.add_task(&task));
         self.tasks.push(task);
     }
 
-    pub fn remove_task(&mut self, task_id: u32) {
+    // Check `task`, as it would be saved, against the project's rules
+    pub fn check_rules(&self, task: &Task) -> Result<()> {
+        validation::evaluate(
+            &self.settings.rules,
+            task,
+            &self.tasks,
+            &self.settings.priority_scale,
+        )
+    }
+
+    // Keep `tasks` in manual order; ties keep insertion order
+    pub fn sort_tasks(&mut self) {
+        self.tasks.sort_by(|a, b| a.rank.cmp(&b.rank));
+    }
+
+    // Give every task a fresh, evenly spread rank in the current order
+    pub fn rerank_tasks(&mut self) {
+        let ranks = ranking::spread(self.tasks.len());
+        for (task, rank) in self.tasks.iter_mut().zip(ranks) {
+            task.rank = rank;
+        }
+    }
+
+    // Rank tasks from files saved before ranks existed, in their old stored order. Also
+    // repairs duplicate or malformed ranks. Returns true if any changed.
+    pub fn assign_missing_ranks(&mut self) -> bool {
+        let mut ranks: Vec<&str> = self.tasks.iter().map(|t| t.rank.as_str()).collect();
+        ranks.sort_unstable();
+        let sound =
+            ranks.iter().all(|r| ranking::is_valid(r)) && ranks.windows(2).all(|w| w[0] < w[1]);
+        if sound {
+            return false;
+        }
+        if self.tasks.iter().all(|t| t.rank.is_empty()) {
+            self.tasks.sort_by_key(|t| t.sort_order);
+        } else {
+            self.sort_tasks();
+        }
+        self.rerank_tasks();
+        true
+    }
+
+    // Move a task to a zero-based position in the manual order. Only the moved task's rank
+    // changes, unless its new neighbours leave no room and the order has to be re-spread.
+    pub fn move_task_to(&mut self, task_id: u32, position: usize) -> Result<()> {
+        self.sort_tasks();
+        let index = self
+            .tasks
+            .iter()
+            .position(|t| t.id == task_id)
+            .ok_or(TaskMasterError::TaskNotFound(task_id))?;
+
+        let task = self.tasks.remove(index);
+        let position = position.min(self.tasks.len());
+        self.tasks.insert(position, task);
+
+        let neighbour = |tasks: &[Task], at: Option<usize>| {
+            at.and_then(|at| tasks.get(at))
+                .map(|t: &Task| t.rank.clone())
+        };
+        let before = neighbour(&self.tasks, position.checked_sub(1));
+        let after = neighbour(&self.tasks, Some(position + 1));
+        let room = match (&before, &after) {
+            (Some(before), Some(after)) => {
+                ranking::is_valid(before) && ranking::is_valid(after) && before < after
+            }
+            (Some(rank), None) | (None, Some(rank)) => ranking::is_valid(rank),
+            (None, None) => true,
+        };
+        let rank = room.then(|| ranking::between(before.as_deref(), after.as_deref()));
+        match rank.filter(|rank| rank.len() <= ranking::MAX_LEN) {
+            Some(rank) => self.tasks[position].rank = rank,
+            None => self.rerank_tasks(),
+        }
+        Ok(())
+    }
+
+    // Move a task to just before or just after another one in the manual order
+    pub fn move_task_next_to(&mut self, task_id: u32, other_id: u32, after: bool) -> Result<()> {
+        if task_id == other_id {
+            return Err(TaskMasterError::InvalidOperation(
+                "A task can't be moved next to itself".to_string(),
+            ));
+        }
+        self.get_task(task_id)?;
+        self.sort_tasks();
+        let others: Vec<u32> = self
+            .tasks
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| *id != task_id)
+            .collect();
+        let index = others
+            .iter()
+            .position(|id| *id == other_id)
+            .ok_or(TaskMasterError::TaskNotFound(other_id))?;
+        self.move_task_to(task_id, if after { index + 1 } else { index })
+    }
+
+    pub fn move_task_up(&mut self, task_id: u32) -> Result<()> {
+        let index = self.task_position(task_id)?;
+        self.move_task_to(task_id, index.saturating_sub(1))
+    }
+
+    pub fn move_task_down(&mut self, task_id: u32) -> Result<()> {
+        let index = self.task_position(task_id)?;
+        self.move_task_to(task_id, index + 1)
+    }
+
+    // Zero-based position of a task in the manual order
+    pub fn task_position(&mut self, task_id: u32) -> Result<usize> {
+        self.sort_tasks();
+        self.tasks
+            .iter()
+            .position(|t| t.id == task_id)
+            .ok_or(TaskMasterError::TaskNotFound(task_id))
+    }
+
+    // The project's counter, or one past the highest ID for projects saved without one
+    pub fn next_task_id(&self) -> Result<u32> {
+        Ok(ids::next_id(self.tasks.iter().map(|t| t.id))?.max(self.task_counter))
+    }
+
+    // Give UUIDs to a project and tasks saved before UUIDs existed. Returns true if any changed.
+    pub fn assign_missing_uuids(&mut self) -> bool {
+        let mut changed = false;
+        if self.uuid.is_empty() {
+            self.uuid = ids::new_uuid();
+            changed = true;
+        }
+        for task in self.tasks.iter_mut().filter(|t| t.uuid.is_empty()) {
+            task.uuid = ids::new_uuid();
+            changed = true;
+        }
+        changed
+    }
+
+    // Move every task of `source` into this project. Tasks keep their IDs unless those are
+    // taken here, in which case they are renumbered and dependencies and parents follow.
+    // Tags are merged with this project's: a tag already used here under different case takes
+    // the existing spelling, and the source's default tags join this project's.
+    // With a tag prefix, moved tasks' tags become "prefix/tag" and they gain the prefix as a
+    // tag of its own. Returns the (old, new) ID pairs of renumbered tasks.
+    pub fn merge_from(
+        &mut self,
+        source: &mut Project,
+        tag_prefix: Option<&str>,
+    ) -> Result<Vec<(u32, u32)>> {
+        if source.id == self.id {
+            return Err(TaskMasterError::InvalidOperation(
+                "Cannot merge a project into itself".to_string(),
+            ));
+        }
+        if let Some(task) = source
+            .tasks
+            .iter()
+            .find(|t| !self.settings.workflow.contains(&t.status))
+        {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Task {} has status '{}', which project {} does not use",
+                task.id, task.status, self.id
+            )));
+        }
+
+        let mut used: HashSet<u32> = self.tasks.iter().map(|t| t.id).collect();
+        let colliding: HashSet<u32> = source
+            .tasks
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| used.contains(id))
+            .collect();
+        used.extend(source.tasks.iter().map(|t| t.id));
+
+        let mut renumbered = Vec::new();
+        let mut next = ids::next_id(used.iter().copied())?.max(self.task_counter);
+        for task in &source.tasks {
+            if colliding.contains(&task.id) {
+                renumbered.push((task.id, next));
+                next = ids::after(next)?;
+            }
+        }
+        let remap = |id: u32| {
+            renumbered
+                .iter()
+                .find(|(old, _)| *old == id)
+                .map_or(id, |(_, new)| *new)
+        };
+
+        let mut vocabulary: Vec<String> = self.settings.default_tags.clone();
+        for tag in self.tasks.iter().flat_map(|t| &t.tags) {
+            if !vocabulary
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(tag))
+            {
+                vocabulary.push(tag.clone());
+            }
+        }
+        let canonical = |tag: &String| {
+            vocabulary
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(tag))
+                .unwrap_or(tag)
+                .clone()
+        };
+        for tag in &source.settings.default_tags {
+            let tag = canonical(tag);
+            if !self.settings.default_tags.contains(&tag) {
+                self.settings.default_tags.push(tag);
+            }
+        }
+
+        // Milestones join this project's, matched by name
+        let mut milestone_map = Vec::new();
+        for milestone in &source.milestones {
+            let id = match self
+                .milestones
+                .iter()
+                .find(|m| m.name.eq_ignore_ascii_case(&milestone.name))
+            {
+                Some(existing) => existing.id,
+                None => self.add_milestone(&milestone.name, milestone.due)?,
+            };
+            milestone_map.push((milestone.id, id));
+        }
+
+        for mut task in source.tasks.drain(..) {
+            let mut tags: Vec<String> = Vec::new();
+            for tag in task.tags.iter().map(canonical) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            task.tags = tags;
+            task.milestone = task.milestone.and_then(|old| {
+                milestone_map
+                    .iter()
+                    .find(|(from, _)| *from == old)
+                    .map(|(_, to)| *to)
+            });
+            task.id = remap(task.id);
+            task.parent = task.parent.map(remap);
+            if let Some(deps) = task.dependencies.as_mut() {
+                for dep in deps.iter_mut() {
+                    dep.task = remap(dep.task);
+                }
+            }
+            if let Some(prefix) = tag_prefix {
+                task.tags = task
+                    .tags
+                    .iter()
+                    .map(|tag| format!("{}/{}", prefix, tag))
+                    .collect();
+                task.tags.push(prefix.to_string());
+            }
+            task.touch();
+            self.push_task(task);
+        }
+
+        Ok(renumbered)
+    }
+
+    // Fold a duplicate task into the one kept and delete it. The kept task gains the
+    // duplicate's dependencies, status history, edits, tags, checklist items, attachments,
+    // custom fields and logged time; it takes the higher priority and earlier due date.
+    // Tasks that depended on or were split from the duplicate now point at the kept task.
+    pub fn merge_tasks(&mut self, keep_id: u32, duplicate_id: u32) -> Result<()> {
+        if keep_id == duplicate_id {
+            return Err(TaskMasterError::InvalidOperation(
+                "Cannot merge a task into itself".to_string(),
+            ));
+        }
+        self.get_task(keep_id)?;
+        let duplicate = self.get_task(duplicate_id)?.clone();
+        let scale = self.settings.priority_scale.clone();
+        let before = self.tasks.clone();
+
+        self.drop_task(duplicate_id);
+        for task in &mut self.tasks {
+            if task.parent == Some(duplicate_id) {
+                task.parent = Some(keep_id);
+            }
+            if let Some(deps) = task.dependencies.as_mut() {
+                if let Some(position) = deps.iter().position(|d| d.task == duplicate_id) {
+                    let edge = deps.remove(position);
+                    if task.id != keep_id && !deps.iter().any(|d| d.task == keep_id) {
+                        deps.push(Dependency {
+                            task: keep_id,
+                            ..edge
+                        });
+                    }
+                }
+            }
+        }
+
+        let keep = self.get_task_mut(keep_id)?;
+        let mut deps = keep.dependencies.take().unwrap_or_default();
+        for dep in duplicate.dependencies.iter().flatten() {
+            if dep.task != keep_id && !deps.iter().any(|d| d.task == dep.task) {
+                deps.push(*dep);
+            }
+        }
+        keep.dependencies = Some(deps).filter(|d| !d.is_empty());
+
+        keep.history.extend(duplicate.history);
+        keep.history.sort_by_key(|change| change.changed_at);
+        keep.edits.extend(duplicate.edits);
+        keep.edits.sort_by_key(|edit| edit.changed_at);
+        for tag in duplicate.tags {
+            if !keep.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                keep.tags.push(tag);
+            }
+        }
+        for item in duplicate.checklist {
+            if !keep.checklist.iter().any(|i| i.text == item.text) {
+                keep.checklist.push(item);
+            }
+        }
+        keep.attachments.extend(duplicate.attachments);
+        for (name, value) in duplicate.custom_fields {
+            keep.custom_fields.entry(name).or_insert(value);
+        }
+        keep.logged_hours += duplicate.logged_hours;
+        keep.time_log.extend(duplicate.time_log);
+        keep.estimate_hours = match (keep.estimate_hours, duplicate.estimate_hours) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        if scale.weight(&duplicate.priority) > scale.weight(&keep.priority) {
+            keep.priority = duplicate.priority;
+        }
+        keep.due = match (keep.due, duplicate.due) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        keep.milestone = keep.milestone.or(duplicate.milestone);
+        keep.blocked_reason = keep.blocked_reason.take().or(duplicate.blocked_reason);
+        keep.record_edit(
+            "merged",
+            "",
+            &format!("task {} '{}'", duplicate.id, duplicate.title),
+        );
+        keep.touch();
+
+        // Combined dependencies must still form a graph that can be ordered
+        if let Err(e) = self.get_task_execution_order() {
+            self.tasks = before;
+            self.graph.clear();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Delete a task, dealing with the tasks that depend on it as `policy` says so none is
+    // left waiting on a task that no longer exists. Returns the IDs of those dependents.
+    pub fn remove_task(&mut self, task_id: u32, policy: OrphanPolicy) -> Result<Vec<u32>> {
+        let removed = self.get_task(task_id)?.clone();
+        let dependents: Vec<u32> = self
+            .tasks
+            .iter()
+            .filter(|t| t.depends_on(task_id))
+            .map(|t| t.id)
+            .collect();
+
+        if policy == OrphanPolicy::Refuse && !dependents.is_empty() {
+            let ids: Vec<String> = dependents.iter().map(|id| id.to_string()).collect();
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Task {} is a dependency of task(s) {}",
+                task_id,
+                ids.join(", ")
+            )));
+        }
+
+        for &dependent in &dependents {
+            self.remove_task_dependency(dependent, task_id)?;
+            if policy != OrphanPolicy::Transfer {
+                continue;
+            }
+            // The dependent now waits directly on what the removed task waited on
+            for dep in removed.dependencies.iter().flatten() {
+                if dep.task == dependent || self.get_task(dependent)?.depends_on(dep.task) {
+                    continue;
+                }
+                self.add_typed_dependency(dependent, *dep)?;
+            }
+        }
+
+        self.drop_task(task_id);
+        Ok(dependents)
+    }
+
+    // Take a task out without touching anything that refers to it
+    fn drop_task(&mut self, task_id: u32) {
         self.tasks.retain(|task| task.id != task_id);
+        self.graph.update(|graph| {
+            graph.remove_task(task_id);
+            Ok(())
+        });
     }
 
+    // Returns the ID of the next occurrence when this completes a recurring task
     pub fn update_task(
         &mut self,
         task_id: u32,
         new_title: String,
         new_status: TaskStatus,
         new_priority: TaskPriority,
-    ) -> Result<()> {
-        let task = self
+    ) -> Result<Option<u32>> {
+        let task = self.get_task(task_id)?;
+
+        // Reject moves the project's workflow does not allow
+        self.settings
+            .workflow
+            .validate_transition(&task.status, &new_status)?;
+
+        let completed = task.status != TaskStatus::Done && new_status == TaskStatus::Done;
+        let mut updated = task.clone();
+        updated.update(new_title, new_status, new_priority);
+        self.check_rules(&updated)?;
+        *self.get_task_mut(task_id)? = updated;
+
+        if completed {
+            self.propagate_completion(task_id);
+            Ok(self.spawn_next_occurrence(task_id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Move a task to another status and leave everything else as it is. Like `update_task`,
+    // returns the ID of the next occurrence when this completes a recurring task.
+    pub fn set_task_status(&mut self, task_id: u32, new_status: TaskStatus) -> Result<Option<u32>> {
+        let task = self.get_task(task_id)?;
+        self.settings
+            .workflow
+            .validate_transition(&task.status, &new_status)?;
+
+        let completed = task.status != TaskStatus::Done && new_status == TaskStatus::Done;
+        let mut updated = task.clone();
+        updated.set_status(new_status);
+        self.check_rules(&updated)?;
+        *self.get_task_mut(task_id)? = updated;
+
+        if completed {
+            self.propagate_completion(task_id);
+            Ok(self.spawn_next_occurrence(task_id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Events raised since the last call, e.g. dependents freed up by a finished task
+    pub fn take_events(&mut self) -> Vec<TaskEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // Act on the project's propagation setting for the dependents of a task just finished:
+    // raise an Unblocked event for each one that can now start and, if asked to, move those
+    // parked in a "Blocked" workflow status back to the initial status
+    fn propagate_completion(&mut self, task_id: u32) {
+        if self.settings.propagation == Propagation::Off {
+            return;
+        }
+
+        let now = Utc::now();
+        let ready: Vec<u32> = self
+            .graph
+            .with(&self.tasks, |graph| {
+                let dependents = graph.get_dependents(task_id);
+                Ok(self
+                    .tasks
+                    .iter()
+                    .filter(|t| {
+                        dependents.contains(&t.id)
+                            && t.status != TaskStatus::Done
+                            && t.blocked_reason.is_none()
+                            && graph.are_dependencies_met(t.id, &self.tasks, now)
+                    })
+                    .map(|t| t.id)
+                    .collect())
+            })
+            .unwrap_or_default();
+
+        // Loaded and constructed workflows always have a state; without one, nothing is unparked
+        let initial = self.settings.workflow.initial_state().ok();
+        let unblock = self.settings.propagation == Propagation::Unblock;
+        for id in ready {
+            let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) else {
+                continue;
+            };
+            let parked = matches!(&task.status, TaskStatus::Custom(name) if name.eq_ignore_ascii_case("blocked"));
+            if let Some(initial) = initial.as_ref().filter(|initial| {
+                unblock && parked && self.settings.workflow.can_transition(&task.status, initial)
+            }) {
+                task.set_status(initial.clone());
+            }
+            self.events.push(TaskEvent::Unblocked {
+                task_id: id,
+                run_id: RunId::new(),
+                dependency: task_id,
+                status: task.status.clone(),
+            });
+        }
+    }
+
+    // Apply one change to every task matching the filter, returning the IDs changed. Either
+    // every matching task is updated or, if any update is rejected (e.g. by the workflow or
+    // the project rules), none is.
+    pub fn bulk_update(&mut self, filter: &TaskFilter, change: &TaskChange) -> Result<Vec<u32>> {
+        let ids: Vec<u32> = self
             .tasks
-            .iter_mut()
-            .find(|task| task.id == task_id)
-            .ok_or(TaskMasterError::TaskNotFound(task_id))?;
+            .iter()
+            .filter(|t| filter.matches(t))
+            .map(|t| t.id)
+            .collect();
+
+        let before = self.tasks.clone();
+        let raised = self.events.len();
+        for id in &ids {
+            if let Err(e) = self.apply_change(*id, change) {
+                self.tasks = before;
+                self.graph.clear();
+                self.events.truncate(raised);
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Task {}: {}; no tasks were changed",
+                    id, e
+                )));
+            }
+        }
+        Ok(ids)
+    }
 
-        task.title = new_title;
-        task.status = new_status;
-        task.priority = new_priority;
+    fn apply_change(&mut self, task_id: u32, change: &TaskChange) -> Result<()> {
+        if let Some(due) = change.due {
+            self.set_task_due(task_id, due)?;
+        }
+        if let Some(milestone) = change.milestone {
+            self.set_task_milestone(task_id, milestone)?;
+        }
+        for (name, value) in &change.fields {
+            self.set_task_field(task_id, name, value.as_deref())?;
+        }
+
+        if !change.add_tags.is_empty() || !change.remove_tags.is_empty() {
+            let task = self.get_task_mut(task_id)?;
+            let before = task.tags.join(", ");
+            task.tags
+                .retain(|t| !change.remove_tags.iter().any(|r| r.eq_ignore_ascii_case(t)));
+            for tag in &change.add_tags {
+                if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    task.tags.push(tag.clone());
+                }
+            }
+            let after = task.tags.join(", ");
+            task.record_edit("tags", &before, &after);
+            task.touch();
+        }
+
+        if change.status.is_some() || change.priority.is_some() {
+            let task = self.get_task(task_id)?;
+            let status = change.status.clone().unwrap_or_else(|| task.status.clone());
+            let priority = change
+                .priority
+                .clone()
+                .unwrap_or_else(|| task.priority.clone());
+            self.update_task(task_id, task.title.clone(), status, priority)?;
+        }
+        Ok(())
+    }
+
+    // Add the next occurrence of a recurring task, returning its ID
+    fn spawn_next_occurrence(&mut self, task_id: u32) -> Option<u32> {
+        let task = self.tasks.iter().find(|t| t.id == task_id)?;
+        task.recurrence.as_ref()?;
+
+        let id = self.next_task_id().ok()?;
+        let mut next = task.next_occurrence(id, self.settings.workflow.initial_state().ok()?);
+        if let Some(habit) = &mut next.habit {
+            habit.record_completion(Local::now().date_naive());
+        }
+        self.push_task(next);
+        Some(id)
+    }
+
+    // Add a task for each follow-up the handler put on `result` and each of `rules` matching
+    // the task and outcome, linked to the run. Returns each follow-up's title with the new
+    // task's ID or why it couldn't be added, such as a validation rule rejecting it; the
+    // others are added either way. A follow-up with the same title as an open task from an
+    // earlier run of the same task isn't added again, so a task that keeps failing doesn't
+    // pile up copies.
+    pub fn add_follow_ups(
+        &mut self,
+        result: &JobResult,
+        rules: &[FollowUpRule],
+    ) -> Vec<(String, Result<u32>)> {
+        let mut wanted: Vec<(String, Result<FollowUp>)> = result
+            .follow_ups
+            .iter()
+            .map(|follow_up| (follow_up.title.clone(), Ok(follow_up.clone())))
+            .collect();
+        if let Ok(task) = self.get_task(result.task_id) {
+            for rule in rules.iter().filter(|r| r.matches(task, &result.outcome)) {
+                wanted.push((rule.title.clone(), rule.follow_up(self, task)));
+            }
+        }
+
+        let mut outcomes = Vec::new();
+        for (title, follow_up) in wanted {
+            let added = follow_up.and_then(|follow_up| self.add_follow_up(result, &follow_up));
+            match added {
+                Ok(None) => {}
+                Ok(Some((title, id))) => outcomes.push((title, Ok(id))),
+                Err(e) => outcomes.push((title, Err(e))),
+            }
+        }
+        outcomes
+    }
+
+    // The new task's title and ID, or None for a repeat
+    fn add_follow_up(
+        &mut self,
+        result: &JobResult,
+        follow_up: &FollowUp,
+    ) -> Result<Option<(String, u32)>> {
+        let repeated = self.tasks.iter().any(|t| {
+            t.title == follow_up.title
+                && t.status != TaskStatus::Done
+                && t.follow_up_of
+                    .as_ref()
+                    .is_some_and(|origin| origin.task_id == result.task_id)
+        });
+        if repeated {
+            return Ok(None);
+        }
+
+        let mut task = self.new_task(self.next_task_id()?, follow_up.title.clone())?;
+        if let Some(priority) = &follow_up.priority {
+            task.priority = priority.clone();
+        }
+        for tag in &follow_up.tags {
+            if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                task.tags.push(tag.clone());
+            }
+        }
+        task.follow_up_of = Some(FollowUpOrigin {
+            task_id: result.task_id,
+            run_id: result.run_id.clone(),
+        });
+        let id = task.id;
+        self.add_task(task)?;
+        Ok(Some((follow_up.title.clone(), id)))
+    }
+
+    pub fn set_task_due(&mut self, task_id: u32, due: Option<DateTime<Utc>>) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        let format = |due: Option<DateTime<Utc>>| {
+            due.as_ref()
+                .map_or_else(|| "none".to_string(), dates::format_local)
+        };
+        task.record_edit("due", &format(task.due), &format(due));
+        task.due = due;
+        // A new due date gets a fresh set of reminders
+        task.last_reminded = None;
+        task.touch();
+        Ok(())
+    }
+
+    pub fn set_task_planned_start(
+        &mut self,
+        task_id: u32,
+        start: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        let format = |start: Option<DateTime<Utc>>| {
+            start
+                .as_ref()
+                .map_or_else(|| "none".to_string(), dates::format_local)
+        };
+        task.record_edit("planned start", &format(task.planned_start), &format(start));
+        task.planned_start = start;
+        task.touch();
+        Ok(())
+    }
+
+    // Set a custom field after checking it against the project's schema, or clear it with None
+    pub fn set_task_field(&mut self, task_id: u32, name: &str, value: Option<&str>) -> Result<()> {
+        let (name, value) = match value {
+            Some(value) => {
+                let (name, value) = fields::normalize_field(&self.settings.fields, name, value)?;
+                (name, Some(value))
+            }
+            None => (name.to_string(), None),
+        };
+
+        let task = self.get_task_mut(task_id)?;
+        let before = task.custom_fields.get(&name).cloned().unwrap_or_default();
+        match &value {
+            Some(value) => {
+                task.custom_fields.insert(name.clone(), value.clone());
+            }
+            None => {
+                task.custom_fields.remove(&name);
+            }
+        }
+        task.record_edit(
+            &format!("field {}", name),
+            &before,
+            value.as_deref().unwrap_or_default(),
+        );
+        task.touch();
+        Ok(())
+    }
+
+    pub fn set_task_reminders(
+        &mut self,
+        task_id: u32,
+        reminders: Option<Vec<ReminderRule>>,
+    ) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        task.reminders = reminders;
+        task.touch();
+        Ok(())
+    }
+
+    pub fn set_task_blocked(&mut self, task_id: u32, reason: Option<String>) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        task.blocked_reason = reason;
+        task.touch();
+        Ok(())
+    }
+
+    pub fn set_task_recurrence(
+        &mut self,
+        task_id: u32,
+        recurrence: Option<RecurrencePattern>,
+    ) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        // Habits only make sense for tasks that come round again
+        if recurrence.is_none() {
+            task.habit = None;
+        }
+        task.recurrence = recurrence;
+        task.touch();
+        Ok(())
+    }
+
+    // Track a recurring task as a habit whose streaks survive `skips` missed periods, or
+    // stop tracking it with None. Changing the allowance keeps the record so far.
+    pub fn set_task_habit(&mut self, task_id: u32, skips: Option<u32>) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        match skips {
+            Some(_) if task.recurrence.is_none() => {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Task {} does not recur; use set-recurrence before making it a habit",
+                    task_id
+                )));
+            }
+            Some(skips) => match &mut task.habit {
+                Some(habit) => habit.skips = skips,
+                None => task.habit = Some(Habit::new(Local::now().date_naive(), skips)),
+            },
+            None => task.habit = None,
+        }
+        task.touch();
         Ok(())
     }
 
@@ -54,6 +1113,247 @@ impl Project {
             .ok_or(TaskMasterError::TaskNotFound(task_id))
     }
 
+    // Unfinished tasks that are not blocked by dependencies or a manual block
+    pub fn ready_tasks(&self) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|t| t.status != TaskStatus::Done && !t.is_blocked(&self.tasks))
+            .collect()
+    }
+
+    // Tasks ordered from most to least urgent priority, keeping insertion order for ties
+    pub fn tasks_by_priority(&self) -> Vec<&Task> {
+        let scale = &self.settings.priority_scale;
+        let mut tasks: Vec<&Task> = self.tasks.iter().collect();
+        tasks.sort_by(|a, b| scale.compare(&a.priority, &b.priority));
+        tasks
+    }
+
+    // Tasks ordered from most to least urgent by computed urgency score
+    pub fn tasks_by_urgency(&self, coefficients: &UrgencyCoefficients) -> Vec<(&Task, f64)> {
+        let now = Utc::now();
+        let mut scored: Vec<(&Task, f64)> = self
+            .tasks
+            .iter()
+            .map(|task| {
+                let score =
+                    coefficients.score(task, &self.tasks, &self.settings.priority_scale, now);
+                (task, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored
+    }
+
+    // Counts by status and priority, overdue tasks, completion and average age as of now
+    pub fn stats(&self) -> ProjectStats {
+        ProjectStats::for_project(self, Utc::now())
+    }
+
+    pub fn priority_label(&self, task: &Task) -> String {
+        self.settings.priority_scale.label(&task.priority)
+    }
+
+    // A task ID as shown to users, with the project's prefix
+    pub fn task_ref(&self, task_id: u32) -> String {
+        format!("{}{}", self.settings.id_prefix, task_id)
+    }
+
+    // A new task following the project's conventions: the workflow's initial state,
+    // the default priority and the default tags
+    pub fn new_task(&self, id: u32, title: String) -> Result<Task> {
+        let priority = self
+            .settings
+            .default_priority
+            .clone()
+            .unwrap_or(TaskPriority::Medium);
+        let mut task = Task::new(id, title, self.settings.workflow.initial_state()?, priority);
+        task.tags = self.settings.default_tags.clone();
+        Ok(task)
+    }
+
+    // The caller may change the task's dependencies, so the graph is rebuilt on next use
+    pub fn get_task_mut(&mut self, task_id: u32) -> Result<&mut Task> {
+        self.graph.clear();
+        self.tasks
+            .iter_mut()
+            .find(|task| task.id == task_id)
+            .ok_or(TaskMasterError::TaskNotFound(task_id))
+    }
+
+    pub fn attach_to_task(&mut self, task_id: u32, attachment: Attachment) -> Result<()> {
+        let task = self.get_task_mut(task_id)?;
+        if task.attachments.iter().any(|a| a.name == attachment.name) {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Task {} already has an attachment named '{}'",
+                task_id, attachment.name
+            )));
+        }
+        task.attachments.push(attachment);
+        task.touch();
+        Ok(())
+    }
+
+    pub fn detach_from_task(&mut self, task_id: u32, name: &str) -> Result<Attachment> {
+        let task = self.get_task_mut(task_id)?;
+        let position = task
+            .attachments
+            .iter()
+            .position(|a| a.name == name)
+            .ok_or_else(|| {
+                TaskMasterError::InvalidOperation(format!(
+                    "Task {} has no attachment named '{}'",
+                    task_id, name
+                ))
+            })?;
+        task.touch();
+        Ok(task.attachments.remove(position))
+    }
+
+    // Add a milestone, returning its ID. Names are unique within a project, ignoring case.
+    pub fn add_milestone(&mut self, name: &str, due: Option<DateTime<Utc>>) -> Result<u32> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TaskMasterError::InvalidOperation(
+                "A milestone needs a name".to_string(),
+            ));
+        }
+        if self
+            .milestones
+            .iter()
+            .any(|m| m.name.eq_ignore_ascii_case(name))
+        {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Milestone '{}' already exists",
+                name
+            )));
+        }
+
+        let id = ids::next_id(self.milestones.iter().map(|m| m.id))?;
+        self.milestones.push(Milestone {
+            id,
+            name: name.to_string(),
+            due,
+        });
+        self.sort_milestones();
+        Ok(id)
+    }
+
+    fn sort_milestones(&mut self) {
+        self.milestones
+            .sort_by_key(|m| (m.due.is_none(), m.due, m.id));
+    }
+
+    // Look a milestone up by ID or, ignoring case, by name
+    pub fn find_milestone(&self, key: &str) -> Result<&Milestone> {
+        let key = key.trim();
+        self.milestones
+            .iter()
+            .find(|m| key.parse::<u32>() == Ok(m.id) || m.name.eq_ignore_ascii_case(key))
+            .ok_or_else(|| TaskMasterError::MilestoneNotFound(key.to_string()))
+    }
+
+    pub fn milestone_name(&self, id: u32) -> Option<&str> {
+        self.milestones
+            .iter()
+            .find(|m| m.id == id)
+            .map(|m| m.name.as_str())
+    }
+
+    // Remove a milestone; its tasks stay in the project without one
+    pub fn remove_milestone(&mut self, id: u32) -> Result<Milestone> {
+        let index = self
+            .milestones
+            .iter()
+            .position(|m| m.id == id)
+            .ok_or_else(|| TaskMasterError::MilestoneNotFound(id.to_string()))?;
+        for task in self.tasks.iter_mut().filter(|t| t.milestone == Some(id)) {
+            task.milestone = None;
+            task.touch();
+        }
+        Ok(self.milestones.remove(index))
+    }
+
+    // Assign a task to a milestone, or take it out of its milestone with None
+    pub fn set_task_milestone(&mut self, task_id: u32, milestone: Option<u32>) -> Result<()> {
+        let name = |id: Option<u32>| {
+            id.and_then(|id| self.milestone_name(id))
+                .unwrap_or("none")
+                .to_string()
+        };
+        if let Some(id) = milestone {
+            if self.milestone_name(id).is_none() {
+                return Err(TaskMasterError::MilestoneNotFound(id.to_string()));
+            }
+        }
+        let before = name(self.get_task(task_id)?.milestone);
+        let after = name(milestone);
+
+        let task = self.get_task_mut(task_id)?;
+        task.record_edit("milestone", &before, &after);
+        task.milestone = milestone;
+        task.touch();
+        Ok(())
+    }
+
+    // Task counts for each milestone, in milestone order
+    pub fn milestone_progress(&self) -> Vec<MilestoneProgress> {
+        self.milestones
+            .iter()
+            .map(|milestone| {
+                let tasks: Vec<&Task> = self
+                    .tasks
+                    .iter()
+                    .filter(|t| t.milestone == Some(milestone.id))
+                    .collect();
+                MilestoneProgress {
+                    id: milestone.id,
+                    name: milestone.name.clone(),
+                    due: milestone.due,
+                    total: tasks.len(),
+                    done: tasks
+                        .iter()
+                        .filter(|t| t.status == TaskStatus::Done)
+                        .count(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn set_task_estimate(&mut self, task_id: u32, hours: f64) -> Result<()> {
+        if !hours.is_finite() || hours <= 0.0 {
+            return Err(TaskMasterError::InvalidOperation(
+                "Estimates must be a positive number of hours".to_string(),
+            ));
+        }
+
+        let task = self.get_task_mut(task_id)?;
+        task.estimate_hours = Some(hours);
+        task.touch();
+        Ok(())
+    }
+
+    // Log time worked on `date`, optionally by a named user, for timesheets
+    pub fn log_task_time(
+        &mut self,
+        task_id: u32,
+        hours: f64,
+        date: NaiveDate,
+        user: Option<String>,
+    ) -> Result<()> {
+        if !hours.is_finite() || hours <= 0.0 {
+            return Err(TaskMasterError::InvalidOperation(
+                "Logged time must be a positive number of hours".to_string(),
+            ));
+        }
+
+        let task = self.get_task_mut(task_id)?;
+        task.logged_hours += hours;
+        task.time_log.push(TimeEntry { date, hours, user });
+        task.touch();
+        Ok(())
+    }
+
     pub fn display(&self) {
         println!("Project ID: {}, Name: {}", self.id, self.name);
         println!("Tasks:");
@@ -64,6 +1364,12 @@ impl Project {
 
     // Task dependency methods
     pub fn add_task_dependency(&mut self, task_id: u32, dependency_id: u32) -> Result<()> {
+        self.add_typed_dependency(task_id, Dependency::new(dependency_id))
+    }
+
+    // Add a dependency of any type, or change the type and lag of an existing one
+    pub fn add_typed_dependency(&mut self, task_id: u32, dependency: Dependency) -> Result<()> {
+        let dependency_id = dependency.task;
         // Check if both tasks exist
         let task_exists = self.tasks.iter().any(|t| t.id == task_id);
         let dependency_exists = self.tasks.iter().any(|t| t.id == dependency_id);
@@ -76,36 +1382,32 @@ impl Project {
             return Err(TaskMasterError::TaskNotFound(dependency_id));
         }
 
-        // Use a DependencyGraph to manage dependencies
-        let mut graph = DependencyGraph::new();
-
-        // Add existing dependencies
-        for task in &self.tasks {
-            if let Some(deps) = task.dependencies.as_ref() {
-                for &dep_id in deps {
-                    graph.add_dependency(task.id, dep_id)?;
-                }
-            }
-        }
-
-        // Add the new dependency
-        graph.add_dependency(task_id, dependency_id)?;
+        // Add the new dependency to the project's graph, which checks for cycles
+        let deps = self.graph.with(&self.tasks, |graph| {
+            graph.add_dependency(task_id, dependency)?;
+            Ok(graph.get_edges(task_id))
+        })?;
 
         // Update the task's dependencies
-        for task in &mut self.tasks {
-            if task.id == task_id {
-                let deps = graph.get_dependencies(task_id);
-                if deps.is_empty() {
-                    task.dependencies = None;
-                } else {
-                    task.dependencies = Some(deps.into_iter().collect());
-                }
-            }
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
+            let before = format_dependencies(&task.dependencies);
+            task.dependencies = Some(deps).filter(|deps| !deps.is_empty());
+            task.record_edit(
+                "dependencies",
+                &before,
+                &format_dependencies(&task.dependencies),
+            );
+            task.touch();
         }
 
         Ok(())
     }
 
+    // Drop the cached dependency graph after changing `tasks` directly
+    pub fn invalidate_dependency_graph(&self) {
+        self.graph.clear();
+    }
+
     pub fn remove_task_dependency(&mut self, task_id: u32, dependency_id: u32) -> Result<()> {
         // Check if both tasks exist
         let task_exists = self.tasks.iter().any(|t| t.id == task_id);
@@ -114,15 +1416,27 @@ impl Project {
             return Err(TaskMasterError::TaskNotFound(task_id));
         }
 
+        self.graph.update(|graph| {
+            graph.remove_dependency(task_id, dependency_id);
+            Ok(())
+        });
+
         // Update the task's dependencies
         for task in &mut self.tasks {
             if task.id == task_id {
+                let before = format_dependencies(&task.dependencies);
                 if let Some(deps) = task.dependencies.as_mut() {
-                    deps.retain(|&id| id != dependency_id);
+                    deps.retain(|dep| dep.task != dependency_id);
                     if deps.is_empty() {
                         task.dependencies = None;
                     }
                 }
+                task.record_edit(
+                    "dependencies",
+                    &before,
+                    &format_dependencies(&task.dependencies),
+                );
+                task.touch();
             }
         }
 
@@ -130,19 +1444,10 @@ impl Project {
     }
 
     pub fn get_task_execution_order(&self) -> Result<Vec<&Task>> {
-        let mut graph = DependencyGraph::new();
-
-        // Add existing dependencies
-        for task in &self.tasks {
-            if let Some(deps) = task.dependencies.as_ref() {
-                for &dep_id in deps {
-                    graph.add_dependency(task.id, dep_id)?;
-                }
-            }
-        }
-
         // Get the execution order as task IDs
-        let ordered_ids = graph.get_execution_order(&self.tasks)?;
+        let ordered_ids = self
+            .graph
+            .with(&self.tasks, |graph| graph.get_execution_order(&self.tasks))?;
 
         // Convert IDs to task references
         let mut ordered_tasks = Vec::new();
@@ -154,4 +1459,144 @@ impl Project {
 
         Ok(ordered_tasks)
     }
+
+    // The open tasks in waves that can run concurrently, each after the one before
+    pub fn execution_levels(&self) -> Result<Vec<Vec<u32>>> {
+        let open: Vec<Task> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status != TaskStatus::Done)
+            .cloned()
+            .collect();
+        self.graph
+            .with(&self.tasks, |graph| Ok(graph.execution_levels(&open)))
+    }
+
+    // Check the invariants the rest of the code relies on: unique task IDs, a usable workflow,
+    // statuses from that workflow, and dependencies on existing tasks without cycles
+    pub fn validate(&self) -> Result<()> {
+        let workflow = &self.settings.workflow;
+        Workflow::new(workflow.states.clone(), workflow.transitions.clone())?;
+
+        let mut ids = HashSet::new();
+        for task in &self.tasks {
+            if !ids.insert(task.id) {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Duplicate task ID: {}",
+                    task.id
+                )));
+            }
+            if task.title.trim().is_empty() {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Task {} has an empty title",
+                    task.id
+                )));
+            }
+            if !workflow.contains(&task.status) {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Task {} has status '{}', which is not in the project's workflow",
+                    task.id, task.status
+                )));
+            }
+        }
+
+        let mut graph = DependencyGraph::new();
+        for task in &self.tasks {
+            for dep in task.dependencies.iter().flatten() {
+                let dep_id = dep.task;
+                if !ids.contains(&dep_id) {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Task {} depends on missing task {}",
+                        task.id, dep_id
+                    )));
+                }
+                graph.add_dependency(task.id, *dep).map_err(|e| match e {
+                    TaskMasterError::InvalidOperation(msg) => TaskMasterError::InvalidOperation(
+                        format!("Task {} depends on {}: {}", task.id, dep_id, msg),
+                    ),
+                    other => other,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // The unfinished tasks that closing `task_id` would complete: the task itself plus every
+    // upstream dependency that no unfinished task outside the chain needs, in execution order
+    pub fn completion_chain(&self, task_id: u32) -> Result<Vec<&Task>> {
+        self.get_task(task_id)?;
+
+        let mut chain = HashSet::from([task_id]);
+        loop {
+            let candidates: Vec<u32> = self
+                .tasks
+                .iter()
+                .filter(|t| chain.contains(&t.id))
+                .flat_map(|t| t.dependency_ids())
+                .filter(|dep| !chain.contains(dep))
+                .collect();
+
+            let mut grew = false;
+            for dep in candidates {
+                let exclusive = self
+                    .tasks
+                    .iter()
+                    .filter(|t| t.depends_on(dep))
+                    .all(|t| chain.contains(&t.id) || t.status == TaskStatus::Done);
+                if exclusive && chain.insert(dep) {
+                    grew = true;
+                }
+            }
+
+            if !grew {
+                break;
+            }
+        }
+
+        Ok(self
+            .get_task_execution_order()?
+            .into_iter()
+            .filter(|t| chain.contains(&t.id) && t.status != TaskStatus::Done)
+            .collect())
+    }
+
+    // Mark the whole completion chain Done. Every transition is checked against the
+    // workflow first, so either all tasks close or none do.
+    pub fn close_chain(&mut self, task_id: u32) -> Result<Vec<u32>> {
+        let ids: Vec<u32> = self
+            .completion_chain(task_id)?
+            .iter()
+            .map(|t| t.id)
+            .collect();
+
+        for &id in &ids {
+            let task = self.get_task(id)?;
+            self.settings
+                .workflow
+                .validate_transition(&task.status, &TaskStatus::Done)?;
+        }
+
+        for &id in &ids {
+            let task = self.get_task_mut(id)?;
+            task.set_status(TaskStatus::Done);
+            self.spawn_next_occurrence(id);
+        }
+        // Once the whole chain is closed, so only tasks left open are announced
+        for &id in &ids {
+            self.propagate_completion(id);
+        }
+
+        Ok(ids)
+    }
+}
+
+fn format_dependencies(dependencies: &Option<Vec<Dependency>>) -> String {
+    match dependencies {
+        Some(deps) if !deps.is_empty() => {
+            let ids: Vec<String> = deps.iter().map(|dep| dep.to_string()).collect();
+            ids.join(", ")
+        }
+        _ => "none".to_string(),
+    }
 }
@@ -0,0 +1,225 @@
+use std::io::{self, IsTerminal, Write};
+
+use clap::{Arg, Command};
+use crossterm::cursor::MoveUp;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+
+use crate::completions::{self, Slot};
+use crate::error::{Result, TaskMasterError};
+
+// When a command is typed without some of its required arguments, ask for them instead of
+// failing: project and task IDs are picked from lists read from the data directory, values
+// with a fixed set of choices from those choices, and anything else is typed in. Only done
+// when both ends are a terminal and `--no-prompt` isn't given, so scripts still get clap's
+// error straight away.
+
+// Rows of a picker shown at once
+const PICKER_ROWS: usize = 10;
+
+pub fn enabled(args: &[String]) -> bool {
+    io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && !args
+            .iter()
+            .take_while(|a| *a != "--")
+            .any(|a| a == "--no-prompt")
+}
+
+// `args` (the whole command line, program name first) with the missing required arguments
+// asked for and added: positionals at the end, in order, and options as `--name=value`
+pub fn fill_missing(mut root: Command, args: &[String]) -> Result<Vec<String>> {
+    root.build();
+    let words = args.get(1..).unwrap_or_default();
+    let line = completions::walk(&root, words)
+        .ok_or_else(|| TaskMasterError::InvalidOperation("Unknown command".to_string()))?;
+    let command = line.command;
+    if line.pending.is_some() {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "'{}' needs a value",
+            words.last().map(String::as_str).unwrap_or_default()
+        )));
+    }
+
+    let mut filled = args.to_vec();
+    // An argument after `--` is always a positional, so add the options before any
+    let mut options_at = filled
+        .iter()
+        .position(|a| a == "--")
+        .unwrap_or(filled.len());
+    if !line.only_positionals {
+        for arg in command
+            .get_arguments()
+            .filter(|a| !a.is_positional() && a.is_required_set())
+        {
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            let given = words
+                .iter()
+                .any(|w| *w == format!("--{}", long) || w.starts_with(&format!("--{}=", long)));
+            if given {
+                continue;
+            }
+            let value = ask(arg)?;
+            filled.insert(options_at, format!("--{}={}", long, value));
+            options_at += 1;
+        }
+    }
+
+    let args: Vec<&Arg> = command.get_positionals().collect();
+    let mut positionals: Vec<String> = line.positionals.iter().map(|p| p.to_string()).collect();
+    for index in positionals.len()..args.len() {
+        let arg = args[index];
+        // An optional positional can come before a required one, as create-project's ID does
+        if !arg.is_required_set() {
+            continue;
+        }
+        let value = match completions::slot(command, &args, index) {
+            Slot::Project => pick(
+                label(arg),
+                completions::project_ids(&line.data_dir),
+                "No projects yet; create one with create-project",
+            )?,
+            Slot::Task(at) => {
+                let project = positionals
+                    .get(at)
+                    .and_then(|p| p.parse::<u32>().ok())
+                    .unwrap_or_default();
+                pick(
+                    label(arg),
+                    completions::task_ids(&line.data_dir, project),
+                    &format!("Project {} has no tasks", project),
+                )?
+            }
+            Slot::Other => ask(arg)?,
+        };
+        positionals.push(value.clone());
+        filled.push(value);
+    }
+    Ok(filled)
+}
+
+// A value for an argument that isn't an ID: one of its choices, or typed in
+fn ask(arg: &Arg) -> Result<String> {
+    let choices = completions::possible_values(arg);
+    if !choices.is_empty() {
+        return pick(label(arg), choices, "");
+    }
+    loop {
+        print!("{}: ", label(arg));
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Err(cancelled());
+        }
+        let answer = answer.trim();
+        if !answer.is_empty() {
+            return Ok(answer.to_string());
+        }
+    }
+}
+
+// What to call an argument when asking for it: its help text, or else its name
+fn label(arg: &Arg) -> String {
+    match arg.get_help() {
+        Some(help) => help.to_string(),
+        None => arg.get_id().replace('-', " "),
+    }
+}
+
+// Choose one of `choices` ((value, description) pairs) with the arrow keys; typing narrows
+// the list to the entries containing what was typed
+fn pick(label: String, choices: Vec<(String, String)>, empty: &str) -> Result<String> {
+    if choices.is_empty() {
+        return Err(TaskMasterError::InvalidOperation(empty.to_string()));
+    }
+    let width = choices.iter().map(|(v, _)| v.len()).max().unwrap_or(0);
+    let lines: Vec<String> = choices
+        .iter()
+        .map(|(value, description)| format!("{:<width$}  {}", value, description))
+        .collect();
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    let mut drawn = 0;
+    // Raw mode has to end however choosing does
+    let result = choose(&label, &lines, &mut stdout, &mut drawn);
+    let cleared = clear(&mut stdout, drawn);
+    disable_raw_mode()?;
+    cleared?;
+
+    let index = result?;
+    println!("? {}: {}", label, lines[index].trim_end());
+    Ok(choices[index].0.clone())
+}
+
+// The index of the line chosen; `drawn` keeps count of the lines on screen
+fn choose(
+    label: &str,
+    lines: &[String],
+    stdout: &mut io::Stdout,
+    drawn: &mut usize,
+) -> Result<usize> {
+    let mut filter = String::new();
+    let mut selected = 0;
+    loop {
+        let shown: Vec<usize> = (0..lines.len())
+            .filter(|i| lines[*i].to_lowercase().contains(&filter.to_lowercase()))
+            .collect();
+        selected = selected.min(shown.len().saturating_sub(1));
+        // Keep the selection inside the window
+        let first = selected.saturating_sub(PICKER_ROWS - 1);
+
+        clear(stdout, *drawn)?;
+        *drawn = 0;
+        write!(stdout, "? {} (type to filter): {}\r\n", label, filter)?;
+        for (row, i) in shown.iter().enumerate().skip(first).take(PICKER_ROWS) {
+            let marker = if row == selected { ">" } else { " " };
+            write!(stdout, "{} {}\r\n", marker, lines[*i])?;
+        }
+        if shown.is_empty() {
+            write!(stdout, "  (no matches)\r\n")?;
+        }
+        stdout.flush()?;
+        *drawn = 1 + shown.len().clamp(1, PICKER_ROWS);
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Enter if !shown.is_empty() => return Ok(shown[selected]),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Err(cancelled())
+                }
+                KeyCode::Esc => return Err(cancelled()),
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Down => selected += 1,
+                KeyCode::Char(c) => {
+                    filter.push(c);
+                    selected = 0;
+                }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    selected = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Wipe the last `lines` lines written, leaving the cursor where they started
+fn clear(stdout: &mut io::Stdout, lines: usize) -> Result<()> {
+    if lines > 0 {
+        execute!(
+            stdout,
+            MoveUp(lines as u16),
+            Clear(ClearType::FromCursorDown)
+        )?;
+    }
+    Ok(())
+}
+
+fn cancelled() -> TaskMasterError {
+    TaskMasterError::InvalidOperation("Cancelled".to_string())
+}
@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// The keys a search term can use, for errors and help
+pub const KEYS: &str = "status, priority, tag, field, project, milestone, due, due.before, \
+                        due.after, created.before, created.after, updated.before, updated.after";
+
+// A parsed search such as `status:todo priority:high due.before:2024-07-01 tag:backend login`.
+// Every term must match; a leading '-' negates a term, and anything without a known key
+// (or in double quotes) is looked for in the title.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    // A workflow state, or "open" (not done) or "blocked"
+    Status(String),
+    Priority(String),
+    Tag(String),
+    Field(String, String),
+    // A project ID, or part of its name
+    Project(String),
+    Milestone(String),
+    Due(DateFilter),
+    Created(DateFilter),
+    Updated(DateFilter),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DateFilter {
+    Before(DateTime<Utc>),
+    After(DateTime<Utc>),
+    Set,
+    Unset,
+    // Before now; due dates only
+    Overdue,
+}
+
+impl DateFilter {
+    fn matches(&self, date: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        match self {
+            DateFilter::Before(limit) => date.is_some_and(|d| d < *limit),
+            DateFilter::After(limit) => date.is_some_and(|d| d > *limit),
+            DateFilter::Set => date.is_some(),
+            DateFilter::Unset => date.is_none(),
+            DateFilter::Overdue => date.is_some_and(|d| d < now),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        for (token, quoted) in tokenize(input)? {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                _ => (false, token),
+            };
+            let filter = if quoted {
+                Filter::Text(token)
+            } else {
+                parse_filter(&token)?
+            };
+            terms.push(Term { negated, filter });
+        }
+        Ok(Query { terms })
+    }
+
+    pub fn matches(&self, project: &Project, task: &Task, now: DateTime<Utc>) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(project, task, now) != term.negated)
+    }
+}
+
+impl Filter {
+    fn matches(&self, project: &Project, task: &Task, now: DateTime<Utc>) -> bool {
+        match self {
+            Filter::Status(status) => match status.to_lowercase().as_str() {
+                "open" => task.status != TaskStatus::Done,
+                "blocked" => task.is_blocked(&project.tasks),
+                _ => status
+                    .parse::<TaskStatus>()
+                    .is_ok_and(|s| s.to_string().eq_ignore_ascii_case(&task.status.to_string())),
+            },
+            Filter::Priority(priority) => {
+                project.priority_label(task).eq_ignore_ascii_case(priority)
+                    || task.priority.to_string().eq_ignore_ascii_case(priority)
+            }
+            Filter::Tag(tag) => task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Filter::Field(name, value) => task.field_matches(name, value),
+            Filter::Project(project_ref) => match project_ref.parse::<u32>() {
+                Ok(id) => project.id == id,
+                Err(_) => contains(&project.name, project_ref),
+            },
+            Filter::Milestone(name) => task
+                .milestone
+                .and_then(|id| project.milestone_name(id))
+                .is_some_and(|milestone| contains(milestone, name)),
+            Filter::Due(filter) => filter.matches(task.due, now),
+            Filter::Created(filter) => filter.matches(Some(task.created_at), now),
+            Filter::Updated(filter) => filter.matches(Some(task.updated_at), now),
+            Filter::Text(text) => contains(&task.title, text),
+        }
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+fn parse_filter(token: &str) -> Result<Filter> {
+    let Some((key, value)) = token.split_once(':') else {
+        return Ok(Filter::Text(token.to_string()));
+    };
+    if value.is_empty() {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Search term '{}' needs a value after the ':'",
+            token
+        )));
+    }
+    let value = value.to_string();
+
+    let filter = match key.to_lowercase().as_str() {
+        "status" => Filter::Status(value),
+        "priority" => Filter::Priority(value),
+        "tag" => Filter::Tag(value),
+        "field" => {
+            let Some((name, field_value)) = value.split_once('=') else {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Search term '{}' should look like field:name=value",
+                    token
+                )));
+            };
+            Filter::Field(name.to_string(), field_value.to_string())
+        }
+        "project" => Filter::Project(value),
+        "milestone" => Filter::Milestone(value),
+        "due" => Filter::Due(match value.to_lowercase().as_str() {
+            "none" => DateFilter::Unset,
+            "any" => DateFilter::Set,
+            "overdue" => DateFilter::Overdue,
+            _ => {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "Search term '{}': use due:none, due:any, due:overdue, due.before:<date> \
+                     or due.after:<date>",
+                    token
+                )))
+            }
+        }),
+        "due.before" => Filter::Due(DateFilter::Before(dates::parse_datetime(&value)?)),
+        "due.after" => Filter::Due(DateFilter::After(dates::parse_datetime(&value)?)),
+        "created.before" => Filter::Created(DateFilter::Before(dates::parse_datetime(&value)?)),
+        "created.after" => Filter::Created(DateFilter::After(dates::parse_datetime(&value)?)),
+        "updated.before" => Filter::Updated(DateFilter::Before(dates::parse_datetime(&value)?)),
+        "updated.after" => Filter::Updated(DateFilter::After(dates::parse_datetime(&value)?)),
+        _ => {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Unknown search key '{}'; use {}, or put the text in double quotes",
+                key, KEYS
+            )))
+        }
+    };
+    Ok(filter)
+}
+
+// Split on whitespace, keeping double-quoted stretches together. Each token comes with
+// whether it was entirely quoted; a quote after a key (tag:"two words") only groups the value.
+fn tokenize(input: &str) -> Result<Vec<(String, bool)>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut started = false;
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if !in_quotes && !started {
+                    quoted = true;
+                }
+                in_quotes = !in_quotes;
+                started = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if started {
+                    tokens.push((std::mem::take(&mut current), quoted));
+                }
+                started = false;
+                quoted = false;
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err(TaskMasterError::InvalidOperation(
+            "Unclosed quote in search".to_string(),
+        ));
+    }
+    if started {
+        tokens.push((current, quoted));
+    }
+    Ok(tokens.into_iter().filter(|(t, _)| !t.is_empty()).collect())
+}
+
+// The tasks matching `query` across `projects`, project by project
+pub fn search<'a>(
+    projects: &'a [Project],
+    query: &Query,
+    now: DateTime<Utc>,
+) -> Vec<(&'a Project, &'a Task)> {
+    projects
+        .iter()
+        .flat_map(|project| {
+            project
+                .tasks
+                .iter()
+                .filter(move |task| query.matches(project, task, now))
+                .map(move |task| (project, task))
+        })
+        .collect()
+}
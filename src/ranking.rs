@@ -0,0 +1,99 @@
+// Fractional ranks for the manual task order. A rank is a string of base-62 digits read as
+// a fraction after the point, so there is always room for another rank between two others
+// and moving a task only rewrites that task's rank. Ranks never end in the zero digit,
+// which keeps every fraction to a single spelling.
+
+// In ASCII order, so comparing ranks as strings compares the fractions
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: usize = 62;
+
+// Ranks squeezed in repeatedly at one spot get longer; past this the order is re-spread
+pub const MAX_LEN: usize = 16;
+
+fn digit(c: u8) -> usize {
+    DIGITS.iter().position(|d| *d == c).unwrap_or(0)
+}
+
+// Whether `rank` is a non-empty run of rank digits without a trailing zero
+pub fn is_valid(rank: &str) -> bool {
+    !rank.is_empty() && !rank.ends_with('0') && rank.bytes().all(|c| DIGITS.contains(&c))
+}
+
+// A rank strictly between `before` and `after`; None stands for the start or end of the
+// order. The two must be distinct valid ranks in order.
+pub fn between(before: Option<&str>, after: Option<&str>) -> String {
+    match (before, after) {
+        (Some(before), None) => next(before),
+        (before, after) => midpoint(before.unwrap_or("").as_bytes(), after.map(str::as_bytes)),
+    }
+}
+
+// The shortest rank after `rank`, so appending to a long list grows ranks slowly: bump the
+// last digit that can be bumped and drop what follows it
+fn next(rank: &str) -> String {
+    let bytes = rank.as_bytes();
+    match bytes.iter().rposition(|c| digit(*c) < BASE - 1) {
+        Some(index) => {
+            let mut out = bytes[..index].to_vec();
+            out.push(DIGITS[digit(bytes[index]) + 1]);
+            String::from_utf8_lossy(&out).to_string()
+        }
+        None => midpoint(bytes, None),
+    }
+}
+
+// Halfway between two fractions, as digits; `high` of None is 1
+fn midpoint(low: &[u8], high: Option<&[u8]>) -> String {
+    if let Some(high) = high {
+        // Keep the shared leading digits and split the rest
+        let shared = high
+            .iter()
+            .enumerate()
+            .take_while(|(i, c)| low.get(*i).copied().unwrap_or(DIGITS[0]) == **c)
+            .count();
+        if shared > 0 {
+            let rest = midpoint(low.get(shared..).unwrap_or(&[]), Some(&high[shared..]));
+            return format!("{}{}", String::from_utf8_lossy(&high[..shared]), rest);
+        }
+    }
+
+    let low_digit = low.first().map_or(0, |c| digit(*c));
+    let high_digit = high.and_then(|h| h.first()).map_or(BASE, |c| digit(*c));
+    if high_digit - low_digit > 1 {
+        return (DIGITS[(low_digit + high_digit).div_ceil(2)] as char).to_string();
+    }
+    match high {
+        // The high digit alone is already between the two
+        Some(high) if high.len() > 1 => (high[0] as char).to_string(),
+        _ => format!(
+            "{}{}",
+            DIGITS[low_digit] as char,
+            midpoint(low.get(1..).unwrap_or(&[]), None)
+        ),
+    }
+}
+
+// `count` ranks in order, spread evenly and all of the same short length
+pub fn spread(count: usize) -> Vec<String> {
+    let mut width = 1;
+    let mut slots = BASE as u128;
+    while slots <= count as u128 {
+        width += 1;
+        slots *= BASE as u128;
+    }
+
+    (1..=count as u128)
+        .map(|i| {
+            let mut value = i * slots / (count as u128 + 1);
+            let mut digits = vec![DIGITS[0]; width];
+            for place in digits.iter_mut().rev() {
+                *place = DIGITS[(value % BASE as u128) as usize];
+                value /= BASE as u128;
+            }
+            while digits.last() == Some(&DIGITS[0]) {
+                digits.pop();
+            }
+            String::from_utf8_lossy(&digits).to_string()
+        })
+        .collect()
+}
@@ -0,0 +1,257 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+use crate::error::Result;
+use crate::project::Project;
+use crate::schedule;
+
+// Which ready task starts first when there are more of them than free workers.
+// `TaskExecutor::execute_project` puts each open task in a `ReadyQueue` once everything it
+// depends on has finished, the policy orders the queue, and free workers take tasks from
+// the front.
+pub trait ReadyQueuePolicy: Send + Sync + Debug {
+    fn name(&self) -> &str;
+
+    // Sort `ready` (task IDs) into the order the tasks should start
+    fn order(&self, project: &Project, ready: &mut [u32]);
+}
+
+// Most urgent priority first, ties in dependency order
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityFirst;
+
+impl ReadyQueuePolicy for PriorityFirst {
+    fn name(&self) -> &str {
+        "priority first"
+    }
+
+    fn order(&self, project: &Project, ready: &mut [u32]) {
+        let scale = &project.settings.priority_scale;
+        ready.sort_by(|a, b| match (project.get_task(*a), project.get_task(*b)) {
+            (Ok(a), Ok(b)) => scale.compare(&a.priority, &b.priority),
+            _ => Ordering::Equal,
+        });
+    }
+}
+
+// Smallest estimate first. Tasks without an estimate could be any length, so they go last.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShortestJobFirst;
+
+impl ReadyQueuePolicy for ShortestJobFirst {
+    fn name(&self) -> &str {
+        "shortest job first"
+    }
+
+    fn order(&self, project: &Project, ready: &mut [u32]) {
+        let estimate = |id: &u32| {
+            project
+                .get_task(*id)
+                .ok()
+                .and_then(|task| task.estimate_hours)
+        };
+        ready.sort_by(|a, b| match (estimate(a), estimate(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+    }
+}
+
+// Longest chain of remaining work first: a task's own estimate plus the longest run of
+// estimates through the tasks waiting on it, so what holds up the most goes first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CriticalPathFirst;
+
+impl ReadyQueuePolicy for CriticalPathFirst {
+    fn name(&self) -> &str {
+        "critical path first"
+    }
+
+    fn order(&self, project: &Project, ready: &mut [u32]) {
+        let tails = remaining_chains(project);
+        let tail = |id: &u32| tails.get(id).copied().unwrap_or(0.0);
+        ready.sort_by(|a, b| tail(b).total_cmp(&tail(a)));
+    }
+}
+
+// For every task, the hours from its start to the end of the longest chain of dependent
+// tasks after it. Unestimated and finished tasks count as taking no time.
+fn remaining_chains(project: &Project) -> HashMap<u32, f64> {
+    let mut tails: HashMap<u32, f64> = HashMap::new();
+    let Ok(order) = project.get_task_execution_order() else {
+        return tails;
+    };
+    // Dependents come later in the execution order, so walking it backwards has every
+    // dependent's chain ready when it's needed
+    for task in order.iter().rev() {
+        let longest_after = project
+            .tasks
+            .iter()
+            .filter(|t| t.depends_on(task.id))
+            .filter_map(|t| tails.get(&t.id))
+            .fold(0.0, |longest: f64, tail| longest.max(*tail));
+        tails.insert(task.id, schedule::duration_of(task) + longest_after);
+    }
+    tails
+}
+
+// What to do with the task at the front of a `ReadyQueue`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Next {
+    Run(u32),
+    // The task can't run because this dependency failed; it counts as failed too
+    Skip(u32, u32),
+}
+
+// The open tasks of a project, released to the queue as the open tasks they depend on
+// finish. Tasks finished already don't hold anything back.
+pub struct ReadyQueue<'a> {
+    project: &'a Project,
+    policy: &'a dyn ReadyQueuePolicy,
+    open: HashSet<u32>,
+    waiting: Vec<u32>,
+    ready: Vec<u32>,
+    finished: HashSet<u32>,
+    failed: HashSet<u32>,
+}
+
+impl<'a> ReadyQueue<'a> {
+    pub fn new(project: &'a Project, policy: &'a dyn ReadyQueuePolicy) -> Result<Self> {
+        // In dependency order, which also rejects cycles
+        let waiting: Vec<u32> = project.execution_levels()?.concat();
+        Ok(ReadyQueue {
+            project,
+            policy,
+            open: waiting.iter().copied().collect(),
+            waiting,
+            ready: Vec::new(),
+            finished: HashSet::new(),
+            failed: HashSet::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.waiting.len() + self.ready.len()
+    }
+
+    // The next task to start, or None while nothing is ready
+    pub fn pop(&mut self) -> Option<Next> {
+        self.release();
+        if self.ready.is_empty() {
+            return None;
+        }
+        let id = self.ready.remove(0);
+        let failed_dep = self
+            .project
+            .get_task(id)
+            .ok()
+            .and_then(|task| task.dependency_ids().find(|dep| self.failed.contains(dep)));
+        match failed_dep {
+            Some(dep) => {
+                self.finish(id, false);
+                Some(Next::Skip(id, dep))
+            }
+            None => Some(Next::Run(id)),
+        }
+    }
+
+    pub fn finish(&mut self, id: u32, succeeded: bool) {
+        self.finished.insert(id);
+        if !succeeded {
+            self.failed.insert(id);
+        }
+    }
+
+    // Move the tasks whose dependencies have all finished to the ready queue
+    fn release(&mut self) {
+        let (open, finished) = (&self.open, &self.finished);
+        let is_ready = |id: &u32| {
+            self.project.get_task(*id).map_or(true, |task| {
+                task.dependency_ids()
+                    .all(|dep| !open.contains(&dep) || finished.contains(&dep))
+            })
+        };
+        let (released, waiting): (Vec<u32>, Vec<u32>) =
+            self.waiting.iter().copied().partition(is_ready);
+        if released.is_empty() {
+            return;
+        }
+        self.waiting = waiting;
+        self.ready.extend(released);
+        self.policy.order(self.project, &mut self.ready);
+    }
+}
+
+// Where a task lands in a simulated run, in hours from the start
+#[derive(Debug, Clone)]
+pub struct SimulatedSlot {
+    pub task_id: u32,
+    pub worker: usize,
+    pub start: f64,
+    pub finish: f64,
+}
+
+// A run of the project's open tasks as `execute_project` would schedule it, with each task
+// taking its estimate, for comparing policies without running anything
+#[derive(Debug, Clone)]
+pub struct SimulatedRun {
+    pub slots: Vec<SimulatedSlot>,
+    pub makespan: f64,
+}
+
+impl SimulatedRun {
+    pub fn finish_of(&self, task_id: u32) -> Option<f64> {
+        self.slots
+            .iter()
+            .find(|slot| slot.task_id == task_id)
+            .map(|slot| slot.finish)
+    }
+}
+
+pub fn simulate(
+    project: &Project,
+    policy: &dyn ReadyQueuePolicy,
+    workers: usize,
+) -> Result<SimulatedRun> {
+    let mut queue = ReadyQueue::new(project, policy)?;
+    let mut free: Vec<usize> = (0..workers.max(1)).rev().collect();
+    // (finish, worker, task) for the tasks started and not yet finished
+    let mut running: Vec<(f64, usize, u32)> = Vec::new();
+    let mut slots = Vec::new();
+    let mut now = 0.0;
+    loop {
+        while let Some(&worker) = free.last() {
+            match queue.pop() {
+                Some(Next::Run(id)) => {
+                    let finish = now + schedule::duration_of(project.get_task(id)?);
+                    free.pop();
+                    running.push((finish, worker, id));
+                    slots.push(SimulatedSlot {
+                        task_id: id,
+                        worker,
+                        start: now,
+                        finish,
+                    });
+                }
+                Some(Next::Skip(..)) => {}
+                None => break,
+            }
+        }
+        // Tasks finish one at a time, the earliest first
+        let Some(index) = (0..running.len()).min_by(|a, b| running[*a].0.total_cmp(&running[*b].0))
+        else {
+            break;
+        };
+        let (finish, worker, id) = running.swap_remove(index);
+        now = finish;
+        free.push(worker);
+        queue.finish(id, true);
+    }
+    Ok(SimulatedRun {
+        slots,
+        makespan: now,
+    })
+}
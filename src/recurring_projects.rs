@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::ids;
+use crate::periodic_tasks::RecurrencePattern;
+use crate::project::Project;
+use crate::storage::Storage;
+
+pub const RECURRING_PROJECTS_FILE: &str = "recurring_projects.json";
+
+// A project that is re-created from a template project on a schedule,
+// e.g. a monthly close checklist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringProject {
+    pub id: u32,
+    // Instances are named "<name> #<n>"
+    pub name: String,
+    // Project whose tasks and settings are copied into each instance
+    pub template_project: u32,
+    pub pattern: RecurrencePattern,
+    pub next_run: SystemTime,
+    #[serde(default)]
+    pub occurrences: u32,
+    // Project IDs of every instance created so far, oldest first
+    #[serde(default)]
+    pub instances: Vec<u32>,
+}
+
+impl RecurringProject {
+    pub fn is_due(&self) -> bool {
+        SystemTime::now() >= self.next_run
+    }
+
+    // Create the next instance, archiving the earlier ones
+    pub fn instantiate(&mut self, storage: &mut dyn Storage) -> Result<Project> {
+        let template = storage.load_project(self.template_project)?;
+        let occurrence = self.occurrences + 1;
+        let id = storage.next_project_id()?;
+        let instance = template.instantiate(id, format!("{} #{}", self.name, occurrence))?;
+        storage.save_project(&instance)?;
+
+        for &previous in &self.instances {
+            // Instances deleted by hand are simply skipped
+            if let Ok(mut project) = storage.load_project(previous) {
+                if !project.archived {
+                    project.archived = true;
+                    storage.save_project(&project)?;
+                }
+            }
+        }
+
+        self.instances.push(id);
+        self.occurrences = occurrence;
+        self.next_run = self.pattern.get_next_occurrence(SystemTime::now());
+        Ok(instance)
+    }
+}
+
+// Recurring project entries stored as `recurring_projects.json` in the data directory
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RecurringProjects {
+    entries: Vec<RecurringProject>,
+}
+
+impl RecurringProjects {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(RECURRING_PROJECTS_FILE);
+        if !path.exists() {
+            return Ok(RecurringProjects::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(RECURRING_PROJECTS_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(
+        &mut self,
+        name: &str,
+        template_project: u32,
+        pattern: RecurrencePattern,
+        first_run: SystemTime,
+    ) -> Result<&RecurringProject> {
+        let id = ids::next_id(self.entries.iter().map(|e| e.id))?;
+        self.entries.push(RecurringProject {
+            id,
+            name: name.to_string(),
+            template_project,
+            pattern,
+            next_run: first_run,
+            occurrences: 0,
+            instances: Vec::new(),
+        });
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+
+    pub fn remove(&mut self, id: u32) -> Result<RecurringProject> {
+        let position = self
+            .entries
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| {
+                TaskMasterError::InvalidOperation(format!("No recurring project with ID {}", id))
+            })?;
+        Ok(self.entries.remove(position))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RecurringProject> {
+        self.entries.iter()
+    }
+
+    // Instantiate every due entry, returning the projects created
+    pub fn run_due(&mut self, storage: &mut dyn Storage) -> Result<Vec<Project>> {
+        let mut created = Vec::new();
+        for entry in self.entries.iter_mut().filter(|e| e.is_due()) {
+            created.push(entry.instantiate(storage)?);
+        }
+        Ok(created)
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// When to remind about a task relative to its due date
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ReminderRule {
+    // This many minutes before the task is due
+    Before { minutes: i64 },
+    AtDue,
+    // Repeatedly once the task is overdue
+    EveryOverdue { minutes: i64 },
+}
+
+impl ReminderRule {
+    // Global default: a day ahead and when the task falls due
+    pub fn defaults() -> Vec<ReminderRule> {
+        vec![
+            ReminderRule::Before { minutes: 24 * 60 },
+            ReminderRule::AtDue,
+        ]
+    }
+
+    // The most recent time this rule fired at or before `now`, if any
+    fn last_fire(&self, due: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ReminderRule::Before { minutes } => due
+                .checked_sub_signed(Duration::minutes(*minutes))
+                .filter(|at| *at <= now),
+            ReminderRule::AtDue => Some(due).filter(|at| *at <= now),
+            ReminderRule::EveryOverdue { minutes } => {
+                let first = due.checked_add_signed(Duration::minutes(*minutes));
+                if *minutes <= 0 || first.is_none_or(|first| now < first) {
+                    return None;
+                }
+                let periods = (now - due).num_minutes() / minutes;
+                Some(due + Duration::minutes(periods * minutes))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ReminderRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReminderRule::Before { minutes } => write!(f, "{} before", format_minutes(*minutes)),
+            ReminderRule::AtDue => write!(f, "at due time"),
+            ReminderRule::EveryOverdue { minutes } => {
+                write!(f, "every {} overdue", format_minutes(*minutes))
+            }
+        }
+    }
+}
+
+impl FromStr for ReminderRule {
+    type Err = TaskMasterError;
+
+    // "1w", "1d", "2h" (before due), "due", or "every:1h" (while overdue)
+    fn from_str(s: &str) -> Result<Self> {
+        let input = s.trim().to_lowercase();
+        if input == "due" {
+            return Ok(ReminderRule::AtDue);
+        }
+
+        let (repeat, spec) = match input.strip_prefix("every:") {
+            Some(spec) => (true, spec),
+            None => (false, input.as_str()),
+        };
+        let minutes = dates::parse_duration(spec)?.num_minutes();
+        if minutes <= 0 {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Reminder offset must be positive: {}",
+                s
+            )));
+        }
+
+        Ok(if repeat {
+            ReminderRule::EveryOverdue { minutes }
+        } else {
+            ReminderRule::Before { minutes }
+        })
+    }
+}
+
+fn format_minutes(minutes: i64) -> String {
+    if minutes % (7 * 24 * 60) == 0 {
+        format!("{}w", minutes / (7 * 24 * 60))
+    } else if minutes % (24 * 60) == 0 {
+        format!("{}d", minutes / (24 * 60))
+    } else if minutes % 60 == 0 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+// A reminder that has come due for a task
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    pub task_id: u32,
+    pub title: String,
+    pub due: DateTime<Utc>,
+    pub rule: ReminderRule,
+    pub fire_at: DateTime<Utc>,
+    pub habit: bool,
+}
+
+impl Reminder {
+    pub fn message(&self) -> String {
+        if self.habit && self.due <= self.fire_at {
+            format!(
+                "Habit {} '{}' is waiting for you today; doing it keeps your streak going",
+                self.task_id, self.title
+            )
+        } else if self.due <= self.fire_at && self.rule != ReminderRule::AtDue {
+            format!(
+                "Task {} '{}' is overdue (was due {})",
+                self.task_id,
+                self.title,
+                dates::format_local(&self.due)
+            )
+        } else {
+            format!(
+                "Task {} '{}' is due {} ({})",
+                self.task_id,
+                self.title,
+                dates::format_local(&self.due),
+                self.rule
+            )
+        }
+    }
+}
+
+// The reminder a task should get now, honouring its own schedule or the defaults.
+// Only fire times after the last reminder count, so each one is sent once. Habits get
+// no repeated overdue reminders, just one gentle nudge once they fall due.
+pub fn pending_reminder(
+    task: &Task,
+    defaults: &[ReminderRule],
+    now: DateTime<Utc>,
+) -> Option<Reminder> {
+    if task.status == TaskStatus::Done {
+        return None;
+    }
+    let due = task.due?;
+    let rules = task.reminders.as_deref().unwrap_or(defaults);
+
+    rules
+        .iter()
+        .filter(|rule| task.habit.is_none() || !matches!(rule, ReminderRule::EveryOverdue { .. }))
+        .filter_map(|rule| rule.last_fire(due, now).map(|at| (rule, at)))
+        .filter(|(_, at)| task.last_reminded.is_none_or(|last| *at > last))
+        .max_by_key(|(_, at)| *at)
+        .map(|(rule, fire_at)| Reminder {
+            task_id: task.id,
+            title: task.title.clone(),
+            due,
+            rule: rule.clone(),
+            fire_at,
+            habit: task.habit.is_some(),
+        })
+}
+
+// Collect pending reminders for a project and mark them as sent
+pub fn collect_reminders(
+    project: &mut Project,
+    defaults: &[ReminderRule],
+    now: DateTime<Utc>,
+) -> Vec<Reminder> {
+    let mut reminders = Vec::new();
+    for task in &mut project.tasks {
+        if let Some(reminder) = pending_reminder(task, defaults, now) {
+            task.last_reminded = Some(now);
+            reminders.push(reminder);
+        }
+    }
+    reminders
+}
+
+// Parse a comma-separated reminder schedule such as "1w,1d,due,every:1h"
+pub fn parse_schedule(input: &str) -> Result<Vec<ReminderRule>> {
+    input
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
@@ -0,0 +1,307 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::appearance::escape_html;
+use crate::dates;
+use crate::locale;
+use crate::project::Project;
+use crate::stats::ProjectStats;
+use crate::task::{Task, TaskStatus};
+
+// How far ahead deadlines count as upcoming
+pub const UPCOMING_DAYS: i64 = 14;
+// How far back completions count as recent
+pub const RECENT_DAYS: i64 = 7;
+
+// One task as it appears in a report
+#[derive(Debug, Clone)]
+pub struct ReportTask {
+    pub id: String,
+    pub title: String,
+    pub priority: String,
+    pub due: Option<DateTime<Utc>>,
+    pub completed: Option<DateTime<Utc>>,
+}
+
+impl ReportTask {
+    pub fn of(project: &Project, task: &Task) -> Self {
+        ReportTask {
+            id: format!("{}{}", project.settings.id_prefix, task.id),
+            title: task.title.clone(),
+            priority: project.settings.priority_scale.label(&task.priority),
+            due: task.due,
+            completed: task.completed_at(),
+        }
+    }
+}
+
+// A shareable snapshot of where a project stands
+#[derive(Debug, Clone)]
+pub struct ProjectReport {
+    pub title: String,
+    pub description: String,
+    pub owner: Option<String>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub generated: DateTime<Utc>,
+    pub stats: ProjectStats,
+    // In workflow order, skipping empty statuses
+    pub by_status: Vec<(String, Vec<ReportTask>)>,
+    // Most overdue first
+    pub overdue: Vec<ReportTask>,
+    // Due within UPCOMING_DAYS, soonest first
+    pub upcoming: Vec<ReportTask>,
+    // Done within RECENT_DAYS, latest first
+    pub completed: Vec<ReportTask>,
+}
+
+impl ProjectReport {
+    pub fn build(project: &Project, now: DateTime<Utc>) -> Self {
+        let stats = ProjectStats::for_project(project, now);
+        let entry = |task: &Task| ReportTask::of(project, task);
+
+        let mut ordered: Vec<&Task> = project.tasks.iter().collect();
+        ordered.sort_by(|a, b| a.rank.cmp(&b.rank));
+
+        let by_status = stats
+            .by_status
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(status, _)| {
+                let tasks = ordered
+                    .iter()
+                    .filter(|t| t.status.to_string() == *status)
+                    .map(|t| entry(t))
+                    .collect();
+                (status.clone(), tasks)
+            })
+            .collect();
+
+        let open: Vec<&Task> = ordered
+            .iter()
+            .copied()
+            .filter(|t| t.status != TaskStatus::Done)
+            .collect();
+        let mut overdue: Vec<ReportTask> = open
+            .iter()
+            .filter(|t| t.due.is_some_and(|due| due < now))
+            .map(|t| entry(t))
+            .collect();
+        overdue.sort_by_key(|t| t.due);
+        let mut upcoming: Vec<ReportTask> = open
+            .iter()
+            .filter(|t| {
+                t.due
+                    .is_some_and(|due| due >= now && due <= now + Duration::days(UPCOMING_DAYS))
+            })
+            .map(|t| entry(t))
+            .collect();
+        upcoming.sort_by_key(|t| t.due);
+        let mut completed: Vec<ReportTask> = ordered
+            .iter()
+            .map(|t| entry(t))
+            .filter(|t| {
+                t.completed
+                    .is_some_and(|at| at >= now - Duration::days(RECENT_DAYS))
+            })
+            .collect();
+        completed.sort_by_key(|t| std::cmp::Reverse(t.completed));
+
+        ProjectReport {
+            title: project.label(),
+            description: project.description.clone(),
+            owner: project.owner.clone(),
+            deadline: project.deadline,
+            generated: now,
+            stats,
+            by_status,
+            overdue,
+            upcoming,
+            completed,
+        }
+    }
+
+    // Headline figures, shared by both formats
+    fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Tasks: {}, {} done ({:.0}%)",
+            self.stats.total,
+            self.stats.done,
+            self.stats.completion_percentage().unwrap_or(0.0)
+        )];
+        lines.push(format!("Overdue: {}", self.stats.overdue));
+        if let Some(age) = self.stats.average_age_days {
+            lines.push(format!(
+                "Average age of open tasks: {} days",
+                locale::number(age, 1)
+            ));
+        }
+        if let Some(owner) = &self.owner {
+            lines.push(format!("Owner: {}", owner));
+        }
+        if let Some(deadline) = &self.deadline {
+            lines.push(format!("Deadline: {}", dates::format_local(deadline)));
+        }
+        for milestone in &self.stats.milestones {
+            lines.push(format!("Milestone {}", milestone.describe(self.generated)));
+        }
+        lines
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Status report: {}\n\n", markdown_escape(&self.title));
+        md.push_str(&format!(
+            "_Generated {}_\n\n",
+            dates::format_local(&self.generated)
+        ));
+        if !self.description.is_empty() {
+            md.push_str(&format!("{}\n\n", self.description));
+        }
+
+        md.push_str("## Summary\n\n");
+        for line in self.summary_lines() {
+            md.push_str(&format!("- {}\n", line));
+        }
+
+        for (heading, tasks) in self.sections() {
+            md.push_str(&format!("\n## {}\n\n", heading));
+            if tasks.is_empty() {
+                md.push_str("_None_\n");
+            }
+            for task in tasks {
+                md.push_str(&format!(
+                    "- **{}** {} ({}{})\n",
+                    task.id,
+                    markdown_escape(&task.title),
+                    task.priority,
+                    task_dates(task)
+                ));
+            }
+        }
+
+        md.push_str("\n## Tasks by status\n");
+        for (status, tasks) in &self.by_status {
+            md.push_str(&format!("\n### {} ({})\n\n", status, tasks.len()));
+            for task in tasks {
+                md.push_str(&format!(
+                    "- [{}] **{}** {} ({}{})\n",
+                    if task.completed.is_some() { "x" } else { " " },
+                    task.id,
+                    markdown_escape(&task.title),
+                    task.priority,
+                    task_dates(task)
+                ));
+            }
+        }
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = format!("Status report: {}", self.title);
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+        html.push_str(STYLE);
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+        html.push_str(&format!(
+            "<p class=\"generated\">Generated {}</p>\n",
+            dates::format_local(&self.generated)
+        ));
+        if !self.description.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", escape_html(&self.description)));
+        }
+
+        html.push_str("<h2>Summary</h2>\n<ul>\n");
+        for line in self.summary_lines() {
+            html.push_str(&format!("<li>{}</li>\n", escape_html(&line)));
+        }
+        html.push_str("</ul>\n");
+
+        for (heading, tasks) in self.sections() {
+            html.push_str(&format!("<h2>{}</h2>\n", heading));
+            html_task_table(&mut html, tasks);
+        }
+
+        html.push_str("<h2>Tasks by status</h2>\n");
+        for (status, tasks) in &self.by_status {
+            html.push_str(&format!(
+                "<h3>{} ({})</h3>\n",
+                escape_html(status),
+                tasks.len()
+            ));
+            html_task_table(&mut html, tasks);
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    fn sections(&self) -> [(String, &[ReportTask]); 3] {
+        [
+            ("Overdue".to_string(), &self.overdue),
+            (
+                format!("Due in the next {} days", UPCOMING_DAYS),
+                &self.upcoming,
+            ),
+            (
+                format!("Completed in the last {} days", RECENT_DAYS),
+                &self.completed,
+            ),
+        ]
+    }
+}
+
+fn html_task_table(html: &mut String, tasks: &[ReportTask]) {
+    if tasks.is_empty() {
+        html.push_str("<p class=\"none\">None</p>\n");
+        return;
+    }
+    html.push_str("<table>\n<tr><th>ID</th><th>Title</th><th>Priority</th><th>Due</th><th>Completed</th></tr>\n");
+    for task in tasks {
+        let format =
+            |at: &Option<DateTime<Utc>>| at.as_ref().map(dates::format_local).unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&task.id),
+            escape_html(&task.title),
+            escape_html(&task.priority),
+            format(&task.due),
+            format(&task.completed)
+        ));
+    }
+    html.push_str("</table>\n");
+}
+
+// ", due ...", ", done ..." or nothing
+fn task_dates(task: &ReportTask) -> String {
+    let mut text = String::new();
+    if let Some(due) = &task.due {
+        text.push_str(&format!(", due {}", dates::format_local(due)));
+    }
+    if let Some(completed) = &task.completed {
+        text.push_str(&format!(", done {}", dates::format_local(completed)));
+    }
+    text
+}
+
+// Keep titles from turning into Markdown formatting
+pub fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+const STYLE: &str = "<style>
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #222; max-width: 900px; margin: 24px auto; padding: 0 16px; }
+h1 { border-bottom: 2px solid #222; padding-bottom: 4px; }
+h2 { margin-top: 28px; border-bottom: 1px solid #ccc; }
+.generated, .none { color: #777; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; border-bottom: 1px solid #ddd; padding: 4px 8px; font-size: 14px; }
+th { background: #f4f4f4; }
+@media print { h2 { break-after: avoid; } tr { break-inside: avoid; } }
+</style>
+";
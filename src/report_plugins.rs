@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::{Result, TaskMasterError};
+use crate::project::Project;
+
+// Report scripts live in `<data-dir>/reports`
+pub const REPORTS_DIR: &str = "reports";
+
+// Bumped whenever the snapshot changes in a way scripts could trip over
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// What a report script reads on standard input: a copy of the data, so nothing the script
+// does can change it
+#[derive(Debug, Serialize)]
+pub struct Snapshot<'a> {
+    pub version: u32,
+    pub generated: DateTime<Utc>,
+    pub projects: &'a [Project],
+}
+
+impl<'a> Snapshot<'a> {
+    pub fn new(projects: &'a [Project], generated: DateTime<Utc>) -> Self {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            generated,
+            projects,
+        }
+    }
+}
+
+// What the script is asked to produce, passed in TASKMASTER_REPORT_FORMAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Markdown,
+    Html,
+    // Checked and pretty-printed before it is shown
+    Json,
+}
+
+impl OutputFormat {
+    fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Html => "html",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+// The scripts in the reports directory, by file name
+pub fn list<P: AsRef<Path>>(data_dir: P) -> Result<Vec<String>> {
+    let dir = data_dir.as_ref().join(REPORTS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+// Find a script by file name, with or without its extension. Names are looked up inside the
+// reports directory only.
+pub fn find<P: AsRef<Path>>(data_dir: P, name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "'{}' is not a report name; use the file name of a script in the {} directory",
+            name, REPORTS_DIR
+        )));
+    }
+
+    let scripts = list(&data_dir)?;
+    let found = scripts.iter().find(|s| *s == name).or_else(|| {
+        scripts
+            .iter()
+            .find(|s| Path::new(s).file_stem().is_some_and(|stem| stem == name))
+    });
+    match found {
+        Some(script) => Ok(data_dir.as_ref().join(REPORTS_DIR).join(script)),
+        None => Err(TaskMasterError::InvalidOperation(format!(
+            "No report script named '{}' in {}",
+            name,
+            data_dir.as_ref().join(REPORTS_DIR).display()
+        ))),
+    }
+}
+
+// Run a report script with the snapshot as JSON on standard input and return what it prints.
+// Scripts ending in .py, .sh or .js run under python3, sh or node; anything else must be
+// executable itself.
+pub fn run(script: &Path, snapshot: &Snapshot, format: OutputFormat) -> Result<String> {
+    let interpreter = match script.extension().and_then(|e| e.to_str()) {
+        Some("py") => Some("python3"),
+        Some("sh") => Some("sh"),
+        Some("js") => Some("node"),
+        _ => None,
+    };
+    let mut command = match interpreter {
+        Some(program) => {
+            let mut command = Command::new(program);
+            command.arg(script);
+            command
+        }
+        None => Command::new(script),
+    };
+
+    let mut child = command
+        .env("TASKMASTER_REPORT_FORMAT", format.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            TaskMasterError::InvalidOperation(format!(
+                "Could not run report script {}: {}",
+                script.display(),
+                e
+            ))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A script that exits without reading everything is not an error in itself
+        let _ = stdin.write_all(serde_json::to_string(snapshot)?.as_bytes());
+    }
+    let finished = child.wait_with_output()?;
+
+    if !finished.status.success() {
+        let stderr = String::from_utf8_lossy(&finished.stderr);
+        return Err(TaskMasterError::InvalidOperation(format!(
+            "Report script {} exited with {}: {}",
+            script.display(),
+            finished.status,
+            stderr.trim()
+        )));
+    }
+
+    let output = String::from_utf8_lossy(&finished.stdout).into_owned();
+    if format == OutputFormat::Json {
+        let value: serde_json::Value = serde_json::from_str(&output).map_err(|e| {
+            TaskMasterError::InvalidOperation(format!(
+                "Report script {} did not print valid JSON: {}",
+                script.display(),
+                e
+            ))
+        })?;
+        return Ok(format!("{}\n", serde_json::to_string_pretty(&value)?));
+    }
+    Ok(output)
+}
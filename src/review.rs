@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::project::Project;
+use crate::task::TaskStatus;
+
+pub const REVIEW_FILE: &str = "review.json";
+
+// Default for `wip_age_limit_hours` in the config: three days in progress
+pub fn default_wip_age_limit_hours() -> i64 {
+    72
+}
+
+// Why a task was put up for review
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ReviewReason {
+    // In progress for longer than the WIP age limit
+    AgingWip,
+}
+
+impl ReviewReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ReviewReason::AgingWip => "in progress too long",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub project: u32,
+    pub task: u32,
+    pub reason: ReviewReason,
+    pub added: DateTime<Utc>,
+}
+
+// Tasks waiting for someone to look at them, stored as `review.json` in the data directory so
+// it covers every project
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReviewQueue {
+    items: Vec<ReviewItem>,
+}
+
+impl ReviewQueue {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(REVIEW_FILE);
+        if !path.exists() {
+            return Ok(ReviewQueue::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(REVIEW_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Oldest first
+    pub fn items(&self) -> &[ReviewItem] {
+        &self.items
+    }
+
+    pub fn contains(&self, project: u32, task: u32, reason: ReviewReason) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.project == project && item.task == task && item.reason == reason)
+    }
+
+    // Queue a task for review, returning false if it is already queued for the same reason
+    pub fn add(
+        &mut self,
+        project: u32,
+        task: u32,
+        reason: ReviewReason,
+        now: DateTime<Utc>,
+    ) -> bool {
+        if self.contains(project, task, reason) {
+            return false;
+        }
+        self.items.push(ReviewItem {
+            project,
+            task,
+            reason,
+            added: now,
+        });
+        true
+    }
+
+    // Take a task off the queue, whatever it was queued for. Returns how many entries went.
+    pub fn dismiss(&mut self, project: u32, task: u32) -> usize {
+        let before = self.items.len();
+        self.items
+            .retain(|item| item.project != project || item.task != task);
+        before - self.items.len()
+    }
+
+    // Queue the project's tasks that have been in progress longer than `limit`, and drop aging
+    // WIP entries for its tasks that have since moved on or been deleted. Returns the newly
+    // queued tasks with how long they have been in progress.
+    pub fn flag_aging_wip(
+        &mut self,
+        project: &Project,
+        limit: Duration,
+        now: DateTime<Utc>,
+    ) -> Vec<(u32, Duration)> {
+        self.items.retain(|item| {
+            item.project != project.id
+                || item.reason != ReviewReason::AgingWip
+                || project
+                    .tasks
+                    .iter()
+                    .any(|task| task.id == item.task && task.status == TaskStatus::InProgress)
+        });
+
+        let mut flagged = Vec::new();
+        for task in &project.tasks {
+            let Some(age) = task.wip_age(now) else {
+                continue;
+            };
+            if age > limit && self.add(project.id, task.id, ReviewReason::AgingWip, now) {
+                flagged.push((task.id, age));
+            }
+        }
+        flagged
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+// Files quick-captured tasks into a project when their tags or title match.
+// Rules live in `config.json` under `routes` and are tried in order.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoutingRule {
+    pub project: u32,
+    // Matches when the task has any of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // ...or a word in its title starts with any of these, so "crash" matches "crashes"
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    // Defaults applied to routed tasks
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    // Due date relative to capture time, e.g. "2d"
+    #[serde(default)]
+    pub due_in: Option<String>,
+}
+
+impl RoutingRule {
+    pub fn matches(&self, capture: &Capture) -> bool {
+        let tag_match = self
+            .tags
+            .iter()
+            .any(|tag| capture.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+
+        let title = capture.title.to_lowercase();
+        let words: Vec<&str> = title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let keyword_match = self.keywords.iter().any(|keyword| {
+            let keyword = keyword.to_lowercase();
+            words.iter().any(|word| word.starts_with(&keyword))
+        });
+
+        tag_match || keyword_match
+    }
+
+    pub fn describe(&self) -> String {
+        let mut when = Vec::new();
+        if !self.tags.is_empty() {
+            when.push(format!("tags {}", self.tags.join(", ")));
+        }
+        if !self.keywords.is_empty() {
+            when.push(format!("keywords {}", self.keywords.join(", ")));
+        }
+
+        let mut line = format!("{} -> project {}", when.join(" or "), self.project);
+        if let Some(priority) = &self.priority {
+            line.push_str(&format!(", priority {}", priority));
+        }
+        if !self.add_tags.is_empty() {
+            line.push_str(&format!(", tags +{}", self.add_tags.join(" +")));
+        }
+        if let Some(due_in) = &self.due_in {
+            line.push_str(&format!(", due in {}", due_in));
+        }
+        line
+    }
+}
+
+// A quick-add line split into its title and "#tag" words
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+impl Capture {
+    pub fn parse(text: &str) -> Self {
+        let mut words = Vec::new();
+        let mut tags = Vec::new();
+        for word in text.split_whitespace() {
+            match word.strip_prefix('#') {
+                Some(tag) if !tag.is_empty() => tags.push(tag.to_string()),
+                _ => words.push(word),
+            }
+        }
+
+        Capture {
+            title: words.join(" "),
+            tags,
+        }
+    }
+}
+
+// The first rule matching the capture, if any
+pub fn route<'a>(rules: &'a [RoutingRule], capture: &Capture) -> Option<&'a RoutingRule> {
+    rules.iter().find(|rule| rule.matches(capture))
+}
@@ -0,0 +1,431 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::Serialize;
+
+use crate::error::{Result, TaskMasterError};
+use crate::locale;
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+use crate::task_dependencies::{Dependency, DependencyKind};
+
+// A hypothetical change to evaluate without touching the project
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scenario {
+    Baseline,
+    // Hold the task back by this many hours before it can start
+    Delay(u32, f64),
+    // Drop the task, releasing everything that waited on it
+    Remove(u32),
+}
+
+// Earliest start/finish for every task, in hours from now, assuming unlimited
+// parallelism and each unfinished task taking its estimate
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub start: HashMap<u32, f64>,
+    pub finish: HashMap<u32, f64>,
+    pub critical_path: Vec<u32>,
+    // Unfinished tasks without an estimate, counted as taking no time
+    pub unestimated: Vec<u32>,
+}
+
+impl Schedule {
+    pub fn compute(project: &Project, scenario: &Scenario) -> Result<Self> {
+        let removed = match scenario {
+            Scenario::Remove(id) => Some(*id),
+            _ => None,
+        };
+
+        let mut start: HashMap<u32, f64> = HashMap::new();
+        let mut finish: HashMap<u32, f64> = HashMap::new();
+        let mut critical_parent: HashMap<u32, u32> = HashMap::new();
+        let mut unestimated = Vec::new();
+
+        for task in project.get_task_execution_order()? {
+            if Some(task.id) == removed {
+                continue;
+            }
+
+            // A task starts as soon as all its dependencies allow: after the dependency
+            // finishes or starts, plus lag, or early enough to finish after it for the
+            // finish-to-finish and start-to-finish types. Lag counts as working hours here.
+            let duration = duration_of(task);
+            let mut earliest = 0.0;
+            let mut parent = None;
+            for dep in task.dependencies.iter().flatten() {
+                let (Some(&dep_start), Some(&dep_finish)) =
+                    (start.get(&dep.task), finish.get(&dep.task))
+                else {
+                    continue;
+                };
+                let lag = dep.lag().num_minutes() as f64 / 60.0;
+                let bound: f64 = match dep.kind {
+                    DependencyKind::FinishToStart => dep_finish + lag,
+                    DependencyKind::StartToStart => dep_start + lag,
+                    DependencyKind::FinishToFinish => dep_finish + lag - duration,
+                    DependencyKind::StartToFinish => dep_start + lag - duration,
+                };
+                let bound = bound.max(0.0);
+                if parent.is_none() || bound > earliest {
+                    earliest = bound;
+                    parent = Some(dep.task);
+                }
+            }
+            if let Some(parent) = parent {
+                critical_parent.insert(task.id, parent);
+            }
+
+            if let Scenario::Delay(id, hours) = scenario {
+                if *id == task.id {
+                    earliest += hours;
+                }
+            }
+
+            if task.status != TaskStatus::Done && task.estimate_hours.is_none() {
+                unestimated.push(task.id);
+            }
+
+            start.insert(task.id, earliest);
+            finish.insert(task.id, earliest + duration);
+        }
+
+        // Walk back from the latest finishing task to recover the critical path
+        let mut critical_path = Vec::new();
+        let mut current = finish
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(&id, _)| id);
+        while let Some(id) = current {
+            critical_path.push(id);
+            current = critical_parent.get(&id).copied();
+        }
+        critical_path.reverse();
+
+        Ok(Schedule {
+            start,
+            finish,
+            critical_path,
+            unestimated,
+        })
+    }
+
+    // Hours until everything is finished
+    pub fn makespan(&self) -> f64 {
+        self.finish.values().copied().fold(0.0, f64::max)
+    }
+}
+
+// Hours an unfinished task is expected to take: its estimate, or none without one
+pub fn duration_of(task: &Task) -> f64 {
+    if task.status == TaskStatus::Done {
+        0.0
+    } else {
+        task.estimate_hours.unwrap_or(0.0)
+    }
+}
+
+// Every task that depends on `task_id`, directly or through other tasks
+pub fn downstream_of(project: &Project, task_id: u32) -> HashSet<u32> {
+    let mut found = HashSet::new();
+    let mut frontier = vec![task_id];
+
+    while let Some(current) = frontier.pop() {
+        for task in &project.tasks {
+            if task.depends_on(current) && found.insert(task.id) {
+                frontier.push(task.id);
+            }
+        }
+    }
+
+    found
+}
+
+// How a scenario moves downstream finish times and the critical path
+#[derive(Debug, Clone)]
+pub struct ImpactReport {
+    pub task_id: u32,
+    pub scenario: Scenario,
+    // (task ID, hours later; negative means earlier) for downstream tasks that move
+    pub shifts: Vec<(u32, f64)>,
+    pub makespan_before: f64,
+    pub makespan_after: f64,
+    pub critical_before: Vec<u32>,
+    pub critical_after: Vec<u32>,
+    pub unestimated: Vec<u32>,
+}
+
+impl ImpactReport {
+    pub fn analyze(project: &Project, scenario: Scenario) -> Result<Self> {
+        let task_id = match &scenario {
+            Scenario::Delay(id, _) | Scenario::Remove(id) => *id,
+            Scenario::Baseline => {
+                return Err(TaskMasterError::InvalidOperation(
+                    "Impact analysis needs a delay or removal to evaluate".to_string(),
+                ))
+            }
+        };
+        project.get_task(task_id)?;
+
+        let before = Schedule::compute(project, &Scenario::Baseline)?;
+        let after = Schedule::compute(project, &scenario)?;
+
+        let mut affected: Vec<u32> = downstream_of(project, task_id).into_iter().collect();
+        if matches!(scenario, Scenario::Delay(..)) {
+            affected.push(task_id);
+        }
+        affected.sort();
+
+        let shifts = affected
+            .into_iter()
+            .filter_map(|id| {
+                let old = before.finish.get(&id)?;
+                let new = after.finish.get(&id)?;
+                let shift = new - old;
+                (shift.abs() > f64::EPSILON).then_some((id, shift))
+            })
+            .collect();
+
+        Ok(ImpactReport {
+            task_id,
+            scenario,
+            shifts,
+            makespan_before: before.makespan(),
+            makespan_after: after.makespan(),
+            critical_before: before.critical_path,
+            critical_after: after.critical_path,
+            unestimated: before.unestimated,
+        })
+    }
+
+    pub fn critical_path_changed(&self) -> bool {
+        self.critical_before != self.critical_after
+    }
+
+    pub fn display(&self, project: &Project) {
+        let title = |id: u32| {
+            project
+                .get_task(id)
+                .map(|t| t.title.clone())
+                .unwrap_or_default()
+        };
+
+        match &self.scenario {
+            Scenario::Delay(id, hours) => {
+                println!(
+                    "Impact of delaying task {} by {}:",
+                    id,
+                    locale::hours(*hours, 1)
+                )
+            }
+            Scenario::Remove(id) => println!("Impact of removing task {}:", id),
+            Scenario::Baseline => {}
+        }
+
+        if let Scenario::Remove(_) = self.scenario {
+            let released: Vec<u32> = project
+                .tasks
+                .iter()
+                .filter(|t| t.depends_on(self.task_id))
+                .map(|t| t.id)
+                .collect();
+            if !released.is_empty() {
+                println!("  Tasks that would lose a dependency: {:?}", released);
+            }
+        }
+
+        if self.shifts.is_empty() {
+            println!("  No downstream task finishes at a different time");
+        } else {
+            for (id, shift) in &self.shifts {
+                println!(
+                    "  Task {} ({}): {} by {}",
+                    id,
+                    title(*id),
+                    if *shift > 0.0 { "slips" } else { "moves up" },
+                    locale::hours(shift.abs(), 1)
+                );
+            }
+        }
+
+        println!(
+            "  Project finish: {} -> {} from now",
+            locale::hours(self.makespan_before, 1),
+            locale::hours(self.makespan_after, 1)
+        );
+        if self.critical_path_changed() {
+            println!(
+                "  Critical path changes: {:?} -> {:?}",
+                self.critical_before, self.critical_after
+            );
+        } else {
+            println!("  Critical path unchanged: {:?}", self.critical_before);
+        }
+
+        if !self.unestimated.is_empty() {
+            println!(
+                "  Note: tasks {:?} have no estimate and are counted as taking no time",
+                self.unestimated
+            );
+        }
+    }
+}
+
+// Working time used to turn scheduled hours into calendar dates
+#[derive(Debug, Clone, Copy)]
+pub struct WorkCalendar {
+    pub hours_per_day: f64,
+    pub weekends: bool,
+}
+
+impl Default for WorkCalendar {
+    fn default() -> Self {
+        WorkCalendar {
+            hours_per_day: 8.0,
+            weekends: false,
+        }
+    }
+}
+
+impl WorkCalendar {
+    pub fn is_working_day(&self, date: NaiveDate) -> bool {
+        self.weekends || !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+
+    // The first working day on or after `date`
+    pub(crate) fn first_working_day(&self, mut date: NaiveDate) -> NaiveDate {
+        while !self.is_working_day(date) {
+            date += Duration::days(1);
+        }
+        date
+    }
+
+    // The working day `days` working days after `start`
+    fn add_working_days(&self, start: NaiveDate, days: u32) -> NaiveDate {
+        let mut date = self.first_working_day(start);
+        for _ in 0..days {
+            date = self.first_working_day(date + Duration::days(1));
+        }
+        date
+    }
+
+    // Zero-based index of the working day an hour offset falls on; work ending exactly at
+    // the end of a day finishes on that day
+    fn day_index(&self, hours: f64, finishing: bool) -> u32 {
+        let days = hours / self.hours_per_day;
+        let index = if finishing {
+            days.ceil() - 1.0
+        } else {
+            days.floor()
+        };
+        index.max(0.0) as u32
+    }
+}
+
+// A task placed on the calendar
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledTask {
+    pub id: u32,
+    pub title: String,
+    pub start: NaiveDate,
+    pub finish: NaiveDate,
+    // Remaining work; 0 for finished or unestimated tasks
+    pub hours: f64,
+    pub dependencies: Vec<Dependency>,
+    pub done: bool,
+    pub critical: bool,
+}
+
+// The forward pass laid out on working days from a start date, ready for a Gantt chart
+#[derive(Debug, Clone, Serialize)]
+pub struct DatedSchedule {
+    pub project: u32,
+    pub start: NaiveDate,
+    pub finish: NaiveDate,
+    pub hours_per_day: f64,
+    pub tasks: Vec<ScheduledTask>,
+    pub unestimated: Vec<u32>,
+}
+
+impl DatedSchedule {
+    pub fn plan(project: &Project, start: NaiveDate, calendar: &WorkCalendar) -> Result<Self> {
+        if calendar.hours_per_day <= 0.0 {
+            return Err(TaskMasterError::InvalidOperation(
+                "Hours per day must be positive".to_string(),
+            ));
+        }
+
+        let schedule = Schedule::compute(project, &Scenario::Baseline)?;
+        let start = calendar.first_working_day(start);
+
+        let mut tasks = Vec::new();
+        for task in project.get_task_execution_order()? {
+            let (Some(&begin), Some(&end)) =
+                (schedule.start.get(&task.id), schedule.finish.get(&task.id))
+            else {
+                continue;
+            };
+            let first_day = calendar.day_index(begin, false);
+            let last_day = calendar.day_index(end, true).max(first_day);
+            tasks.push(ScheduledTask {
+                id: task.id,
+                title: task.title.clone(),
+                start: calendar.add_working_days(start, first_day),
+                finish: calendar.add_working_days(start, last_day),
+                hours: end - begin,
+                dependencies: task.dependencies.clone().unwrap_or_default(),
+                done: task.status == TaskStatus::Done,
+                critical: schedule.critical_path.contains(&task.id),
+            });
+        }
+
+        let finish = tasks.iter().map(|t| t.finish).max().unwrap_or(start);
+        Ok(DatedSchedule {
+            project: project.id,
+            start,
+            finish,
+            hours_per_day: calendar.hours_per_day,
+            tasks,
+            unestimated: schedule.unestimated,
+        })
+    }
+
+    pub fn display(&self) {
+        println!(
+            "Schedule for project {} ({} to {}, {} working days):",
+            self.project,
+            locale::date(self.start),
+            locale::date(self.finish),
+            locale::hours(self.hours_per_day, 1)
+        );
+        println!(
+            "  {:<5} {:<30} {:<10}  {:<10}  {:>6}  Depends on",
+            "ID", "Title", "Start", "Finish", "Hours"
+        );
+        for task in &self.tasks {
+            let mut title: String = task.title.chars().take(30).collect();
+            if task.done {
+                title = format!("{} (done)", title).chars().take(30).collect();
+            }
+            let deps: Vec<String> = task.dependencies.iter().map(|d| d.to_string()).collect();
+            println!(
+                "{} {:<5} {:<30} {:<10}  {:<10}  {:>6}  {}",
+                if task.critical { "*" } else { " " },
+                task.id,
+                title,
+                locale::date(task.start),
+                locale::date(task.finish),
+                locale::number(task.hours, 1),
+                deps.join(", ")
+            );
+        }
+        println!("  * on the critical path");
+
+        if !self.unestimated.is_empty() {
+            println!(
+                "  Note: tasks {:?} have no estimate and are counted as taking no time",
+                self.unestimated
+            );
+        }
+    }
+}
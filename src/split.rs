@@ -0,0 +1,140 @@
+use crate::error::{Result, TaskMasterError};
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+use crate::task_dependencies::Dependency;
+
+// Estimates above this many hours are flagged as too big to track as one task
+pub const DEFAULT_MAX_HOURS: f64 = 16.0;
+// Checklists longer than this are flagged as hiding several tasks
+pub const DEFAULT_MAX_CHECKLIST_ITEMS: usize = 5;
+
+// Why a task was flagged as oversized
+#[derive(Debug, Clone)]
+pub struct OversizedTask {
+    pub task_id: u32,
+    pub estimate_hours: Option<f64>,
+    pub checklist_items: usize,
+}
+
+// Unfinished tasks whose estimate or checklist is beyond the given limits
+pub fn find_oversized(project: &Project, max_hours: f64, max_items: usize) -> Vec<OversizedTask> {
+    project
+        .tasks
+        .iter()
+        .filter(|t| t.status != TaskStatus::Done)
+        .filter(|t| {
+            t.estimate_hours.is_some_and(|h| h > max_hours) || t.checklist.len() > max_items
+        })
+        .map(|t| OversizedTask {
+            task_id: t.id,
+            estimate_hours: t.estimate_hours,
+            checklist_items: t.checklist.len(),
+        })
+        .collect()
+}
+
+// How to break a task up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitStrategy {
+    // One subtask per open checklist item
+    Checklist,
+    // A number of equal parts sharing the estimate
+    Parts(u32),
+}
+
+impl SplitStrategy {
+    // Checklists make natural subtasks; otherwise split the estimate into roughly max-sized parts
+    pub fn suggest(task: &Task, max_hours: f64) -> Self {
+        if task.checklist.iter().any(|item| !item.done) {
+            return SplitStrategy::Checklist;
+        }
+        let hours = task.estimate_hours.unwrap_or(max_hours);
+        SplitStrategy::Parts(((hours / max_hours).ceil() as u32).max(2))
+    }
+}
+
+// Subtasks to be created for a parent, not yet added to the project
+#[derive(Debug, Clone)]
+pub struct SplitPlan {
+    pub parent_id: u32,
+    pub subtasks: Vec<Task>,
+}
+
+impl SplitPlan {
+    pub fn new(project: &Project, parent_id: u32, strategy: SplitStrategy) -> Result<Self> {
+        let parent = project.get_task(parent_id)?;
+        let first_id = project.next_task_id()?;
+
+        let titles: Vec<String> = match strategy {
+            SplitStrategy::Checklist => parent
+                .checklist
+                .iter()
+                .filter(|item| !item.done)
+                .map(|item| item.text.clone())
+                .collect(),
+            SplitStrategy::Parts(parts) => (1..=parts)
+                .map(|part| format!("{} (part {}/{})", parent.title, part, parts))
+                .collect(),
+        };
+
+        if titles.len() < 2 {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "Task {} would split into fewer than two subtasks",
+                parent_id
+            )));
+        }
+        let last_id = u32::try_from(titles.len() - 1)
+            .ok()
+            .and_then(|offset| first_id.checked_add(offset));
+        if last_id.is_none() {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "No IDs left for {} subtasks after {}",
+                titles.len(),
+                first_id
+            )));
+        }
+
+        let share = parent.estimate_hours.map(|h| h / titles.len() as f64);
+        let subtasks = titles
+            .into_iter()
+            .enumerate()
+            .map(|(offset, title)| {
+                let mut subtask = Task::new(
+                    first_id + offset as u32,
+                    title,
+                    TaskStatus::ToDo,
+                    parent.priority.clone(),
+                );
+                subtask.parent = Some(parent_id);
+                subtask.tags = parent.tags.clone();
+                // Subtasks wait on whatever the parent was waiting on
+                subtask.dependencies = parent.dependencies.clone();
+                subtask.estimate_hours = share;
+                subtask
+            })
+            .collect();
+
+        Ok(SplitPlan {
+            parent_id,
+            subtasks,
+        })
+    }
+
+    // Add the subtasks and make the parent depend on them, so anything that
+    // depended on the parent now also waits for every subtask
+    pub fn apply(self, project: &mut Project) -> Result<Vec<u32>> {
+        let ids: Vec<u32> = self.subtasks.iter().map(|t| t.id).collect();
+        for subtask in self.subtasks {
+            project.add_task(subtask)?;
+        }
+
+        let parent = project.get_task_mut(self.parent_id)?;
+        parent.dependencies = Some(ids.iter().copied().map(Dependency::new).collect());
+        // The work now lives in the subtasks
+        parent.estimate_hours = None;
+        parent.checklist.retain(|item| item.done);
+        parent.touch();
+
+        Ok(ids)
+    }
+}
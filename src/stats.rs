@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+
+use crate::locale;
+use crate::milestone::MilestoneProgress;
+use crate::project::Project;
+use crate::task::TaskStatus;
+
+// Task counts and progress for one project, or several rolled together
+#[derive(Debug, Clone)]
+pub struct ProjectStats {
+    pub label: String,
+    pub total: usize,
+    // In workflow order, then any statuses the workflow no longer lists
+    pub by_status: Vec<(String, usize)>,
+    // Most urgent first
+    pub by_priority: Vec<(String, usize)>,
+    pub done: usize,
+    // Unfinished tasks whose due date has passed
+    pub overdue: usize,
+    // Mean age of unfinished tasks; None when everything is done
+    pub average_age_days: Option<f64>,
+    // Named "Project / Milestone" when several projects are rolled together
+    pub milestones: Vec<MilestoneProgress>,
+}
+
+impl ProjectStats {
+    pub fn for_project(project: &Project, now: DateTime<Utc>) -> Self {
+        Self::from_projects(&project.name, std::slice::from_ref(project), now)
+    }
+
+    pub fn from_projects(label: &str, projects: &[Project], now: DateTime<Utc>) -> Self {
+        let mut by_status: Vec<(String, usize)> = Vec::new();
+        // Label, weight on its project's scale, count
+        let mut by_priority: Vec<(String, u8, usize)> = Vec::new();
+        let mut total = 0;
+        let mut done = 0;
+        let mut overdue = 0;
+        let mut open_age_days = Vec::new();
+        let mut milestones = Vec::new();
+
+        for project in projects {
+            for mut progress in project.milestone_progress() {
+                if projects.len() > 1 {
+                    progress.name = format!("{} / {}", project.name, progress.name);
+                }
+                milestones.push(progress);
+            }
+
+            for state in &project.settings.workflow.states {
+                let name = state.to_string();
+                if !by_status.iter().any(|(s, _)| *s == name) {
+                    by_status.push((name, 0));
+                }
+            }
+
+            let scale = &project.settings.priority_scale;
+            for task in &project.tasks {
+                total += 1;
+
+                let status = task.status.to_string();
+                match by_status.iter_mut().find(|(s, _)| *s == status) {
+                    Some((_, count)) => *count += 1,
+                    None => by_status.push((status, 1)),
+                }
+
+                let label = scale.label(&task.priority);
+                let weight = scale.weight(&task.priority);
+                match by_priority.iter_mut().find(|(l, _, _)| *l == label) {
+                    Some((_, _, count)) => *count += 1,
+                    None => by_priority.push((label, weight, 1)),
+                }
+
+                if task.status == TaskStatus::Done {
+                    done += 1;
+                    continue;
+                }
+                // Missed habits are not counted as overdue work
+                if task.habit.is_none() && task.due.is_some_and(|due| due < now) {
+                    overdue += 1;
+                }
+                open_age_days.push((now - task.created_at).num_seconds() as f64 / 86_400.0);
+            }
+        }
+
+        by_priority.sort_by_key(|(_, weight, _)| std::cmp::Reverse(*weight));
+        let average_age_days = if open_age_days.is_empty() {
+            None
+        } else {
+            Some(open_age_days.iter().sum::<f64>() / open_age_days.len() as f64)
+        };
+
+        ProjectStats {
+            label: label.to_string(),
+            total,
+            by_status,
+            by_priority: by_priority
+                .into_iter()
+                .map(|(label, _, count)| (label, count))
+                .collect(),
+            done,
+            overdue,
+            average_age_days,
+            milestones,
+        }
+    }
+
+    // Share of tasks that are Done; None for a project without tasks
+    pub fn completion_percentage(&self) -> Option<f64> {
+        if self.total == 0 {
+            None
+        } else {
+            Some(self.done as f64 * 100.0 / self.total as f64)
+        }
+    }
+
+    pub fn display(&self) {
+        println!("Stats for {}:", self.label);
+        if self.total == 0 {
+            println!("  No tasks");
+            return;
+        }
+
+        println!(
+            "  Tasks: {}, {} done ({:.0}%)",
+            self.total,
+            self.done,
+            self.completion_percentage().unwrap_or(0.0)
+        );
+        println!("  Overdue: {}", self.overdue);
+        if let Some(age) = self.average_age_days {
+            println!(
+                "  Average age of open tasks: {} days",
+                locale::number(age, 1)
+            );
+        }
+        println!("  By status:");
+        for (status, count) in &self.by_status {
+            println!("    {}: {}", status, count);
+        }
+        println!("  By priority:");
+        for (priority, count) in &self.by_priority {
+            println!("    {}: {}", priority, count);
+        }
+        if !self.milestones.is_empty() {
+            println!("  By milestone:");
+            for milestone in &self.milestones {
+                println!("    {}", milestone.describe(Utc::now()));
+            }
+        }
+    }
+
+    // One-line summary for the TUI footer
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} tasks", self.total)];
+        if let Some(percentage) = self.completion_percentage() {
+            parts.push(format!("{:.0}% done", percentage));
+        }
+        parts.extend(
+            self.by_status
+                .iter()
+                .filter(|(status, count)| *count > 0 && status != "Done")
+                .map(|(status, count)| format!("{} {}", count, status)),
+        );
+        if self.overdue > 0 {
+            parts.push(format!("{} overdue", self.overdue));
+        }
+        if let Some(age) = self.average_age_days {
+            parts.push(format!("avg age {}d", locale::number(age, 1)));
+        }
+        format!("{}: {}", self.label, parts.join(" | "))
+    }
+}
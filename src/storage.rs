@@ -1,4 +1,7 @@
+use std::time::SystemTime;
+
 use crate::error::Result;
+use crate::ids;
 use crate::project::Project;
 use crate::task::Task;
 
@@ -12,4 +15,24 @@ pub trait Storage {
     fn save_task(&self, project_id: u32, task: &Task) -> Result<()>;
     fn load_task(&self, project_id: u32, task_id: u32) -> Result<Task>;
     fn delete_task(&self, project_id: u32, task_id: u32) -> Result<()>;
+
+    // ID allocation for callers that don't pick their own IDs
+    fn next_project_id(&self) -> Result<u32> {
+        // From the directory listing, so an unreadable project's ID isn't handed out again
+        ids::next_id(self.project_ids()?)
+    }
+
+    fn next_task_id(&self, project_id: u32) -> Result<u32> {
+        self.load_project(project_id)?.next_task_id()
+    }
+
+    // IDs of every stored project, for callers that don't need the projects themselves
+    fn project_ids(&self) -> Result<Vec<u32>> {
+        Ok(self.list_projects()?.iter().map(|p| p.id).collect())
+    }
+
+    // When a project was last saved, if the backend can tell cheaply; used to validate caches
+    fn project_modified(&self, _id: u32) -> Option<SystemTime> {
+        None
+    }
 }
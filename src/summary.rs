@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::project::Project;
+use crate::storage::Storage;
+use crate::task::TaskStatus;
+
+pub const SUMMARY_CACHE_FILE: &str = "summary_cache.json";
+
+// The few numbers a shell prompt needs from a project
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProjectSummary {
+    pub archived: bool,
+    pub open: usize,
+    pub in_progress: usize,
+    // Due dates of unfinished tasks, so overdue counts stay right as time passes
+    pub due: Vec<DateTime<Utc>>,
+}
+
+impl ProjectSummary {
+    pub fn of(project: &Project) -> Self {
+        let open: Vec<_> = project
+            .tasks
+            .iter()
+            .filter(|t| t.status != TaskStatus::Done)
+            .collect();
+        ProjectSummary {
+            archived: project.archived,
+            open: open.len(),
+            in_progress: open
+                .iter()
+                .filter(|t| t.status == TaskStatus::InProgress)
+                .count(),
+            due: open.iter().filter_map(|t| t.due).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CachedSummary {
+    modified: SystemTime,
+    summary: ProjectSummary,
+}
+
+// Per-project summaries stored as `summary_cache.json` in the data directory, each valid
+// while its project file is unchanged, so a prompt doesn't parse every project on each draw
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SummaryCache {
+    #[serde(default)]
+    projects: BTreeMap<u32, CachedSummary>,
+}
+
+impl SummaryCache {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(SUMMARY_CACHE_FILE);
+        // A damaged cache is rebuilt rather than reported
+        Ok(fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(SUMMARY_CACHE_FILE);
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    // Re-read projects saved since they were cached and drop deleted ones.
+    // Returns whether anything changed.
+    pub fn refresh(&mut self, storage: &dyn Storage) -> Result<bool> {
+        let ids = storage.project_ids()?;
+        let before = self.projects.len();
+        self.projects.retain(|id, _| ids.contains(id));
+        let mut changed = self.projects.len() != before;
+
+        for id in ids {
+            let modified = storage.project_modified(id);
+            let fresh = match (modified, self.projects.get(&id)) {
+                (Some(modified), Some(cached)) => cached.modified == modified,
+                _ => false,
+            };
+            if fresh {
+                continue;
+            }
+
+            let Ok(project) = storage.load_project(id) else {
+                continue;
+            };
+            self.projects.insert(
+                id,
+                CachedSummary {
+                    modified: modified.unwrap_or_else(SystemTime::now),
+                    summary: ProjectSummary::of(&project),
+                },
+            );
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    // Summaries of active projects, or of one project
+    pub fn summaries(&self, project_id: Option<u32>) -> Vec<&ProjectSummary> {
+        self.projects
+            .iter()
+            .filter(|(id, cached)| match project_id {
+                Some(project_id) => **id == project_id,
+                None => !cached.summary.archived,
+            })
+            .map(|(_, cached)| &cached.summary)
+            .collect()
+    }
+}
+
+// A compact line for tmux or prompt status bars, e.g. "12 open | 2 running | 1 overdue"
+pub fn statusline(summaries: &[&ProjectSummary], now: DateTime<Utc>) -> String {
+    let open: usize = summaries.iter().map(|s| s.open).sum();
+    let running: usize = summaries.iter().map(|s| s.in_progress).sum();
+    let overdue = summaries
+        .iter()
+        .flat_map(|s| &s.due)
+        .filter(|due| **due < now)
+        .count();
+    format!("{} open | {} running | {} overdue", open, running, overdue)
+}
@@ -0,0 +1,192 @@
+use std::io::{self, IsTerminal};
+
+use crossterm::terminal;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::appearance::{self, ProjectColor};
+
+// Listings printed as aligned columns. Cells can be colored and whole rows dimmed, and one
+// column (usually the title) is cut short with "…" so rows fit the terminal. Output that
+// isn't going to a terminal keeps every value whole.
+
+// Narrowest the shrinking column gets, so titles stay recognizable on tiny terminals
+const MIN_SHRUNK_WIDTH: usize = 12;
+const GAP: &str = "  ";
+
+pub struct Cell {
+    text: String,
+    color: Option<ProjectColor>,
+}
+
+impl Cell {
+    pub fn colored(text: impl Into<String>, color: Option<ProjectColor>) -> Self {
+        Cell {
+            text: text.into(),
+            color,
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::colored(text, None)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::colored(text, None)
+    }
+}
+
+struct Row {
+    cells: Vec<Cell>,
+    dim: bool,
+    // Lines shown under the row, such as a task's attachments
+    notes: Vec<String>,
+}
+
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Row>,
+    indent: usize,
+    // The column cut short when rows are wider than the terminal
+    shrink: Option<usize>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+            indent: 0,
+            shrink: None,
+        }
+    }
+
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn with_shrinking(mut self, column: usize) -> Self {
+        self.shrink = Some(column);
+        self
+    }
+
+    // Add a row; a dimmed one is drawn faint, without its cells' colors
+    pub fn row(&mut self, cells: Vec<Cell>, dim: bool) {
+        self.rows.push(Row {
+            cells,
+            dim,
+            notes: Vec::new(),
+        });
+    }
+
+    // A line under the last row added
+    pub fn note(&mut self, text: String) {
+        if let Some(row) = self.rows.last_mut() {
+            row.notes.push(text);
+        }
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render(terminal_width()));
+    }
+
+    fn render(&self, max_width: Option<usize>) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.width()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.cells.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(cell.text.width());
+            }
+        }
+        if let (Some(max_width), Some(column)) = (max_width, self.shrink) {
+            let total = self.indent
+                + widths.iter().sum::<usize>()
+                + GAP.len() * widths.len().saturating_sub(1);
+            if total > max_width && column < widths.len() {
+                let over = total - max_width;
+                widths[column] = widths[column]
+                    .saturating_sub(over)
+                    .max(MIN_SHRUNK_WIDTH.min(widths[column]));
+            }
+        }
+
+        let pad = " ".repeat(self.indent);
+        let last = widths.len().saturating_sub(1);
+        let mut out = String::new();
+        let header: Vec<String> = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, h)| fit(h, widths[i], i == last))
+            .collect();
+        out.push_str(&format!("{}{}\n", pad, header.join(GAP).trim_end()));
+
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .cells
+                .iter()
+                .enumerate()
+                .take(widths.len())
+                .map(|(i, cell)| {
+                    let text = fit(&cell.text, widths[i], i == last);
+                    if row.dim {
+                        return text;
+                    }
+                    // Padding stays outside the color so it doesn't count as text
+                    let trimmed = text.trim_end();
+                    format!(
+                        "{}{}",
+                        appearance::paint(trimmed, cell.color),
+                        &text[trimmed.len()..]
+                    )
+                })
+                .collect();
+            let line = cells.join(GAP);
+            let line = line.trim_end();
+            if row.dim {
+                out.push_str(&format!("{}{}\n", pad, appearance::dim(line)));
+            } else {
+                out.push_str(&format!("{}{}\n", pad, line));
+            }
+            for note in &row.notes {
+                out.push_str(&format!("{}  {}\n", pad, note));
+            }
+        }
+        out
+    }
+}
+
+// `text` cut or padded to `width` columns; the last column isn't padded
+fn fit(text: &str, width: usize, last: bool) -> String {
+    let mut fitted = if text.width() > width {
+        let mut cut = String::new();
+        let mut used = 0;
+        for c in text.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w + 1 > width {
+                break;
+            }
+            cut.push(c);
+            used += w;
+        }
+        cut.push('…');
+        cut
+    } else {
+        text.to_string()
+    };
+    if !last {
+        let used = fitted.width();
+        fitted.push_str(&" ".repeat(width.saturating_sub(used)));
+    }
+    fitted
+}
+
+fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    terminal::size().ok().map(|(width, _)| width as usize)
+}
@@ -1,37 +1,226 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::attachments::Attachment;
+use crate::error::TaskMasterError;
+use crate::follow_ups::FollowUpOrigin;
+use crate::habits::Habit;
+use crate::ids;
+use crate::periodic_tasks::RecurrencePattern;
+use crate::reminders::ReminderRule;
+use crate::task_dependencies::{self, Dependency};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum TaskStatus {
     ToDo,
     InProgress,
     Done,
+    // A project-specific state defined by the project's workflow
+    Custom(String),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskStatus::ToDo => write!(f, "ToDo"),
+            TaskStatus::InProgress => write!(f, "InProgress"),
+            TaskStatus::Done => write!(f, "Done"),
+            TaskStatus::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = TaskMasterError;
+
+    // Built-in names are matched loosely; anything else becomes a custom state
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        match name.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+            "" => Err(TaskMasterError::InvalidOperation(
+                "Status cannot be empty".to_string(),
+            )),
+            "todo" => Ok(TaskStatus::ToDo),
+            "inprogress" => Ok(TaskStatus::InProgress),
+            "done" => Ok(TaskStatus::Done),
+            _ => Ok(TaskStatus::Custom(name.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum TaskPriority {
     Low,
     Medium,
     High,
+    // A level on the project's numeric priority scale
+    Level(u8),
+}
+
+impl fmt::Display for TaskPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskPriority::Low => write!(f, "Low"),
+            TaskPriority::Medium => write!(f, "Medium"),
+            TaskPriority::High => write!(f, "High"),
+            TaskPriority::Level(level) => write!(f, "{}", level),
+        }
+    }
+}
+
+// A single status transition in a task's history
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusChange {
+    pub from: TaskStatus,
+    pub to: TaskStatus,
+    pub changed_at: DateTime<Utc>,
+}
+
+// Time logged against a task on a given day, for timesheets
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub date: NaiveDate,
+    pub hours: f64,
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+// A change to one of a task's fields other than its status, kept for `history`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldEdit {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+// One step of a task's checklist
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChecklistItem {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+impl ChecklistItem {
+    pub fn new(text: &str) -> Self {
+        ChecklistItem {
+            text: text.to_string(),
+            done: false,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Task {
     pub id: u32,
+    #[serde(default)]
+    pub uuid: String,
     pub title: String,
     pub status: TaskStatus,
     pub priority: TaskPriority,
-    pub dependencies: Option<Vec<u32>>, // IDs of tasks this task depends on
+    pub dependencies: Option<Vec<Dependency>>, // Tasks this task depends on
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub history: Vec<StatusChange>,
+    #[serde(default)]
+    pub edits: Vec<FieldEdit>,
+    #[serde(default)]
+    pub estimate_hours: Option<f64>,
+    // Time explicitly logged against the task
+    #[serde(default)]
+    pub logged_hours: f64,
+    // When and by whom that time was logged
+    #[serde(default)]
+    pub time_log: Vec<TimeEntry>,
+    // Actual / estimated effort, stored when the task is completed
+    #[serde(default)]
+    pub estimate_ratio: Option<f64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    // Set on subtasks created by splitting a larger task
+    #[serde(default)]
+    pub parent: Option<u32>,
+    // Completing a recurring task adds its next occurrence to the project
+    #[serde(default)]
+    pub recurrence: Option<RecurrencePattern>,
+    // Place in the project's manual order, as a fractional rank; lower comes first
+    #[serde(default)]
+    pub rank: String,
+    // The position older files stored instead of a rank; only read to rank them on load
+    #[serde(default, skip_serializing)]
+    pub sort_order: u32,
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+    // When work is planned to begin, e.g. as suggested by auto-scheduling
+    #[serde(default)]
+    pub planned_start: Option<DateTime<Utc>>,
+    // The task's own reminder schedule; None falls back to the configured defaults
+    #[serde(default)]
+    pub reminders: Option<Vec<ReminderRule>>,
+    #[serde(default)]
+    pub last_reminded: Option<DateTime<Utc>>,
+    // Manual block, e.g. waiting on a customer
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+    // User-defined metadata, typed by the project's field schema
+    #[serde(default)]
+    pub custom_fields: HashMap<String, String>,
+    // The project milestone this task counts towards
+    #[serde(default)]
+    pub milestone: Option<u32>,
+    // Track completions of this recurring task as a habit
+    #[serde(default)]
+    pub habit: Option<Habit>,
+    // Set on tasks a handler asked for after running another task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_up_of: Option<FollowUpOrigin>,
 }
 
 impl Task {
     pub fn new(id: u32, title: String, status: TaskStatus, priority: TaskPriority) -> Self {
+        let now = Utc::now();
         Task {
             id,
+            uuid: ids::new_uuid(),
             title,
             status,
             priority,
             dependencies: None,
+            attachments: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            history: Vec::new(),
+            edits: Vec::new(),
+            estimate_hours: None,
+            logged_hours: 0.0,
+            time_log: Vec::new(),
+            estimate_ratio: None,
+            tags: Vec::new(),
+            checklist: Vec::new(),
+            parent: None,
+            recurrence: None,
+            rank: String::new(),
+            sort_order: 0,
+            due: None,
+            planned_start: None,
+            reminders: None,
+            last_reminded: None,
+            blocked_reason: None,
+            custom_fields: HashMap::new(),
+            milestone: None,
+            habit: None,
+            follow_up_of: None,
         }
     }
 
@@ -41,55 +230,266 @@ impl Task {
         new_status: TaskStatus,
         new_priority: TaskPriority,
     ) {
+        self.record_edit("title", &self.title.clone(), &new_title);
+        self.record_edit(
+            "priority",
+            &self.priority.to_string(),
+            &new_priority.to_string(),
+        );
         self.title = new_title;
-        self.status = new_status;
+        self.set_status(new_status);
         self.priority = new_priority;
+        self.touch();
+    }
+
+    // Remember a field change for the task's history; unchanged values are ignored
+    pub fn record_edit(&mut self, field: &str, before: &str, after: &str) {
+        if before != after {
+            self.edits.push(FieldEdit {
+                field: field.to_string(),
+                before: before.to_string(),
+                after: after.to_string(),
+                changed_at: Utc::now(),
+            });
+        }
+    }
+
+    // Status changes and field edits together, oldest first
+    pub fn timeline(&self) -> Vec<FieldEdit> {
+        let mut timeline: Vec<FieldEdit> = self
+            .history
+            .iter()
+            .map(|change| FieldEdit {
+                field: "status".to_string(),
+                before: change.from.to_string(),
+                after: change.to.to_string(),
+                changed_at: change.changed_at,
+            })
+            .chain(self.edits.iter().cloned())
+            .collect();
+        timeline.sort_by_key(|edit| edit.changed_at);
+        timeline
+    }
+
+    // Change the status, recording the transition in the task's history
+    pub fn set_status(&mut self, new_status: TaskStatus) {
+        if self.status != new_status {
+            let now = Utc::now();
+            self.history.push(StatusChange {
+                from: self.status.clone(),
+                to: new_status.clone(),
+                changed_at: now,
+            });
+            self.status = new_status;
+            self.updated_at = now;
+
+            if self.status == TaskStatus::Done {
+                self.record_estimate_ratio();
+            }
+        }
+    }
+
+    // Tracked effort: logged time if any, otherwise the time spent InProgress
+    pub fn actual_hours(&self) -> f64 {
+        if self.logged_hours > 0.0 {
+            return self.logged_hours;
+        }
+
+        let mut total = 0.0;
+        let mut started: Option<DateTime<Utc>> = None;
+        for change in &self.history {
+            if change.to == TaskStatus::InProgress {
+                started = Some(change.changed_at);
+            } else if let Some(start) = started.take() {
+                total += (change.changed_at - start).num_seconds() as f64 / 3600.0;
+            }
+        }
+        total
+    }
+
+    fn record_estimate_ratio(&mut self) {
+        if let Some(estimate) = self.estimate_hours.filter(|e| *e > 0.0) {
+            let actual = self.actual_hours();
+            if actual > 0.0 {
+                self.estimate_ratio = Some(actual / estimate);
+            }
+        }
+    }
+
+    // A fresh copy of a recurring task to follow this one once it is done
+    pub fn next_occurrence(&self, id: u32, status: TaskStatus) -> Task {
+        let mut next = Task::new(id, self.title.clone(), status, self.priority.clone());
+        next.dependencies = self.dependencies.clone();
+        next.tags = self.tags.clone();
+        next.checklist = self
+            .checklist
+            .iter()
+            .map(|item| ChecklistItem::new(&item.text))
+            .collect();
+        next.estimate_hours = self.estimate_hours;
+        next.parent = self.parent;
+        next.recurrence = self.recurrence.clone();
+        next.reminders = self.reminders.clone();
+        next.custom_fields = self.custom_fields.clone();
+        next.milestone = self.milestone;
+        next.habit = self.habit.clone();
+        // The next occurrence falls due one period after this one
+        if let (Some(due), Some(pattern)) = (self.due, &self.recurrence) {
+            next.due = Some(pattern.get_next_occurrence(due.into()).into());
+        }
+        next
+    }
+
+    // Past its due date and not done yet
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.status != TaskStatus::Done && self.due.is_some_and(|due| due < now)
+    }
+
+    // Case-insensitive match on a custom field's value
+    pub fn field_matches(&self, name: &str, value: &str) -> bool {
+        self.custom_fields
+            .iter()
+            .any(|(key, v)| key.eq_ignore_ascii_case(name) && v.eq_ignore_ascii_case(value))
+    }
+
+    // When the task was last moved to Done; None while it is open
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        if self.status != TaskStatus::Done {
+            return None;
+        }
+        self.history
+            .iter()
+            .rev()
+            .find(|change| change.to == TaskStatus::Done)
+            .map(|change| change.changed_at)
+    }
+
+    // When work on the task began: its first move out of ToDo, or its creation if it never
+    // sat in ToDo. None while it is in ToDo.
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        if self.status == TaskStatus::ToDo {
+            return None;
+        }
+        self.history
+            .iter()
+            .find(|change| change.from == TaskStatus::ToDo && change.to != TaskStatus::ToDo)
+            .map_or(Some(self.created_at), |change| Some(change.changed_at))
+    }
+
+    // When the task last moved to In Progress; None unless it is in progress now
+    pub fn in_progress_since(&self) -> Option<DateTime<Utc>> {
+        if self.status != TaskStatus::InProgress {
+            return None;
+        }
+        self.history
+            .iter()
+            .rev()
+            .find(|change| change.to == TaskStatus::InProgress)
+            .map_or(Some(self.created_at), |change| Some(change.changed_at))
+    }
+
+    // How long the task has been in progress without moving on
+    pub fn wip_age(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.in_progress_since().map(|since| now - since)
+    }
+
+    // IDs of the tasks this one depends on, whatever the type of dependency
+    pub fn dependency_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.dependencies.iter().flatten().map(|dep| dep.task)
+    }
+
+    pub fn depends_on(&self, task_id: u32) -> bool {
+        self.dependency_ids().any(|id| id == task_id)
+    }
+
+    // Mark the task as modified now
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
     }
 
     pub fn display(&self) {
         println!(
-            "Task ID: {}, Title: {}, Status: {:?}, Priority: {:?}",
+            "Task ID: {}, Title: {}, Status: {}, Priority: {}",
             self.id, self.title, self.status, self.priority
         );
 
         if let Some(deps) = &self.dependencies {
             if !deps.is_empty() {
-                println!("  Dependencies: {:?}", deps);
+                println!("  Dependencies: {}", task_dependencies::format_list(deps));
             }
         }
+
+        for attachment in &self.attachments {
+            println!("  Attachment: {}", attachment.describe());
+        }
     }
 
-    // Add a method to check if this task can be started
-    pub fn can_start(&self, tasks: &[Task]) -> bool {
-        if let Some(deps) = &self.dependencies {
-            if deps.is_empty() {
-                return true;
-            }
+    // Dependencies that keep the task from starting now, such as a finish-to-start
+    // dependency that is not Done yet; a missing dependency also blocks, as in can_start
+    pub fn blocking_dependencies(&self, tasks: &[Task]) -> Vec<u32> {
+        let now = Utc::now();
+        let task_map: HashMap<u32, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
 
-            // Create a map for quick lookup
-            let task_map: HashMap<u32, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
-
-            // Check each dependency
-            for &dep_id in deps {
-                if let Some(&dep_task) = task_map.get(&dep_id) {
-                    if let TaskStatus::Done = dep_task.status {
-                        // This dependency is met
-                    } else {
-                        // This dependency is not met
-                        return false;
-                    }
-                } else {
-                    // Dependency task not found, consider it not met
-                    return false;
-                }
-            }
+        self.dependencies
+            .iter()
+            .flatten()
+            .filter(|dep| !dep.allows_start(task_map.get(&dep.task).copied(), now))
+            .map(|dep| dep.task)
+            .collect()
+    }
+
+    // Dependencies that keep the task from being finished now, e.g. a finish-to-finish
+    // dependency that is still open
+    pub fn finish_blockers(&self, tasks: &[Task]) -> Vec<u32> {
+        let now = Utc::now();
+        let task_map: HashMap<u32, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
+
+        self.dependencies
+            .iter()
+            .flatten()
+            .filter(|dep| !dep.allows_finish(task_map.get(&dep.task).copied(), now))
+            .map(|dep| dep.task)
+            .collect()
+    }
+
+    pub fn is_blocked(&self, tasks: &[Task]) -> bool {
+        self.status != TaskStatus::Done
+            && (self.blocked_reason.is_some() || !self.blocking_dependencies(tasks).is_empty())
+    }
+
+    // Status as shown to users, with the derived Blocked state and what causes it
+    pub fn status_label(&self, tasks: &[Task]) -> String {
+        if self.status == TaskStatus::Done {
+            return self.status.to_string();
+        }
 
-            true
+        let mut reasons = Vec::new();
+        if let Some(reason) = &self.blocked_reason {
+            reasons.push(reason.clone());
+        }
+        let blockers = self.blocking_dependencies(tasks);
+        if !blockers.is_empty() {
+            let ids: Vec<String> = blockers.iter().map(|id| id.to_string()).collect();
+            let noun = if ids.len() == 1 { "task" } else { "tasks" };
+            reasons.push(format!("waiting on {} {}", noun, ids.join(", ")));
+        }
+
+        if reasons.is_empty() {
+            self.status.to_string()
         } else {
-            // No dependencies, can start immediately
-            true
+            format!("{} (Blocked: {})", self.status, reasons.join("; "))
         }
     }
+
+    // Add a method to check if this task can be started
+    pub fn can_start(&self, tasks: &[Task]) -> bool {
+        self.blocking_dependencies(tasks).is_empty()
+    }
+
+    // Whether every dependency, of whatever type, lets the task be finished now
+    pub fn can_finish(&self, tasks: &[Task]) -> bool {
+        self.finish_blockers(tasks).is_empty()
+    }
 }
 
 pub struct TaskBuilder {
@@ -97,7 +497,7 @@ pub struct TaskBuilder {
     title: String,
     status: Option<TaskStatus>,
     priority: Option<TaskPriority>,
-    dependencies: Option<Vec<u32>>,
+    dependencies: Option<Vec<Dependency>>,
 }
 
 impl TaskBuilder {
@@ -123,25 +523,18 @@ impl TaskBuilder {
 
     pub fn dependency(mut self, dependency_id: u32) -> Self {
         let deps = self.dependencies.get_or_insert(Vec::new());
-        deps.push(dependency_id);
+        deps.push(Dependency::new(dependency_id));
         self
     }
 
     pub fn build(self) -> Task {
-        Task {
-            id: self.id,
-            title: self.title,
-            status: self.status.unwrap_or(TaskStatus::ToDo),
-            priority: self.priority.unwrap_or(TaskPriority::Medium),
-            dependencies: if let Some(deps) = self.dependencies {
-                if deps.is_empty() {
-                    None
-                } else {
-                    Some(deps)
-                }
-            } else {
-                None
-            },
-        }
+        let mut task = Task::new(
+            self.id,
+            self.title,
+            self.status.unwrap_or(TaskStatus::ToDo),
+            self.priority.unwrap_or(TaskPriority::Medium),
+        );
+        task.dependencies = self.dependencies.filter(|deps| !deps.is_empty());
+        task
     }
 }
@@ -1,16 +1,295 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
 
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dates;
 use crate::error::{Result, TaskMasterError};
 use crate::task::Task;
 
+// How a dependency constrains the task waiting on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum DependencyKind {
+    // Start once the dependency is finished
+    #[default]
+    FinishToStart,
+    // Start once the dependency has started
+    StartToStart,
+    // Finish only after the dependency is finished
+    FinishToFinish,
+    // Finish only after the dependency has started
+    StartToFinish,
+}
+
+impl DependencyKind {
+    // "fs", "ss", "ff" or "sf", or the names spelled out
+    pub fn parse(input: &str) -> Result<Self> {
+        match input
+            .trim()
+            .to_lowercase()
+            .replace(['_', '-', ' '], "")
+            .as_str()
+        {
+            "fs" | "finishtostart" => Ok(DependencyKind::FinishToStart),
+            "ss" | "starttostart" => Ok(DependencyKind::StartToStart),
+            "ff" | "finishtofinish" => Ok(DependencyKind::FinishToFinish),
+            "sf" | "starttofinish" => Ok(DependencyKind::StartToFinish),
+            _ => Err(TaskMasterError::InvalidOperation(format!(
+                "Unknown dependency type '{}'; use fs, ss, ff or sf",
+                input
+            ))),
+        }
+    }
+
+    // Whether the dependency has to be finished, rather than just started
+    fn needs_finish(self) -> bool {
+        matches!(
+            self,
+            DependencyKind::FinishToStart | DependencyKind::FinishToFinish
+        )
+    }
+
+    // Whether the constraint holds back the start of the dependent, rather than its finish
+    fn gates_start(self) -> bool {
+        matches!(
+            self,
+            DependencyKind::FinishToStart | DependencyKind::StartToStart
+        )
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyKind::FinishToStart => write!(f, "FS"),
+            DependencyKind::StartToStart => write!(f, "SS"),
+            DependencyKind::FinishToFinish => write!(f, "FF"),
+            DependencyKind::StartToFinish => write!(f, "SF"),
+        }
+    }
+}
+
+// What deleting a task does to the tasks that depend on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanPolicy {
+    // Keep the task if anything depends on it
+    Refuse,
+    // Drop the dependents' edges to it
+    Cascade,
+    // Replace their edges to it with its own dependencies, so the order is kept
+    Transfer,
+}
+
+// What happens to a task's dependents when it is finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Propagation {
+    // Nothing; dependents simply stop showing as Blocked
+    #[default]
+    Off,
+    // Announce each dependent that can now start
+    Notify,
+    // Announce them and move those sitting in a "Blocked" workflow status back to the
+    // initial status
+    Unblock,
+}
+
+impl Propagation {
+    pub fn parse(input: &str) -> Result<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "off" | "none" => Ok(Propagation::Off),
+            "notify" => Ok(Propagation::Notify),
+            "unblock" => Ok(Propagation::Unblock),
+            _ => Err(TaskMasterError::InvalidOperation(format!(
+                "Unknown propagation '{}'; use off, notify or unblock",
+                input
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Propagation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Propagation::Off => write!(f, "off"),
+            Propagation::Notify => write!(f, "notify"),
+            Propagation::Unblock => write!(f, "unblock"),
+        }
+    }
+}
+
+// An edge from a task to one it depends on. Older project files stored bare task IDs,
+// which still load as finish-to-start without lag.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredDependency")]
+pub struct Dependency {
+    pub task: u32,
+    #[serde(default)]
+    pub kind: DependencyKind,
+    // Extra wait after the dependency starts or finishes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lag_minutes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredDependency {
+    Id(u32),
+    Edge {
+        task: u32,
+        #[serde(default)]
+        kind: DependencyKind,
+        #[serde(default)]
+        lag_minutes: Option<i64>,
+    },
+}
+
+impl From<StoredDependency> for Dependency {
+    fn from(stored: StoredDependency) -> Self {
+        match stored {
+            StoredDependency::Id(task) => Dependency::new(task),
+            StoredDependency::Edge {
+                task,
+                kind,
+                lag_minutes,
+            } => Dependency {
+                task,
+                kind,
+                lag_minutes,
+            },
+        }
+    }
+}
+
+impl Dependency {
+    // Finish-to-start without lag, the usual "do this first"
+    pub fn new(task: u32) -> Self {
+        Dependency {
+            task,
+            kind: DependencyKind::FinishToStart,
+            lag_minutes: None,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: DependencyKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_lag(mut self, lag: Option<Duration>) -> Self {
+        self.lag_minutes = lag.map(|lag| lag.num_minutes()).filter(|m| *m != 0);
+        self
+    }
+
+    pub fn lag(&self) -> Duration {
+        Duration::minutes(self.lag_minutes.unwrap_or(0))
+    }
+
+    // When the dependency stops holding its dependent back, once it has started or finished
+    // as its type requires; None while that hasn't happened, or if the task is missing
+    pub fn satisfied_at(&self, dependency: Option<&Task>) -> Option<DateTime<Utc>> {
+        let dependency = dependency?;
+        let event = if self.kind.needs_finish() {
+            dependency.completed_at()
+        } else {
+            dependency.started_at()
+        };
+        event.map(|at| at + self.lag())
+    }
+
+    // Whether the dependent may start at `now`; finish constraints never hold back a start
+    pub fn allows_start(&self, dependency: Option<&Task>, now: DateTime<Utc>) -> bool {
+        !self.kind.gates_start() || self.allows_finish(dependency, now)
+    }
+
+    // Whether the dependent may be finished at `now`
+    pub fn allows_finish(&self, dependency: Option<&Task>, now: DateTime<Utc>) -> bool {
+        self.satisfied_at(dependency).is_some_and(|at| at <= now)
+    }
+
+    // The type and lag, e.g. "SS" or "FS +2d"; None for a plain finish-to-start
+    pub fn label(&self) -> Option<String> {
+        match (self.kind, self.lag_minutes) {
+            (DependencyKind::FinishToStart, None) => None,
+            (kind, None) => Some(kind.to_string()),
+            (kind, Some(_)) => Some(format!("{} +{}", kind, dates::format_duration(self.lag()))),
+        }
+    }
+}
+
+// e.g. "3", "3 (SS)" or "3 (FS +2d)"
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label() {
+            Some(label) => write!(f, "{} ({})", self.task, label),
+            None => write!(f, "{}", self.task),
+        }
+    }
+}
+
+// e.g. "[2, 3 (SS +1d)]", as shown next to a task
+pub fn format_list(dependencies: &[Dependency]) -> String {
+    let items: Vec<String> = dependencies.iter().map(|dep| dep.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+// Every dependency cycle among `tasks`, read straight from their dependency lists so that
+// data the graph would refuse (e.g. a hand-edited project file) can still be inspected. Each
+// cycle starts and ends at its lowest task ID, and at most `limit` are returned.
+pub fn find_cycles(tasks: &[Task], limit: usize) -> Vec<Vec<u32>> {
+    let mut edges: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for task in tasks {
+        let mut deps: Vec<u32> = task.dependency_ids().collect();
+        deps.sort_unstable();
+        deps.dedup();
+        edges.insert(task.id, deps);
+    }
+
+    // Search from each task for paths back to it through higher IDs only, so every cycle
+    // is found once, from its lowest ID
+    fn search(
+        start: u32,
+        node: u32,
+        edges: &BTreeMap<u32, Vec<u32>>,
+        path: &mut Vec<u32>,
+        cycles: &mut Vec<Vec<u32>>,
+        limit: usize,
+    ) {
+        for &next in edges.get(&node).into_iter().flatten() {
+            if cycles.len() >= limit {
+                return;
+            }
+            if next == start {
+                let mut cycle = path.clone();
+                cycle.push(start);
+                cycles.push(cycle);
+            } else if next > start && !path.contains(&next) {
+                path.push(next);
+                search(start, next, edges, path, cycles, limit);
+                path.pop();
+            }
+        }
+    }
+
+    let mut cycles = Vec::new();
+    for &start in edges.keys() {
+        if cycles.len() >= limit {
+            break;
+        }
+        search(start, start, &edges, &mut vec![start], &mut cycles, limit);
+    }
+    cycles
+}
+
 // Represents a directed graph of task dependencies
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct DependencyGraph {
     // Maps from task ID to the IDs of tasks that depend on it
     dependents: HashMap<u32, HashSet<u32>>,
 
-    // Maps from task ID to the IDs of tasks it depends on
-    dependencies: HashMap<u32, HashSet<u32>>,
+    // Maps from task ID to the edges to tasks it depends on, keyed by their IDs
+    dependencies: HashMap<u32, HashMap<u32, Dependency>>,
 }
 
 impl DependencyGraph {
@@ -21,8 +300,39 @@ impl DependencyGraph {
         }
     }
 
-    // Add a dependency relationship: task_id depends on dependency_id
-    pub fn add_dependency(&mut self, task_id: u32, dependency_id: u32) -> Result<()> {
+    // The graph of every dependency on the given tasks; fails if they contain a cycle
+    pub fn from_tasks(tasks: &[Task]) -> Result<Self> {
+        let mut graph = DependencyGraph::new();
+        for task in tasks {
+            graph.add_task(task)?;
+        }
+        Ok(graph)
+    }
+
+    // Add the edges from a task to everything it depends on
+    pub fn add_task(&mut self, task: &Task) -> Result<()> {
+        for dep in task.dependencies.iter().flatten() {
+            self.add_dependency(task.id, *dep)?;
+        }
+        Ok(())
+    }
+
+    // Drop a task along with every edge to or from it
+    pub fn remove_task(&mut self, task_id: u32) {
+        for dependency_id in self.get_dependencies(task_id) {
+            self.remove_dependency(task_id, dependency_id);
+        }
+        for dependent_id in self.get_dependents(task_id) {
+            self.remove_dependency(dependent_id, task_id);
+        }
+        self.dependencies.remove(&task_id);
+        self.dependents.remove(&task_id);
+    }
+
+    // Add a dependency relationship: task_id depends on dependency.task. Adding an edge
+    // that already exists replaces its type and lag.
+    pub fn add_dependency(&mut self, task_id: u32, dependency: Dependency) -> Result<()> {
+        let dependency_id = dependency.task;
         if task_id == dependency_id {
             return Err(TaskMasterError::InvalidOperation(
                 "A task cannot depend on itself".to_string(),
@@ -30,22 +340,22 @@ impl DependencyGraph {
         }
 
         // Check for circular dependency before adding
-        if self.would_create_cycle(task_id, dependency_id) {
-            return Err(TaskMasterError::InvalidOperation(
-                "Adding this dependency would create a cycle".to_string(),
-            ));
+        if let Some(path) = self.dependency_path(dependency_id, task_id) {
+            let mut cycle = vec![task_id];
+            cycle.extend(path);
+            return Err(TaskMasterError::DependencyCycle(cycle));
         }
 
         // Add to dependencies map
         self.dependencies
             .entry(task_id)
-            .or_insert_with(HashSet::new)
-            .insert(dependency_id);
+            .or_default()
+            .insert(dependency_id, dependency);
 
         // Add to dependents map
         self.dependents
             .entry(dependency_id)
-            .or_insert_with(HashSet::new)
+            .or_default()
             .insert(task_id);
 
         Ok(())
@@ -69,43 +379,66 @@ impl DependencyGraph {
 
     // Get all tasks that the given task directly depends on
     pub fn get_dependencies(&self, task_id: u32) -> HashSet<u32> {
-        self.dependencies.get(&task_id).cloned().unwrap_or_default()
+        self.dependencies
+            .get(&task_id)
+            .map(|edges| edges.keys().copied().collect())
+            .unwrap_or_default()
     }
 
-    // Check if adding a dependency would create a cycle
-    fn would_create_cycle(&self, task_id: u32, new_dependency_id: u32) -> bool {
-        // If new_dependency_id depends on task_id (directly or indirectly),
-        // adding this dependency would create a cycle
-        let mut visited = HashSet::new();
-        let mut queue = VecDeque::new();
+    // The edges from the given task to its dependencies, by dependency ID
+    pub fn get_edges(&self, task_id: u32) -> Vec<Dependency> {
+        let mut edges: Vec<Dependency> = self
+            .dependencies
+            .get(&task_id)
+            .map(|edges| edges.values().copied().collect())
+            .unwrap_or_default();
+        edges.sort_by_key(|edge| edge.task);
+        edges
+    }
 
-        // Start from the new dependency
-        queue.push_back(new_dependency_id);
+    // The shortest chain of dependencies leading from `from` to `to`, both included, if
+    // `from` depends on `to` directly or indirectly
+    fn dependency_path(&self, from: u32, to: u32) -> Option<Vec<u32>> {
+        let mut came_from: HashMap<u32, u32> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
 
         while let Some(current) = queue.pop_front() {
-            if current == task_id {
-                // Found a path back to task_id, which would create a cycle
-                return true;
+            if current == to {
+                let mut path = vec![to];
+                let mut node = to;
+                while let Some(&previous) = came_from.get(&node) {
+                    path.push(previous);
+                    node = previous;
+                }
+                path.reverse();
+                return Some(path);
             }
 
-            if visited.insert(current) {
-                // Add all the dependents of current to the queue
-                if let Some(deps) = self.dependents.get(&current) {
-                    for &dep in deps {
-                        queue.push_back(dep);
-                    }
+            // Follow what current depends on in turn, in ID order so the path is stable
+            let mut deps: Vec<u32> = self
+                .dependencies
+                .get(&current)
+                .map(|edges| edges.keys().copied().collect())
+                .unwrap_or_default();
+            deps.sort_unstable();
+            for dep in deps {
+                if visited.insert(dep) {
+                    came_from.insert(dep, current);
+                    queue.push_back(dep);
                 }
             }
         }
 
-        false
+        None
     }
 
     // Get a topological ordering of tasks (if no cycles exist)
     // Fixed to avoid lifetime issues by accepting a reference to tasks and returning task IDs
     pub fn get_execution_order(&self, tasks: &[Task]) -> Result<Vec<u32>> {
         let mut result = Vec::new();
-        let mut temp_marks = HashSet::new();
+        // The path being explored, so a cycle can be reported in full
+        let mut temp_marks = Vec::new();
         let mut perm_marks = HashSet::new();
 
         // Create a set of all task IDs for easy checking
@@ -115,28 +448,27 @@ impl DependencyGraph {
         fn visit(
             node: u32,
             graph: &DependencyGraph,
-            temp_marks: &mut HashSet<u32>,
+            temp_marks: &mut Vec<u32>,
             perm_marks: &mut HashSet<u32>,
             result: &mut Vec<u32>,
             task_ids: &HashSet<u32>,
         ) -> Result<()> {
-            if temp_marks.contains(&node) {
+            if let Some(start) = temp_marks.iter().position(|&id| id == node) {
                 // Cycle detected
-                return Err(TaskMasterError::InvalidOperation(format!(
-                    "Circular dependency detected involving task {}",
-                    node
-                )));
+                let mut cycle = temp_marks[start..].to_vec();
+                cycle.push(node);
+                return Err(TaskMasterError::DependencyCycle(cycle));
             }
 
             if !perm_marks.contains(&node) {
-                temp_marks.insert(node);
+                temp_marks.push(node);
 
                 // Visit all dependencies
                 for &dep in &graph.get_dependencies(node) {
                     visit(dep, graph, temp_marks, perm_marks, result, task_ids)?;
                 }
 
-                temp_marks.remove(&node);
+                temp_marks.pop();
                 perm_marks.insert(node);
 
                 // Add to result if this task is in our input list
@@ -165,30 +497,113 @@ impl DependencyGraph {
         Ok(result)
     }
 
-    // Check if all dependencies of a task are met (i.e., all dependencies are complete)
-    pub fn are_dependencies_met(&self, task_id: u32, tasks: &[Task]) -> bool {
-        let dependencies = self.get_dependencies(task_id);
-        if dependencies.is_empty() {
-            return true;
+    // Group `tasks` into waves that can run concurrently: a task goes in the wave after the
+    // latest of its dependencies, whatever their type. Dependencies on tasks not in `tasks`
+    // (e.g. ones already done) are treated as met. Within a wave, tasks keep their order.
+    pub fn execution_levels(&self, tasks: &[Task]) -> Vec<Vec<u32>> {
+        let task_ids: HashSet<u32> = tasks.iter().map(|t| t.id).collect();
+        let mut levels: HashMap<u32, usize> = HashMap::new();
+
+        // Each pass places the tasks whose dependencies are all placed; the graph is acyclic,
+        // so every pass places at least one task
+        while levels.len() < tasks.len() {
+            let placed_before = levels.len();
+            for task in tasks {
+                if levels.contains_key(&task.id) {
+                    continue;
+                }
+                let deps: Vec<u32> = self
+                    .get_dependencies(task.id)
+                    .into_iter()
+                    .filter(|dep| task_ids.contains(dep))
+                    .collect();
+                if deps.iter().all(|dep| levels.contains_key(dep)) {
+                    let level = deps.iter().map(|dep| levels[dep] + 1).max().unwrap_or(0);
+                    levels.insert(task.id, level);
+                }
+            }
+            if levels.len() == placed_before {
+                break;
+            }
         }
 
+        let mut waves: Vec<Vec<u32>> = Vec::new();
+        for task in tasks {
+            if let Some(&level) = levels.get(&task.id) {
+                if waves.len() <= level {
+                    waves.resize(level + 1, Vec::new());
+                }
+                waves[level].push(task.id);
+            }
+        }
+        waves
+    }
+
+    // Check if a task may start at `now`: finish-to-start dependencies must be done and
+    // start-to-start ones under way, each plus its lag. A missing dependency is never met.
+    pub fn are_dependencies_met(&self, task_id: u32, tasks: &[Task], now: DateTime<Utc>) -> bool {
         let task_map: HashMap<u32, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
+        self.get_edges(task_id)
+            .iter()
+            .all(|edge| edge.allows_start(task_map.get(&edge.task).copied(), now))
+    }
 
-        for &dep_id in &dependencies {
-            if let Some(&dep_task) = task_map.get(&dep_id) {
-                // Check if dependency is done
-                if let crate::task::TaskStatus::Done = dep_task.status {
-                    // Dependency is met
-                } else {
-                    // Dependency is not met
-                    return false;
-                }
-            } else {
-                // Dependency task not found, consider it not met
-                return false;
+    // Check if a task may be finished at `now`, which every type of dependency constrains
+    pub fn can_finish(&self, task_id: u32, tasks: &[Task], now: DateTime<Utc>) -> bool {
+        let task_map: HashMap<u32, &Task> = tasks.iter().map(|t| (t.id, t)).collect();
+        self.get_edges(task_id)
+            .iter()
+            .all(|edge| edge.allows_finish(task_map.get(&edge.task).copied(), now))
+    }
+}
+
+// A project's dependency graph, built from its tasks on first use and then kept up to date
+// as tasks and edges are added or removed, so large projects don't rebuild it for every
+// operation. It is not saved: the edges already live on the tasks. Clearing it makes the
+// next use rebuild it, which is how changes made outside the incremental paths are picked up.
+#[derive(Default)]
+pub struct GraphCache(Mutex<Option<DependencyGraph>>);
+
+impl GraphCache {
+    // Run `f` on the graph, building it from `tasks` first if needed
+    pub fn with<R>(
+        &self,
+        tasks: &[Task],
+        f: impl FnOnce(&mut DependencyGraph) -> Result<R>,
+    ) -> Result<R> {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let graph = match cached.as_mut() {
+            Some(graph) => graph,
+            None => cached.insert(DependencyGraph::from_tasks(tasks)?),
+        };
+        f(graph)
+    }
+
+    // Apply a change to the graph if it has been built; otherwise the next build sees it
+    pub fn update(&self, f: impl FnOnce(&mut DependencyGraph) -> Result<()>) {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(graph) = cached.as_mut() {
+            if f(graph).is_err() {
+                *cached = None;
             }
         }
+    }
+
+    pub fn clear(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+impl Clone for GraphCache {
+    fn clone(&self) -> Self {
+        let cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        GraphCache(Mutex::new(cached.clone()))
+    }
+}
 
-        true
+impl fmt::Debug for GraphCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let built = self.0.lock().is_ok_and(|cached| cached.is_some());
+        write!(f, "GraphCache {{ built: {} }}", built)
     }
 }
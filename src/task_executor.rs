@@ -1,103 +1,505 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
-use std::thread;
 use std::time::{Duration, Instant};
 
+use tokio::sync::mpsc;
+
+use crate::async_executor::TaskEvent;
+use crate::cancellation::CancellationFlag;
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosMonkey;
+use crate::correlation::RunId;
 use crate::error::{Result, TaskMasterError};
-use crate::task::{Task};
-use crate::worker_pool::{JobResult, TaskJob, WorkerPool};
+use crate::project::Project;
+use crate::ready_queue::{Next, ReadyQueue, ReadyQueuePolicy};
+use crate::task::{Task, TaskStatus};
+use crate::task_handler::{TaskHandler, TaskHandlerRegistry};
+use crate::worker_pool::{panic_message, JobOutcome, JobResult, TaskJob, WorkerPool};
+
+// How long the ready-queue loop waits for results before checking for timeouts again
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// A task whose job hasn't returned yet
+struct RunningTask {
+    task_id: u32,
+    started: Instant,
+    cancel: CancellationFlag,
+}
+
+// What a handler run's follow-ups are asked of once its outcome is known
+struct FollowUpSource {
+    handler: Box<dyn TaskHandler>,
+    task: Task,
+}
 
 pub struct TaskExecutor {
     worker_pool: WorkerPool,
-    running_tasks: Arc<Mutex<HashMap<u32, Instant>>>,
+    workers: usize,
+    // By run, since tasks of different projects can share an ID
+    running_tasks: Arc<Mutex<HashMap<RunId, RunningTask>>>,
+    // Why runs stopped counting as running before their jobs returned: timed out or
+    // cancelled. Only changed with `running_tasks` locked, so a job can't miss its entry.
+    interrupted: Arc<Mutex<HashMap<RunId, JobOutcome>>>,
     timeout: Duration,
+    // How many more times a failed handler is run before its failure is reported
+    retries: u32,
+    // Where each job's outcome is also announced, for listeners such as NotificationSystem
+    events: Option<mpsc::Sender<TaskEvent>>,
+    // Handler runs whose results haven't been handed out yet
+    follow_up_sources: Mutex<HashMap<RunId, FollowUpSource>>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<ChaosMonkey>>,
 }
 
 impl TaskExecutor {
     pub fn new(thread_count: usize, timeout_seconds: u64) -> Self {
-        let worker_pool = WorkerPool::new(thread_count);
+        let workers = thread_count.max(1);
+        let worker_pool = WorkerPool::new(workers);
         let running_tasks = Arc::new(Mutex::new(HashMap::new()));
         let timeout = Duration::from_secs(timeout_seconds);
 
         TaskExecutor {
             worker_pool,
+            workers,
             running_tasks,
+            interrupted: Arc::new(Mutex::new(HashMap::new())),
             timeout,
+            retries: 0,
+            events: None,
+            follow_up_sources: Mutex::new(HashMap::new()),
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
+
+    // Announce every job's outcome on the event bus as well as in its JobResult
+    pub fn with_event_bus(mut self, events: mpsc::Sender<TaskEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    // Run a handler that returns an error up to `retries` more times, within the same job.
+    // Panics, cancelled runs and runs over the timeout aren't retried.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    // Inject faults into every handler this executor runs
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Arc<ChaosMonkey>) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    // Submit a task and return the run ID that identifies this execution
+    pub fn execute_task(&self, task: Task) -> Result<RunId> {
+        self.submit(task, |_, cancel| {
+            // Simulate task execution
+            cancel.sleep(Duration::from_secs(2))?;
+            Ok(Vec::new())
+        })
+    }
+
+    // Submit a task to be run by a specific handler from a registry. Its JobResult carries
+    // the follow-ups the handler asks for.
+    pub fn execute_with_handler(&self, task: Task, handler: Box<dyn TaskHandler>) -> Result<RunId> {
+        let source = FollowUpSource {
+            handler: handler.clone_box(),
+            task: task.clone(),
+        };
+        let run_id = self.submit(task, move |task, cancel| {
+            handler.execute(task, cancel)?;
+            Ok(vec![format!("Handled by {}", handler.name())])
+        })?;
+        // Results are only handed out on this thread, so this is in before anyone asks
+        self.follow_up_sources
+            .lock()
+            .unwrap()
+            .insert(run_id.clone(), source);
+        Ok(run_id)
+    }
+
+    // Run every open task in the project, each once the open tasks it depends on have
+    // finished. `policy` orders the tasks that are ready, and one is submitted whenever a
+    // worker is free. Tasks whose dependencies failed are skipped and reported as failed, and
+    // a run over the timeout is reported as timed out without waiting for its handler.
+    // Results come back in the order the jobs finished.
+    pub fn execute_project(
+        &self,
+        project: &Project,
+        policy: &dyn ReadyQueuePolicy,
+    ) -> Result<Vec<JobResult>> {
+        let results = self.execute_ready(&[project], policy, |task| self.execute_task(task))?;
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+
+    // Like `execute_project` for several projects at once, with each task run by its handler
+    // from the registry; a task no handler accepts fails the run before anything is
+    // submitted. Each project keeps to its `max_concurrent` setting, and free workers go to
+    // the projects in turn so one with many ready tasks doesn't hold up the others. Results
+    // come back with the ID of the project they belong to.
+    pub fn execute_projects_with(
+        &self,
+        projects: &[&Project],
+        registry: &TaskHandlerRegistry,
+        policy: &dyn ReadyQueuePolicy,
+    ) -> Result<Vec<(u32, JobResult)>> {
+        for task in projects
+            .iter()
+            .flat_map(|p| &p.tasks)
+            .filter(|t| t.status != TaskStatus::Done)
+        {
+            if !registry.has_handler(task) {
+                return Err(TaskMasterError::InvalidOperation(format!(
+                    "No handler available for task: {}",
+                    task.id
+                )));
+            }
+        }
+        self.execute_ready(projects, policy, |task| {
+            let handler = registry
+                .get_handler_for_task(&task)
+                .map(|h| h.clone_box())
+                .ok_or_else(|| {
+                    TaskMasterError::InvalidOperation(format!(
+                        "No handler available for task: {}",
+                        task.id
+                    ))
+                })?;
+            self.execute_with_handler(task, handler)
+        })
+    }
+
+    fn execute_ready<F>(
+        &self,
+        projects: &[&Project],
+        policy: &dyn ReadyQueuePolicy,
+        submit: F,
+    ) -> Result<Vec<(u32, JobResult)>>
+    where
+        F: Fn(Task) -> Result<RunId>,
+    {
+        let mut queues = Vec::new();
+        for project in projects {
+            let queue = ReadyQueue::new(project, policy)?;
+            let limit = project
+                .settings
+                .max_concurrent
+                .map_or(self.workers, |n| n.max(1));
+            crate::log_event!(
+                Info,
+                "Running {} open task(s) of project {} on up to {} worker(s), {}",
+                queue.len(),
+                project.id,
+                limit.min(self.workers),
+                policy.name()
+            );
+            queues.push((queue, limit));
+        }
+        let mut in_flight = vec![0; projects.len()];
+        // Which project each submitted run belongs to
+        let mut owners: HashMap<RunId, usize> = HashMap::new();
+        // Runs reported as timed out whose jobs may still return
+        let mut timed_out: HashSet<RunId> = HashSet::new();
+        let mut results = Vec::new();
+        loop {
+            // One task per project per pass, while workers are free
+            let mut started = true;
+            while started && owners.len() < self.workers {
+                started = false;
+                for (index, (queue, limit)) in queues.iter_mut().enumerate() {
+                    if owners.len() >= self.workers || in_flight[index] >= *limit {
+                        continue;
+                    }
+                    let project = projects[index];
+                    match queue.pop() {
+                        Some(Next::Run(id)) => {
+                            let run_id = submit(project.get_task(id)?.clone())?;
+                            owners.insert(run_id, index);
+                            in_flight[index] += 1;
+                        }
+                        Some(Next::Skip(id, dep)) => results.push((
+                            project.id,
+                            JobResult {
+                                task_id: id,
+                                run_id: RunId::new(),
+                                outcome: JobOutcome::Failed(TaskMasterError::InvalidOperation(
+                                    format!("Skipped because task {} failed", dep),
+                                )),
+                                follow_ups: Vec::new(),
+                            },
+                        )),
+                        None => continue,
+                    }
+                    started = true;
+                }
+            }
+            if owners.is_empty() {
+                break;
+            }
+
+            // Wait a little at a time, so runs over the time limit are noticed even while
+            // nothing finishes
+            let mut finished = Vec::new();
+            for result in self.collect_results_timeout(POLL_INTERVAL) {
+                if owners.contains_key(&result.run_id) {
+                    finished.push(result);
+                } else if timed_out.remove(&result.run_id) {
+                    crate::log_event!(
+                        Info,
+                        "[{}] Task {} returned after it was timed out",
+                        result.run_id,
+                        result.task_id
+                    );
+                } else {
+                    crate::log_event!(
+                        Warn,
+                        "[{}] Result for task {} matches no run of this batch: {}",
+                        result.run_id,
+                        result.task_id,
+                        result.outcome
+                    );
+                }
+            }
+            // A handler that ignores cancellation may never return, so its run is reported
+            // as timed out now rather than waited for
+            for (run_id, task_id) in self.time_out_runs() {
+                if owners.contains_key(&run_id) {
+                    timed_out.insert(run_id.clone());
+                    finished.push(self.with_follow_ups(JobResult {
+                        task_id,
+                        run_id,
+                        outcome: JobOutcome::TimedOut,
+                        follow_ups: Vec::new(),
+                    }));
+                }
+            }
+            for result in finished {
+                let Some(index) = owners.remove(&result.run_id) else {
+                    continue;
+                };
+                in_flight[index] -= 1;
+                queues[index]
+                    .0
+                    .finish(result.task_id, result.outcome.is_success());
+                results.push((projects[index].id, result));
+            }
         }
+        Ok(results)
     }
 
-    pub fn execute_task(&self, task: Task) -> Result<()> {
+    fn submit<F>(&self, task: Task, work: F) -> Result<RunId>
+    where
+        F: Fn(&Task, &CancellationFlag) -> Result<Vec<String>> + Send + 'static,
+    {
         let task_id = task.id;
+        let run_id = RunId::new();
         let task_arc = Arc::new(task);
+        let cancel = CancellationFlag::new();
 
         // Mark the task as running
         {
             let mut running = self.running_tasks.lock().unwrap();
-            running.insert(task_id, Instant::now());
+            running.insert(
+                run_id.clone(),
+                RunningTask {
+                    task_id,
+                    started: Instant::now(),
+                    cancel: cancel.clone(),
+                },
+            );
         }
 
         // Clone for the closure
         let running_tasks = Arc::clone(&self.running_tasks);
+        let interrupted = Arc::clone(&self.interrupted);
+        let job_run_id = run_id.clone();
+        let events = self.events.clone();
+        let retries = self.retries;
+        #[cfg(feature = "chaos")]
+        let chaos = self.chaos.clone();
 
         let job = TaskJob {
             id: task_id,
+            run_id: run_id.clone(),
             task: Arc::clone(&task_arc),
-            handler: Box::new(move |task| {
-                // Simulate task execution
-                println!("Executing task: {}", task.title);
-                thread::sleep(Duration::from_secs(2));
+            cancel,
+            handler: Box::new(move |task, cancel| {
+                let attempt = || {
+                    #[cfg(feature = "chaos")]
+                    let injected = chaos
+                        .as_ref()
+                        .map_or(Ok(()), |chaos| chaos.before_handler(task_id));
+                    #[cfg(not(feature = "chaos"))]
+                    let injected: Result<()> = Ok(());
 
-                // Mark the task as completed
-                {
+                    injected.and_then(|_| {
+                        crate::log_event!(Info, "[{}] Executing task: {}", job_run_id, task.title);
+                        work(&task, &cancel)
+                    })
+                };
+
+                // Caught here as well as by the pool, so a panicking task isn't left
+                // marked as running
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    let mut retried = 0;
+                    loop {
+                        match attempt() {
+                            Err(e) if retried < retries && !cancel.is_cancelled() => {
+                                retried += 1;
+                                crate::log_event!(
+                                    Warn,
+                                    "[{}] Task {} failed: {}; retrying ({} of {})",
+                                    job_run_id,
+                                    task_id,
+                                    e,
+                                    retried,
+                                    retries
+                                );
+                            }
+                            result => break result,
+                        }
+                    }
+                }));
+
+                // Mark the task as completed, unless it was timed out or cancelled already
+                let interruption = {
                     let mut running = running_tasks.lock().unwrap();
-                    running.remove(&task_id);
+                    running.remove(&job_run_id);
+                    interrupted.lock().unwrap().remove(&job_run_id)
+                };
+                let outcome = match (interruption, result) {
+                    (Some(interruption), _) => interruption,
+                    (None, Ok(Ok(outputs))) => JobOutcome::Success { outputs },
+                    (None, Ok(Err(e))) => JobOutcome::Failed(e),
+                    (None, Err(payload)) => JobOutcome::Panicked(panic_message(payload.as_ref())),
+                };
+
+                if let Some(events) = &events {
+                    // Never hold up a worker for a slow listener
+                    let event = outcome.event(task_id, job_run_id.clone());
+                    if let Err(e) = events.try_send(event) {
+                        crate::log_event!(Warn, "[{}] Event not delivered: {}", job_run_id, e);
+                    }
                 }
 
-                Ok(())
+                outcome
             }),
         };
 
-        self.worker_pool.execute(job)
+        self.worker_pool.execute(job)?;
+        Ok(run_id)
     }
 
+    // Stop every run of the task
     pub fn cancel_task(&self, task_id: u32) -> Result<()> {
         let mut running = self.running_tasks.lock().unwrap();
-        if running.remove(&task_id).is_some() {
-            Ok(())
-        } else {
-            Err(TaskMasterError::TaskNotFound(task_id))
+        let runs: Vec<RunId> = running
+            .iter()
+            .filter(|(_, task)| task.task_id == task_id)
+            .map(|(run_id, _)| run_id.clone())
+            .collect();
+        if runs.is_empty() {
+            return Err(TaskMasterError::TaskNotFound(task_id));
+        }
+        let mut interrupted = self.interrupted.lock().unwrap();
+        for run_id in runs {
+            if let Some(task) = running.remove(&run_id) {
+                // Handlers that poll the flag stop; others run on, but their result is
+                // reported as cancelled
+                task.cancel.cancel();
+            }
+            interrupted.insert(run_id, JobOutcome::Cancelled);
         }
+        Ok(())
     }
 
     pub fn check_timeouts(&self) -> Vec<u32> {
+        self.time_out_runs()
+            .into_iter()
+            .map(|(_, task_id)| task_id)
+            .collect()
+    }
+
+    // Cancel the runs over the time limit, returning each with its task
+    fn time_out_runs(&self) -> Vec<(RunId, u32)> {
         let mut running = self.running_tasks.lock().unwrap();
         let now = Instant::now();
 
-        let timed_out: Vec<u32> = running
+        let timed_out: Vec<RunId> = running
             .iter()
-            .filter(|(_, start_time)| now.duration_since(**start_time) > self.timeout)
-            .map(|(id, _)| *id)
+            .filter(|(_, task)| now.duration_since(task.started) > self.timeout)
+            .map(|(run_id, _)| run_id.clone())
             .collect();
 
-        for id in &timed_out {
-            running.remove(id);
+        let mut interrupted = self.interrupted.lock().unwrap();
+        let mut runs = Vec::new();
+        for run_id in timed_out {
+            if let Some(task) = running.remove(&run_id) {
+                task.cancel.cancel();
+                runs.push((run_id.clone(), task.task_id));
+            }
+            interrupted.insert(run_id, JobOutcome::TimedOut);
         }
 
-        timed_out
+        runs
     }
 
     pub fn collect_results(&self) -> Vec<JobResult> {
         let mut results = Vec::new();
 
         while let Some(result) = self.worker_pool.try_get_result() {
-            results.push(result);
+            results.push(self.with_follow_ups(result));
         }
 
         results
     }
 
+    // Block until the next job finishes
+    pub fn wait_for_result(&self) -> Result<JobResult> {
+        Ok(self.with_follow_ups(self.worker_pool.get_result()?))
+    }
+
+    // Wait up to `timeout` for every submitted job to finish, returning the results that
+    // came in; fewer than were submitted means the rest are still running
+    pub fn collect_results_timeout(&self, timeout: Duration) -> Vec<JobResult> {
+        self.worker_pool
+            .collect_results_timeout(timeout)
+            .into_iter()
+            .map(|result| self.with_follow_ups(result))
+            .collect()
+    }
+
+    // Wait up to `timeout` for a job for `task_id` to finish. Other tasks' results that
+    // arrive in the meantime are kept for the next call that asks for results.
+    pub fn wait_for(&self, task_id: u32, timeout: Duration) -> Option<JobResult> {
+        self.worker_pool
+            .wait_for(task_id, timeout)
+            .map(|result| self.with_follow_ups(result))
+    }
+
+    // Jobs submitted whose results haven't been collected yet
+    pub fn pending_results(&self) -> usize {
+        self.worker_pool.pending()
+    }
+
+    // Ask the handler that ran the job what should follow its outcome
+    fn with_follow_ups(&self, mut result: JobResult) -> JobResult {
+        let source = self
+            .follow_up_sources
+            .lock()
+            .unwrap()
+            .remove(&result.run_id);
+        if let Some(source) = source {
+            result.follow_ups = source.handler.follow_ups(&source.task, &result.outcome);
+        }
+        result
+    }
+
     pub fn is_task_running(&self, task_id: u32) -> bool {
         let running = self.running_tasks.lock().unwrap();
-        running.contains_key(&task_id)
+        running.values().any(|task| task.task_id == task_id)
     }
 }
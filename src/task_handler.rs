@@ -1,13 +1,19 @@
 use std::any::Any;
 use std::fmt::Debug;
+use std::time::Duration;
 
+use crate::cancellation::CancellationFlag;
 use crate::error::Result;
-use crate::task::Task;
+use crate::follow_ups::FollowUp;
+use crate::priority::PriorityScale;
+use crate::task::{Task, TaskPriority};
+use crate::worker_pool::JobOutcome;
 
 // A trait that all task handlers must implement
 pub trait TaskHandler: Send + Sync + Debug {
-    // Execute the task
-    fn execute(&self, task: &Task) -> Result<()>;
+    // Execute the task. Handlers that take a while should poll `cancel` between steps and
+    // return early once it is raised.
+    fn execute(&self, task: &Task, cancel: &CancellationFlag) -> Result<()>;
 
     // Get the name of the handler
     fn name(&self) -> &str;
@@ -20,6 +26,12 @@ pub trait TaskHandler: Send + Sync + Debug {
 
     // Convert to Any for downcasting
     fn as_any(&self) -> &dyn Any;
+
+    // Tasks to add to the project once a run of `task` has ended with `outcome`, such as a
+    // task to fix what made it fail. None by default.
+    fn follow_ups(&self, _task: &Task, _outcome: &JobOutcome) -> Vec<FollowUp> {
+        Vec::new()
+    }
 }
 
 // Make TaskHandler objects cloneable
@@ -47,14 +59,17 @@ impl TaskHandlerRegistry {
         self.handlers.push(handler);
     }
 
-    pub fn get_handler_for_task(&self, task: &Task) -> Option<&Box<dyn TaskHandler>> {
-        self.handlers.iter().find(|h| h.can_handle(task))
+    pub fn get_handler_for_task(&self, task: &Task) -> Option<&dyn TaskHandler> {
+        self.handlers
+            .iter()
+            .find(|h| h.can_handle(task))
+            .map(|h| h.as_ref())
     }
 
     pub fn execute_task(&self, task: &Task) -> Result<()> {
         if let Some(handler) = self.get_handler_for_task(task) {
             println!("Executing task with handler: {}", handler.name());
-            handler.execute(task)
+            handler.execute(task, &CancellationFlag::new())
         } else {
             Err(crate::error::TaskMasterError::InvalidOperation(format!(
                 "No handler available for task: {}",
@@ -63,6 +78,10 @@ impl TaskHandlerRegistry {
         }
     }
 
+    pub fn has_handler(&self, task: &Task) -> bool {
+        self.get_handler_for_task(task).is_some()
+    }
+
     pub fn list_handlers(&self) -> Vec<&str> {
         self.handlers.iter().map(|h| h.name()).collect()
     }
@@ -85,7 +104,7 @@ impl BasicTaskHandler {
 }
 
 impl TaskHandler for BasicTaskHandler {
-    fn execute(&self, task: &Task) -> Result<()> {
+    fn execute(&self, task: &Task, _cancel: &CancellationFlag) -> Result<()> {
         println!("Basic handler executing task: {}", task.title);
         // Simulate doing something with the task
         Ok(())
@@ -113,23 +132,39 @@ impl TaskHandler for BasicTaskHandler {
 #[derive(Debug, Clone)]
 pub struct PriorityTaskHandler {
     name: String,
-    priority_levels: Vec<crate::task::TaskPriority>,
+    priority_levels: Vec<TaskPriority>,
+    scale: PriorityScale,
+    // Also accept any task at least this urgent on the scale (0-100)
+    min_weight: Option<u8>,
 }
 
 impl PriorityTaskHandler {
-    pub fn new(name: &str, priority_levels: Vec<crate::task::TaskPriority>) -> Self {
+    pub fn new(name: &str, priority_levels: Vec<TaskPriority>) -> Self {
         PriorityTaskHandler {
             name: name.to_string(),
             priority_levels,
+            scale: PriorityScale::default(),
+            min_weight: None,
+        }
+    }
+
+    // Handle every task whose priority weighs at least `min_weight` on `scale`
+    pub fn at_least(name: &str, scale: PriorityScale, min_weight: u8) -> Self {
+        PriorityTaskHandler {
+            name: name.to_string(),
+            priority_levels: Vec::new(),
+            scale,
+            min_weight: Some(min_weight),
         }
     }
 }
 
 impl TaskHandler for PriorityTaskHandler {
-    fn execute(&self, task: &Task) -> Result<()> {
+    fn execute(&self, task: &Task, _cancel: &CancellationFlag) -> Result<()> {
         println!(
-            "Priority handler executing {:?} priority task: {}",
-            task.priority, task.title
+            "Priority handler executing {} priority task: {}",
+            self.scale.label(&task.priority),
+            task.title
         );
         // Prioritized task execution logic would go here
         Ok(())
@@ -140,9 +175,49 @@ impl TaskHandler for PriorityTaskHandler {
     }
 
     fn can_handle(&self, task: &Task) -> bool {
-        self.priority_levels
-            .iter()
-            .any(|p| std::mem::discriminant(p) == std::mem::discriminant(&task.priority))
+        self.priority_levels.contains(&task.priority)
+            || self
+                .min_weight
+                .is_some_and(|min| self.scale.weight(&task.priority) >= min)
+    }
+
+    fn clone_box(&self) -> Box<dyn TaskHandler> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Accepts every task and simulates work by sleeping, like TaskExecutor::execute_task
+#[derive(Debug, Clone)]
+pub struct SimulatedTaskHandler {
+    name: String,
+    duration: Duration,
+}
+
+impl SimulatedTaskHandler {
+    pub fn new(name: &str, duration: Duration) -> Self {
+        SimulatedTaskHandler {
+            name: name.to_string(),
+            duration,
+        }
+    }
+}
+
+impl TaskHandler for SimulatedTaskHandler {
+    fn execute(&self, task: &Task, cancel: &CancellationFlag) -> Result<()> {
+        println!("Simulated handler working on task: {}", task.title);
+        cancel.sleep(self.duration)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn can_handle(&self, _task: &Task) -> bool {
+        true
     }
 
     fn clone_box(&self) -> Box<dyn TaskHandler> {
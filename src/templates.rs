@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::locale;
+use crate::priority::PriorityScale;
+use crate::project::Project;
+use crate::task::{ChecklistItem, Task, TaskStatus};
+use crate::task_dependencies::{Dependency, DependencyKind};
+
+pub const TEMPLATES_FILE: &str = "templates.json";
+pub const PROJECT_TEMPLATES_FILE: &str = "project_templates.json";
+
+// Stands for each component in the titles of a project template's tasks
+pub const COMPONENT_PLACEHOLDER: &str = "{component}";
+
+fn default_priority() -> String {
+    "medium".to_string()
+}
+
+// A reusable blueprint for tasks that are created over and over
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskTemplate {
+    pub name: String,
+    // Title with `{date}`, `{project}` or `{n}` placeholders filled in when used
+    pub title_pattern: String,
+    // Kept as text so numeric levels are read against the target project's scale
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub checklist: Vec<String>,
+    #[serde(default)]
+    pub estimate_hours: Option<f64>,
+}
+
+impl TaskTemplate {
+    pub fn new(name: &str, title_pattern: &str) -> Self {
+        TaskTemplate {
+            name: name.to_string(),
+            title_pattern: title_pattern.to_string(),
+            priority: default_priority(),
+            tags: Vec::new(),
+            checklist: Vec::new(),
+            estimate_hours: None,
+        }
+    }
+
+    // Fill in `{key}` placeholders; `{date}` is always today's local date
+    pub fn render_title(&self, vars: &[(&str, String)]) -> String {
+        let mut title = self.title_pattern.replace(
+            "{date}",
+            &chrono::Local::now().format("%Y-%m-%d").to_string(),
+        );
+        for (key, value) in vars {
+            title = title.replace(&format!("{{{}}}", key), value);
+        }
+        title
+    }
+
+    pub fn instantiate(
+        &self,
+        id: u32,
+        status: TaskStatus,
+        scale: &PriorityScale,
+        vars: &[(&str, String)],
+    ) -> Result<Task> {
+        let priority = scale.parse(&self.priority)?;
+        let mut task = Task::new(id, self.render_title(vars), status, priority);
+        task.tags = self.tags.clone();
+        task.checklist = self
+            .checklist
+            .iter()
+            .map(|text| ChecklistItem::new(text))
+            .collect();
+        task.estimate_hours = self.estimate_hours;
+        Ok(task)
+    }
+
+    pub fn display(&self) {
+        println!("Template: {}", self.name);
+        println!("  Title: {}", self.title_pattern);
+        println!("  Priority: {}", self.priority);
+        if !self.tags.is_empty() {
+            println!("  Tags: {}", self.tags.join(", "));
+        }
+        if let Some(estimate) = self.estimate_hours {
+            println!("  Estimate: {}", locale::hours(estimate, 1));
+        }
+        for item in &self.checklist {
+            println!("  [ ] {}", item);
+        }
+    }
+}
+
+// Named templates stored as `templates.json` in the data directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TemplateLibrary {
+    templates: BTreeMap<String, TaskTemplate>,
+}
+
+impl TemplateLibrary {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(TEMPLATES_FILE);
+        if !path.exists() {
+            return Ok(TemplateLibrary::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(TEMPLATES_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<&TaskTemplate> {
+        self.templates
+            .get(name)
+            .ok_or_else(|| TaskMasterError::TemplateNotFound(name.to_string()))
+    }
+
+    // Add or replace a template, returning the one it replaced
+    pub fn insert(&mut self, template: TaskTemplate) -> Option<TaskTemplate> {
+        self.templates.insert(template.name.clone(), template)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<TaskTemplate> {
+        self.templates
+            .remove(name)
+            .ok_or_else(|| TaskMasterError::TemplateNotFound(name.to_string()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TaskTemplate> {
+        self.templates.values()
+    }
+}
+
+// A dependency declared by task titles rather than IDs, e.g. "Deploy {component}" depends on
+// "Test {component}". When a project is created from a template, each `{component}` task is
+// copied once per component and the rule adds an edge between the tasks of each component.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DependencyRule {
+    pub task: String,
+    pub depends_on: String,
+    #[serde(default)]
+    pub kind: DependencyKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lag_minutes: Option<i64>,
+}
+
+impl DependencyRule {
+    pub fn new(
+        task: &str,
+        depends_on: &str,
+        kind: DependencyKind,
+        lag: Option<Duration>,
+    ) -> Result<Self> {
+        let (task, depends_on) = (task.trim(), depends_on.trim());
+        if task.is_empty() || depends_on.is_empty() {
+            return Err(TaskMasterError::InvalidOperation(
+                "A dependency rule needs both task titles".to_string(),
+            ));
+        }
+        if task.eq_ignore_ascii_case(depends_on) {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "'{}' cannot depend on itself",
+                task
+            )));
+        }
+        Ok(DependencyRule {
+            task: task.to_string(),
+            depends_on: depends_on.to_string(),
+            kind,
+            lag_minutes: lag.map(|lag| lag.num_minutes()).filter(|m| *m != 0),
+        })
+    }
+
+    // The titles the rule links for each component, or the titles as written if it mentions
+    // no component or there are none
+    pub fn expand(&self, components: &[String]) -> Vec<(String, String)> {
+        let templated = self.task.contains(COMPONENT_PLACEHOLDER)
+            || self.depends_on.contains(COMPONENT_PLACEHOLDER);
+        if !templated || components.is_empty() {
+            return vec![(self.task.clone(), self.depends_on.clone())];
+        }
+        components
+            .iter()
+            .map(|component| {
+                (
+                    self.task.replace(COMPONENT_PLACEHOLDER, component),
+                    self.depends_on.replace(COMPONENT_PLACEHOLDER, component),
+                )
+            })
+            .collect()
+    }
+
+    pub fn dependency(&self, task: u32) -> Dependency {
+        Dependency::new(task)
+            .with_kind(self.kind)
+            .with_lag(self.lag_minutes.map(Duration::minutes))
+    }
+}
+
+impl fmt::Display for DependencyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' after '{}'", self.task, self.depends_on)?;
+        match self.dependency(0).label() {
+            Some(label) => write!(f, " ({})", label),
+            None => Ok(()),
+        }
+    }
+}
+
+// Whole projects saved by name as `project_templates.json` in the data directory. Each is
+// stored reset to its initial state and re-created with `Project::instantiate`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProjectTemplateLibrary {
+    templates: BTreeMap<String, Project>,
+}
+
+impl ProjectTemplateLibrary {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(PROJECT_TEMPLATES_FILE);
+        if !path.exists() {
+            return Ok(ProjectTemplateLibrary::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(PROJECT_TEMPLATES_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Project> {
+        self.templates
+            .get(name)
+            .ok_or_else(|| TaskMasterError::TemplateNotFound(name.to_string()))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Result<&mut Project> {
+        self.templates
+            .get_mut(name)
+            .ok_or_else(|| TaskMasterError::TemplateNotFound(name.to_string()))
+    }
+
+    // Save a reset copy of `project` under `name`, returning the template it replaced
+    pub fn insert(&mut self, name: &str, project: &Project) -> Result<Option<Project>> {
+        let template = project.instantiate(0, project.name.clone())?;
+        Ok(self.templates.insert(name.to_string(), template))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Project> {
+        self.templates
+            .remove(name)
+            .ok_or_else(|| TaskMasterError::TemplateNotFound(name.to_string()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Project)> {
+        self.templates.iter()
+    }
+}
@@ -0,0 +1,373 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::appearance::escape_html;
+use crate::dates;
+use crate::error::{Result, TaskMasterError};
+use crate::export::csv_field;
+use crate::locale;
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// Which way time is rounded to the increment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Rounding {
+    #[default]
+    Up,
+    Nearest,
+    Down,
+}
+
+impl Rounding {
+    pub fn parse(input: &str) -> Result<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "up" => Ok(Rounding::Up),
+            "nearest" => Ok(Rounding::Nearest),
+            "down" => Ok(Rounding::Down),
+            _ => Err(TaskMasterError::InvalidOperation(format!(
+                "Unknown rounding '{}'; use up, nearest or down",
+                input
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rounding::Up => write!(f, "up"),
+            Rounding::Nearest => write!(f, "nearest"),
+            Rounding::Down => write!(f, "down"),
+        }
+    }
+}
+
+// How each day's time on a task is rounded on a timesheet, e.g. up to the next quarter hour
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub struct RoundingRule {
+    // 0 keeps exact times
+    #[serde(default)]
+    pub increment_minutes: u32,
+    #[serde(default)]
+    pub mode: Rounding,
+}
+
+impl RoundingRule {
+    pub fn apply(&self, hours: f64) -> f64 {
+        if self.increment_minutes == 0 {
+            return hours;
+        }
+        let increments = hours * 60.0 / self.increment_minutes as f64;
+        // Ignore floating-point noise such as 0.25h coming out as 15.000000001 minutes
+        let increments = if (increments - increments.round()).abs() < 1e-9 {
+            increments.round()
+        } else {
+            match self.mode {
+                Rounding::Up => increments.ceil(),
+                Rounding::Nearest => increments.round(),
+                Rounding::Down => increments.floor(),
+            }
+        };
+        increments * self.increment_minutes as f64 / 60.0
+    }
+
+    pub fn describe(&self) -> String {
+        if self.increment_minutes == 0 {
+            "exact".to_string()
+        } else {
+            format!("{} to {} minutes", self.mode, self.increment_minutes)
+        }
+    }
+}
+
+// Where a row's time came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeSource {
+    // Entries recorded with `log-time`
+    Logged,
+    // Time the task spent In Progress, for tasks with no logged time
+    Tracked,
+    // No time, only the completion
+    None,
+}
+
+impl fmt::Display for TimeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeSource::Logged => write!(f, "logged"),
+            TimeSource::Tracked => write!(f, "tracked"),
+            TimeSource::None => write!(f, ""),
+        }
+    }
+}
+
+// Time on one task on one day, by one person
+#[derive(Debug, Clone)]
+pub struct TimesheetRow {
+    pub date: NaiveDate,
+    pub project: String,
+    pub task: String,
+    pub title: String,
+    pub user: Option<String>,
+    // Rounded by the timesheet's rule
+    pub hours: f64,
+    pub source: TimeSource,
+    // The task was marked Done that day
+    pub completed: bool,
+}
+
+// Per-day, per-task time and completions over a date range, for invoicing or compliance
+#[derive(Debug, Clone)]
+pub struct Timesheet {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub user: Option<String>,
+    pub rounding: RoundingRule,
+    pub generated: DateTime<Utc>,
+    // By date, then project and task
+    pub rows: Vec<TimesheetRow>,
+}
+
+impl Timesheet {
+    // Time comes from logged entries; tasks with no logged time at all fall back to their
+    // In Progress spans, attributed to the project owner. With `user`, only that person's
+    // time is included, and completions only on their rows or in projects they own.
+    pub fn build(
+        projects: &[Project],
+        from: NaiveDate,
+        to: NaiveDate,
+        user: Option<&str>,
+        rounding: RoundingRule,
+        now: DateTime<Utc>,
+    ) -> Result<Self> {
+        if to < from {
+            return Err(TaskMasterError::InvalidOperation(format!(
+                "The range ends ({}) before it starts ({})",
+                to, from
+            )));
+        }
+        let in_range = |date: &NaiveDate| *date >= from && *date <= to;
+        let matches_user = |name: Option<&str>| {
+            user.is_none_or(|wanted| name.is_some_and(|name| name.eq_ignore_ascii_case(wanted)))
+        };
+
+        let mut rows = Vec::new();
+        for project in projects {
+            for task in &project.tasks {
+                let row =
+                    |date: NaiveDate, user: Option<String>, hours: f64, source| TimesheetRow {
+                        date,
+                        project: project.name.clone(),
+                        task: format!("{}{}", project.settings.id_prefix, task.id),
+                        title: task.title.clone(),
+                        user,
+                        hours,
+                        source,
+                        completed: false,
+                    };
+                let mut task_rows: Vec<TimesheetRow> = Vec::new();
+
+                // Sum each person's time per day before rounding
+                let mut days: Vec<(NaiveDate, Option<String>, f64)> = Vec::new();
+                let source = if !task.time_log.is_empty() {
+                    for entry in task.time_log.iter().filter(|e| in_range(&e.date)) {
+                        add_hours(&mut days, entry.date, entry.user.clone(), entry.hours);
+                    }
+                    TimeSource::Logged
+                } else if task.logged_hours == 0.0 {
+                    // Logged hours from before entries were dated can't be placed on a day
+                    for (date, hours) in tracked_days(task) {
+                        if in_range(&date) {
+                            add_hours(&mut days, date, project.owner.clone(), hours);
+                        }
+                    }
+                    TimeSource::Tracked
+                } else {
+                    TimeSource::None
+                };
+                for (date, name, hours) in days {
+                    if matches_user(name.as_deref()) {
+                        task_rows.push(row(date, name, rounding.apply(hours), source));
+                    }
+                }
+
+                if let Some(date) = task
+                    .completed_at()
+                    .map(|at| at.with_timezone(&Local).date_naive())
+                {
+                    if in_range(&date) {
+                        let mut marked = false;
+                        for r in task_rows.iter_mut().filter(|r| r.date == date) {
+                            r.completed = true;
+                            marked = true;
+                        }
+                        if !marked && matches_user(project.owner.as_deref()) {
+                            let mut done = row(date, project.owner.clone(), 0.0, TimeSource::None);
+                            done.completed = true;
+                            task_rows.push(done);
+                        }
+                    }
+                }
+                rows.extend(task_rows);
+            }
+        }
+        rows.sort_by(|a, b| {
+            (a.date, &a.project, &a.task, &a.user).cmp(&(b.date, &b.project, &b.task, &b.user))
+        });
+
+        Ok(Timesheet {
+            from,
+            to,
+            user: user.map(str::to_string),
+            rounding,
+            generated: now,
+            rows,
+        })
+    }
+
+    pub fn total_hours(&self) -> f64 {
+        self.rows.iter().map(|r| r.hours).sum()
+    }
+
+    // Daily totals, in date order
+    pub fn days(&self) -> Vec<(NaiveDate, f64)> {
+        let mut days: Vec<(NaiveDate, f64)> = Vec::new();
+        for row in &self.rows {
+            match days.last_mut() {
+                Some((date, hours)) if *date == row.date => *hours += row.hours,
+                _ => days.push((row.date, row.hours)),
+            }
+        }
+        days
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,project,task,title,user,hours,source,completed\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.2},{},{}\n",
+                row.date,
+                csv_field(&row.project),
+                csv_field(&row.task),
+                csv_field(&row.title),
+                csv_field(row.user.as_deref().unwrap_or("")),
+                row.hours,
+                row.source,
+                if row.completed { "yes" } else { "no" }
+            ));
+        }
+        csv
+    }
+
+    // A printable page; save it as PDF from the browser's print dialog
+    pub fn to_html(&self) -> String {
+        let title = match &self.user {
+            Some(user) => format!("Timesheet for {}: {} to {}", user, self.from, self.to),
+            None => format!("Timesheet: {} to {}", self.from, self.to),
+        };
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+        html.push_str(STYLE);
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+        html.push_str(&format!(
+            "<p class=\"generated\">Generated {}; times rounded {}</p>\n",
+            dates::format_local(&self.generated),
+            self.rounding.describe()
+        ));
+
+        if self.rows.is_empty() {
+            html.push_str("<p class=\"none\">No time or completions in this period</p>\n");
+        }
+        for (date, hours) in self.days() {
+            html.push_str(&format!(
+                "<h2>{} <span class=\"total\">{}</span></h2>\n",
+                locale::weekday_date(date),
+                locale::hours(hours, 2)
+            ));
+            html.push_str("<table>\n<tr><th>Project</th><th>Task</th><th>Title</th><th>User</th><th class=\"hours\">Hours</th><th>Completed</th></tr>\n");
+            for row in self.rows.iter().filter(|r| r.date == date) {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"hours\">{}</td><td>{}</td></tr>\n",
+                    escape_html(&row.project),
+                    escape_html(&row.task),
+                    escape_html(&row.title),
+                    escape_html(row.user.as_deref().unwrap_or("")),
+                    locale::number(row.hours, 2),
+                    if row.completed { "&#10003;" } else { "" }
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str(&format!(
+            "<p class=\"grand-total\">Total: {}</p>\n",
+            locale::hours(self.total_hours(), 2)
+        ));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn add_hours(
+    days: &mut Vec<(NaiveDate, Option<String>, f64)>,
+    date: NaiveDate,
+    user: Option<String>,
+    hours: f64,
+) {
+    match days.iter_mut().find(|(d, u, _)| *d == date && *u == user) {
+        Some((_, _, total)) => *total += hours,
+        None => days.push((date, user, hours)),
+    }
+}
+
+// Hours spent In Progress, split at local midnight
+fn tracked_days(task: &Task) -> Vec<(NaiveDate, f64)> {
+    let mut days: Vec<(NaiveDate, f64)> = Vec::new();
+    let mut started: Option<DateTime<Utc>> = None;
+    for change in &task.history {
+        if change.to == TaskStatus::InProgress {
+            started = Some(change.changed_at);
+            continue;
+        }
+        let Some(start) = started.take() else {
+            continue;
+        };
+        let mut from = start.with_timezone(&Local);
+        let end = change.changed_at.with_timezone(&Local);
+        while from < end {
+            let date = from.date_naive();
+            let next_midnight = (date + Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+                .filter(|midnight| *midnight > from)
+                .unwrap_or(end);
+            let until = next_midnight.min(end);
+            let hours = (until - from).num_seconds() as f64 / 3600.0;
+            match days.iter_mut().find(|(d, _)| *d == date) {
+                Some((_, total)) => *total += hours,
+                None => days.push((date, hours)),
+            }
+            from = until;
+        }
+    }
+    days
+}
+
+const STYLE: &str = "<style>
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #222; max-width: 900px; margin: 24px auto; padding: 0 16px; }
+h1 { border-bottom: 2px solid #222; padding-bottom: 4px; }
+h2 { margin-top: 24px; font-size: 17px; border-bottom: 1px solid #ccc; }
+.total { float: right; font-weight: normal; }
+.generated, .none { color: #777; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; border-bottom: 1px solid #ddd; padding: 4px 8px; font-size: 14px; }
+th { background: #f4f4f4; }
+.hours { text-align: right; }
+.grand-total { margin-top: 24px; font-weight: bold; text-align: right; }
+@media print { h2 { break-after: avoid; } tr { break-inside: avoid; } }
+</style>
+";
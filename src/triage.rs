@@ -0,0 +1,153 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::priority::PriorityScale;
+use crate::task::TaskPriority;
+
+pub const TRIAGE_FILE: &str = "triage.json";
+
+// Estimates agree when the largest is at most this many times the smallest
+const AGREEMENT_RATIO: f64 = 2.0;
+
+// One person's call on a task; either part may be left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vote {
+    pub user: String,
+    pub priority: Option<TaskPriority>,
+    pub estimate_hours: Option<f64>,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TaskVotes {
+    project: u32,
+    task: u32,
+    votes: Vec<Vote>,
+}
+
+// What the votes on a task settle on
+#[derive(Debug, Clone)]
+pub struct Consensus {
+    // The most common choice, the higher priority on a tie
+    pub priority: Option<TaskPriority>,
+    // The median estimate
+    pub estimate_hours: Option<f64>,
+    // Everyone picked the same priority and the estimates are close together;
+    // otherwise it is worth talking it through before applying
+    pub agreed: bool,
+}
+
+// Hidden votes waiting to be revealed, stored as `triage.json` in the data directory so
+// everyone sharing it can vote before the team looks at the result
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TriageVotes {
+    entries: Vec<TaskVotes>,
+}
+
+impl TriageVotes {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(TRIAGE_FILE);
+        if !path.exists() {
+            return Ok(TriageVotes::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(TRIAGE_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Record a vote, replacing the user's earlier vote on the task. Returns how many people
+    // have voted on it.
+    pub fn vote(&mut self, project: u32, task: u32, vote: Vote) -> Result<usize> {
+        if vote.priority.is_none() && vote.estimate_hours.is_none() {
+            return Err(TaskMasterError::InvalidOperation(
+                "A vote needs a priority, an estimate, or both".to_string(),
+            ));
+        }
+        if vote
+            .estimate_hours
+            .is_some_and(|hours| !hours.is_finite() || hours <= 0.0)
+        {
+            return Err(TaskMasterError::InvalidOperation(
+                "Estimates must be a positive number of hours".to_string(),
+            ));
+        }
+
+        let position = match self
+            .entries
+            .iter()
+            .position(|e| e.project == project && e.task == task)
+        {
+            Some(position) => position,
+            None => {
+                self.entries.push(TaskVotes {
+                    project,
+                    task,
+                    votes: Vec::new(),
+                });
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[position];
+        entry
+            .votes
+            .retain(|v| !v.user.eq_ignore_ascii_case(&vote.user));
+        entry.votes.push(vote);
+        Ok(entry.votes.len())
+    }
+
+    pub fn votes(&self, project: u32, task: u32) -> &[Vote] {
+        self.entries
+            .iter()
+            .find(|e| e.project == project && e.task == task)
+            .map_or(&[], |e| e.votes.as_slice())
+    }
+
+    // Forget the votes on a task once they have been applied
+    pub fn clear(&mut self, project: u32, task: u32) {
+        self.entries
+            .retain(|e| !(e.project == project && e.task == task));
+    }
+}
+
+pub fn consensus(votes: &[Vote], scale: &PriorityScale) -> Consensus {
+    // Count the votes for each priority
+    let mut tally: Vec<(&TaskPriority, usize)> = Vec::new();
+    for priority in votes.iter().filter_map(|v| v.priority.as_ref()) {
+        match tally.iter_mut().find(|(p, _)| *p == priority) {
+            Some((_, count)) => *count += 1,
+            None => tally.push((priority, 1)),
+        }
+    }
+    let priority = tally
+        .iter()
+        .max_by_key(|(p, count)| (*count, scale.weight(p)))
+        .map(|(p, _)| (*p).clone());
+
+    let mut estimates: Vec<f64> = votes.iter().filter_map(|v| v.estimate_hours).collect();
+    estimates.sort_by(f64::total_cmp);
+    let estimate_hours = match estimates.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(estimates[n / 2]),
+        n => Some((estimates[n / 2 - 1] + estimates[n / 2]) / 2.0),
+    };
+
+    let estimates_close = match (estimates.first(), estimates.last()) {
+        (Some(low), Some(high)) => *high <= low * AGREEMENT_RATIO,
+        _ => true,
+    };
+    Consensus {
+        priority,
+        estimate_hours,
+        agreed: tally.len() <= 1 && estimates_close,
+    }
+}
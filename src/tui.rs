@@ -1,8 +1,10 @@
 use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use chrono::{Local, Utc};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -10,28 +12,77 @@ use tui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
-    text::{Span, Spans, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs},
     Terminal,
 };
 
+use crate::appearance::ProjectColor;
+use crate::burndown;
+use crate::config::Config;
+use crate::dates;
 use crate::error::Result;
 use crate::file_storage::FileStorage;
+use crate::goals::{GoalProgress, Goals};
+use crate::interactive::InteractiveShell;
+use crate::locale;
+use crate::logs::{self, Level};
+use crate::planning::{self, WeekPlan, PLANNING_DAYS};
+use crate::priority::PriorityScale;
 use crate::project::Project;
+use crate::schedule::WorkCalendar;
 use crate::storage::Storage;
-use crate::task::{Task, TaskPriority, TaskStatus};
+use crate::task::{Task, TaskStatus};
+use crate::task_dependencies::{self, OrphanPolicy};
+use crate::urgency::UrgencyCoefficients;
 
 enum InputMode {
     Normal,
     Editing,
+    // Typing a shell command after ':'
+    Command,
+    // Choosing a task for the Dependencies tab's task to depend on
+    PickingDependency,
 }
 
 enum AppTab {
     Projects,
     Tasks,
+    Planning,
+    Dependencies,
+    Logs,
     Help,
 }
 
+// A line of the planning board: a section heading or a task in it
+enum PlanRow {
+    Backlog,
+    Day(usize),
+    Task(u32),
+}
+
+// A line of the dependency tree: a section heading or a task, indented by how many steps
+// it is from the task the tree is about. A task reached a second way is shown again but
+// not expanded.
+enum DepRow {
+    Heading(&'static str),
+    Task {
+        id: u32,
+        depth: usize,
+        upstream: bool,
+        repeated: bool,
+    },
+}
+
+// Where Shift+arrow keys move the selected task in the manual order
+#[derive(Clone, Copy)]
+enum TaskMove {
+    Up,
+    Down,
+    Top,
+    Bottom,
+}
+
 struct App {
     tabs: Vec<&'static str>,
     active_tab: AppTab,
@@ -39,10 +90,40 @@ struct App {
     projects_state: ListState,
     tasks: Vec<Task>,
     tasks_state: ListState,
+    priority_scale: PriorityScale,
+    // The selected project's task ID prefix
+    id_prefix: String,
     input_mode: InputMode,
     input: String,
     storage: FileStorage,
     status_message: String,
+    urgency: UrgencyCoefficients,
+    // Tasks are listed by urgency unless switched to the project's manual order
+    sort_by_urgency: bool,
+    // List tasks milestone by milestone, keeping the order within each
+    group_by_milestone: bool,
+    // The selected project's week on the Planning tab
+    plan: Option<WeekPlan>,
+    plan_rows: Vec<PlanRow>,
+    plan_state: ListState,
+    calendar: WorkCalendar,
+    // Runs ':' commands; its output stays up until the next key
+    shell: InteractiveShell,
+    command_output: Vec<String>,
+    // Logs tab filters: minimum level and text matched against module and message
+    log_level: Level,
+    log_search: String,
+    // Shown with the projects they link, progress measured against the loaded projects
+    goals: Goals,
+    // Tasks in progress for longer than this get a red row of age dots
+    wip_age_limit: chrono::Duration,
+    // The task the Dependencies tab is about and its tree
+    dep_focus: Option<u32>,
+    dep_rows: Vec<DepRow>,
+    dep_state: ListState,
+    // Tasks offered when picking a new dependency
+    dep_candidates: Vec<u32>,
+    dep_candidates_state: ListState,
 }
 
 impl App {
@@ -55,27 +136,383 @@ impl App {
 
         // Initialize list states
         let mut projects_state = ListState::default();
-        let mut tasks_state = ListState::default();
+        let tasks_state = ListState::default();
 
         // If there are projects, select the first one
         if !projects.is_empty() {
             projects_state.select(Some(0));
         }
+        let config = Config::load("./data")?;
+        locale::install_settings(&config.locale);
 
         Ok(App {
-            tabs: vec!["Projects", "Tasks", "Help"],
+            tabs: vec![
+                "Projects",
+                "Tasks",
+                "Planning",
+                "Dependencies",
+                "Logs",
+                "Help",
+            ],
             active_tab: AppTab::Projects,
             projects,
             projects_state,
             tasks: Vec::new(),
             tasks_state,
+            priority_scale: PriorityScale::default(),
+            id_prefix: String::new(),
             input_mode: InputMode::Normal,
             input: String::new(),
             storage,
             status_message: String::new(),
+            urgency: config.urgency,
+            sort_by_urgency: true,
+            group_by_milestone: false,
+            plan: None,
+            plan_rows: Vec::new(),
+            plan_state: ListState::default(),
+            calendar: WorkCalendar::default(),
+            shell: InteractiveShell::new(&PathBuf::from("./data"))?,
+            command_output: Vec::new(),
+            log_level: Level::Debug,
+            log_search: String::new(),
+            goals: Goals::load("./data")?,
+            wip_age_limit: chrono::Duration::hours(config.wip_age_limit_hours),
+            dep_focus: None,
+            dep_rows: Vec::new(),
+            dep_state: ListState::default(),
+            dep_candidates: Vec::new(),
+            dep_candidates_state: ListState::default(),
         })
     }
 
+    // Run the command line through the interactive shell, with the selected project as its
+    // current one. Returns false when the command asks to quit.
+    fn run_command(&mut self) -> Result<bool> {
+        let input = std::mem::take(&mut self.input);
+        if let Some(project) = self
+            .projects_state
+            .selected()
+            .and_then(|i| self.projects.get(i))
+        {
+            self.shell.set_current_project(project.id);
+        }
+
+        let mut output = Vec::new();
+        let keep_going = match self.shell.execute(&input, &mut output) {
+            Ok(keep_going) => keep_going,
+            Err(e) => {
+                output.push(format!("Error: {}", e));
+                true
+            }
+        };
+
+        // Pick up whatever the command changed, following `open` to its project
+        self.projects = self.storage.list_projects()?;
+        let selected = self
+            .shell
+            .current_project_id()
+            .and_then(|id| self.projects.iter().position(|p| p.id == id))
+            .or_else(|| {
+                self.projects_state
+                    .selected()
+                    .map(|i| i.min(self.projects.len().saturating_sub(1)))
+            })
+            .filter(|_| !self.projects.is_empty());
+        self.projects_state.select(selected);
+        match self.active_tab {
+            AppTab::Planning => self.load_plan(None)?,
+            AppTab::Dependencies => self.load_dependencies(self.dep_focus)?,
+            _ => self.load_project_tasks()?,
+        }
+
+        // A one-line reply fits the status line; longer output gets its own pane
+        if output.len() > 1 {
+            self.status_message.clear();
+            self.command_output = output;
+        } else {
+            self.status_message = output.pop().unwrap_or_default();
+        }
+        Ok(keep_going)
+    }
+
+    // Complete the command name being typed, listing the candidates when it is ambiguous
+    fn complete_command(&mut self) {
+        if self.input.contains(' ') {
+            return;
+        }
+        let candidates = InteractiveShell::complete(&self.input);
+        match candidates.as_slice() {
+            [] => self.status_message = "No matching command".to_string(),
+            [only] => self.input = format!("{} ", only),
+            [first, rest @ ..] => {
+                let mut common = first.to_string();
+                for candidate in rest {
+                    while !candidate.starts_with(common.as_str()) {
+                        common.pop();
+                    }
+                }
+                self.input = common;
+                self.status_message = format!("Matches: {}", candidates.join(", "));
+            }
+        }
+    }
+
+    // Build the week for the selected project, keeping `keep` selected when given
+    fn load_plan(&mut self, keep: Option<u32>) -> Result<()> {
+        self.load_project_tasks()?;
+        let Some(project) = self
+            .projects_state
+            .selected()
+            .and_then(|i| self.projects.get(i))
+        else {
+            self.plan = None;
+            self.plan_rows.clear();
+            self.plan_state.select(None);
+            return Ok(());
+        };
+
+        let plan = WeekPlan::build(project, Local::now().date_naive(), &self.calendar);
+        let mut rows = vec![PlanRow::Backlog];
+        rows.extend(plan.backlog.iter().map(|id| PlanRow::Task(*id)));
+        for (index, day) in plan.days.iter().enumerate() {
+            rows.push(PlanRow::Day(index));
+            rows.extend(day.tasks.iter().map(|id| PlanRow::Task(*id)));
+        }
+
+        let selected = rows
+            .iter()
+            .position(|row| matches!(row, PlanRow::Task(id) if Some(*id) == keep))
+            .or_else(|| rows.iter().position(|row| matches!(row, PlanRow::Task(_))));
+        self.plan_state.select(selected);
+        self.plan_rows = rows;
+        self.plan = Some(plan);
+        Ok(())
+    }
+
+    fn selected_plan_task(&self) -> Option<u32> {
+        match self
+            .plan_state
+            .selected()
+            .and_then(|i| self.plan_rows.get(i))
+        {
+            Some(PlanRow::Task(id)) => Some(*id),
+            _ => None,
+        }
+    }
+
+    // Plan the selected task for a day of the week, or send it back to the backlog
+    fn plan_selected_task(&mut self, day: Option<usize>) -> Result<()> {
+        let (Some(task_id), Some(plan)) = (self.selected_plan_task(), self.plan.as_ref()) else {
+            return Ok(());
+        };
+        let date = day.and_then(|day| plan.days.get(day)).map(|d| d.date);
+        let Some(project) = self
+            .projects_state
+            .selected()
+            .and_then(|i| self.projects.get(i))
+        else {
+            return Ok(());
+        };
+
+        match self.storage.load_project(project.id) {
+            Ok(mut loaded_project) => {
+                match planning::plan_task(&mut loaded_project, task_id, date) {
+                    Ok(_) => {
+                        self.storage.save_project(&loaded_project)?;
+                        self.load_plan(Some(task_id))?;
+                        self.status_message = match day
+                            .and_then(|day| self.plan.as_ref().and_then(|plan| plan.days.get(day)))
+                        {
+                            Some(day) if day.over_capacity() => format!(
+                                "Warning: {} is over capacity ({} of {})",
+                                locale::short_weekday_date(day.date),
+                                locale::hours(day.hours, 1),
+                                locale::hours(day.capacity, 1)
+                            ),
+                            Some(day) => {
+                                format!("Planned for {}.", locale::short_weekday_date(day.date))
+                            }
+                            None => "Moved to the backlog.".to_string(),
+                        };
+                    }
+                    Err(e) => self.status_message = format!("Error: {}", e),
+                }
+            }
+            Err(e) => self.status_message = format!("Error loading project: {}", e),
+        }
+        Ok(())
+    }
+
+    // Move the selected task one day later, or earlier; a day before the first goes back
+    // to the backlog
+    fn shift_selected_task(&mut self, later: bool) -> Result<()> {
+        let (Some(task_id), Some(plan)) = (self.selected_plan_task(), self.plan.as_ref()) else {
+            return Ok(());
+        };
+        let day = match (plan.day_of(task_id), later) {
+            (None, true) => Some(0),
+            (None, false) => return Ok(()),
+            (Some(day), true) => Some((day + 1).min(PLANNING_DAYS - 1)),
+            (Some(day), false) => day.checked_sub(1),
+        };
+        self.plan_selected_task(day)
+    }
+
+    // Build the dependency tree of `focus`, or of the first task when it is gone
+    fn load_dependencies(&mut self, focus: Option<u32>) -> Result<()> {
+        self.load_project_tasks()?;
+        let focus = focus
+            .filter(|id| self.tasks.iter().any(|t| t.id == *id))
+            .or_else(|| self.tasks.first().map(|t| t.id));
+        self.dep_focus = focus;
+        self.dep_rows.clear();
+        let Some(focus) = focus else {
+            self.dep_state.select(None);
+            return Ok(());
+        };
+        // Keep the task selected on the Tasks tab too
+        if let Some(position) = self.tasks.iter().position(|t| t.id == focus) {
+            self.tasks_state.select(Some(position));
+        }
+
+        for (heading, upstream) in [("Depends on", true), ("Needed by", false)] {
+            self.dep_rows.push(DepRow::Heading(heading));
+            let mut seen = vec![focus];
+            dependency_rows(
+                &self.tasks,
+                focus,
+                1,
+                upstream,
+                &mut seen,
+                &mut self.dep_rows,
+            );
+        }
+        let first = self
+            .dep_rows
+            .iter()
+            .position(|row| matches!(row, DepRow::Task { .. }));
+        self.dep_state.select(first);
+        Ok(())
+    }
+
+    fn selected_dep_row(&self) -> Option<&DepRow> {
+        self.dep_state.selected().and_then(|i| self.dep_rows.get(i))
+    }
+
+    // Offer every task the focused one could depend on: not itself, not already a direct
+    // dependency and not one that depends on it, which would make a cycle
+    fn open_dependency_picker(&mut self) {
+        let Some(focus) = self.dep_focus else {
+            self.status_message = "Select a task first.".to_string();
+            return;
+        };
+        let Some(task) = self.tasks.iter().find(|t| t.id == focus) else {
+            return;
+        };
+        let mut dependents = vec![focus];
+        dependency_rows(
+            &self.tasks,
+            focus,
+            1,
+            false,
+            &mut dependents,
+            &mut Vec::new(),
+        );
+        self.dep_candidates = self
+            .tasks
+            .iter()
+            .filter(|t| !dependents.contains(&t.id) && !task.depends_on(t.id))
+            .map(|t| t.id)
+            .collect();
+        if self.dep_candidates.is_empty() {
+            self.status_message = "No other task can be added as a dependency.".to_string();
+            return;
+        }
+        self.dep_candidates_state.select(Some(0));
+        self.status_message.clear();
+        self.input_mode = InputMode::PickingDependency;
+    }
+
+    // Make the focused task depend on the task picked from the list
+    fn add_picked_dependency(&mut self) -> Result<()> {
+        self.input_mode = InputMode::Normal;
+        let (Some(focus), Some(picked)) = (
+            self.dep_focus,
+            self.dep_candidates_state
+                .selected()
+                .and_then(|i| self.dep_candidates.get(i))
+                .copied(),
+        ) else {
+            return Ok(());
+        };
+        self.edit_dependency(focus, picked, true)
+    }
+
+    // Remove the edge between the highlighted task and the focused one, in whichever
+    // direction it runs. Only tasks one step away have such an edge.
+    fn remove_selected_dependency(&mut self) -> Result<()> {
+        let Some(focus) = self.dep_focus else {
+            return Ok(());
+        };
+        let (id, upstream) = match self.selected_dep_row() {
+            Some(DepRow::Task {
+                id,
+                depth: 1,
+                upstream,
+                ..
+            }) => (*id, *upstream),
+            Some(DepRow::Task { .. }) => {
+                self.status_message =
+                    "Only direct dependencies and dependents can be removed.".to_string();
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        if upstream {
+            self.edit_dependency(focus, id, false)
+        } else {
+            self.edit_dependency(id, focus, false)
+        }
+    }
+
+    fn edit_dependency(&mut self, task_id: u32, dependency_id: u32, add: bool) -> Result<()> {
+        let Some(project) = self
+            .projects_state
+            .selected()
+            .and_then(|i| self.projects.get(i))
+        else {
+            return Ok(());
+        };
+
+        match self.storage.load_project(project.id) {
+            Ok(mut loaded_project) => {
+                let result = if add {
+                    loaded_project.add_task_dependency(task_id, dependency_id)
+                } else {
+                    loaded_project.remove_task_dependency(task_id, dependency_id)
+                };
+                match result {
+                    Ok(_) => {
+                        self.storage.save_project(&loaded_project)?;
+                        self.load_dependencies(self.dep_focus)?;
+                        self.status_message = if add {
+                            format!("Task {} now depends on task {}.", task_id, dependency_id)
+                        } else {
+                            format!(
+                                "Task {} no longer depends on task {}.",
+                                task_id, dependency_id
+                            )
+                        };
+                    }
+                    Err(e) => self.status_message = format!("Error: {}", e),
+                }
+            }
+            Err(e) => self.status_message = format!("Error loading project: {}", e),
+        }
+        Ok(())
+    }
+
     fn load_project_tasks(&mut self) -> Result<()> {
         // If a project is selected, load its tasks
         if let Some(index) = self.projects_state.selected() {
@@ -83,7 +520,29 @@ impl App {
                 // Load the project to get its tasks
                 match self.storage.load_project(project.id) {
                     Ok(loaded_project) => {
-                        self.tasks = loaded_project.tasks;
+                        if self.sort_by_urgency {
+                            self.tasks = loaded_project
+                                .tasks_by_urgency(&self.urgency)
+                                .into_iter()
+                                .map(|(task, _)| task.clone())
+                                .collect();
+                        } else {
+                            self.tasks = loaded_project.tasks.clone();
+                        }
+                        if self.group_by_milestone {
+                            // Milestone order, then tasks without one
+                            self.tasks.sort_by_key(|t| {
+                                t.milestone
+                                    .and_then(|id| {
+                                        loaded_project.milestones.iter().position(|m| m.id == id)
+                                    })
+                                    .unwrap_or(usize::MAX)
+                            });
+                        }
+                        self.priority_scale = loaded_project.settings.priority_scale.clone();
+                        self.id_prefix = loaded_project.settings.id_prefix.clone();
+                        // Keep the listed copy current for the detail pane and stats footer
+                        self.projects[index] = loaded_project;
                         // Reset task selection
                         if !self.tasks.is_empty() {
                             self.tasks_state.select(Some(0));
@@ -161,20 +620,35 @@ impl App {
                     }
                 };
 
-                let title = parts[1].to_string();
-
-                // Create the task
-                let task = Task::new(id, title, TaskStatus::ToDo, TaskPriority::Medium);
+                // An optional due date follows the title after '@', e.g. "Ship it @ next friday"
+                let (title, due) = match parts[1].split_once('@') {
+                    Some((title, when)) => match dates::parse_datetime(when) {
+                        Ok(due) => (title.trim().to_string(), Some(due)),
+                        Err(e) => {
+                            self.status_message = e.to_string();
+                            return Ok(());
+                        }
+                    },
+                    None => (parts[1].to_string(), None),
+                };
 
-                // Load the full project, add the task, and save
+                // Load the full project, add the task with its defaults, and save
                 match self.storage.load_project(project.id) {
                     Ok(mut loaded_project) => {
-                        loaded_project.add_task(task);
-                        self.storage.save_project(&loaded_project)?;
-                        self.status_message = "Task added successfully.".to_string();
+                        let added = loaded_project.new_task(id, title).and_then(|mut task| {
+                            task.due = due;
+                            loaded_project.add_task(task)
+                        });
+                        match added {
+                            Ok(_) => {
+                                self.storage.save_project(&loaded_project)?;
+                                self.status_message = "Task added successfully.".to_string();
 
-                        // Reload tasks
-                        self.load_project_tasks()?;
+                                // Reload tasks
+                                self.load_project_tasks()?;
+                            }
+                            Err(e) => self.status_message = format!("Error adding task: {}", e),
+                        }
                     }
                     Err(e) => {
                         self.status_message = format!("Error loading project: {}", e);
@@ -191,6 +665,52 @@ impl App {
         Ok(())
     }
 
+    // Reorder the selected task within its project and keep it selected
+    fn move_selected_task(&mut self, to: TaskMove) -> Result<()> {
+        if self.sort_by_urgency {
+            self.status_message = "Press s to switch to manual order first.".to_string();
+            return Ok(());
+        }
+        if self.group_by_milestone {
+            self.status_message = "Press m to stop grouping by milestone first.".to_string();
+            return Ok(());
+        }
+        let (Some(project_index), Some(task_index)) =
+            (self.projects_state.selected(), self.tasks_state.selected())
+        else {
+            return Ok(());
+        };
+        let (Some(project), Some(task)) =
+            (self.projects.get(project_index), self.tasks.get(task_index))
+        else {
+            return Ok(());
+        };
+        let task_id = task.id;
+
+        match self.storage.load_project(project.id) {
+            Ok(mut loaded_project) => {
+                let moved = match to {
+                    TaskMove::Up => loaded_project.move_task_up(task_id),
+                    TaskMove::Down => loaded_project.move_task_down(task_id),
+                    TaskMove::Top => loaded_project.move_task_to(task_id, 0),
+                    TaskMove::Bottom => loaded_project.move_task_to(task_id, usize::MAX),
+                };
+
+                match moved.and_then(|_| loaded_project.task_position(task_id)) {
+                    Ok(position) => {
+                        self.storage.save_project(&loaded_project)?;
+                        self.tasks = loaded_project.tasks;
+                        self.tasks_state.select(Some(position));
+                    }
+                    Err(e) => self.status_message = format!("Error: {}", e),
+                }
+            }
+            Err(e) => self.status_message = format!("Error loading project: {}", e),
+        }
+
+        Ok(())
+    }
+
     // Move selection up in the current list
     fn select_previous(&mut self) {
         match self.active_tab {
@@ -220,6 +740,25 @@ impl App {
                 };
                 self.tasks_state.select(Some(i));
             }
+            AppTab::Planning => {
+                // Headings are skipped
+                let current = self.plan_state.selected().unwrap_or(0);
+                if let Some(i) = (0..current)
+                    .rev()
+                    .find(|i| matches!(self.plan_rows[*i], PlanRow::Task(_)))
+                {
+                    self.plan_state.select(Some(i));
+                }
+            }
+            AppTab::Dependencies => {
+                let current = self.dep_state.selected().unwrap_or(0);
+                if let Some(i) = (0..current)
+                    .rev()
+                    .find(|i| matches!(self.dep_rows[*i], DepRow::Task { .. }))
+                {
+                    self.dep_state.select(Some(i));
+                }
+            }
             _ => {}
         }
     }
@@ -253,11 +792,294 @@ impl App {
                 };
                 self.tasks_state.select(Some(i));
             }
+            AppTab::Planning => {
+                let start = self.plan_state.selected().map_or(0, |i| i + 1);
+                if let Some(i) = (start..self.plan_rows.len())
+                    .find(|i| matches!(self.plan_rows[*i], PlanRow::Task(_)))
+                {
+                    self.plan_state.select(Some(i));
+                }
+            }
+            AppTab::Dependencies => {
+                let start = self.dep_state.selected().map_or(0, |i| i + 1);
+                if let Some(i) = (start..self.dep_rows.len())
+                    .find(|i| matches!(self.dep_rows[*i], DepRow::Task { .. }))
+                {
+                    self.dep_state.select(Some(i));
+                }
+            }
             _ => {}
         }
     }
 }
 
+// Lines describing a task for the detail pane
+fn project_detail_lines(project: &Project, goals: &[GoalProgress]) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            project.label(),
+            project_style(project.color).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::raw(format!("ID: {}", project.id))),
+    ];
+
+    if let Some(owner) = &project.owner {
+        lines.push(Line::from(Span::raw(format!("Owner: {}", owner))));
+    }
+
+    if let Some(parent_id) = project.parent_id {
+        lines.push(Line::from(Span::raw(format!(
+            "Parent project: {}",
+            parent_id
+        ))));
+    }
+
+    lines.push(Line::from(Span::raw(format!(
+        "Created: {}",
+        dates::format_local(&project.created_at)
+    ))));
+
+    if let Some(deadline) = &project.deadline {
+        lines.push(Line::from(Span::raw(format!(
+            "Deadline: {}",
+            dates::format_local(deadline)
+        ))));
+    }
+
+    let done = project
+        .tasks
+        .iter()
+        .filter(|t| t.status == TaskStatus::Done)
+        .count();
+    lines.push(Line::from(Span::raw(format!(
+        "Tasks: {} ({} done)",
+        project.tasks.len(),
+        done
+    ))));
+
+    // Open tasks over the last two weeks
+    let trend = burndown::burndown(project, 14, Local::now().date_naive());
+    lines.push(Line::from(vec![
+        Span::raw("Open, last 14 days: "),
+        Span::styled(
+            burndown::sparkline(&trend),
+            Style::default().fg(Color::Cyan),
+        ),
+    ]));
+
+    for goal in goals {
+        lines.push(Line::from(vec![
+            Span::raw("Goal: "),
+            Span::styled(goal.describe(), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+
+    if !project.description.is_empty() {
+        lines.push(Line::from(Span::raw("")));
+        for line in project.description.lines() {
+            lines.push(Line::from(Span::raw(line.to_string())));
+        }
+    }
+
+    lines
+}
+
+fn task_detail_lines(
+    task: &Task,
+    tasks: &[Task],
+    scale: &PriorityScale,
+    id_prefix: &str,
+) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            task.title.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::raw(format!("ID: {}{}", id_prefix, task.id))),
+        Line::from(Span::raw(format!("Status: {}", task.status_label(tasks)))),
+        Line::from(vec![
+            Span::raw("Priority: "),
+            Span::styled(
+                scale.label(&task.priority),
+                Style::default().fg(priority_color(scale.weight(&task.priority))),
+            ),
+        ]),
+        Line::from(Span::raw(format!(
+            "Created: {}",
+            dates::format_local(&task.created_at)
+        ))),
+        Line::from(Span::raw(format!(
+            "Updated: {}",
+            dates::format_local(&task.updated_at)
+        ))),
+    ];
+
+    if let Some(due) = &task.due {
+        lines.push(Line::from(Span::raw(format!(
+            "Due: {}",
+            dates::format_local(due)
+        ))));
+    }
+
+    if let Some(estimate) = task.estimate_hours {
+        lines.push(Line::from(Span::raw(format!(
+            "Estimate: {}, tracked: {}",
+            locale::hours(estimate, 1),
+            locale::hours(task.actual_hours(), 1)
+        ))));
+    }
+
+    if !task.tags.is_empty() {
+        lines.push(Line::from(Span::raw(format!(
+            "Tags: {}",
+            task.tags.join(", ")
+        ))));
+    }
+
+    if let Some(parent) = task.parent {
+        lines.push(Line::from(Span::raw(format!("Subtask of: {}", parent))));
+    }
+
+    if let Some(recurrence) = &task.recurrence {
+        lines.push(Line::from(Span::raw(format!("Repeats: {}", recurrence))));
+    }
+
+    if let Some(deps) = &task.dependencies {
+        lines.push(Line::from(Span::raw(format!(
+            "Depends on: {}",
+            task_dependencies::format_list(deps)
+        ))));
+    }
+
+    for item in &task.checklist {
+        lines.push(Line::from(Span::raw(format!(
+            "[{}] {}",
+            if item.done { "x" } else { " " },
+            item.text
+        ))));
+    }
+
+    for attachment in &task.attachments {
+        lines.push(Line::from(Span::raw(format!(
+            "Attachment: {}",
+            attachment.describe()
+        ))));
+    }
+
+    if !task.history.is_empty() {
+        lines.push(Line::from(Span::raw("")));
+        lines.push(Line::from(Span::raw("History:")));
+        for change in &task.history {
+            lines.push(Line::from(Span::raw(format!(
+                "  {} {} -> {}",
+                dates::format_local(&change.changed_at),
+                change.from,
+                change.to
+            ))));
+        }
+    }
+
+    lines
+}
+
+// Red for urgent, yellow for normal, green for low priority work
+// Text style for a project's name in its chosen color
+fn project_style(color: Option<ProjectColor>) -> Style {
+    let color = match color {
+        Some(ProjectColor::Red) => Color::Red,
+        Some(ProjectColor::Green) => Color::Green,
+        Some(ProjectColor::Yellow) => Color::Yellow,
+        Some(ProjectColor::Blue) => Color::Blue,
+        Some(ProjectColor::Magenta) => Color::Magenta,
+        Some(ProjectColor::Cyan) => Color::Cyan,
+        Some(ProjectColor::White) => Color::White,
+        Some(ProjectColor::Gray) => Color::Gray,
+        None => return Style::default(),
+    };
+    Style::default().fg(color)
+}
+
+fn priority_color(weight: u8) -> Color {
+    if weight >= 70 {
+        Color::Red
+    } else if weight >= 40 {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
+
+// Append the tasks `id` depends on (upstream) or that depend on it, each followed by its own,
+// one level deeper. `seen` holds the tasks already expanded, and collects the ones added.
+fn dependency_rows(
+    tasks: &[Task],
+    id: u32,
+    depth: usize,
+    upstream: bool,
+    seen: &mut Vec<u32>,
+    rows: &mut Vec<DepRow>,
+) {
+    let next: Vec<u32> = if upstream {
+        tasks
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.dependency_ids().collect())
+            .unwrap_or_default()
+    } else {
+        tasks
+            .iter()
+            .filter(|t| t.depends_on(id))
+            .map(|t| t.id)
+            .collect()
+    };
+
+    for next_id in next {
+        let repeated = seen.contains(&next_id);
+        rows.push(DepRow::Task {
+            id: next_id,
+            depth,
+            upstream,
+            repeated,
+        });
+        if !repeated {
+            seen.push(next_id);
+            dependency_rows(tasks, next_id, depth + 1, upstream, seen, rows);
+        }
+    }
+}
+
+// A glyph for where a task stands: done, blocked, in progress or not started
+fn status_glyph(task: &Task, tasks: &[Task]) -> Span<'static> {
+    let (glyph, color) = if task.status == TaskStatus::Done {
+        ("\u{2713}", Color::Green)
+    } else if task.is_blocked(tasks) {
+        ("\u{2717}", Color::Red)
+    } else if task.status == TaskStatus::InProgress {
+        ("\u{25b6}", Color::Yellow)
+    } else {
+        ("\u{25cb}", Color::White)
+    };
+    Span::styled(format!("{} ", glyph), Style::default().fg(color))
+}
+
+// One dot per day a task has been in progress, up to five: green while young, yellow past
+// half the WIP age limit and red beyond it. None for tasks not in progress.
+fn age_dots(task: &Task, limit: chrono::Duration) -> Option<Span<'static>> {
+    let age = task.wip_age(Utc::now())?;
+    let days = (age.num_days() + 1).clamp(1, 5) as usize;
+    let color = if age > limit {
+        Color::Red
+    } else if age * 2 > limit {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    Some(Span::styled(
+        format!(" {}", "\u{25cf}".repeat(days)),
+        Style::default().fg(color),
+    ))
+}
+
 pub fn run_tui() -> Result<()> {
     // Set up terminal
     enable_raw_mode()?;
@@ -266,6 +1088,9 @@ pub fn run_tui() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    // Events go to the Logs tab instead of over the screen
+    logs::set_echo(false);
+
     // Create app state
     let mut app = App::new()?;
 
@@ -284,17 +1109,14 @@ pub fn run_tui() -> Result<()> {
                         Constraint::Length(3),
                         Constraint::Min(1),
                         Constraint::Length(3),
+                        Constraint::Length(1),
                     ]
                     .as_ref(),
                 )
                 .split(size);
 
             // Create tabs
-            let tabs_vec: Vec<Spans> = app
-                .tabs
-                .iter()
-                .map(|t| Spans::from(Span::raw(*t)))
-                .collect();
+            let tabs_vec: Vec<Line> = app.tabs.iter().map(|t| Line::from(Span::raw(*t))).collect();
             let tabs = Tabs::new(tabs_vec)
                 .block(Block::default().borders(Borders::ALL).title("Tabs"))
                 .style(Style::default().fg(Color::White))
@@ -302,7 +1124,10 @@ pub fn run_tui() -> Result<()> {
                 .select(match app.active_tab {
                     AppTab::Projects => 0,
                     AppTab::Tasks => 1,
-                    AppTab::Help => 2,
+                    AppTab::Planning => 2,
+                    AppTab::Dependencies => 3,
+                    AppTab::Logs => 4,
+                    AppTab::Help => 5,
                 })
                 .divider("|");
 
@@ -316,10 +1141,10 @@ pub fn run_tui() -> Result<()> {
                         .projects
                         .iter()
                         .map(|p| {
-                            ListItem::new(Spans::from(Span::raw(format!(
-                                "ID: {} - {}",
-                                p.id, p.name
-                            ))))
+                            ListItem::new(Line::from(vec![
+                                Span::raw(format!("ID: {} - ", p.id)),
+                                Span::styled(p.label(), project_style(p.color)),
+                            ]))
                         })
                         .collect();
 
@@ -328,43 +1153,396 @@ pub fn run_tui() -> Result<()> {
                         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
                         .highlight_symbol("> ");
 
-                    f.render_stateful_widget(projects, chunks[1], &mut app.projects_state);
+                    // Split the area between the list and a detail pane for the selected project
+                    let project_chunks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(
+                            [Constraint::Percentage(60), Constraint::Percentage(40)].as_ref(),
+                        )
+                        .split(chunks[1]);
+
+                    let details = match app
+                        .projects_state
+                        .selected()
+                        .and_then(|i| app.projects.get(i))
+                    {
+                        Some(project) => {
+                            let goals: Vec<GoalProgress> = app
+                                .goals
+                                .iter()
+                                .filter(|g| g.links_project(project.id))
+                                .map(|g| g.progress(&app.projects))
+                                .collect();
+                            project_detail_lines(project, &goals)
+                        }
+                        None => vec![Line::from(Span::raw("No project selected"))],
+                    };
+                    let detail_pane = Paragraph::new(details)
+                        .block(Block::default().borders(Borders::ALL).title("Details"));
+
+                    f.render_stateful_widget(projects, project_chunks[0], &mut app.projects_state);
+                    f.render_widget(detail_pane, project_chunks[1]);
                 }
                 AppTab::Tasks => {
                     // Task list
+                    let milestones = app
+                        .projects_state
+                        .selected()
+                        .and_then(|i| app.projects.get(i))
+                        .map(|p| p.milestones.as_slice())
+                        .unwrap_or_default();
+                    let milestone_name = |task: &Task| {
+                        task.milestone
+                            .and_then(|id| milestones.iter().find(|m| m.id == id))
+                            .map(|m| m.name.clone())
+                    };
                     let task_items: Vec<ListItem> = app
                         .tasks
                         .iter()
                         .map(|t| {
-                            ListItem::new(Spans::from(Span::raw(format!(
-                                "ID: {} - {} [Status: {:?}, Priority: {:?}]",
-                                t.id, t.title, t.status, t.priority
-                            ))))
+                            let weight = app.priority_scale.weight(&t.priority);
+                            let style = if t.is_blocked(&app.tasks) {
+                                Style::default().fg(Color::DarkGray)
+                            } else {
+                                Style::default()
+                            };
+                            let mut spans = Vec::new();
+                            if app.group_by_milestone {
+                                spans.push(Span::styled(
+                                    format!(
+                                        "[{}] ",
+                                        milestone_name(t)
+                                            .unwrap_or_else(|| "No milestone".to_string())
+                                    ),
+                                    Style::default().fg(Color::Magenta),
+                                ));
+                            }
+                            spans.extend([
+                                Span::styled(
+                                    format!(
+                                        "ID: {}{} - {} [Status: {}, Priority: ",
+                                        app.id_prefix,
+                                        t.id,
+                                        t.title,
+                                        t.status_label(&app.tasks)
+                                    ),
+                                    style,
+                                ),
+                                Span::styled(
+                                    app.priority_scale.label(&t.priority),
+                                    Style::default().fg(priority_color(weight)),
+                                ),
+                                Span::raw("]"),
+                            ]);
+                            spans.extend(age_dots(t, app.wip_age_limit));
+                            ListItem::new(Line::from(spans))
                         })
                         .collect();
 
+                    let title = match (app.group_by_milestone, app.sort_by_urgency) {
+                        (true, true) => "Tasks (by milestone, then urgency)",
+                        (true, false) => "Tasks (by milestone, then manual order)",
+                        (false, true) => "Tasks (by urgency)",
+                        (false, false) => "Tasks (manual order)",
+                    };
                     let tasks = List::new(task_items)
-                        .block(Block::default().borders(Borders::ALL).title("Tasks"))
+                        .block(Block::default().borders(Borders::ALL).title(title))
                         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
                         .highlight_symbol("> ");
 
-                    f.render_stateful_widget(tasks, chunks[1], &mut app.tasks_state);
+                    // Split the area between the list and a detail pane for the selected task
+                    let task_chunks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(
+                            [Constraint::Percentage(60), Constraint::Percentage(40)].as_ref(),
+                        )
+                        .split(chunks[1]);
+
+                    let details = match app.tasks_state.selected().and_then(|i| app.tasks.get(i)) {
+                        Some(task) => {
+                            let mut lines = task_detail_lines(
+                                task,
+                                &app.tasks,
+                                &app.priority_scale,
+                                &app.id_prefix,
+                            );
+                            if let Some(name) = milestone_name(task) {
+                                lines.insert(4, Line::from(Span::raw(format!("Milestone: {}", name))));
+                            }
+                            lines
+                        }
+                        None => vec![Line::from(Span::raw("No task selected"))],
+                    };
+                    let detail_pane = Paragraph::new(details)
+                        .block(Block::default().borders(Borders::ALL).title("Details"));
+
+                    f.render_stateful_widget(tasks, task_chunks[0], &mut app.tasks_state);
+                    f.render_widget(detail_pane, task_chunks[1]);
+                }
+                AppTab::Planning => {
+                    let task_line = |id: u32| {
+                        let task = app.tasks.iter().find(|t| t.id == id);
+                        let title = task.map_or("", |t| t.title.as_str());
+                        let estimate = match task.map(planning::remaining_hours) {
+                            Some(hours) if hours > 0.0 => format!(" ({})", locale::hours(hours, 1)),
+                            _ => String::new(),
+                        };
+                        let overdue = task
+                            .and_then(|t| t.due)
+                            .is_some_and(|due| due < Utc::now());
+                        let style = if overdue {
+                            Style::default().fg(Color::Red)
+                        } else {
+                            Style::default()
+                        };
+                        let mut spans = vec![Span::styled(
+                            format!(
+                                "  ID: {}{} - {}{}{}",
+                                app.id_prefix,
+                                id,
+                                title,
+                                estimate,
+                                if overdue { " (overdue)" } else { "" }
+                            ),
+                            style,
+                        )];
+                        spans.extend(task.and_then(|t| age_dots(t, app.wip_age_limit)));
+                        Line::from(spans)
+                    };
+                    let heading = Style::default().add_modifier(Modifier::BOLD);
+
+                    let items: Vec<ListItem> = app
+                        .plan_rows
+                        .iter()
+                        .map(|row| match row {
+                            PlanRow::Backlog => ListItem::new(Line::from(Span::styled(
+                                "Unscheduled",
+                                heading.fg(Color::Cyan),
+                            ))),
+                            PlanRow::Day(index) => {
+                                let Some(day) = app.plan.as_ref().and_then(|p| p.days.get(*index))
+                                else {
+                                    return ListItem::new(Line::from(""));
+                                };
+                                let (style, warning) = if day.over_capacity() {
+                                    (heading.fg(Color::Red), "  over capacity!")
+                                } else {
+                                    (heading.fg(Color::Cyan), "")
+                                };
+                                ListItem::new(Line::from(Span::styled(
+                                    format!(
+                                        "{} {}  {} / {}{}",
+                                        index + 1,
+                                        locale::short_weekday_date(day.date),
+                                        locale::hours(day.hours, 1),
+                                        locale::hours(day.capacity, 1),
+                                        warning
+                                    ),
+                                    style,
+                                )))
+                            }
+                            PlanRow::Task(id) => ListItem::new(task_line(*id)),
+                        })
+                        .collect();
+
+                    let board = List::new(items)
+                        .block(Block::default().borders(Borders::ALL).title("This week"))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                        .highlight_symbol("> ");
+
+                    f.render_stateful_widget(board, chunks[1], &mut app.plan_state);
+                }
+                AppTab::Dependencies => {
+                    let find = |id: u32| app.tasks.iter().find(|t| t.id == id);
+                    let heading = Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan);
+                    let items: Vec<ListItem> = app
+                        .dep_rows
+                        .iter()
+                        .map(|row| match row {
+                            DepRow::Heading(text) => {
+                                ListItem::new(Line::from(Span::styled(*text, heading)))
+                            }
+                            DepRow::Task {
+                                id,
+                                depth,
+                                repeated,
+                                ..
+                            } => {
+                                let mut spans = vec![Span::raw("  ".repeat(*depth))];
+                                match find(*id) {
+                                    Some(task) => {
+                                        spans.push(status_glyph(task, &app.tasks));
+                                        spans.push(Span::raw(format!(
+                                            "ID: {}{} - {}",
+                                            app.id_prefix, task.id, task.title
+                                        )));
+                                    }
+                                    None => spans.push(Span::styled(
+                                        format!("ID: {}{} (missing)", app.id_prefix, id),
+                                        Style::default().fg(Color::Red),
+                                    )),
+                                }
+                                if *repeated {
+                                    spans.push(Span::styled(
+                                        " (shown above)",
+                                        Style::default().fg(Color::DarkGray),
+                                    ));
+                                }
+                                ListItem::new(Line::from(spans))
+                            }
+                        })
+                        .collect();
+
+                    let focus = app.dep_focus.and_then(find);
+                    let title = match focus {
+                        Some(task) => {
+                            format!("Dependencies of {}{} - {}", app.id_prefix, task.id, task.title)
+                        }
+                        None => "Dependencies (no task selected)".to_string(),
+                    };
+                    let tree = List::new(items)
+                        .block(Block::default().borders(Borders::ALL).title(title))
+                        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+                        .highlight_symbol("> ");
+
+                    let dep_chunks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(
+                            [Constraint::Percentage(60), Constraint::Percentage(40)].as_ref(),
+                        )
+                        .split(chunks[1]);
+
+                    // Details of the highlighted task, or of the focused one
+                    let shown = match app.selected_dep_row() {
+                        Some(DepRow::Task { id, .. }) => find(*id),
+                        _ => focus,
+                    };
+                    let details = match shown {
+                        Some(task) => task_detail_lines(
+                            task,
+                            &app.tasks,
+                            &app.priority_scale,
+                            &app.id_prefix,
+                        ),
+                        None => vec![Line::from(Span::raw("No task selected"))],
+                    };
+                    let detail_pane = Paragraph::new(details)
+                        .block(Block::default().borders(Borders::ALL).title("Details"));
+
+                    f.render_stateful_widget(tree, dep_chunks[0], &mut app.dep_state);
+                    f.render_widget(detail_pane, dep_chunks[1]);
+
+                    // The picker replaces the detail pane while a dependency is being chosen
+                    if let InputMode::PickingDependency = app.input_mode {
+                        let candidates: Vec<ListItem> = app
+                            .dep_candidates
+                            .iter()
+                            .filter_map(|id| find(*id))
+                            .map(|task| {
+                                ListItem::new(Line::from(vec![
+                                    status_glyph(task, &app.tasks),
+                                    Span::raw(format!(
+                                        "ID: {}{} - {}",
+                                        app.id_prefix, task.id, task.title
+                                    )),
+                                ]))
+                            })
+                            .collect();
+                        let picker = List::new(candidates)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Depend on (Enter to pick, Esc to cancel)"),
+                            )
+                            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                            .highlight_symbol("> ");
+                        f.render_widget(Clear, dep_chunks[1]);
+                        f.render_stateful_widget(
+                            picker,
+                            dep_chunks[1],
+                            &mut app.dep_candidates_state,
+                        );
+                    }
+                }
+                AppTab::Logs => {
+                    // Newest at the bottom, as many as fit
+                    let entries = logs::recent(app.log_level, &app.log_search);
+                    let visible = chunks[1].height.saturating_sub(2) as usize;
+                    let items: Vec<ListItem> = entries
+                        .iter()
+                        .skip(entries.len().saturating_sub(visible))
+                        .map(|entry| {
+                            let color = match entry.level {
+                                Level::Debug => Color::DarkGray,
+                                Level::Info => Color::White,
+                                Level::Warn => Color::Yellow,
+                                Level::Error => Color::Red,
+                            };
+                            ListItem::new(Line::from(vec![
+                                Span::raw(format!(
+                                    "{} ",
+                                    locale::time_with_seconds(entry.at.with_timezone(&Local).time())
+                                )),
+                                Span::styled(
+                                    format!("{:<5} ", entry.level),
+                                    Style::default().fg(color),
+                                ),
+                                Span::styled(
+                                    format!("{}: ", entry.module),
+                                    Style::default().fg(Color::Cyan),
+                                ),
+                                Span::raw(entry.message.clone()),
+                            ]))
+                        })
+                        .collect();
+
+                    let mut title = format!("Logs ({} and above", app.log_level);
+                    if !app.log_search.is_empty() {
+                        title.push_str(&format!(", matching '{}'", app.log_search));
+                    }
+                    title.push(')');
+                    let log_list =
+                        List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+                    f.render_widget(log_list, chunks[1]);
                 }
                 AppTab::Help => {
                     let help_text = vec![
-                        Spans::from(Span::raw("Navigation:")),
-                        Spans::from(Span::raw("  Tab - Switch between tabs")),
-                        Spans::from(Span::raw("  Up/Down - Navigate list")),
-                        Spans::from(Span::raw("  Enter - Select project/task")),
-                        Spans::from(Span::raw("")),
-                        Spans::from(Span::raw("Commands:")),
-                        Spans::from(Span::raw("  a - Add a project/task")),
-                        Spans::from(Span::raw("  d - Delete selected item")),
-                        Spans::from(Span::raw("  q - Quit")),
-                        Spans::from(Span::raw("")),
-                        Spans::from(Span::raw("Input format:")),
-                        Spans::from(Span::raw("  Project: ID Name")),
-                        Spans::from(Span::raw("  Task: ID Title")),
+                        Line::from(Span::raw("Navigation:")),
+                        Line::from(Span::raw("  Tab - Switch between tabs")),
+                        Line::from(Span::raw("  Up/Down - Navigate list")),
+                        Line::from(Span::raw("  s - Sort tasks by urgency or manual order")),
+                        Line::from(Span::raw("  m - Group tasks by milestone")),
+                        Line::from(Span::raw(
+                            "  Shift+Up/Down/Home/End - Reorder the selected task (manual order)",
+                        )),
+                        Line::from(Span::raw("  Enter - Select project/task")),
+                        Line::from(Span::raw(
+                            "  Left/Right - Plan the selected task a day earlier/later (Planning)",
+                        )),
+                        Line::from(Span::raw(
+                            "  1-7 - Plan the selected task for that day, u - Unschedule it (Planning)",
+                        )),
+                        Line::from(Span::raw(
+                            "  Enter - Show the highlighted task's dependencies (Dependencies)",
+                        )),
+                        Line::from(Span::raw("")),
+                        Line::from(Span::raw("Commands:")),
+                        Line::from(Span::raw("  a - Add a project/task (on Logs: search, empty to clear)")),
+                        Line::from(Span::raw("  l - Cycle the minimum log level (Logs)")),
+                        Line::from(Span::raw(
+                            "  : - Run an interactive shell command on the selected project",
+                        )),
+                        Line::from(Span::raw(
+                            "  a - Pick a task for the shown task to depend on (Dependencies)",
+                        )),
+                        Line::from(Span::raw(
+                            "  d - Delete selected item (Dependencies: remove that dependency)",
+                        )),
+                        Line::from(Span::raw("  q - Quit")),
+                        Line::from(Span::raw("")),
+                        Line::from(Span::raw("Input format:")),
+                        Line::from(Span::raw("  Project: ID Name")),
+                        Line::from(Span::raw("  Task: ID Title [@ due, e.g. tomorrow 9am]")),
                     ];
 
                     let help = Paragraph::new(help_text)
@@ -374,14 +1552,33 @@ pub fn run_tui() -> Result<()> {
                 }
             }
 
+            // Output of the last ':' command, over the tab's content
+            if !app.command_output.is_empty() {
+                let lines: Vec<Line> = app
+                    .command_output
+                    .iter()
+                    .map(|line| Line::from(Span::raw(line.as_str())))
+                    .collect();
+                let output = Paragraph::new(lines).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Output (any key to close)"),
+                );
+                f.render_widget(Clear, chunks[1]);
+                f.render_widget(output, chunks[1]);
+            }
+
             // Input bar
             let input_text = Text::from(app.input.as_str());
             let input = Paragraph::new(input_text)
                 .style(match app.input_mode {
-                    InputMode::Normal => Style::default(),
-                    InputMode::Editing => Style::default().fg(Color::Yellow),
+                    InputMode::Normal | InputMode::PickingDependency => Style::default(),
+                    InputMode::Editing | InputMode::Command => Style::default().fg(Color::Yellow),
                 })
-                .block(Block::default().borders(Borders::ALL).title("Input"));
+                .block(Block::default().borders(Borders::ALL).title(match app.input_mode {
+                    InputMode::Command => "Command (Tab completes, 'help' lists commands)",
+                    _ => "Input",
+                }));
 
             f.render_widget(input, chunks[2]);
 
@@ -399,8 +1596,19 @@ pub fn run_tui() -> Result<()> {
                 f.render_widget(status, status_chunk);
             }
 
+            // Stats footer for the selected project
+            if let Some(project) = app
+                .projects_state
+                .selected()
+                .and_then(|i| app.projects.get(i))
+            {
+                let footer = Paragraph::new(Line::from(Span::raw(project.stats().summary())))
+                    .style(Style::default().fg(Color::DarkGray));
+                f.render_widget(footer, chunks[3]);
+            }
+
             // Set cursor position when in editing mode
-            if let InputMode::Editing = app.input_mode {
+            if let InputMode::Editing | InputMode::Command = app.input_mode {
                 f.set_cursor(chunks[2].x + app.input.len() as u16 + 1, chunks[2].y + 1);
             }
         })?;
@@ -409,8 +1617,22 @@ pub fn run_tui() -> Result<()> {
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
                 match app.input_mode {
+                    InputMode::Normal if !app.command_output.is_empty() => {
+                        app.command_output.clear();
+                    }
                     InputMode::Normal => match key.code {
                         KeyCode::Char('q') => break,
+                        KeyCode::Char(':') => {
+                            app.input_mode = InputMode::Command;
+                            app.input.clear();
+                            app.status_message.clear();
+                        }
+                        KeyCode::Char('a') if matches!(app.active_tab, AppTab::Dependencies) => {
+                            app.open_dependency_picker();
+                        }
+                        KeyCode::Char('d') if matches!(app.active_tab, AppTab::Dependencies) => {
+                            app.remove_selected_dependency()?;
+                        }
                         KeyCode::Char('a') => {
                             app.input_mode = InputMode::Editing;
                             app.input.clear();
@@ -444,7 +1666,10 @@ pub fn run_tui() -> Result<()> {
                                                     // Load the project, remove the task, and save
                                                     match app.storage.load_project(project.id) {
                                                         Ok(mut loaded_project) => {
-                                                            loaded_project.remove_task(task_id);
+                                                            loaded_project.remove_task(
+                                                                task_id,
+                                                                OrphanPolicy::Cascade,
+                                                            )?;
                                                             app.storage
                                                                 .save_project(&loaded_project)?;
                                                             app.status_message =
@@ -470,31 +1695,97 @@ pub fn run_tui() -> Result<()> {
                             // Switch tabs
                             app.active_tab = match app.active_tab {
                                 AppTab::Projects => AppTab::Tasks,
-                                AppTab::Tasks => AppTab::Help,
+                                AppTab::Tasks => AppTab::Planning,
+                                AppTab::Planning => AppTab::Dependencies,
+                                AppTab::Dependencies => AppTab::Logs,
+                                AppTab::Logs => AppTab::Help,
                                 AppTab::Help => AppTab::Projects,
                             };
 
                             // If switching to Tasks tab, load tasks for the selected project
+                            match app.active_tab {
+                                AppTab::Tasks => app.load_project_tasks()?,
+                                AppTab::Planning => app.load_plan(None)?,
+                                AppTab::Dependencies => {
+                                    // The tree is about the task selected on the Tasks tab
+                                    let focus = app
+                                        .tasks_state
+                                        .selected()
+                                        .and_then(|i| app.tasks.get(i))
+                                        .map(|t| t.id);
+                                    app.load_dependencies(focus)?;
+                                }
+                                _ => {}
+                            }
+                        }
+                        KeyCode::Left | KeyCode::Right
+                            if matches!(app.active_tab, AppTab::Planning) =>
+                        {
+                            app.shift_selected_task(key.code == KeyCode::Right)?;
+                        }
+                        KeyCode::Char(c @ '1'..='7')
+                            if matches!(app.active_tab, AppTab::Planning) =>
+                        {
+                            let day = c as usize - '1' as usize;
+                            app.plan_selected_task(Some(day))?;
+                        }
+                        KeyCode::Char('l') if matches!(app.active_tab, AppTab::Logs) => {
+                            app.log_level = app.log_level.next();
+                        }
+                        KeyCode::Char('u') if matches!(app.active_tab, AppTab::Planning) => {
+                            app.plan_selected_task(None)?;
+                        }
+                        KeyCode::Char('s') => {
+                            if let AppTab::Tasks = app.active_tab {
+                                app.sort_by_urgency = !app.sort_by_urgency;
+                                app.load_project_tasks()?;
+                            }
+                        }
+                        KeyCode::Char('m') => {
                             if let AppTab::Tasks = app.active_tab {
+                                app.group_by_milestone = !app.group_by_milestone;
                                 app.load_project_tasks()?;
                             }
                         }
+                        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                            if let AppTab::Tasks = app.active_tab {
+                                app.move_selected_task(TaskMove::Up)?;
+                            }
+                        }
+                        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                            if let AppTab::Tasks = app.active_tab {
+                                app.move_selected_task(TaskMove::Down)?;
+                            }
+                        }
+                        KeyCode::Home if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                            if let AppTab::Tasks = app.active_tab {
+                                app.move_selected_task(TaskMove::Top)?;
+                            }
+                        }
+                        KeyCode::End if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                            if let AppTab::Tasks = app.active_tab {
+                                app.move_selected_task(TaskMove::Bottom)?;
+                            }
+                        }
                         KeyCode::Up => {
                             app.select_previous();
                         }
                         KeyCode::Down => {
                             app.select_next();
                         }
+                        KeyCode::Enter if matches!(app.active_tab, AppTab::Dependencies) => {
+                            if let Some(DepRow::Task { id, .. }) = app.selected_dep_row() {
+                                let id = *id;
+                                app.load_dependencies(Some(id))?;
+                            }
+                        }
                         KeyCode::Enter => {
                             // Select the current item
-                            match app.active_tab {
-                                AppTab::Projects => {
-                                    if app.projects_state.selected().is_some() {
-                                        app.active_tab = AppTab::Tasks;
-                                        app.load_project_tasks()?;
-                                    }
+                            if let AppTab::Projects = app.active_tab {
+                                if app.projects_state.selected().is_some() {
+                                    app.active_tab = AppTab::Tasks;
+                                    app.load_project_tasks()?;
                                 }
-                                _ => {}
                             }
                         }
                         _ => {}
@@ -509,6 +1800,9 @@ pub fn run_tui() -> Result<()> {
                                 AppTab::Tasks => {
                                     app.add_task()?;
                                 }
+                                AppTab::Logs => {
+                                    app.log_search = std::mem::take(&mut app.input);
+                                }
                                 _ => {}
                             }
                             app.input_mode = InputMode::Normal;
@@ -525,6 +1819,40 @@ pub fn run_tui() -> Result<()> {
                         }
                         _ => {}
                     },
+                    InputMode::PickingDependency => match key.code {
+                        KeyCode::Enter => app.add_picked_dependency()?,
+                        KeyCode::Up => {
+                            let i = app.dep_candidates_state.selected().unwrap_or(0);
+                            app.dep_candidates_state.select(Some(i.saturating_sub(1)));
+                        }
+                        KeyCode::Down => {
+                            let i = app.dep_candidates_state.selected().map_or(0, |i| i + 1);
+                            let last = app.dep_candidates.len().saturating_sub(1);
+                            app.dep_candidates_state.select(Some(i.min(last)));
+                        }
+                        KeyCode::Esc => app.input_mode = InputMode::Normal,
+                        _ => {}
+                    },
+                    InputMode::Command => match key.code {
+                        KeyCode::Enter => {
+                            app.input_mode = InputMode::Normal;
+                            if !app.run_command()? {
+                                break;
+                            }
+                        }
+                        KeyCode::Tab => app.complete_command(),
+                        KeyCode::Char(c) => {
+                            app.input.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            app.input.pop();
+                        }
+                        KeyCode::Esc => {
+                            app.input_mode = InputMode::Normal;
+                            app.input.clear();
+                        }
+                        _ => {}
+                    },
                 }
             }
         }
@@ -538,6 +1866,7 @@ pub fn run_tui() -> Result<()> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
+    logs::set_echo(true);
 
     Ok(())
 }
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::priority::PriorityScale;
+use crate::project::Project;
+use crate::task::{Task, TaskStatus};
+
+// Weights for each urgency term, set under `urgency` in `config.json`. The defaults follow
+// taskwarrior's, so a High-priority task due today scores about 13.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UrgencyCoefficients {
+    // Scaled by the priority's weight, 0 for the least urgent to 1 for the most
+    pub priority: f64,
+    // Scaled from 0.2 (two weeks or more away) to 1.0 (a week or more overdue)
+    pub due: f64,
+    // Scaled by age up to `age_max_days`
+    pub age: f64,
+    pub age_max_days: f64,
+    // Unfinished tasks that other unfinished tasks are waiting on
+    pub blocking: f64,
+    // Tasks waiting on dependencies or blocked by hand; usually negative
+    pub blocked: f64,
+    pub in_progress: f64,
+    // Added once per matching tag, e.g. {"urgent": 5.0, "someday": -3.0}
+    pub tags: HashMap<String, f64>,
+}
+
+impl Default for UrgencyCoefficients {
+    fn default() -> Self {
+        UrgencyCoefficients {
+            priority: 6.0,
+            due: 12.0,
+            age: 2.0,
+            age_max_days: 365.0,
+            blocking: 8.0,
+            blocked: -5.0,
+            in_progress: 4.0,
+            tags: HashMap::new(),
+        }
+    }
+}
+
+impl UrgencyCoefficients {
+    // Urgency of `task` among `tasks`; finished tasks score 0
+    pub fn score(
+        &self,
+        task: &Task,
+        tasks: &[Task],
+        scale: &PriorityScale,
+        now: DateTime<Utc>,
+    ) -> f64 {
+        if task.status == TaskStatus::Done {
+            return 0.0;
+        }
+
+        let mut score = self.priority * scale.weight(&task.priority) as f64 / 100.0;
+
+        if let Some(due) = task.due {
+            // A missed habit stays as pressing as on its due date rather than escalating
+            let due = if task.habit.is_some() {
+                due.max(now)
+            } else {
+                due
+            };
+            score += self.due * due_factor(due, now);
+        }
+
+        if self.age_max_days > 0.0 {
+            let age_days = (now - task.created_at).num_seconds() as f64 / 86_400.0;
+            score += self.age * (age_days / self.age_max_days).clamp(0.0, 1.0);
+        }
+
+        let blocking = tasks
+            .iter()
+            .any(|other| other.status != TaskStatus::Done && other.depends_on(task.id));
+        if blocking {
+            score += self.blocking;
+        }
+        if task.is_blocked(tasks) {
+            score += self.blocked;
+        }
+        if task.status == TaskStatus::InProgress {
+            score += self.in_progress;
+        }
+
+        for tag in &task.tags {
+            if let Some(weight) = self.tags.get(tag) {
+                score += weight;
+            }
+        }
+
+        score
+    }
+}
+
+// Tasks across `projects` that can be worked on now, unfinished with nothing blocking them,
+// from most to least urgent. Ties go to the more urgent priority, then project and task order.
+pub fn actionable<'a>(
+    projects: &'a [Project],
+    coefficients: &UrgencyCoefficients,
+    now: DateTime<Utc>,
+) -> Vec<(&'a Project, &'a Task, f64)> {
+    let mut ranked: Vec<(&Project, &Task, f64)> = projects
+        .iter()
+        .flat_map(|project| {
+            project
+                .tasks
+                .iter()
+                .filter(|task| task.status != TaskStatus::Done && !task.is_blocked(&project.tasks))
+                .map(move |task| {
+                    let scale = &project.settings.priority_scale;
+                    let score = coefficients.score(task, &project.tasks, scale, now);
+                    (project, task, score)
+                })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.2.total_cmp(&a.2).then_with(|| {
+            let weight = |(project, task, _): &(&Project, &Task, f64)| {
+                project.settings.priority_scale.weight(&task.priority)
+            };
+            weight(b).cmp(&weight(a))
+        })
+    });
+    ranked
+}
+
+// 1.0 at a week or more overdue, falling linearly to 0.2 at two weeks or more away
+fn due_factor(due: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let days_until = (due - now).num_seconds() as f64 / 86_400.0;
+    if days_until <= -7.0 {
+        1.0
+    } else if days_until >= 14.0 {
+        0.2
+    } else {
+        (14.0 - days_until) * 0.8 / 21.0 + 0.2
+    }
+}
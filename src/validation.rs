@@ -0,0 +1,130 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::priority::PriorityScale;
+use crate::task::{Task, TaskPriority, TaskStatus};
+
+// A per-project rule checked whenever a task is added or updated
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ValidationRule {
+    NonEmptyTitle,
+    // Unfinished tasks at least this urgent must have a due date
+    DueDateRequired { priority: TaskPriority },
+    // At most this many tasks InProgress at once
+    MaxInProgress { limit: usize },
+}
+
+// One broken rule for one task
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Violation {
+    pub task_id: u32,
+    pub rule: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {}: {} ({})", self.task_id, self.message, self.rule)
+    }
+}
+
+impl fmt::Display for ValidationRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationRule::NonEmptyTitle => write!(f, "non-empty-title"),
+            ValidationRule::DueDateRequired { priority } => write!(f, "due-required:{}", priority),
+            ValidationRule::MaxInProgress { limit } => write!(f, "max-in-progress:{}", limit),
+        }
+    }
+}
+
+impl ValidationRule {
+    // "non-empty-title", "due-required:<priority>" or "max-in-progress:<n>"; priorities are
+    // read on the project's scale
+    pub fn parse(input: &str, scale: &PriorityScale) -> Result<Self> {
+        let input = input.trim();
+        let (name, arg) = match input.split_once(':') {
+            Some((name, arg)) => (name.trim().to_lowercase(), Some(arg.trim())),
+            None => (input.to_lowercase(), None),
+        };
+
+        match (name.as_str(), arg) {
+            ("non-empty-title", None) => Ok(ValidationRule::NonEmptyTitle),
+            ("due-required", Some(priority)) => Ok(ValidationRule::DueDateRequired {
+                priority: scale.parse(priority)?,
+            }),
+            ("max-in-progress", Some(limit)) => {
+                let limit = limit.parse().map_err(|_| {
+                    TaskMasterError::InvalidOperation(format!(
+                        "max-in-progress needs a number, got '{}'",
+                        limit
+                    ))
+                })?;
+                Ok(ValidationRule::MaxInProgress { limit })
+            }
+            _ => Err(TaskMasterError::InvalidOperation(format!(
+                "Unknown rule '{}'; use non-empty-title, due-required:<priority> or max-in-progress:<n>",
+                input
+            ))),
+        }
+    }
+
+    // Check `task` as it would be saved, alongside the project's other tasks
+    pub fn check(&self, task: &Task, others: &[Task], scale: &PriorityScale) -> Option<Violation> {
+        let message = match self {
+            ValidationRule::NonEmptyTitle if task.title.trim().is_empty() => {
+                "title must not be empty".to_string()
+            }
+            ValidationRule::DueDateRequired { priority }
+                if task.due.is_none()
+                    && task.status != TaskStatus::Done
+                    && scale.compare(&task.priority, priority) != Ordering::Greater =>
+            {
+                format!(
+                    "tasks with priority {} or higher need a due date",
+                    scale.label(priority)
+                )
+            }
+            ValidationRule::MaxInProgress { limit } if task.status == TaskStatus::InProgress => {
+                let in_progress = others
+                    .iter()
+                    .filter(|t| t.id != task.id && t.status == TaskStatus::InProgress)
+                    .count()
+                    + 1;
+                if in_progress <= *limit {
+                    return None;
+                }
+                format!("at most {} tasks may be InProgress", limit)
+            }
+            _ => return None,
+        };
+
+        Some(Violation {
+            task_id: task.id,
+            rule: self.to_string(),
+            message,
+        })
+    }
+}
+
+// Every rule `task` breaks, as a ValidationFailed error
+pub fn evaluate(
+    rules: &[ValidationRule],
+    task: &Task,
+    others: &[Task],
+    scale: &PriorityScale,
+) -> Result<()> {
+    let violations: Vec<Violation> = rules
+        .iter()
+        .filter_map(|rule| rule.check(task, others, scale))
+        .collect();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(TaskMasterError::ValidationFailed(violations))
+    }
+}
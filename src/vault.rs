@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::project::Project;
+use crate::storage::Storage;
+use crate::task::TaskStatus;
+use crate::task_dependencies::OrphanPolicy;
+
+pub const VAULT_STATE_FILE: &str = "vault_sync.json";
+
+// Marks a checkbox line with the task it is linked to, as an Obsidian block ID
+const MARKER: &str = "^tm-";
+
+// Each Markdown note in a vault maps to a project and each checkbox line to a task.
+// The state remembers what was last synced so a change on one side can be told apart
+// from a change on the other.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VaultState {
+    // Keyed by the note's path
+    #[serde(default)]
+    notes: BTreeMap<String, NoteLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteLink {
+    pub project: u32,
+    // Whether each linked task was checked at the last sync
+    #[serde(default)]
+    pub tasks: BTreeMap<u32, bool>,
+}
+
+impl VaultState {
+    pub fn load<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(VAULT_STATE_FILE);
+        if !path.exists() {
+            return Ok(VaultState::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, data_dir: P) -> Result<()> {
+        let path = data_dir.as_ref().join(VAULT_STATE_FILE);
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// What one sync did to a note and its project
+#[derive(Debug, Clone)]
+pub struct NoteReport {
+    pub note: PathBuf,
+    pub project: u32,
+    pub changes: Vec<String>,
+}
+
+// A "- [ ] Title ^tm-3" line split into its parts
+struct Checkbox {
+    // Indentation and bullet, kept as written
+    prefix: String,
+    done: bool,
+    title: String,
+    id: Option<u32>,
+}
+
+impl Checkbox {
+    fn parse(line: &str) -> Option<Self> {
+        let body = line.trim_start();
+        let indent = &line[..line.len() - body.len()];
+        let bullet = ["- ", "* ", "+ "]
+            .into_iter()
+            .find(|bullet| body.starts_with(bullet))?;
+        let rest = &body[bullet.len()..];
+        let (done, rest) = if let Some(rest) = rest.strip_prefix("[ ]") {
+            (false, rest)
+        } else if let Some(rest) = rest
+            .strip_prefix("[x]")
+            .or_else(|| rest.strip_prefix("[X]"))
+        {
+            (true, rest)
+        } else {
+            return None;
+        };
+
+        let mut title = rest.trim();
+        let mut id = None;
+        if let Some((head, marker)) = title.rsplit_once(MARKER) {
+            if let Ok(parsed) = marker.trim().parse::<u32>() {
+                id = Some(parsed);
+                title = head.trim_end();
+            }
+        }
+
+        Some(Checkbox {
+            prefix: format!("{}{}", indent, bullet),
+            done,
+            title: title.to_string(),
+            id,
+        })
+    }
+
+    fn render(&self) -> String {
+        let mut line = format!(
+            "{}[{}] {}",
+            self.prefix,
+            if self.done { "x" } else { " " },
+            self.title
+        );
+        if let Some(id) = self.id {
+            line.push_str(&format!(" {}{}", MARKER, id));
+        }
+        line
+    }
+}
+
+// Sync every Markdown note under `vault` (skipping hidden folders such as .obsidian) with its
+// project, creating a project named after the note when none is linked or matches by name
+pub fn sync_vault(
+    vault: &Path,
+    storage: &mut dyn Storage,
+    state: &mut VaultState,
+) -> Result<Vec<NoteReport>> {
+    let mut notes = Vec::new();
+    collect_notes(vault, &mut notes)?;
+    notes.sort();
+
+    let mut reports = Vec::new();
+    let mut linked = HashSet::new();
+    for note in notes {
+        let key = note.to_string_lossy().to_string();
+        let link = state.notes.get(&key).cloned();
+
+        let mut project = match link
+            .as_ref()
+            .and_then(|l| storage.load_project(l.project).ok())
+        {
+            Some(project) => project,
+            None => project_for_note(&note, storage)?,
+        };
+        // A project re-linked to a new note starts from a clean slate
+        let link = link.filter(|l| l.project == project.id);
+
+        let text = fs::read_to_string(&note)?;
+        let modified: DateTime<Utc> = fs::metadata(&note)?.modified()?.into();
+        let (new_text, new_link, changes) = sync_note(&text, &mut project, link.as_ref(), modified);
+
+        storage.save_project(&project)?;
+        if new_text != text {
+            fs::write(&note, new_text)?;
+        }
+        state.notes.insert(key.clone(), new_link);
+        linked.insert(key);
+
+        reports.push(NoteReport {
+            note,
+            project: project.id,
+            changes,
+        });
+    }
+
+    // Forget notes under this vault that have been deleted; their projects are kept
+    let prefix = vault.to_string_lossy().to_string();
+    state
+        .notes
+        .retain(|key, _| !key.starts_with(&prefix) || linked.contains(key));
+    Ok(reports)
+}
+
+fn collect_notes(dir: &Path, notes: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_notes(&path, notes)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            notes.push(path);
+        }
+    }
+    Ok(())
+}
+
+// The unarchived project named like the note, or a new one
+fn project_for_note(note: &Path, storage: &mut dyn Storage) -> Result<Project> {
+    let name = note
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Some(project) = storage
+        .list_projects()?
+        .into_iter()
+        .find(|p| !p.archived && p.name.eq_ignore_ascii_case(&name))
+    {
+        return Ok(project);
+    }
+
+    let project = Project::new(storage.next_project_id()?, name);
+    storage.save_project(&project)?;
+    Ok(project)
+}
+
+// Reconcile one note with its project, returning the new note text, the link to remember
+// and a description of each change. A checkbox changed on only one side since the last
+// sync takes that side; when both changed, the more recent edit wins.
+pub fn sync_note(
+    text: &str,
+    project: &mut Project,
+    link: Option<&NoteLink>,
+    note_modified: DateTime<Utc>,
+) -> (String, NoteLink, Vec<String>) {
+    let empty = BTreeMap::new();
+    let previous = link.map_or(&empty, |l| &l.tasks);
+    let mut lines: Vec<Option<String>> = text.lines().map(|l| Some(l.to_string())).collect();
+    let mut synced = BTreeMap::new();
+    let mut changes = Vec::new();
+
+    for slot in lines.iter_mut() {
+        let Some(mut item) = slot.as_deref().and_then(Checkbox::parse) else {
+            continue;
+        };
+
+        let existing = item
+            .id
+            .filter(|id| !synced.contains_key(id))
+            .and_then(|id| project.get_task(id).ok());
+        match existing {
+            Some(task) => {
+                let id = task.id;
+                let task_done = task.status == TaskStatus::Done;
+                let last = previous.get(&id).copied();
+                let note_changed = last.is_some_and(|last| last != item.done);
+                let task_changed = last.is_some_and(|last| last != task_done);
+
+                let mut done = match (note_changed, task_changed) {
+                    _ if item.done == task_done => task_done,
+                    (true, false) => item.done,
+                    (false, true) => task_done,
+                    _ if note_modified > task.updated_at => item.done,
+                    _ => task_done,
+                };
+
+                if done != task_done {
+                    let status = if done {
+                        Ok(TaskStatus::Done)
+                    } else {
+                        project.settings.workflow.initial_state()
+                    };
+                    let (title, priority) = (task.title.clone(), task.priority.clone());
+                    let updated = status.and_then(|status| {
+                        project.update_task(id, title, status.clone(), priority)?;
+                        Ok(status)
+                    });
+                    match updated {
+                        Ok(status) => changes.push(format!("task {} -> {}", id, status)),
+                        Err(e) => {
+                            changes.push(format!("task {} left unchanged: {}", id, e));
+                            done = task_done;
+                        }
+                    }
+                }
+                if done != item.done {
+                    item.done = done;
+                    *slot = Some(item.render());
+                    changes.push(format!(
+                        "note: {} \"{}\"",
+                        if done { "checked" } else { "unchecked" },
+                        item.title
+                    ));
+                }
+                synced.insert(id, done);
+            }
+            // Deleted from the project since the last sync
+            None if item.id.is_some_and(|id| previous.contains_key(&id)) => {
+                changes.push(format!("note: removed \"{}\"", item.title));
+                *slot = None;
+            }
+            None => {
+                let added = project.next_task_id().and_then(|id| {
+                    let mut task = project.new_task(id, item.title.clone())?;
+                    if item.done {
+                        task.set_status(TaskStatus::Done);
+                    }
+                    project.add_task(task).map(|_| id)
+                });
+                match added {
+                    Ok(id) => {
+                        item.id = Some(id);
+                        *slot = Some(item.render());
+                        synced.insert(id, item.done);
+                        changes.push(format!("task {} added: {}", id, item.title));
+                    }
+                    Err(e) => changes.push(format!("\"{}\" not added: {}", item.title, e)),
+                }
+            }
+        }
+    }
+
+    // Tasks removed from the note go; tasks new in the project are appended
+    let mut removed = Vec::new();
+    let mut appended = Vec::new();
+    for task in &project.tasks {
+        if synced.contains_key(&task.id) {
+            continue;
+        }
+        if previous.contains_key(&task.id) {
+            removed.push(task.id);
+            continue;
+        }
+        let done = task.status == TaskStatus::Done;
+        appended.push(
+            Checkbox {
+                prefix: "- ".to_string(),
+                done,
+                title: task.title.clone(),
+                id: Some(task.id),
+            }
+            .render(),
+        );
+        changes.push(format!("note: added \"{}\"", task.title));
+        synced.insert(task.id, done);
+    }
+    for id in removed {
+        if project.remove_task(id, OrphanPolicy::Cascade).is_ok() {
+            changes.push(format!("task {} removed", id));
+        }
+    }
+
+    let mut new_text: Vec<String> = lines.into_iter().flatten().collect();
+    new_text.extend(appended);
+    let mut new_text = new_text.join("\n");
+    if text.ends_with('\n') || text.is_empty() {
+        new_text.push('\n');
+    }
+
+    let link = NoteLink {
+        project: project.id,
+        tasks: synced,
+    };
+    (new_text, link, changes)
+}
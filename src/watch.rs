@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local, Utc};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType},
+};
+
+use crate::async_executor::TaskEvent;
+use crate::dates;
+use crate::error::Result;
+use crate::event_log::{EventLog, LoggedEvent};
+use crate::locale;
+use crate::project::Project;
+use crate::storage::Storage;
+use crate::task::TaskStatus;
+
+// How far back the view looks in the event log for runs and recent events
+const EVENT_WINDOW_HOURS: i64 = 24;
+
+// Tasks due within this many hours are highlighted
+const DUE_SOON_HOURS: i64 = 24;
+
+// One line of the view, colored as a whole
+pub struct Row {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+impl Row {
+    fn plain(text: String) -> Self {
+        Row { text, color: None }
+    }
+
+    fn colored(text: String, color: Color) -> Self {
+        Row {
+            text,
+            color: Some(color),
+        }
+    }
+}
+
+// The project's open tasks by how soon they are due, the runs that have started but not
+// finished, and the latest events, fitted to `height` lines
+pub fn render(
+    project: &Project,
+    events: &[LoggedEvent],
+    now: DateTime<Utc>,
+    height: usize,
+) -> Vec<Row> {
+    let events: Vec<&LoggedEvent> = events
+        .iter()
+        .filter(|e| e.project_id == Some(project.id))
+        .collect();
+    let heading = |text: &str| Row::colored(text.to_string(), Color::Cyan);
+
+    let mut rows = vec![
+        Row::plain(format!(
+            "{}  {}  (q to quit)",
+            project.label(),
+            locale::time_with_seconds(now.with_timezone(&Local).time())
+        )),
+        Row::plain(String::new()),
+    ];
+
+    // Runs still going: started, with no later event ending them
+    let mut running: BTreeMap<String, (u32, DateTime<Utc>)> = BTreeMap::new();
+    for logged in &events {
+        let run = logged.event.run_id().to_string();
+        match &logged.event {
+            TaskEvent::Started { task_id, .. } => {
+                running.insert(run, (*task_id, logged.recorded_at));
+            }
+            TaskEvent::Completed { .. }
+            | TaskEvent::Failed { .. }
+            | TaskEvent::Timeout { .. }
+            | TaskEvent::Terminated { .. } => {
+                running.remove(&run);
+            }
+            TaskEvent::Unblocked { .. } => {}
+        }
+    }
+    let mut running: Vec<(String, u32, DateTime<Utc>)> = running
+        .into_iter()
+        .map(|(run, (task, started))| (run, task, started))
+        .collect();
+    running.sort_by_key(|(_, _, started)| *started);
+
+    let mut due: Vec<_> = project
+        .tasks
+        .iter()
+        .filter(|t| t.status != TaskStatus::Done)
+        .filter_map(|t| t.due.map(|due| (due, t)))
+        .collect();
+    due.sort_by_key(|(due, _)| *due);
+
+    // Running runs and recent events get up to a quarter of the screen each, due tasks the rest
+    let budget = height.saturating_sub(rows.len() + 6).max(3);
+    let run_lines = running.len().min(budget / 4).max(1);
+    let event_lines = events.len().min(budget / 4).max(1);
+    let due_lines = budget.saturating_sub(run_lines + event_lines).max(1);
+
+    rows.push(heading("Due"));
+    if due.is_empty() {
+        rows.push(Row::plain("  No open tasks with due dates".to_string()));
+    }
+    for (due, task) in due.iter().take(due_lines) {
+        let left = *due - now;
+        let title = format!("{} - {}", project.task_ref(task.id), task.title);
+        if left < chrono::Duration::zero() {
+            rows.push(Row::colored(
+                format!("  {:>10} overdue  {}", dates::format_age(-left), title),
+                Color::Red,
+            ));
+        } else if left < chrono::Duration::hours(DUE_SOON_HOURS) {
+            rows.push(Row::colored(
+                format!("  {:>10} left     {}", dates::format_age(left), title),
+                Color::Yellow,
+            ));
+        } else {
+            rows.push(Row::plain(format!(
+                "  {:>10} left     {}",
+                dates::format_age(left),
+                title
+            )));
+        }
+    }
+    if due.len() > due_lines {
+        rows.push(Row::plain(format!("  ... {} more", due.len() - due_lines)));
+    }
+
+    rows.push(Row::plain(String::new()));
+    rows.push(heading("Running"));
+    if running.is_empty() {
+        rows.push(Row::plain("  Nothing running".to_string()));
+    }
+    for (run, task_id, started) in running.iter().take(run_lines) {
+        let title = project
+            .get_task(*task_id)
+            .map(|t| t.title.clone())
+            .unwrap_or_default();
+        rows.push(Row::colored(
+            format!(
+                "  [{}] {} - {}  for {}",
+                run,
+                project.task_ref(*task_id),
+                title,
+                dates::format_age(now - *started)
+            ),
+            Color::Green,
+        ));
+    }
+
+    rows.push(Row::plain(String::new()));
+    rows.push(heading("Recent events"));
+    if events.is_empty() {
+        rows.push(Row::plain("  None".to_string()));
+    }
+    for logged in events.iter().skip(events.len().saturating_sub(event_lines)) {
+        let color = match logged.event {
+            TaskEvent::Failed { .. } | TaskEvent::Timeout { .. } => Some(Color::Red),
+            _ => None,
+        };
+        rows.push(Row {
+            text: format!(
+                "  {}  {}",
+                locale::time_with_seconds(logged.recorded_at.with_timezone(&Local).time()),
+                logged.event
+            ),
+            color,
+        });
+    }
+
+    rows
+}
+
+// Keep a live view of the project on screen until q, Esc or Ctrl+C. It is redrawn every
+// `interval` for the countdowns, and straight away when the project file or event log changes.
+pub fn watch(
+    storage: &dyn Storage,
+    log: &EventLog,
+    project_id: u32,
+    interval: Duration,
+) -> Result<()> {
+    let mut project = storage.load_project(project_id)?;
+
+    let mut stdout = io::stdout();
+    enable_raw_mode()?;
+    execute!(stdout, Hide)?;
+    let result = watch_loop(storage, log, &mut project, interval);
+    execute!(stdout, Show, ResetColor)?;
+    disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn watch_loop(
+    storage: &dyn Storage,
+    log: &EventLog,
+    project: &mut Project,
+    interval: Duration,
+) -> Result<()> {
+    let mut stdout = io::stdout();
+    let mut project_seen: Option<SystemTime> = storage.project_modified(project.id);
+    let mut log_seen = log.modified();
+    let mut events = recent_events(log)?;
+    let mut last_draw: Option<std::time::Instant> = None;
+
+    loop {
+        let modified = storage.project_modified(project.id);
+        let mut changed = false;
+        if modified != project_seen {
+            project_seen = modified;
+            // A project caught mid-save is picked up on the next change
+            if let Ok(reloaded) = storage.load_project(project.id) {
+                *project = reloaded;
+                changed = true;
+            }
+        }
+        if log.modified() != log_seen {
+            log_seen = log.modified();
+            events = recent_events(log)?;
+            changed = true;
+        }
+
+        if changed || last_draw.is_none_or(|at| at.elapsed() >= interval) {
+            let (width, height) = terminal::size()?;
+            let rows = render(project, &events, Utc::now(), height as usize);
+            queue!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+            for (line, row) in rows.iter().take(height as usize).enumerate() {
+                let text: String = row.text.chars().take(width as usize).collect();
+                queue!(stdout, MoveTo(0, line as u16))?;
+                match row.color {
+                    Some(color) => {
+                        queue!(stdout, SetForegroundColor(color), Print(text), ResetColor)?
+                    }
+                    None => queue!(stdout, Print(text))?,
+                }
+            }
+            stdout.flush()?;
+            last_draw = Some(std::time::Instant::now());
+        }
+
+        if event::poll(Duration::from_millis(200))? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn recent_events(log: &EventLog) -> Result<Vec<LoggedEvent>> {
+    log.read_range(
+        Some(Utc::now() - chrono::Duration::hours(EVENT_WINDOW_HOURS)),
+        None,
+    )
+}
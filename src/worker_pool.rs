@@ -1,96 +1,345 @@
-use std::sync::{mpsc, Arc, Mutex};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::async_executor::{FailureCause, TaskEvent};
+use crate::cancellation::CancellationFlag;
+use crate::correlation::RunId;
 use crate::error::{Result, TaskMasterError};
+use crate::follow_ups::FollowUp;
 use crate::task::Task;
 
-// Message types for the worker pool
-enum Message {
-    NewTask(TaskJob),
-    Terminate,
-}
+// Times an idle worker yields and looks again before going to sleep
+const SPIN_ROUNDS: usize = 8;
+
+// How often a dropped pool looks again at a worker it is waiting for
+const SHUTDOWN_POLL: Duration = Duration::from_millis(10);
 
 // A job to be executed by the worker pool
 pub struct TaskJob {
     pub id: u32,
+    pub run_id: RunId,
     pub task: Arc<Task>,
-    pub handler: Box<dyn FnOnce(Arc<Task>) -> Result<()> + Send + 'static>,
+    // Passed to the handler, for whoever submitted the job to stop it early
+    pub cancel: CancellationFlag,
+    pub handler: Box<dyn FnOnce(Arc<Task>, CancellationFlag) -> JobOutcome + Send + 'static>,
+}
+
+// How a job ended
+#[derive(Debug)]
+pub enum JobOutcome {
+    // Anything the job reported back, such as which handler ran it
+    Success { outputs: Vec<String> },
+    Failed(TaskMasterError),
+    // Ran past the executor's timeout; it may still have finished its work
+    TimedOut,
+    // Cancelled while it ran; it may still have finished its work
+    Cancelled,
+    // The job panicked, with the panic's message
+    Panicked(String),
+}
+
+impl JobOutcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self, JobOutcome::Success { .. })
+    }
+
+    // The event announcing this outcome, as the event log records it
+    pub fn event(&self, task_id: u32, run_id: RunId) -> TaskEvent {
+        match self {
+            JobOutcome::Success { .. } => TaskEvent::Completed { task_id, run_id },
+            JobOutcome::Failed(e) => TaskEvent::Failed {
+                task_id,
+                run_id,
+                error_message: e.to_string(),
+                cause: Some(FailureCause::Error(e.kind().to_string())),
+            },
+            JobOutcome::TimedOut => TaskEvent::Timeout { task_id, run_id },
+            JobOutcome::Cancelled => TaskEvent::Terminated { task_id, run_id },
+            JobOutcome::Panicked(message) => TaskEvent::Failed {
+                task_id,
+                run_id,
+                error_message: message.clone(),
+                cause: Some(FailureCause::Panicked),
+            },
+        }
+    }
+}
+
+impl From<Result<()>> for JobOutcome {
+    fn from(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => JobOutcome::Success {
+                outputs: Vec::new(),
+            },
+            Err(e) => JobOutcome::Failed(e),
+        }
+    }
+}
+
+impl fmt::Display for JobOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobOutcome::Success { outputs } if outputs.is_empty() => write!(f, "finished"),
+            JobOutcome::Success { outputs } => write!(f, "finished ({})", outputs.join("; ")),
+            JobOutcome::Failed(e) => write!(f, "failed: {}", e),
+            JobOutcome::TimedOut => write!(f, "timed out"),
+            JobOutcome::Cancelled => write!(f, "was cancelled"),
+            JobOutcome::Panicked(message) => write!(f, "panicked: {}", message),
+        }
+    }
 }
 
 // Result of a completed job
 pub struct JobResult {
     pub task_id: u32,
-    pub success: bool,
-    pub error_message: Option<String>,
+    pub run_id: RunId,
+    pub outcome: JobOutcome,
+    // Tasks the handler asked for given the outcome; filled in by TaskExecutor
+    pub follow_ups: Vec<FollowUp>,
+}
+
+// State shared by the pool and its workers. Every worker has its own deque, and jobs are
+// dealt out to the deques in turn, so a worker taking its next job only contends with
+// submitters and thieves touching that one deque. A worker whose deque runs dry steals
+// from the back of the others' before going to sleep.
+struct Shared {
+    deques: Vec<Mutex<VecDeque<TaskJob>>>,
+    // Jobs sitting in any deque
+    queued: AtomicUsize,
+    // Workers asleep or about to be; submitters only take the sleep lock when this isn't 0
+    sleepers: AtomicUsize,
+    sleep: Mutex<()>,
+    wake: Condvar,
+    shutting_down: AtomicBool,
+}
+
+impl Shared {
+    // The next job for worker `id`: its own oldest job, or else the newer half of another
+    // worker's deque, trying them in order from the next worker along. Taking half at once
+    // means a thief comes back to steal far less often than it would one job at a time.
+    fn find_job(&self, id: usize) -> Option<TaskJob> {
+        if self.queued.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+        if let Some(job) = self.deques[id].lock().unwrap().pop_front() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Some(job);
+        }
+        let count = self.deques.len();
+        for offset in 1..count {
+            let victim = (id + offset) % count;
+            let mut stolen = {
+                let mut deque = self.deques[victim].lock().unwrap();
+                let keep = deque.len() / 2;
+                deque.split_off(keep)
+            };
+            let Some(job) = stolen.pop_front() else {
+                continue;
+            };
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            if !stolen.is_empty() {
+                self.deques[id].lock().unwrap().append(&mut stolen);
+            }
+            return Some(job);
+        }
+        None
+    }
+
+    // Block until there may be a job to take; false once the pool is shutting down and
+    // every queued job has been taken
+    fn wait_for_work(&self) -> bool {
+        let mut guard = self.sleep.lock().unwrap();
+        // Announce the sleep before the last check, so a submitter either sees a sleeper
+        // and wakes it, or this check sees the submitted job
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+        while self.queued.load(Ordering::SeqCst) == 0 && !self.shutting_down.load(Ordering::SeqCst)
+        {
+            guard = self.wake.wait(guard).unwrap();
+        }
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+        self.queued.load(Ordering::SeqCst) > 0
+    }
+
+    fn wake_one(&self) {
+        if self.sleepers.load(Ordering::SeqCst) > 0 {
+            let _guard = self.sleep.lock().unwrap();
+            self.wake.notify_one();
+        }
+    }
 }
 
 // The worker pool
 pub struct WorkerPool {
     workers: Vec<Worker>,
-    sender: mpsc::Sender<Message>,
-    receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
-    results_sender: mpsc::Sender<JobResult>,
+    shared: Arc<Shared>,
+    // Which deque the next job is dealt to
+    next_deque: AtomicUsize,
+    // Jobs submitted whose results haven't come off the results channel yet
+    outstanding: AtomicUsize,
+    // Results taken off the channel while waiting for a particular task, kept for the next
+    // caller in the order they arrived
+    held: Mutex<VecDeque<JobResult>>,
     results_receiver: mpsc::Receiver<JobResult>,
 }
 
 impl WorkerPool {
     pub fn new(size: usize) -> Self {
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let size = size.max(1);
+        let shared = Arc::new(Shared {
+            deques: (0..size).map(|_| Mutex::new(VecDeque::new())).collect(),
+            queued: AtomicUsize::new(0),
+            sleepers: AtomicUsize::new(0),
+            sleep: Mutex::new(()),
+            wake: Condvar::new(),
+            shutting_down: AtomicBool::new(false),
+        });
 
         let (results_sender, results_receiver) = mpsc::channel();
 
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            workers.push(Worker::new(
-                id,
-                Arc::clone(&receiver),
-                results_sender.clone(),
-            ));
+            workers.push(Worker::new(id, Arc::clone(&shared), results_sender.clone()));
         }
 
         WorkerPool {
             workers,
-            sender,
-            receiver,
-            results_sender,
+            shared,
+            next_deque: AtomicUsize::new(0),
+            outstanding: AtomicUsize::new(0),
+            held: Mutex::new(VecDeque::new()),
             results_receiver,
         }
     }
 
     pub fn execute(&self, job: TaskJob) -> Result<()> {
-        self.sender.send(Message::NewTask(job)).map_err(|_| {
-            TaskMasterError::InvalidOperation("Worker pool is disconnected".to_string())
-        })?;
+        if self.shared.shutting_down.load(Ordering::SeqCst) {
+            return Err(TaskMasterError::InvalidOperation(
+                "Worker pool is disconnected".to_string(),
+            ));
+        }
+        let index = self.next_deque.fetch_add(1, Ordering::Relaxed) % self.workers.len();
+        self.outstanding.fetch_add(1, Ordering::SeqCst);
+        // Counted first, so the count never drops below the jobs actually queued
+        self.shared.queued.fetch_add(1, Ordering::SeqCst);
+        self.shared.deques[index].lock().unwrap().push_back(job);
+        self.shared.wake_one();
         Ok(())
     }
 
     pub fn get_result(&self) -> Result<JobResult> {
-        self.results_receiver.recv().map_err(|_| {
+        if let Some(result) = self.held.lock().unwrap().pop_front() {
+            return Ok(result);
+        }
+        let result = self.results_receiver.recv().map_err(|_| {
             TaskMasterError::InvalidOperation("Result channel is disconnected".to_string())
-        })
+        })?;
+        self.outstanding.fetch_sub(1, Ordering::SeqCst);
+        Ok(result)
     }
 
     pub fn try_get_result(&self) -> Option<JobResult> {
-        self.results_receiver.try_recv().ok()
+        if let Some(result) = self.held.lock().unwrap().pop_front() {
+            return Some(result);
+        }
+        let result = self.results_receiver.try_recv().ok()?;
+        self.outstanding.fetch_sub(1, Ordering::SeqCst);
+        Some(result)
+    }
+
+    // Jobs submitted whose results haven't been handed out yet
+    pub fn pending(&self) -> usize {
+        self.outstanding.load(Ordering::SeqCst) + self.held.lock().unwrap().len()
+    }
+
+    // Every result available within `timeout`: returns as soon as all submitted jobs have
+    // reported, or with whatever has finished once the time is up
+    pub fn collect_results_timeout(&self, timeout: Duration) -> Vec<JobResult> {
+        let deadline = Instant::now() + timeout;
+        let mut results: Vec<JobResult> = self.held.lock().unwrap().drain(..).collect();
+        while self.outstanding.load(Ordering::SeqCst) > 0 {
+            match self.next_before(deadline) {
+                Some(result) => results.push(result),
+                None => break,
+            }
+        }
+        results
+    }
+
+    // The result of the first job for `task_id` to finish, waiting at most `timeout`.
+    // Results for other tasks that arrive meanwhile are kept for later calls. None if the
+    // time runs out, or straight away if no job is left that could report.
+    pub fn wait_for(&self, task_id: u32, timeout: Duration) -> Option<JobResult> {
+        {
+            let mut held = self.held.lock().unwrap();
+            if let Some(index) = held.iter().position(|r| r.task_id == task_id) {
+                return held.remove(index);
+            }
+        }
+        let deadline = Instant::now() + timeout;
+        while self.outstanding.load(Ordering::SeqCst) > 0 {
+            let result = self.next_before(deadline)?;
+            if result.task_id == task_id {
+                return Some(result);
+            }
+            self.held.lock().unwrap().push_back(result);
+        }
+        None
+    }
+
+    // The next result off the channel, if one arrives before `deadline`
+    fn next_before(&self, deadline: Instant) -> Option<JobResult> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let result = self.results_receiver.recv_timeout(remaining).ok()?;
+        self.outstanding.fetch_sub(1, Ordering::SeqCst);
+        Some(result)
     }
 }
 
 impl Drop for WorkerPool {
     fn drop(&mut self) {
-        println!("Sending terminate message to all workers.");
-
-        for _ in &self.workers {
-            self.sender.send(Message::Terminate).unwrap();
+        // Workers finish the jobs already queued, then stop
+        crate::log_event!(Info, "Telling all workers to finish up.");
+        self.shared.shutting_down.store(true, Ordering::SeqCst);
+        {
+            let _guard = self.shared.sleep.lock().unwrap();
+            self.shared.wake.notify_all();
         }
 
-        println!("Shutting down all workers.");
+        crate::log_event!(Info, "Shutting down all workers.");
 
         for worker in &mut self.workers {
-            println!("Shutting down worker {}", worker.id);
+            crate::log_event!(Info, "Shutting down worker {}", worker.id);
 
-            if let Some(thread) = worker.thread.take() {
+            let Some(thread) = worker.thread.take() else {
+                continue;
+            };
+            // A job that was timed out or cancelled but ignores its flag may never return;
+            // its worker is left to stop on its own rather than waited for
+            while !thread.is_finished() {
+                let abandoned = worker
+                    .current
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|cancel| cancel.is_cancelled());
+                if abandoned {
+                    crate::log_event!(
+                        Warn,
+                        "Worker {} is still running a cancelled job; not waiting for it",
+                        worker.id
+                    );
+                    break;
+                }
+                thread::sleep(SHUTDOWN_POLL);
+            }
+            if thread.is_finished() {
                 thread.join().unwrap();
             }
         }
@@ -101,49 +350,64 @@ impl Drop for WorkerPool {
 struct Worker {
     id: usize,
     thread: Option<thread::JoinHandle<()>>,
+    // The flag of the job it is running, if any
+    current: Arc<Mutex<Option<CancellationFlag>>>,
 }
 
 impl Worker {
-    fn new(
-        id: usize,
-        receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
-        results_sender: mpsc::Sender<JobResult>,
-    ) -> Self {
+    fn new(id: usize, shared: Arc<Shared>, results_sender: mpsc::Sender<JobResult>) -> Self {
+        let current = Arc::new(Mutex::new(None));
+        let running = Arc::clone(&current);
         let thread = thread::spawn(move || loop {
-            let message = receiver.lock().unwrap().recv().unwrap();
-
-            match message {
-                Message::NewTask(job) => {
-                    println!("Worker {} got a job; executing.", id);
-
-                    let task_id = job.id;
-                    let result = (job.handler)(job.task);
-
-                    let job_result = match result {
-                        Ok(_) => JobResult {
-                            task_id,
-                            success: true,
-                            error_message: None,
-                        },
-                        Err(e) => JobResult {
-                            task_id,
-                            success: false,
-                            error_message: Some(e.to_string()),
-                        },
-                    };
-
-                    results_sender.send(job_result).unwrap();
-                }
-                Message::Terminate => {
-                    println!("Worker {} was told to terminate.", id);
-                    break;
+            let Some(job) = shared.find_job(id).or_else(|| {
+                // Jobs often come in bursts; give the submitter a moment before sleeping
+                (0..SPIN_ROUNDS).find_map(|_| {
+                    thread::yield_now();
+                    shared.find_job(id)
+                })
+            }) else {
+                if shared.wait_for_work() {
+                    continue;
                 }
+                crate::log_event!(Info, "Worker {} was told to terminate.", id);
+                break;
+            };
+
+            crate::log_event!(Info, "Worker {} got job {}; executing.", id, job.run_id);
+
+            // A panicking job takes neither the worker nor the pool down with it
+            let handler = job.handler;
+            let (task, cancel) = (job.task, job.cancel);
+            *running.lock().unwrap() = Some(cancel.clone());
+            let outcome = panic::catch_unwind(AssertUnwindSafe(move || handler(task, cancel)))
+                .unwrap_or_else(|payload| JobOutcome::Panicked(panic_message(payload.as_ref())));
+            *running.lock().unwrap() = None;
+            let job_result = JobResult {
+                task_id: job.id,
+                run_id: job.run_id,
+                outcome,
+                follow_ups: Vec::new(),
+            };
+
+            // Nobody is left to receive it once the pool has been dropped without this worker
+            if results_sender.send(job_result).is_err() {
+                break;
             }
         });
 
         Worker {
             id,
             thread: Some(thread),
+            current,
         }
     }
 }
+
+// The message a panic was raised with
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
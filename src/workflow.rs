@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TaskMasterError};
+use crate::task::TaskStatus;
+
+// A single allowed move between two statuses
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Transition {
+    pub from: TaskStatus,
+    pub to: TaskStatus,
+}
+
+// The set of statuses a project uses and the transitions allowed between them. Loaded
+// workflows go through the same checks as `Workflow::new`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(try_from = "StoredWorkflow")]
+pub struct Workflow {
+    pub states: Vec<TaskStatus>,
+    pub transitions: Vec<Transition>,
+}
+
+// A workflow as written in a file, before it is checked
+#[derive(Deserialize)]
+struct StoredWorkflow {
+    states: Vec<TaskStatus>,
+    transitions: Vec<Transition>,
+}
+
+impl TryFrom<StoredWorkflow> for Workflow {
+    type Error = TaskMasterError;
+
+    fn try_from(stored: StoredWorkflow) -> Result<Self> {
+        Workflow::new(stored.states, stored.transitions)
+    }
+}
+
+impl Default for Workflow {
+    // The classic ToDo/InProgress/Done workflow, where any state can move to any other
+    fn default() -> Self {
+        let states = vec![TaskStatus::ToDo, TaskStatus::InProgress, TaskStatus::Done];
+        let mut transitions = Vec::new();
+        for from in &states {
+            for to in &states {
+                if from != to {
+                    transitions.push(Transition {
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                }
+            }
+        }
+
+        Workflow {
+            states,
+            transitions,
+        }
+    }
+}
+
+impl Workflow {
+    pub fn new(states: Vec<TaskStatus>, transitions: Vec<Transition>) -> Result<Self> {
+        if states.is_empty() {
+            return Err(TaskMasterError::InvalidOperation(
+                "A workflow needs at least one state".to_string(),
+            ));
+        }
+
+        // Every task eventually has to be completable for dependencies to resolve
+        if !states.contains(&TaskStatus::Done) {
+            return Err(TaskMasterError::InvalidOperation(
+                "A workflow must include the Done state".to_string(),
+            ));
+        }
+
+        for transition in &transitions {
+            for status in [&transition.from, &transition.to] {
+                if !states.contains(status) {
+                    return Err(TaskMasterError::InvalidOperation(format!(
+                        "Transition references unknown state: {}",
+                        status
+                    )));
+                }
+            }
+        }
+
+        Ok(Workflow {
+            states,
+            transitions,
+        })
+    }
+
+    // Where new tasks start: ToDo when the workflow has it, otherwise its first state
+    pub fn initial_state(&self) -> Result<TaskStatus> {
+        if self.contains(&TaskStatus::ToDo) {
+            return Ok(TaskStatus::ToDo);
+        }
+        self.states.first().cloned().ok_or_else(|| {
+            TaskMasterError::InvalidOperation("A workflow needs at least one state".to_string())
+        })
+    }
+
+    pub fn contains(&self, status: &TaskStatus) -> bool {
+        self.states.contains(status)
+    }
+
+    pub fn can_transition(&self, from: &TaskStatus, to: &TaskStatus) -> bool {
+        if from == to {
+            // Staying in the same state is always allowed
+            return self.contains(to);
+        }
+
+        self.transitions
+            .iter()
+            .any(|t| &t.from == from && &t.to == to)
+    }
+
+    pub fn validate_transition(&self, from: &TaskStatus, to: &TaskStatus) -> Result<()> {
+        if self.can_transition(from, to) {
+            Ok(())
+        } else {
+            Err(TaskMasterError::InvalidTransition(from.clone(), to.clone()))
+        }
+    }
+
+    // Resolve user input against the workflow's states, ignoring case and separators
+    pub fn parse_status(&self, input: &str) -> Result<TaskStatus> {
+        let wanted = normalize(input);
+        self.states
+            .iter()
+            .find(|s| normalize(&s.to_string()) == wanted)
+            .cloned()
+            .ok_or_else(|| {
+                let names: Vec<String> = self.states.iter().map(|s| s.to_string()).collect();
+                TaskMasterError::InvalidOperation(format!(
+                    "Unknown status '{}'; expected one of: {}",
+                    input,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    // Parse a "FROM:TO" transition specification
+    pub fn parse_transition(&self, spec: &str) -> Result<Transition> {
+        let (from, to) = spec.split_once(':').ok_or_else(|| {
+            TaskMasterError::InvalidOperation(format!("Invalid transition '{}'. Use FROM:TO", spec))
+        })?;
+
+        Ok(Transition {
+            from: self.parse_status(from)?,
+            to: self.parse_status(to)?,
+        })
+    }
+
+    pub fn display(&self) {
+        for line in self.lines() {
+            println!("{}", line);
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let names: Vec<String> = self.states.iter().map(|s| s.to_string()).collect();
+        let mut lines = vec![
+            format!("States: {}", names.join(", ")),
+            "Transitions:".to_string(),
+        ];
+        for transition in &self.transitions {
+            lines.push(format!("  {} -> {}", transition.from, transition.to));
+        }
+        lines
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}